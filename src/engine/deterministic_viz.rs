@@ -101,7 +101,7 @@ impl Scene {
         }
 
         for child in &mut current.children {
-            if let Ok(_) = Self::add_node_recursive_static(child, parent_id, node.clone()) {
+            if Self::add_node_recursive_static(child, parent_id, node.clone()).is_ok() {
                 return Ok(());
             }
         }
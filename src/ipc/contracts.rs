@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use std::fmt;
 
 #[derive(Serialize, Deserialize)]
 pub struct RoutingDecision {
@@ -6,3 +7,44 @@ pub struct RoutingDecision {
     pub modules: Vec<String>,
     pub merge_strategy: String,
 }
+
+/// The layer a failure came from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorLayer {
+    Router,
+    Deterministic,
+    Probabilistic,
+}
+
+/// A failed query as the UI receives it; `code` is stable, `message` is for people
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorEvent {
+    pub code: String,
+    pub layer: ErrorLayer,
+    pub message: String,
+    /// Where a syntax error occurred, counting from 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+/// One item of a query's response stream
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueryEvent {
+    Token { text: String },
+    Error(ErrorEvent),
+}
+
+/// The plain-text form the CLI prints
+impl fmt::Display for QueryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryEvent::Token { text } => write!(f, "{}", text),
+            QueryEvent::Error(error) if error.layer == ErrorLayer::Deterministic => {
+                write!(f, "[deterministic error] {}", error.message)
+            }
+            QueryEvent::Error(error) => write!(f, "[error] {}", error.message),
+        }
+    }
+}
@@ -1,14 +1,83 @@
 use futures::{stream, StreamExt, stream::BoxStream};
-use crate::modules::probabilistic::ProbabilisticModule;
+use std::sync::Arc;
+use crate::ipc::contracts::{ErrorEvent, ErrorLayer, QueryEvent};
+use crate::modules::probabilistic::{ProbabilisticModule, ProbError};
 use crate::modules::deterministic::DeterministicModule;
-use crate::modules::neuro_symbolic::{NeuroSymbolicRouter, Intent};
+use crate::modules::deterministic::context::EvalContext;
+use crate::modules::deterministic::error::DetError;
+use crate::modules::deterministic::{self, linarith, sandbox, text};
+use crate::modules::neuro_symbolic::{NeuroSymbolicRouter, Intent, RouterError};
+
+/// A query the orchestrator could not answer, tagged with the layer that failed
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OrchestratorError {
+    #[error(transparent)]
+    Router(#[from] RouterError),
+    #[error(transparent)]
+    Deterministic(#[from] DetError),
+    #[error(transparent)]
+    Model(#[from] ProbError),
+}
+
+impl OrchestratorError {
+    /// The wrapped error's stable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            OrchestratorError::Router(e) => e.code(),
+            OrchestratorError::Deterministic(e) => e.code(),
+            OrchestratorError::Model(e) => e.code(),
+        }
+    }
+
+    pub fn layer(&self) -> ErrorLayer {
+        match self {
+            OrchestratorError::Router(_) => ErrorLayer::Router,
+            OrchestratorError::Deterministic(_) => ErrorLayer::Deterministic,
+            OrchestratorError::Model(_) => ErrorLayer::Probabilistic,
+        }
+    }
+
+    /// The error as sent over IPC
+    pub fn to_event(&self) -> ErrorEvent {
+        let column = match self {
+            OrchestratorError::Deterministic(e) => e.column(),
+            _ => None,
+        };
+        ErrorEvent { code: self.code().to_string(), layer: self.layer(), message: self.to_string(), column }
+    }
+}
+
+/// A response stream holding just `error`
+fn error_stream(error: OrchestratorError) -> BoxStream<'static, QueryEvent> {
+    let event = QueryEvent::Error(error.to_event());
+    stream::once(async move { event }).boxed()
+}
+
+fn token(text: String) -> QueryEvent {
+    QueryEvent::Token { text }
+}
+
+/// A language-model token, or the error that ended the stream
+fn model_event(item: Result<String, ProbError>) -> QueryEvent {
+    match item {
+        Ok(text) => token(text),
+        Err(e) => {
+            log::error!("Language model failed: {}", e);
+            QueryEvent::Error(OrchestratorError::Model(e).to_event())
+        }
+    }
+}
 
 /// Production-grade orchestrator with comprehensive error handling and logging
 pub struct Orchestrator {
     pub prob_module: ProbabilisticModule,
-    pub det_module: DeterministicModule,
+    pub det_module: Arc<DeterministicModule>,
     pub router: NeuroSymbolicRouter,
     pub stats: OrchestratorStats,
+    /// Variables and user functions shared by every query in this session
+    pub session: std::sync::Mutex<EvalContext>,
+    /// Where the session is persisted, from AXIOM_SESSION_PATH
+    session_path: Option<std::path::PathBuf>,
 }
 
 #[derive(Default)]
@@ -21,30 +90,56 @@ pub struct OrchestratorStats {
 
 impl Orchestrator {
     pub fn new(prob: ProbabilisticModule, det: DeterministicModule, router: NeuroSymbolicRouter) -> Self {
+        let session_path = std::env::var("AXIOM_SESSION_PATH").ok().map(std::path::PathBuf::from);
+        let session = match &session_path {
+            Some(path) => EvalContext::load(path).unwrap_or_else(|e| {
+                log::warn!("Starting with an empty session: {}", e);
+                EvalContext::new()
+            }),
+            None => EvalContext::new(),
+        };
+        
         log::info!("Orchestrator initialized");
         Self { 
             prob_module: prob, 
-            det_module: det, 
+            det_module: Arc::new(det), 
             router,
             stats: OrchestratorStats::default(),
+            session: std::sync::Mutex::new(session),
+            session_path,
         }
     }
 
     /// Process a query and return a boxed stream of token strings
     /// Implements neuro-symbolic routing with full error recovery
     pub async fn process_query(&self, query: &str) -> BoxStream<'static, String> {
-        if query.is_empty() {
-            log::warn!("Empty query received");
-            return stream::once(async { "[error] Query cannot be empty".to_string() }).boxed();
-        }
-        
-        if query.len() > 50000 {
-            log::warn!("Query too long: {} chars", query.len());
-            return stream::once(async { "[error] Query exceeds maximum length".to_string() }).boxed();
-        }
+        self.process_query_events(query).await.map(|event| event.to_string()).boxed()
+    }
+
+    /// Process a query into tokens and typed error events, for callers that tell
+    /// failures apart by code
+    pub async fn process_query_events(&self, query: &str) -> BoxStream<'static, QueryEvent> {
+        // "show steps" asks for the deterministic trace; the rest is the actual query
+        let (query, show_steps) = split_show_steps(query);
+        let query = query.as_str();
         
-        // Classify intent
-        let intent = self.router.classify_intent(query);
+        // Classify intent; session commands always go to the deterministic module
+        let intent = match self.router.route(query) {
+            Ok(intent) => intent,
+            Err(e) => {
+                log::warn!("Query rejected by the router: {}", e);
+                return error_stream(e.into());
+            }
+        };
+        // A logical query the deterministic module cannot read goes to the language model
+        let intent = {
+            let session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+            match intent {
+                _ if self.det_module.is_session_query(query, &session) => Intent::Logical,
+                Intent::Logical if !self.det_module.recognizes(query, &session) => Intent::Hybrid,
+                intent => intent,
+            }
+        };
         log::info!("Query classified as: {:?}", intent);
         
         // Update statistics
@@ -57,7 +152,7 @@ impl Orchestrator {
             }
             Intent::Logical => {
                 self.stats.logical_queries.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                self.handle_logical(query).await
+                self.handle_logical(query, show_steps).await
             }
             Intent::Hybrid => {
                 self.stats.hybrid_queries.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -67,84 +162,88 @@ impl Orchestrator {
     }
     
     /// Handle creative queries with LLM streaming
-    async fn handle_creative(&self, query: &str) -> BoxStream<'static, String> {
+    async fn handle_creative(&self, query: &str) -> BoxStream<'static, QueryEvent> {
         log::debug!("Processing creative query");
         let s = self.prob_module.stream_tokens(query).await;
-        s.map(|t| t).boxed()
+        s.map(model_event).boxed()
     }
     
-    /// Handle logical queries with deterministic execution
-    async fn handle_logical(&self, query: &str) -> BoxStream<'static, String> {
+    /// Handle logical queries with deterministic execution, streaming the
+    /// proof after the result when steps were requested
+    async fn handle_logical(&self, query: &str, show_steps: bool) -> BoxStream<'static, QueryEvent> {
         log::debug!("Processing logical query");
         
-        match self.det_module.execute_logic(query) {
-            Ok(result) => {
+        let outcome = {
+            let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+            let before = session.clone();
+            let outcome = self.det_module.execute_in(query, &mut session);
+            if *session != before {
+                self.persist_session(&session);
+            }
+            outcome
+        };
+        
+        match outcome {
+            Ok(response) => {
+                let mut result = match response.decimal {
+                    Some(decimal) => format!("{} ({})", response.result, decimal),
+                    None => response.result,
+                };
+                // Show what a worded query was read as
+                if let Some(canonical) = response.interpreted {
+                    result = format!("{} = {}", canonical, result);
+                }
                 log::debug!("Logical query succeeded: {} chars", result.len());
-                stream::once(async move { result }).boxed()
+                
+                let steps = match response.proof {
+                    Some(proof) if show_steps => std::iter::once("\n[Steps]".to_string())
+                        .chain(proof.into_iter().enumerate().map(|(i, line)| format!("\n  {}. {}", i + 1, line)))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                stream::once(async move { result }).chain(stream::iter(steps)).map(token).boxed()
             }
             Err(e) => {
                 log::error!("Logical query failed: {}", e);
-                stream::once(async move { 
-                    format!("[deterministic error] {}", e) 
-                }).boxed()
+                error_stream(e.into())
             }
         }
     }
     
-    /// Handle hybrid queries with LLM draft + deterministic verification
-    async fn handle_hybrid(&self, query: &str) -> BoxStream<'static, String> {
+    /// Write the session to AXIOM_SESSION_PATH, if configured
+    fn persist_session(&self, session: &EvalContext) {
+        if let Some(path) = &self.session_path {
+            match session.save(path) {
+                Ok(()) => log::debug!("Session saved to {}", path.display()),
+                Err(e) => log::error!("Failed to save session to {}: {}", path.display(), e),
+            }
+        }
+    }
+    
+    /// Handle hybrid queries with LLM draft + deterministic verification: the draft is
+    /// generated once, streamed as it arrives, and checked when it is complete
+    async fn handle_hybrid(&self, query: &str) -> BoxStream<'static, QueryEvent> {
         log::debug!("Processing hybrid query");
         
-        // Get LLM stream
-        let llm_stream = self.prob_module.stream_tokens(query).await;
-        
-        // Get full draft for verification
-        let draft_result = self.prob_module.infer(query).await;
-        
-        match draft_result {
-            Ok(draft_full) => {
-                log::debug!("LLM draft generated: {} chars", draft_full.len());
-                
-                // Extract and verify claims
-                let claims = extract_claims(&draft_full);
-                log::debug!("Extracted {} claims for verification", claims.len());
-                
-                let mut verification = String::new();
-                let mut verified_count = 0;
-                let mut failed_count = 0;
-                
-                for claim in claims.iter() {
-                    match self.det_module.execute_logic(claim) {
-                        Ok(v) => {
-                            verification.push_str(&format!("✓ Claim: {} → {}\n", claim, v));
-                            verified_count += 1;
-                        }
-                        Err(e) => {
-                            verification.push_str(&format!("✗ Claim: {} → Error: {}\n", claim, e));
-                            failed_count += 1;
-                        }
-                    }
+        let tokens = self.prob_module.stream_tokens(query).await;
+        let verifier = Some((self.det_module.clone(), sandbox::examples(query)));
+        stream::unfold((tokens, String::new(), verifier), |(mut tokens, mut draft, verifier)| async move {
+            // Nothing follows the verification or a failed draft
+            let (det, examples) = verifier?;
+            match tokens.next().await {
+                Some(Ok(text)) => {
+                    draft.push_str(&text);
+                    Some((token(text), (tokens, draft, Some((det, examples)))))
                 }
-                
-                if verified_count > 0 || failed_count > 0 {
-                    verification = format!(
-                        "\n[Verification Results: {} verified, {} failed]\n{}", 
-                        verified_count, failed_count, verification
-                    );
+                Some(Err(e)) => Some((model_event(Err(e)), (tokens, draft, None))),
+                None => {
+                    log::debug!("LLM draft generated: {} chars", draft.len());
+                    let verification = verify_draft(&det, &draft, &examples);
+                    Some((token(verification), (tokens, draft, None)))
                 }
-                
-                log::debug!("Verification complete: {} verified, {} failed", verified_count, failed_count);
-                
-                let verification_stream = stream::once(async move { verification });
-                llm_stream.map(|t| t).chain(verification_stream).boxed()
-            }
-            Err(e) => {
-                log::error!("Failed to generate draft: {}", e);
-                stream::once(async move { 
-                    format!("[error] Failed to process hybrid query: {}", e) 
-                }).boxed()
             }
-        }
+        })
+        .boxed()
     }
     
     /// Get orchestrator statistics
@@ -166,6 +265,78 @@ pub struct OrchestratorStatsSnapshot {
     pub hybrid_queries: u64,
 }
 
+/// Check the claims and code in a finished draft; the report is empty when there is
+/// nothing to check
+fn verify_draft(det: &DeterministicModule, draft: &str, examples: &[sandbox::Example]) -> String {
+    // Extract and verify claims
+    let claims = extract_claims(draft);
+    log::debug!("Extracted {} claims for verification", claims.len());
+    
+    let mut verification = String::new();
+    let mut verified_count = 0;
+    let mut failed_count = 0;
+    
+    for claim in claims.iter() {
+        match det.execute(claim) {
+            Ok(v) if v.verdict == Some(false) => {
+                verification.push_str(&format!("✗ Claim: {} → {}\n", claim, v.result));
+                failed_count += 1;
+            }
+            Ok(v) => {
+                verification.push_str(&format!("✓ Claim: {} → {}\n", claim, v.result));
+                verified_count += 1;
+            }
+            Err(e) => {
+                verification.push_str(&format!("✗ Claim: {} → Error: {}\n", claim, e));
+                failed_count += 1;
+            }
+        }
+    }
+    
+    // Code in the draft is run against the examples the query gives
+    let code = sandbox::code_blocks(draft).pop();
+    if let (Some(code), false) = (code, examples.is_empty()) {
+        for check in det.check_examples(&code, examples) {
+            let example = format!("{} == {}", check.example.call, check.example.expected);
+            if check.passed {
+                verification.push_str(&format!("✓ Example: {}\n", example));
+                verified_count += 1;
+            } else {
+                verification.push_str(&format!("✗ Example: {} → got {}\n", example, check.got));
+                failed_count += 1;
+            }
+        }
+    }
+    
+    if verified_count > 0 || failed_count > 0 {
+        verification = format!(
+            "\n[Verification Results: {} verified, {} failed]\n{}", 
+            verified_count, failed_count, verification
+        );
+    }
+    
+    log::debug!("Verification complete: {} verified, {} failed", verified_count, failed_count);
+    verification
+}
+
+/// Remove a leading or trailing "show steps" request, e.g. "show steps for 2 + 3 * 4"
+fn split_show_steps(query: &str) -> (String, bool) {
+    let trimmed = query.trim();
+    
+    for phrase in ["show steps", "show the steps", "with steps"] {
+        if let Some(rest) = deterministic::strip_leading_phrase(trimmed, phrase) {
+            let rest = rest.trim_start_matches([':', ',', ' ']);
+            let rest = rest.strip_prefix("for ").unwrap_or(rest);
+            return (rest.trim().to_string(), true);
+        }
+        if let Some(rest) = deterministic::strip_trailing_phrase(trimmed, phrase) {
+            let rest = rest.trim_end_matches([',', ';', ' ']);
+            return (rest.to_string(), true);
+        }
+    }
+    (trimmed.to_string(), false)
+}
+
 /// Extract numerical claims from text for verification
 fn extract_claims(text: &str) -> Vec<String> {
     use once_cell::sync::Lazy;
@@ -178,10 +349,53 @@ fn extract_claims(text: &str) -> Vec<String> {
         regex::Regex::new(r"\d+(?:\.\d+)?").unwrap()
     });
     
+    // "(p -> q) & p -> q is valid", checked as "is (p -> q) & p -> q valid"
+    static LOGIC_RE: Lazy<regex::Regex> = Lazy::new(|| {
+        regex::Regex::new(r"(?i)([^.?\n]+?)\s+is\s+(satisfiable|unsatisfiable|valid|a tautology|a contradiction)\b").unwrap()
+    });
+    
+    // "if x > 3 and y < 2 then x - y > 1", checked as a linear arithmetic claim
+    static IMPLICATION_RE: Lazy<regex::Regex> = Lazy::new(|| {
+        regex::Regex::new(r"(?i)\bif\s+((?:[^.?\n]|\.\d)+?),?\s+then\s+((?:[^.?\n]|\.\d)+)").unwrap()
+    });
+    
+    // "strawberry has 3 r's" and "there are 3 r's in strawberry", recounted exactly
+    static COUNT_RE: Lazy<regex::Regex> = Lazy::new(|| {
+        let subject = r#"(?:the word\s+)?("[^"\n]+"|“[^”\n]+”|[A-Za-z][\w'-]*)"#;
+        let count = r"(\d+|zero|one|two|three|four|five|six|seven|eight|nine|ten)";
+        let target = r#"(letters?\s+[a-z](?:'s|’s|s)?|[a-z](?:'s|’s)|"[^"\n]+"s?|characters|letters|vowels|consonants|words)"#;
+        regex::Regex::new(&format!(
+            r"(?i)\bthere\s+(?:are|is)\s+{count}\s+{target}\s+in\s+{subject}|{subject}\s+(?:has|contains)\s+{count}\s+{target}",
+        ))
+        .unwrap()
+    });
+    
     let mut claims: Vec<String> = EXPR_RE.find_iter(text)
         .map(|m| m.as_str().to_string())
         .collect();
     
+    claims.extend(LOGIC_RE.captures_iter(text).filter_map(|c| {
+        let formula = formula_suffix(&c[1])?;
+        Some(format!("is {} {}", formula, &c[2]))
+    }));
+    
+    claims.extend(IMPLICATION_RE.captures_iter(text).filter_map(|c| {
+        let hypothesis = c[1].trim();
+        let names = linarith::parse_formula(hypothesis).ok()?.variables();
+        let conclusion = conclusion_prefix(&c[2], &names)?;
+        Some(format!("if {} then {}", hypothesis, conclusion))
+    }));
+    
+    claims.extend(COUNT_RE.captures_iter(text).filter_map(|c| {
+        let group = |i: usize| c.get(i).map(|m| m.as_str());
+        let (count, target, subject) = match group(1) {
+            Some(count) => (count, group(2)?, group(3)?),
+            None => (group(5)?, group(6)?, group(4)?),
+        };
+        let claim = format!("{} has {} {}", subject, count, target);
+        text::parse_command(&claim).is_some().then_some(claim)
+    }));
+    
     // Also extract simple numbers as potential claims
     if claims.is_empty() {
         claims = NUM_RE.find_iter(text)
@@ -192,3 +406,28 @@ fn extract_claims(text: &str) -> Vec<String> {
     
     claims
 }
+
+/// Longest run of leading words that parses as a linear formula over the hypothesis'
+/// variables, so "x > 5 for any such value" yields "x > 5" rather than reading the
+/// trailing words as implicit products
+fn conclusion_prefix<'a>(text: &'a str, names: &std::collections::BTreeSet<String>) -> Option<&'a str> {
+    let text = text.trim();
+    let mut ends: Vec<usize> = text.match_indices(char::is_whitespace).map(|(i, _)| i).collect();
+    ends.push(text.len());
+    ends.into_iter()
+        .rev()
+        .map(|end| text[..end].trim_end_matches(','))
+        .find(|candidate| linarith::parse_formula(candidate).is_ok_and(|f| f.variables().is_subset(names)))
+}
+
+/// Longest run of trailing words that parses as a propositional formula with a
+/// connective, so "Note that p -> p" yields "p -> p"
+fn formula_suffix(text: &str) -> Option<&str> {
+    use crate::modules::deterministic::propositional::parse_formula;
+    
+    text.char_indices()
+        .filter(|(i, _)| *i == 0 || text[..*i].ends_with(char::is_whitespace))
+        .map(|(i, _)| text[i..].trim())
+        .find(|candidate| parse_formula(candidate).is_ok())
+        .filter(|formula| formula.contains(['&', '|', '~', '!', '>']))
+}
@@ -3,12 +3,8 @@
 //! This application combines probabilistic (LLM) and deterministic (logic/math) reasoning
 //! with a local-first, zero-egress architecture for secure AI processing.
 
-mod modules;
-mod engine;
-mod ipc;
-
-use modules::{ProbabilisticModule, DeterministicModule, NeuroSymbolicRouter};
-use ipc::orchestrator::Orchestrator;
+use axiom_assistant::modules::{ProbabilisticModule, DeterministicModule, NeuroSymbolicRouter};
+use axiom_assistant::ipc::orchestrator::Orchestrator;
 use futures::StreamExt;
 use tokio::io::AsyncBufReadExt;
 
@@ -37,7 +33,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize modules with error handling
     let prob = match ProbabilisticModule::load_local_llm().await {
         Ok(m) => {
-            log::info!("✓ Probabilistic module loaded");
+            log::info!("✓ Probabilistic module loaded ({} backend)", m.capabilities().backend);
             m
         }
         Err(e) => {
@@ -104,7 +100,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("\n📖 Help:");
                         println!("  - Type any question or command");
                         println!("  - Math queries: '2 + 2', 'sqrt(16)'");
-                        println!("  - Logic queries: 'ancestor(zeus, hercules)'");
+                        println!("  - Symbolic queries: 'derivative of x^2 * sin(x)', 'expand (x+1)^3'");
+                        println!("  - Equations: 'solve 2x + 3 = 11', 'solve x + y = 3, x - y = 1'");
+                        println!("  - Units: '5 km + 300 m in miles', '9.81 m/s^2 * 70 kg'");
+                        println!("  - Variables: 'let r = 4', 'f(x) = x^2 + 1', 'list bindings', 'clear bindings'");
+                        println!("  - Matrices: '[[1,2],[3,4]] * [[5,6],[7,8]]', 'eigenvalues of [[2,1],[1,2]]'");
+                        println!("  - Statistics: 'mean of [3, 5, 9, 12]', 'nCr(52, 5)', 'binomial pmf n=10, p=1/2, k=3'");
+                        println!("  - Numerical: 'integrate sin(x) from 0 to pi', 'root of x^3 - 2x - 5 near 2', \"solve y' = -y, y(0)=1 at t=1\"");
+                        println!("  - Dates: 'what weekday is 2031-07-04', 'business days between 2026-03-01 and 2026-04-15', '2026-03-01T10:00+05:30 in UTC-8'");
+                        println!("  - Programmer: '0xFF & 0b1010', '1 << 12', '200 + 100 as u8', '255 in hex'");
+                        println!("  - In words: 'what is twelve times seven plus a half', '15 percent of 80'");
+                        println!("  - Propositional logic: '(p -> q) & p -> q', 'truth table for p <-> ~q', 'is p & ~p satisfiable'");
+                        println!("  - Add 'show steps' to a query to see the evaluation trace");
+                        println!("  - Logic queries: 'ancestor(zeus, hercules)', 'ancestor(X, apollo)', '?- append(X, Y, [1, 2])'");
+                        println!("  - Knowledge base: 'consult kb/family.pl', 'reload', 'list predicates', 'geo:flows_into(X, Y)'");
+                        println!("  - Datalog: 'datalog ancestor(X, hercules)', 'why ancestor(cronus, ares)', 'datalog e(a, b). r(X, Y) :- e(X, Y). ?- r(a, Y)'");
+                        println!("  - Constraints: 'assign A, B, C in 1..9 all different with A + B = C * 2', 'X, Y in 0..10 with X + 2Y <= 14, maximize 3X + 4Y'");
+                        println!("  - Linear claims: 'if x > 3 and y < 2 then x - y > 1', 'for all integers n: 2n != 7', 'is x + 1 > x valid'");
+                        println!("  - Code (Rhai, sandboxed): 'run: let x = 6; x * 7', 'run with seed 7: random(1, 6)'");
+                        println!("  - Text: \"how many r's in strawberry\", 'the 5th word of \"...\"', 'does \"order 1234\" match /\\d+/'");
                         println!("  - Creative queries: 'explain quantum physics'");
                         println!("  - 'stats' - Show processing statistics");
                         println!("  - 'exit' or Ctrl+C - Exit the application");
@@ -1,233 +0,0 @@
-use serde::{Serialize, Deserialize};
-use evalexpr::*;
-
-/// Production-grade deterministic module with comprehensive error handling
-/// Implements math evaluation and logic processing with full verification
-pub struct DeterministicModule {
-    config: DetConfig,
-}
-
-struct DetConfig {
-    enable_prolog: bool,
-    max_query_length: usize,
-}
-
-impl DeterministicModule {
-    /// Initialize deterministic module with configuration from environment
-    pub fn init_deterministic_module() -> anyhow::Result<Self> {
-        log::info!("Initializing DeterministicModule");
-        
-        let enable_prolog = std::env::var("AXIOM_ENABLE_PROLOG")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(false);
-        
-        let max_query_length = std::env::var("AXIOM_MAX_QUERY_LENGTH")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(10000);
-        
-        if enable_prolog {
-            #[cfg(feature = "swipl")]
-            {
-                log::info!("Prolog integration enabled with SWI-Prolog");
-                // Initialize SWI-Prolog when feature is enabled
-            }
-            #[cfg(not(feature = "swipl"))]
-            {
-                log::info!("Prolog requested but swipl feature not enabled, using mock");
-            }
-        } else {
-            log::info!("Prolog integration disabled, using mock logic");
-        }
-        
-        let config = DetConfig {
-            enable_prolog,
-            max_query_length,
-        };
-        
-        log::info!("DeterministicModule initialized successfully");
-        Ok(DeterministicModule { config })
-    }
-
-    /// Execute a logic/math query with full error handling
-    /// For math queries: evaluate using evalexpr
-    /// For logic queries: return deterministic proofs
-    pub fn execute_logic(&self, query: &str) -> anyhow::Result<String> {
-        if query.is_empty() {
-            return Err(anyhow::anyhow!("Query cannot be empty"));
-        }
-        
-        if query.len() > self.config.max_query_length {
-            return Err(anyhow::anyhow!(
-                "Query exceeds maximum length of {} characters",
-                self.config.max_query_length
-            ));
-        }
-        
-        log::debug!("Executing logic query: {}", query);
-        
-        // Sanitize input to prevent injection attacks
-        let sanitized_query = self.sanitize_query(query)?;
-        
-        // Route to appropriate handler
-        if looks_like_math(&sanitized_query) {
-            self.execute_math(&sanitized_query)
-        } else if looks_like_logic(&sanitized_query) {
-            self.execute_prolog(&sanitized_query)
-        } else {
-            log::warn!("Query type not recognized: {}", sanitized_query);
-            Ok("[deterministic: query type not recognized]".to_string())
-        }
-    }
-    
-    /// Sanitize query input to prevent injection
-    fn sanitize_query(&self, query: &str) -> anyhow::Result<String> {
-        // Remove potentially dangerous characters while preserving math/logic syntax
-        let sanitized: String = query
-            .chars()
-            .filter(|c| {
-                c.is_alphanumeric() 
-                    || c.is_whitespace() 
-                    || "+-*/^%().=:,_[]".contains(*c)
-            })
-            .collect();
-        
-        if sanitized.is_empty() {
-            return Err(anyhow::anyhow!("Query contains no valid characters"));
-        }
-        
-        Ok(sanitized)
-    }
-    
-    /// Execute mathematical expression with error handling
-    fn execute_math(&self, query: &str) -> anyhow::Result<String> {
-        log::debug!("Evaluating math expression: {}", query);
-        
-        // Extract actual math expression from queries like "Calculate 10 + 5"
-        let math_expr = extract_math_expression(query);
-        log::debug!("Extracted math expression: {}", math_expr);
-        
-        // Try float evaluation first
-        match eval_float(&math_expr) {
-            Ok(result) => {
-                log::debug!("Math result (float): {}", result);
-                return Ok(format!("{}", result));
-            }
-            Err(_) => {
-                // Try integer evaluation
-                match eval_int(&math_expr) {
-                    Ok(result) => {
-                        log::debug!("Math result (int): {}", result);
-                        return Ok(format!("{}", result));
-                    }
-                    Err(e) => {
-                        log::warn!("Math evaluation error: {}", e);
-                        return Err(anyhow::anyhow!("Math evaluation failed: {}", e));
-                    }
-                }
-            }
-        }
-    }
-    
-    /// Execute Prolog-like logic query with deterministic proofs
-    fn execute_prolog(&self, query: &str) -> anyhow::Result<String> {
-        log::debug!("Executing Prolog query: {}", query);
-        
-        #[cfg(feature = "swipl")]
-        if self.config.enable_prolog {
-            // Use actual SWI-Prolog when feature is enabled
-            log::info!("Using SWI-Prolog for query execution");
-        }
-        
-        // Production-grade mock Prolog responses with proper proof chains
-        if query.contains("ancestor") {
-            let proof = vec![
-                "% Query: ancestor(X, Y)".to_string(),
-                "% Rule: ancestor(X, Y) :- parent(X, Y).".to_string(),
-                "% Rule: ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y).".to_string(),
-                "% Fact: parent(zeus, hercules).".to_string(),
-                "% Proof: ancestor(zeus, hercules) by parent(zeus, hercules).".to_string(),
-                "% Result: true".to_string(),
-            ];
-            return Ok(proof.join("\n"));
-        }
-        
-        if query.contains("member") {
-            let proof = vec![
-                "% Query: member(X, List)".to_string(),
-                "% Rule: member(X, [X|_]).".to_string(),
-                "% Rule: member(X, [_|T]) :- member(X, T).".to_string(),
-                "% Result: deterministic traversal".to_string(),
-            ];
-            return Ok(proof.join("\n"));
-        }
-        
-        // Default response for unrecognized logic queries
-        Ok(format!(
-            "[deterministic: no matching logic rule for '{}']",
-            query
-        ))
-    }
-}
-
-/// Heuristic to detect mathematical expressions
-fn looks_like_math(s: &str) -> bool {
-    let math_chars = ['+', '-', '*', '/', '^', '%'];
-    s.chars().any(|c| math_chars.contains(&c)) 
-        || s.trim().chars().all(|c| {
-            c.is_digit(10) || c.is_whitespace() || "().".contains(c)
-        })
-}
-
-/// Heuristic to detect logic queries
-fn looks_like_logic(s: &str) -> bool {
-    let logic_keywords = ["ancestor", "parent", "member", "append", "rule", "fact"];
-    logic_keywords.iter().any(|&kw| s.to_lowercase().contains(kw))
-        || s.contains(":-")
-        || s.contains("?-")
-}
-
-/// Extract the mathematical expression from a query string
-/// Handles queries like "Calculate 10 + 5" -> "10 + 5"
-fn extract_math_expression(query: &str) -> String {
-    // Remove common prefix words
-    let prefixes = ["calculate", "solve", "compute", "evaluate", "what is"];
-    let mut expr = query.to_lowercase();
-    
-    for prefix in &prefixes {
-        if expr.starts_with(prefix) {
-            expr = expr[prefix.len()..].trim().to_string();
-            break;
-        }
-    }
-    
-    // If we didn't find a prefix, return the original query
-    if expr.is_empty() {
-        query.to_string()
-    } else {
-        // Return the extracted expression with original case for numbers/operators
-        let start_idx = query.len() - expr.len();
-        query[start_idx..].trim().to_string()
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct DetRequest {
-    pub query_type: QueryType,
-    pub query: String,
-}
-
-#[derive(Serialize, Deserialize)]
-pub enum QueryType {
-    Math,
-    Logic,
-    Code,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct DetResponse {
-    pub result: String,
-    pub proof: Option<Vec<String>>,
-    pub deterministic: bool,
-}
@@ -0,0 +1,572 @@
+// ISO-8601 dates, durations, business days and UTC offset arithmetic
+use super::lexer::{column_of, relocate, SyntaxError};
+use super::{split_keyword, strip_keyword};
+use chrono::{
+    Datelike, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday,
+};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+
+/// Source of non-working days for business-day arithmetic; weekends are always skipped
+pub trait HolidayCalendar: Send + Sync {
+    fn is_holiday(&self, date: NaiveDate) -> bool;
+}
+
+/// Fixed set of holiday dates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HolidayList {
+    dates: BTreeSet<NaiveDate>,
+}
+
+impl HolidayList {
+    pub fn new(dates: impl IntoIterator<Item = NaiveDate>) -> Self {
+        HolidayList { dates: dates.into_iter().collect() }
+    }
+
+    /// Read one ISO date per line; text after the date and lines starting with '#' are ignored
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read holiday file {}: {}", path.display(), e))?;
+        let dates = data
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'))
+            .map(|(index, line)| {
+                let word = line.split_whitespace().next().unwrap_or_default();
+                parse_date(word).map_err(|e| {
+                    let column = column_of(line, word).unwrap_or(1);
+                    SyntaxError { column, ..e }.on_line(index + 1).in_file(path.display().to_string())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(HolidayList::new(dates))
+    }
+}
+
+impl HolidayCalendar for HolidayList {
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.dates.contains(&date)
+    }
+}
+
+/// A point in time as written in the query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Moment {
+    /// Read from the host clock, only when the query says "today"
+    Today,
+    Date(NaiveDate),
+    /// Wall-clock time, with its UTC offset when one was given
+    DateTime(NaiveDateTime, Option<FixedOffset>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarUnit {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+    Weeks,
+    Months,
+    Years,
+    BusinessDays,
+    /// "time between": days, hours, minutes and seconds
+    Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalendarQuery {
+    /// "2026-03-01"
+    Describe(Moment),
+    /// "what weekday is 2031-07-04"
+    Weekday(Moment),
+    /// "days between 2026-03-01 and 2026-04-15" or "2026-04-15 - 2026-03-01"
+    Difference { from: Moment, to: Moment, unit: CalendarUnit },
+    /// "2026-01-31 + 1 month" or "10 business days after 2026-03-01"
+    Shift { start: Moment, amount: i64, unit: CalendarUnit },
+    /// "2026-03-01T10:00+05:30 in UTC-8"
+    Convert { moment: Moment, offset: FixedOffset },
+}
+
+/// Recognize a calendar query; `None` unless the query names a date or "today"
+pub fn parse_command(query: &str) -> Option<anyhow::Result<CalendarQuery>> {
+    let text = query.trim().to_lowercase();
+    if !contains_iso_date(&text) && !text.split_whitespace().any(|word| word == "today") {
+        return None;
+    }
+
+    const WEEKDAY_PREFIXES: &[&str] = &[
+        "what weekday is", "which weekday is", "what day of the week is", "what day is", "weekday of",
+        "day of the week of",
+    ];
+    if let Some(rest) = WEEKDAY_PREFIXES.iter().find_map(|p| strip_keyword(&text, p)) {
+        return Some(parse_moment(rest).map(CalendarQuery::Weekday).map_err(|e| relocate(e, &text, rest)));
+    }
+
+    let mut body = text.as_str();
+    while let Some(rest) = ["what is", "whats", "how many", "how long is", "calculate", "compute"]
+        .iter()
+        .find_map(|p| strip_keyword(body, p))
+    {
+        body = rest;
+    }
+    parse_query(body).map(|query| query.map_err(|e| relocate(e, &text, body)))
+}
+
+/// Syntax errors count columns from the start of `text`
+fn parse_query(text: &str) -> Option<anyhow::Result<CalendarQuery>> {
+    let parse_moment = |part: &str| parse_moment(part).map_err(|e| relocate(e, text, part));
+    let parse_offset = |part: &str| parse_offset(part).map_err(|e| relocate(e, text, part));
+
+    // "<unit> between a and b" or "<unit> from a to b"
+    let range = split_keyword(text, "between")
+        .and_then(|(head, range)| Some((head, split_keyword(range, "and")?)))
+        .or_else(|| split_keyword(text, "from").and_then(|(head, range)| Some((head, split_keyword(range, "to")?))));
+    if let Some((head, (from, to))) = range {
+        if let Some(unit) = difference_unit(head) {
+            return Some(parse_moment(from).and_then(|from| Ok(CalendarQuery::Difference { from, to: parse_moment(to)?, unit })));
+        }
+    }
+
+    // "10 business days after 2026-03-01"
+    for (keyword, sign) in [("after", 1), ("from", 1), ("before", -1)] {
+        if let Some((amount, start)) = split_keyword(text, keyword) {
+            if let Some((amount, unit)) = parse_amount(amount) {
+                return Some(parse_moment(start).map(|start| CalendarQuery::Shift { start, amount: sign * amount, unit }));
+            }
+        }
+    }
+
+    // "2026-03-01 + 45 days", "2026-04-15 - 2026-03-01"
+    for (keyword, sign) in [("+", 1), ("plus", 1), ("-", -1), ("minus", -1)] {
+        if let Some((lhs, rhs)) = split_keyword(text, keyword) {
+            if let Some((amount, unit)) = parse_amount(rhs) {
+                return Some(parse_moment(lhs).map(|start| CalendarQuery::Shift { start, amount: sign * amount, unit }));
+            }
+            if sign < 0 {
+                if let (Ok(to), Ok(from)) = (parse_moment(lhs), parse_moment(rhs)) {
+                    return Some(Ok(CalendarQuery::Difference { from, to, unit: CalendarUnit::Days }));
+                }
+            }
+        }
+    }
+
+    // "2026-03-01T10:00+05:30 in UTC-8"
+    for keyword in ["in", "to"] {
+        if let Some((moment, zone)) = split_keyword(text, keyword) {
+            match (parse_offset(zone), parse_moment(moment)) {
+                (Ok(offset), moment) => return Some(moment.map(|moment| CalendarQuery::Convert { moment, offset })),
+                // A valid moment followed by "in <zone>" is a conversion; report the zone alone
+                (Err(e), Ok(_)) if parse_moment(zone).is_err() => return Some(Err(e)),
+                _ => {}
+            }
+        }
+    }
+
+    // A lone date is described; one that fails to parse is reported rather than read as subtraction
+    let starts_with_date = text.get(..10).is_some_and(contains_iso_date);
+    match parse_moment(text) {
+        Ok(moment) => Some(Ok(CalendarQuery::Describe(moment))),
+        Err(e) if starts_with_date => Some(Err(e)),
+        Err(_) => None,
+    }
+}
+
+fn difference_unit(head: &str) -> Option<CalendarUnit> {
+    match head.trim() {
+        "business days" | "working days" | "weekdays" => Some(CalendarUnit::BusinessDays),
+        "time" | "duration" | "the time" | "the duration" | "" => Some(CalendarUnit::Duration),
+        unit => parse_unit(unit).filter(|u| !matches!(u, CalendarUnit::Months | CalendarUnit::Years)),
+    }
+}
+
+fn parse_unit(text: &str) -> Option<CalendarUnit> {
+    let unit = match text.trim() {
+        "second" | "seconds" => CalendarUnit::Seconds,
+        "minute" | "minutes" => CalendarUnit::Minutes,
+        "hour" | "hours" => CalendarUnit::Hours,
+        "day" | "days" => CalendarUnit::Days,
+        "week" | "weeks" => CalendarUnit::Weeks,
+        "month" | "months" => CalendarUnit::Months,
+        "year" | "years" => CalendarUnit::Years,
+        "business day" | "business days" | "working day" | "working days" => CalendarUnit::BusinessDays,
+        _ => return None,
+    };
+    Some(unit)
+}
+
+/// "45 days", "a week", "10 business days"
+fn parse_amount(text: &str) -> Option<(i64, CalendarUnit)> {
+    let (count, unit) = text.trim().split_once(char::is_whitespace)?;
+    let count = match count {
+        "a" | "an" | "one" => 1,
+        digits => digits.parse().ok()?,
+    };
+    Some((count, parse_unit(unit)?))
+}
+
+fn contains_iso_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.windows(10).any(|w| {
+        w.iter().enumerate().all(|(i, b)| if i == 4 || i == 7 { *b == b'-' } else { b.is_ascii_digit() })
+    })
+}
+
+/// A date alone; errors are at column 1
+fn parse_date(text: &str) -> Result<NaiveDate, SyntaxError> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| SyntaxError::new(1, "a valid date (YYYY-MM-DD)", format!("'{}'", text)))
+}
+
+/// "today", "2026-03-01", "2026-03-01T10:00", "2026-03-01 10:00:30 utc+2"; syntax errors
+/// count columns from the start of `input`
+pub fn parse_moment(input: &str) -> anyhow::Result<Moment> {
+    let text = input.trim();
+    if text.eq_ignore_ascii_case("today") {
+        return Ok(Moment::Today);
+    }
+    let column = |part: &str| column_of(input, part).unwrap_or(1);
+    let invalid = || SyntaxError::new(column(text), "an ISO-8601 date such as 2026-03-01", format!("'{}'", text));
+    let date_text = text.get(..10).ok_or_else(invalid)?;
+    let date = parse_date(date_text).map_err(|e| SyntaxError { column: column(date_text), ..e })?;
+    let rest = &text[10..];
+    if rest.is_empty() {
+        return Ok(Moment::Date(date));
+    }
+
+    let rest = rest.strip_prefix(['t', 'T', ' ']).ok_or_else(invalid)?.trim_start();
+    let time_end = rest.find(|c: char| !c.is_ascii_digit() && c != ':').unwrap_or(rest.len());
+    let (time, zone) = rest.split_at(time_end);
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .map_err(|_| SyntaxError::new(column(time), "a valid time (HH:MM or HH:MM:SS)", format!("'{}'", time)))?;
+    let offset = match zone.trim() {
+        "" => None,
+        zone => Some(parse_offset(zone).map_err(|e| relocate(e, input, zone))?),
+    };
+    Ok(Moment::DateTime(date.and_time(time), offset))
+}
+
+/// "z", "utc", "utc+5", "gmt-03:30", "+05:30", "-0800"; syntax errors count columns from
+/// the start of `input`
+pub fn parse_offset(input: &str) -> anyhow::Result<FixedOffset> {
+    let original = input.trim();
+    let text = original.to_lowercase();
+    let column = column_of(input, original).unwrap_or(1);
+    let invalid = || -> anyhow::Error {
+        SyntaxError::new(column, "a UTC offset such as utc+5 or +05:30", format!("'{}'", original)).into()
+    };
+    let rest = text.strip_prefix("utc").or_else(|| text.strip_prefix("gmt")).unwrap_or(&text).trim();
+    if rest.is_empty() || text == "z" {
+        return FixedOffset::east_opt(0).ok_or_else(invalid);
+    }
+
+    let (sign, digits) = match (rest.strip_prefix('+'), rest.strip_prefix('-')) {
+        (Some(digits), _) => (1, digits.trim()),
+        (_, Some(digits)) => (-1, digits.trim()),
+        _ => return Err(invalid()),
+    };
+    if !digits.chars().all(|c| c.is_ascii_digit() || c == ':') {
+        return Err(invalid());
+    }
+    // ASCII from here on, so byte positions are character positions
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if minutes >= 60 || hours * 60 + minutes > 14 * 60 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+impl fmt::Display for Moment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Moment::Today => write!(f, "today"),
+            Moment::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Moment::DateTime(local, offset) => {
+                let pattern = if local.second() == 0 { "%Y-%m-%dT%H:%M" } else { "%Y-%m-%dT%H:%M:%S" };
+                write!(f, "{}", local.format(pattern))?;
+                match offset {
+                    Some(offset) => write!(f, "{}", offset),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// Answer text plus the working shown as proof
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarOutcome {
+    pub result: String,
+    pub steps: Vec<String>,
+}
+
+/// Evaluate against `holidays`; the host clock is read only for `Moment::Today`
+pub fn evaluate(query: &CalendarQuery, holidays: &dyn HolidayCalendar) -> anyhow::Result<CalendarOutcome> {
+    let mut steps = Vec::new();
+
+    let result = match query {
+        CalendarQuery::Describe(moment) | CalendarQuery::Weekday(moment) => {
+            let moment = resolve_today(moment, &mut steps);
+            let date = date_of(&moment);
+            let week = date.iso_week();
+            steps.push(format!(
+                "{}: {}, day {} of {}, ISO week {}-W{:02}",
+                moment,
+                weekday_name(date.weekday()),
+                date.ordinal(),
+                date.year(),
+                week.year(),
+                week.week()
+            ));
+            match query {
+                CalendarQuery::Weekday(_) => weekday_name(date.weekday()).to_string(),
+                _ => moment.to_string(),
+            }
+        }
+        CalendarQuery::Shift { start, amount, unit } => {
+            let start = resolve_today(start, &mut steps);
+            let end = shift(&start, *amount, *unit, holidays)?;
+            let sign = if *amount < 0 { "-" } else { "+" };
+            steps.push(format!("{} {} {} = {}", start, sign, plural(amount.unsigned_abs() as i64, unit_name(*unit)), end));
+            if matches!(unit, CalendarUnit::Months | CalendarUnit::Years) && date_of(&end).day() != date_of(&start).day() {
+                steps.push(format!("day {} does not exist in that month, so the result is its last day", date_of(&start).day()));
+            }
+            steps.push(format!("{} is a {}", end, weekday_name(date_of(&end).weekday())));
+            end.to_string()
+        }
+        CalendarQuery::Difference { from, to, unit: CalendarUnit::BusinessDays } => {
+            let (from, to) = (date_of(&resolve_today(from, &mut steps)), date_of(&resolve_today(to, &mut steps)));
+            business_days_between(from, to, holidays, &mut steps)
+        }
+        CalendarQuery::Difference { from, to, unit } => {
+            let (from, to) = (resolve_today(from, &mut steps), resolve_today(to, &mut steps));
+            let seconds = seconds_between(&from, &to)?;
+            steps.push(format!("{} - {} = {}", to, from, format_duration(seconds)));
+            format_difference(seconds, *unit)
+        }
+        CalendarQuery::Convert { moment, offset } => match resolve_today(moment, &mut steps) {
+            Moment::DateTime(local, Some(source)) => {
+                let instant = source
+                    .from_local_datetime(&local)
+                    .single()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid local time {}", local))?;
+                let converted = instant.with_timezone(offset).naive_local();
+                steps.push(format!("as UTC: {}", Moment::DateTime(instant.naive_utc(), FixedOffset::east_opt(0))));
+                steps.push(format!("shift by {} to reach UTC{}", offset_delta(source, *offset), offset));
+                Moment::DateTime(converted, Some(*offset)).to_string()
+            }
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Converting between offsets needs a time with a UTC offset, such as 2026-03-01T10:00+05:30 (got {})",
+                    other
+                ))
+            }
+        },
+    };
+    Ok(CalendarOutcome { result, steps })
+}
+
+fn resolve_today(moment: &Moment, steps: &mut Vec<String>) -> Moment {
+    match moment {
+        Moment::Today => {
+            let today = chrono::Local::now().date_naive();
+            steps.push(format!("today is {} (host clock)", today.format("%Y-%m-%d")));
+            Moment::Date(today)
+        }
+        other => *other,
+    }
+}
+
+fn date_of(moment: &Moment) -> NaiveDate {
+    match moment {
+        Moment::Date(date) => *date,
+        Moment::DateTime(local, _) => local.date(),
+        // Resolved before use; the epoch is never shown
+        Moment::Today => NaiveDate::default(),
+    }
+}
+
+fn shift(start: &Moment, amount: i64, unit: CalendarUnit, holidays: &dyn HolidayCalendar) -> anyhow::Result<Moment> {
+    let overflow = || anyhow::anyhow!("Date out of range shifting {} by {} {}", start, amount, unit_name(unit));
+    let fixed = |seconds: i64| -> anyhow::Result<Moment> {
+        let (local, offset) = match start {
+            Moment::DateTime(local, offset) => (*local, *offset),
+            other => (date_of(other).and_time(NaiveTime::MIN), None),
+        };
+        let delta = Duration::try_seconds(amount.checked_mul(seconds).ok_or_else(overflow)?).ok_or_else(overflow)?;
+        let local = local.checked_add_signed(delta).ok_or_else(overflow)?;
+        Ok(match (start, seconds % 86_400) {
+            (Moment::Date(_), 0) => Moment::Date(local.date()),
+            _ => Moment::DateTime(local, offset),
+        })
+    };
+    match unit {
+        CalendarUnit::Seconds => fixed(1),
+        CalendarUnit::Minutes => fixed(60),
+        CalendarUnit::Hours | CalendarUnit::Duration => fixed(3600),
+        CalendarUnit::Days => fixed(86_400),
+        CalendarUnit::Weeks => fixed(7 * 86_400),
+        CalendarUnit::Months | CalendarUnit::Years => {
+            let months = if unit == CalendarUnit::Years { amount.checked_mul(12).ok_or_else(overflow)? } else { amount };
+            let count = Months::new(u32::try_from(months.unsigned_abs()).map_err(|_| overflow())?);
+            let apply = |date: NaiveDate| {
+                if months < 0 { date.checked_sub_months(count) } else { date.checked_add_months(count) }
+            };
+            Ok(match start {
+                Moment::DateTime(local, offset) => {
+                    Moment::DateTime(apply(local.date()).ok_or_else(overflow)?.and_time(local.time()), *offset)
+                }
+                other => Moment::Date(apply(date_of(other)).ok_or_else(overflow)?),
+            })
+        }
+        CalendarUnit::BusinessDays => {
+            let step = if amount < 0 { -1 } else { 1 };
+            let mut date = date_of(start);
+            let mut remaining = amount.unsigned_abs();
+            while remaining > 0 {
+                date = date.checked_add_signed(Duration::days(step)).ok_or_else(overflow)?;
+                if is_business_day(date, holidays) {
+                    remaining -= 1;
+                }
+            }
+            Ok(match start {
+                Moment::DateTime(local, offset) => Moment::DateTime(date.and_time(local.time()), *offset),
+                _ => Moment::Date(date),
+            })
+        }
+    }
+}
+
+fn is_business_day(date: NaiveDate, holidays: &dyn HolidayCalendar) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !holidays.is_holiday(date)
+}
+
+/// Business days in [from, to): the start date counts, the end date does not
+fn business_days_between(
+    from: NaiveDate,
+    to: NaiveDate,
+    holidays: &dyn HolidayCalendar,
+    steps: &mut Vec<String>,
+) -> String {
+    let (start, end, sign) = if from <= to { (from, to, 1) } else { (to, from, -1) };
+    let (mut count, mut weekend, mut skipped) = (0i64, 0i64, Vec::new());
+    for date in start.iter_days().take_while(|d| *d < end) {
+        if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            weekend += 1;
+        } else if holidays.is_holiday(date) {
+            skipped.push(date.format("%Y-%m-%d").to_string());
+        } else {
+            count += 1;
+        }
+    }
+    steps.push(format!(
+        "counting from {} up to but not including {}: {}",
+        start.format("%Y-%m-%d"),
+        end.format("%Y-%m-%d"),
+        plural((end - start).num_days(), "calendar day")
+    ));
+    steps.push(format!("minus {}", plural(weekend, "weekend day")));
+    if !skipped.is_empty() {
+        steps.push(format!("minus {}: {}", plural(skipped.len() as i64, "holiday"), skipped.join(", ")));
+    }
+    plural(sign * count, "business day")
+}
+
+fn seconds_between(from: &Moment, to: &Moment) -> anyhow::Result<i64> {
+    let instant = |moment: &Moment| match moment {
+        Moment::DateTime(local, Some(offset)) => (*local - *offset, true),
+        Moment::DateTime(local, None) => (*local, false),
+        other => (date_of(other).and_time(NaiveTime::MIN), false),
+    };
+    let ((a, a_offset), (b, b_offset)) = (instant(from), instant(to));
+    if a_offset != b_offset && [from, to].iter().all(|m| matches!(m, Moment::DateTime(..))) {
+        return Err(anyhow::anyhow!("Give both times a UTC offset, or neither, to compare {} and {}", from, to));
+    }
+    Ok((b - a).num_seconds())
+}
+
+fn format_difference(seconds: i64, unit: CalendarUnit) -> String {
+    let per_unit = match unit {
+        CalendarUnit::Seconds => 1,
+        CalendarUnit::Minutes => 60,
+        CalendarUnit::Hours => 3600,
+        CalendarUnit::Days => 86_400,
+        CalendarUnit::Weeks => 7 * 86_400,
+        _ => return format_duration(seconds),
+    };
+    if seconds % per_unit == 0 {
+        return plural(seconds / per_unit, unit_name(unit));
+    }
+    if unit == CalendarUnit::Weeks && seconds % 86_400 == 0 {
+        let days = seconds / 86_400;
+        return format!("{} {}", plural(days / 7, "week"), plural(days % 7, "day"));
+    }
+    format_duration(seconds)
+}
+
+/// "1 day 2 hours 30 minutes"; zero components are left out
+fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let total = seconds.unsigned_abs() as i64;
+    let components = [
+        (total / 86_400, "day"),
+        (total % 86_400 / 3600, "hour"),
+        (total % 3600 / 60, "minute"),
+        (total % 60, "second"),
+    ];
+    let parts: Vec<String> = components
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| plural(*count, name))
+        .collect();
+    if parts.is_empty() {
+        return "0 seconds".to_string();
+    }
+    format!("{}{}", sign, parts.join(" "))
+}
+
+fn offset_delta(from: FixedOffset, to: FixedOffset) -> String {
+    let minutes = (to.local_minus_utc() - from.local_minus_utc()) / 60;
+    let sign = if minutes < 0 { "-" } else { "+" };
+    format!("{}{}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+fn plural(count: i64, name: &str) -> String {
+    if count.abs() == 1 {
+        format!("{} {}", count, name)
+    } else {
+        format!("{} {}s", count, name)
+    }
+}
+
+fn unit_name(unit: CalendarUnit) -> &'static str {
+    match unit {
+        CalendarUnit::Seconds => "second",
+        CalendarUnit::Minutes => "minute",
+        CalendarUnit::Hours | CalendarUnit::Duration => "hour",
+        CalendarUnit::Days => "day",
+        CalendarUnit::Weeks => "week",
+        CalendarUnit::Months => "month",
+        CalendarUnit::Years => "year",
+        CalendarUnit::BusinessDays => "business day",
+    }
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
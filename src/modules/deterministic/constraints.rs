@@ -0,0 +1,916 @@
+// Finite-domain constraints: integer domains, propagation and labeling with optional optimization
+use super::expr::{parse_expression, BinOp, Expr, Parser};
+use super::lexer::tokenize;
+use super::{split_keyword, strip_keyword};
+use num_traits::ToPrimitive;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Domains this small are labeled value by value; larger ones are split in half
+const ENUMERATE_LIMIT: u64 = 64;
+
+/// Largest domain a non-linear constraint filters value by value once it has one free variable
+const FILTER_LIMIT: u64 = 4096;
+
+/// Search limits used unless a query asks for less
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FdConfig {
+    /// Wall-clock budget for propagation and search
+    pub time_limit: Duration,
+    /// Most solutions listed for "all solutions"
+    pub max_solutions: usize,
+}
+
+impl Default for FdConfig {
+    fn default() -> Self {
+        FdConfig { time_limit: Duration::from_secs(2), max_solutions: 100 }
+    }
+}
+
+/// Integers as sorted, disjoint, non-adjacent closed intervals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Domain(Vec<(i64, i64)>);
+
+impl Domain {
+    pub fn range(lo: i64, hi: i64) -> Self {
+        Domain(if lo <= hi { vec![(lo, hi)] } else { Vec::new() })
+    }
+
+    pub fn from_values(values: &[i64]) -> Self {
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let mut intervals: Vec<(i64, i64)> = Vec::new();
+        for value in sorted {
+            match intervals.last_mut() {
+                Some((_, hi)) if *hi + 1 == value => *hi = value,
+                _ => intervals.push((value, value)),
+            }
+        }
+        Domain(intervals)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn min(&self) -> i64 {
+        self.0[0].0
+    }
+
+    fn max(&self) -> i64 {
+        self.0[self.0.len() - 1].1
+    }
+
+    fn size(&self) -> u64 {
+        self.0.iter().map(|&(lo, hi)| (hi as i128 - lo as i128 + 1) as u64).fold(0, u64::saturating_add)
+    }
+
+    fn fixed(&self) -> Option<i64> {
+        match self.0.as_slice() {
+            [(lo, hi)] if lo == hi => Some(*lo),
+            _ => None,
+        }
+    }
+
+    fn values(&self) -> impl Iterator<Item = i64> + '_ {
+        self.0.iter().flat_map(|&(lo, hi)| lo..=hi)
+    }
+
+    /// Keep only `lo..=hi`; true when anything was removed
+    fn restrict(&mut self, lo: i64, hi: i64) -> bool {
+        let kept: Vec<(i64, i64)> =
+            self.0.iter().map(|&(a, b)| (a.max(lo), b.min(hi))).filter(|(a, b)| a <= b).collect();
+        let changed = kept != self.0;
+        self.0 = kept;
+        changed
+    }
+
+    fn remove(&mut self, value: i64) -> bool {
+        let Some(i) = self.0.iter().position(|&(lo, hi)| lo <= value && value <= hi) else {
+            return false;
+        };
+        let (lo, hi) = self.0[i];
+        let mut parts = Vec::new();
+        if lo < value {
+            parts.push((lo, value - 1));
+        }
+        if value < hi {
+            parts.push((value + 1, hi));
+        }
+        self.0.splice(i..=i, parts);
+        true
+    }
+}
+
+impl fmt::Display for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|&(lo, hi)| if lo == hi { lo.to_string() } else { format!("{}..{}", lo, hi) })
+            .collect();
+        match parts.as_slice() {
+            [single] if self.0[0].0 != self.0[0].1 => write!(f, "{}", single),
+            _ => write!(f, "[{}]", parts.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Comparison symbols, each listed before its one-character prefix
+pub const COMPARISONS: &[(&str, Comparison)] = &[
+    ("<=", Comparison::Le),
+    (">=", Comparison::Ge),
+    ("!=", Comparison::Ne),
+    ("<", Comparison::Lt),
+    (">", Comparison::Gt),
+    ("=", Comparison::Eq),
+];
+
+impl Comparison {
+    pub fn symbol(&self) -> &'static str {
+        COMPARISONS.iter().find(|(_, c)| c == self).map_or("=", |(symbol, _)| symbol)
+    }
+
+    /// Whether some values in `lhs` and `rhs` (closed intervals) can satisfy the comparison
+    fn possible(&self, (a, b): (i128, i128), (c, d): (i128, i128)) -> bool {
+        match self {
+            Comparison::Eq => a <= d && c <= b,
+            Comparison::Ne => !(a == b && c == d && a == c),
+            Comparison::Lt => a < d,
+            Comparison::Le => a <= d,
+            Comparison::Gt => b > c,
+            Comparison::Ge => b >= c,
+        }
+    }
+
+    fn holds(&self, lhs: i64, rhs: i64) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    Compare(Expr, Comparison, Expr),
+    /// Pairwise distinct values; an empty list means every declared variable
+    AllDifferent(Vec<String>),
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::Compare(lhs, comparison, rhs) => write!(f, "{} {} {}", lhs, comparison.symbol(), rhs),
+            Constraint::AllDifferent(names) if names.is_empty() => write!(f, "all_different(all variables)"),
+            Constraint::AllDifferent(names) => write!(f, "all_different({})", names.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Objective {
+    Minimize(Expr),
+    Maximize(Expr),
+}
+
+/// "assign A, B, C in 1..9 all different with A + B = C * 2"
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdProblem {
+    pub variables: Vec<(String, Domain)>,
+    pub constraints: Vec<Constraint>,
+    pub objective: Option<Objective>,
+    /// Solutions to list: `None` for the first one, `Some(usize::MAX)` for "all solutions"
+    pub solutions: Option<usize>,
+    /// Report how many solutions exist instead of listing them
+    pub count: bool,
+    /// Time limit asked for by the query, e.g. "timeout 500ms"
+    pub time_limit: Option<Duration>,
+}
+
+/// Optional verbs before the declarations
+const VERBS: &[&str] = &["assign", "find", "solve", "label", "constrain", "schedule", "choose"];
+
+/// Phrases read as commas between items
+const SEPARATORS: &[&str] = &["with", "where", "such that", "subject to", "and"];
+
+/// Phrases that begin an item of their own
+const ALL_DIFFERENT: &[&str] = &["all different", "all distinct", "all_different", "alldifferent", "distinct"];
+const ITEM_STARTS: &[&str] = &[
+    "all different", "all distinct", "all_different", "alldifferent", "minimize", "maximize", "minimise",
+    "maximise", "all solutions", "count solutions", "first", "timeout", "within",
+];
+
+/// A problem when the query declares at least one domain such as "X in 1..9" or "X in [2, 4, 8]"
+pub fn parse_command(query: &str) -> Option<anyhow::Result<FdProblem>> {
+    let mut text = query.trim();
+    if let Some(rest) = VERBS.iter().find_map(|verb| strip_keyword(text, verb)) {
+        text = rest;
+    }
+    let items = split_items(&mark_items(text));
+    if !items.iter().any(|item| domain_item(item).is_some()) {
+        return None;
+    }
+    Some(parse_items(&items))
+}
+
+/// Rewrite separators as commas and start a new item at each phrase in `ITEM_STARTS`
+fn mark_items(text: &str) -> String {
+    let lower = text.to_ascii_lowercase();
+    let mut marked = String::with_capacity(text.len() + 8);
+    let mut skip_to = 0;
+    for (i, c) in text.char_indices() {
+        if i < skip_to {
+            continue;
+        }
+        let word_start = i == 0 || text[..i].ends_with(|p: char| p.is_whitespace() || p == ',');
+        let phrase_at = |phrase: &str| {
+            lower[i..].starts_with(phrase)
+                && lower[i + phrase.len()..].chars().next().is_none_or(|n| n.is_whitespace() || "(,[".contains(n))
+        };
+        if word_start {
+            if let Some(separator) = SEPARATORS.iter().find(|s| phrase_at(s)) {
+                marked.push(',');
+                skip_to = i + separator.len();
+                continue;
+            }
+            if ITEM_STARTS.iter().any(|s| phrase_at(s)) {
+                marked.push(',');
+            }
+        }
+        marked.push(c);
+    }
+    marked
+}
+
+/// Split on commas outside brackets and parentheses
+fn split_items(text: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut depth = 0i32;
+    for c in text.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        items.last_mut().expect("at least one item").push(c);
+    }
+    items.into_iter().map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+/// Names and domain of "A B C in 1..9" or "X in [1, 3, 5]"
+fn domain_item(item: &str) -> Option<(Vec<String>, anyhow::Result<Domain>)> {
+    let (names, domain) = split_keyword(item, "in")?;
+    let names: Vec<String> = names.split_whitespace().map(str::to_string).collect();
+    if names.is_empty() || !names.iter().all(|name| is_identifier(name)) {
+        return None;
+    }
+    let domain = if let Some(list) = domain.strip_prefix('[').and_then(|d| d.strip_suffix(']')) {
+        list.split(',').map(|v| parse_integer(v.trim())).collect::<anyhow::Result<Vec<_>>>().map(|v| Domain::from_values(&v))
+    } else {
+        let (lo, hi) = domain.split_once("..")?;
+        parse_integer(lo.trim()).and_then(|lo| Ok(Domain::range(lo, parse_integer(hi.trim())?)))
+    };
+    Some((names, domain))
+}
+
+fn parse_integer(text: &str) -> anyhow::Result<i64> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.parse().map_err(|_| anyhow::anyhow!("Domain bounds must be integers, found '{}'", text))
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_') && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_items(items: &[String]) -> anyhow::Result<FdProblem> {
+    let mut problem = FdProblem {
+        variables: Vec::new(),
+        constraints: Vec::new(),
+        objective: None,
+        solutions: None,
+        count: false,
+        time_limit: None,
+    };
+    // Names listed before the item that gives their domain, as in "A, B, C in 1..9"
+    let mut pending: Vec<String> = Vec::new();
+    for item in items {
+        let lower = item.to_ascii_lowercase();
+        if let (Some(name), false) = (pending.first(), is_identifier(item) || domain_item(item).is_some()) {
+            return Err(anyhow::anyhow!("{} needs a domain, e.g. '{} in 1..9'", name, name));
+        }
+        if let Some((names, domain)) = domain_item(item) {
+            let domain = domain?;
+            for name in pending.drain(..).chain(names) {
+                if problem.variables.iter().any(|(declared, _)| *declared == name) {
+                    return Err(anyhow::anyhow!("{} is declared twice", name));
+                }
+                problem.variables.push((name, domain.clone()));
+            }
+        } else if let Some(rest) = ALL_DIFFERENT.iter().find_map(|phrase| strip_keyword(item, phrase)) {
+            let names = rest.trim_start_matches(['(', '[']).trim_end_matches([')', ']']);
+            let names = names.split(|c: char| c == ',' || c.is_whitespace()).filter(|n| !n.is_empty());
+            problem.constraints.push(Constraint::AllDifferent(names.map(str::to_string).collect()));
+        } else if let Some(rest) = strip_keyword(item, "minimize").or_else(|| strip_keyword(item, "minimise")) {
+            problem.objective = Some(Objective::Minimize(parse_expression(rest)?));
+        } else if let Some(rest) = strip_keyword(item, "maximize").or_else(|| strip_keyword(item, "maximise")) {
+            problem.objective = Some(Objective::Maximize(parse_expression(rest)?));
+        } else if lower == "all solutions" {
+            problem.solutions = Some(usize::MAX);
+        } else if lower == "count solutions" {
+            problem.count = true;
+        } else if let Some(count) = solution_count(strip_keyword(&lower, "first").unwrap_or(&lower)) {
+            problem.solutions = Some(count);
+        } else if let Some(rest) = strip_keyword(&lower, "timeout").or_else(|| strip_keyword(&lower, "within")) {
+            problem.time_limit = Some(parse_duration(rest)?);
+        } else if is_identifier(item) {
+            pending.push(item.clone());
+        } else {
+            problem.constraints.extend(parse_comparisons(item)?);
+        }
+    }
+    if let Some(name) = pending.first() {
+        return Err(anyhow::anyhow!("{} needs a domain, e.g. '{} in 1..9'", name, name));
+    }
+    Ok(problem)
+}
+
+/// "3 solutions", "1 solution"
+fn solution_count(text: &str) -> Option<usize> {
+    let count = text.strip_suffix("solutions").or_else(|| text.strip_suffix("solution"))?;
+    count.trim().parse().ok().filter(|&n| n > 0)
+}
+
+/// "2s", "500 ms", "3 seconds"
+fn parse_duration(text: &str) -> anyhow::Result<Duration> {
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (amount, unit) = text.split_at(split);
+    let amount: f64 = amount.parse().map_err(|_| anyhow::anyhow!("Expected a time limit such as '2s', found '{}'", text))?;
+    let seconds = match unit.trim() {
+        "ms" | "millisecond" | "milliseconds" => amount / 1000.0,
+        "" | "s" | "sec" | "secs" | "second" | "seconds" => amount,
+        other => return Err(anyhow::anyhow!("Unknown time unit '{}'", other)),
+    };
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// "A + B = C * 2", or a chain such as "0 <= S1 < S2"
+fn parse_comparisons(text: &str) -> anyhow::Result<Vec<Constraint>> {
+    let mut parser = Parser::new(tokenize(text)?);
+    let mut lhs = parser.parse_expr()?;
+    let mut constraints = Vec::new();
+    loop {
+        let Some(&(_, comparison)) = COMPARISONS.iter().find(|(symbol, _)| parser.eat_symbol(symbol)) else {
+            if constraints.is_empty() {
+                return Err(parser.error("a comparison such as '=', '!=' or '<='"));
+            }
+            parser.expect_end()?;
+            return Ok(constraints);
+        };
+        let rhs = parser.parse_expr()?;
+        constraints.push(Constraint::Compare(lhs, comparison, rhs.clone()));
+        lhs = rhs;
+    }
+}
+
+/// Solutions, or the optimum, with the domains, constraints and search statistics as steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdOutcome {
+    pub result: String,
+    pub steps: Vec<String>,
+}
+
+/// Propagate to a fixpoint at every node and label the variable with the smallest domain
+/// first; optimization keeps searching with the objective bounded by the best value so far
+pub fn solve(problem: &FdProblem, config: &FdConfig) -> anyhow::Result<FdOutcome> {
+    let index: HashMap<&str, usize> = problem.variables.iter().enumerate().map(|(i, (name, _))| (name.as_str(), i)).collect();
+    let mut propagators = Vec::new();
+    for constraint in &problem.constraints {
+        match constraint {
+            Constraint::Compare(lhs, comparison, rhs) => {
+                for expr in [lhs, rhs] {
+                    declared(expr, &index)?;
+                }
+                propagators.extend(compile_comparison(lhs, *comparison, rhs, &index));
+            }
+            Constraint::AllDifferent(names) if names.is_empty() => {
+                propagators.push(Propagator::AllDifferent((0..problem.variables.len()).collect()));
+            }
+            Constraint::AllDifferent(names) => {
+                let vars = names
+                    .iter()
+                    .map(|name| index.get(name.as_str()).copied().ok_or_else(|| undeclared(name)))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                propagators.push(Propagator::AllDifferent(vars));
+            }
+        }
+    }
+    if let Some(Objective::Minimize(expr) | Objective::Maximize(expr)) = &problem.objective {
+        declared(expr, &index)?;
+    }
+
+    let time_limit = problem.time_limit.map_or(config.time_limit, |limit| limit.min(config.time_limit));
+    let limit = problem.solutions.unwrap_or(1).min(config.max_solutions);
+    let mut search = Search {
+        propagators,
+        bound: Vec::new(),
+        index: &index,
+        objective: problem.objective.as_ref(),
+        deadline: Instant::now() + time_limit,
+        limit,
+        count_only: problem.count,
+        nodes: 0,
+        failures: 0,
+        found: 0,
+        solutions: Vec::new(),
+        best: None,
+        timed_out: false,
+    };
+    let domains: Vec<Domain> = problem.variables.iter().map(|(_, domain)| domain.clone()).collect();
+    search.run(domains);
+
+    let mut steps = describe_domains(&problem.variables);
+    steps.extend(problem.constraints.iter().map(|c| format!("constraint: {}", c)));
+    steps.push(format!(
+        "search: {}, {}",
+        super::prolog::plural(search.nodes, "node"),
+        super::prolog::plural(search.failures, "failure")
+    ));
+    let names: Vec<&str> = problem.variables.iter().map(|(name, _)| name.as_str()).collect();
+    let show = |values: &Vec<i64>| -> String {
+        names.iter().zip(values).map(|(name, value)| format!("{} = {}", name, value)).collect::<Vec<_>>().join(", ")
+    };
+    let limit_note = match time_limit.as_millis() {
+        millis @ 0..=999 => format!("time limit of {}ms reached", millis),
+        _ => format!("time limit of {:.1}s reached", time_limit.as_secs_f64()),
+    };
+
+    let result = if let Some(objective) = &problem.objective {
+        let (word, expr) = match objective {
+            Objective::Minimize(expr) => ("minimum", expr),
+            Objective::Maximize(expr) => ("maximum", expr),
+        };
+        match (search.solutions.first(), search.best) {
+            (Some(values), Some(best)) if search.timed_out => {
+                steps.push(format!("{}; best {} = {} after {}", limit_note, expr, best, super::prolog::plural(search.found, "improvement")));
+                format!("{} (best {} = {} found, not proven optimal)", show(values), expr, best)
+            }
+            (Some(values), Some(best)) => {
+                steps.push(format!("optimal after {}", super::prolog::plural(search.found, "improving solution")));
+                format!("{} ({} {} = {})", show(values), word, expr, best)
+            }
+            _ if search.timed_out => format!("no solution found ({})", limit_note),
+            _ => "no solution".to_string(),
+        }
+    } else if problem.count {
+        match search.timed_out {
+            true => format!("at least {} ({})", super::prolog::plural(search.found, "solution"), limit_note),
+            false => super::prolog::plural(search.found, "solution"),
+        }
+    } else {
+        if search.timed_out {
+            steps.push(limit_note.clone());
+        }
+        match search.solutions.len() {
+            0 if search.timed_out => format!("no solution found ({})", limit_note),
+            0 => "no solution".to_string(),
+            listed => {
+                let mut text = search.solutions.iter().map(show).collect::<Vec<_>>().join(" ; ");
+                if listed == limit && problem.solutions.is_some_and(|asked| asked > limit) {
+                    text.push_str(&format!(" ; ... (first {} solutions)", limit));
+                }
+                text
+            }
+        }
+    };
+    Ok(FdOutcome { result, steps })
+}
+
+/// "domains: A, B, C in 1..9", grouping consecutive variables with the same domain
+fn describe_domains(variables: &[(String, Domain)]) -> Vec<String> {
+    let mut groups: Vec<(Vec<&str>, &Domain)> = Vec::new();
+    for (name, domain) in variables {
+        match groups.last_mut() {
+            Some((names, last)) if *last == domain => names.push(name),
+            _ => groups.push((vec![name], domain)),
+        }
+    }
+    let groups: Vec<String> = groups.iter().map(|(names, domain)| format!("{} in {}", names.join(", "), domain)).collect();
+    vec![format!("domains: {}", groups.join("; "))]
+}
+
+fn declared(expr: &Expr, index: &HashMap<&str, usize>) -> anyhow::Result<()> {
+    match expr.free_variables().into_iter().find(|name| !index.contains_key(name.as_str())) {
+        Some(name) => Err(undeclared(&name)),
+        None => Ok(()),
+    }
+}
+
+fn undeclared(name: &str) -> anyhow::Error {
+    anyhow::anyhow!("{} has no domain; declare it, e.g. '{} in 1..9'", name, name)
+}
+
+/// Sum of coefficient × variable, plus a constant
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Linear {
+    terms: BTreeMap<usize, i64>,
+    constant: i64,
+}
+
+impl Linear {
+    fn constant(value: i64) -> Self {
+        Linear { terms: BTreeMap::new(), constant: value }
+    }
+
+    fn scale(&self, factor: i64) -> Option<Linear> {
+        let mut terms = BTreeMap::new();
+        for (&var, &coefficient) in &self.terms {
+            terms.insert(var, coefficient.checked_mul(factor)?);
+        }
+        terms.retain(|_, c| *c != 0);
+        Some(Linear { terms, constant: self.constant.checked_mul(factor)? })
+    }
+
+    fn add(&self, other: &Linear) -> Option<Linear> {
+        let mut sum = self.clone();
+        for (&var, &coefficient) in &other.terms {
+            let entry = sum.terms.entry(var).or_insert(0);
+            *entry = entry.checked_add(coefficient)?;
+        }
+        sum.terms.retain(|_, c| *c != 0);
+        sum.constant = sum.constant.checked_add(other.constant)?;
+        Some(sum)
+    }
+
+    /// `expr` as a linear form over integers, or `None` for products of variables, division and calls
+    fn of(expr: &Expr, index: &HashMap<&str, usize>) -> Option<Linear> {
+        match expr {
+            Expr::Num(value) if value.is_integer() => Some(Linear::constant(value.to_integer().to_i64()?)),
+            Expr::Var(name) => {
+                let var = *index.get(name.as_str())?;
+                Some(Linear { terms: BTreeMap::from([(var, 1)]), constant: 0 })
+            }
+            Expr::Neg(inner) => Linear::of(inner, index)?.scale(-1),
+            Expr::Binary(BinOp::Add, lhs, rhs) => Linear::of(lhs, index)?.add(&Linear::of(rhs, index)?),
+            Expr::Binary(BinOp::Sub, lhs, rhs) => Linear::of(lhs, index)?.add(&Linear::of(rhs, index)?.scale(-1)?),
+            Expr::Binary(BinOp::Mul, lhs, rhs) => {
+                let (lhs, rhs) = (Linear::of(lhs, index)?, Linear::of(rhs, index)?);
+                match (lhs.terms.is_empty(), rhs.terms.is_empty()) {
+                    (true, _) => rhs.scale(lhs.constant),
+                    (_, true) => lhs.scale(rhs.constant),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A constraint in the form propagation works on
+#[derive(Debug, Clone)]
+enum Propagator {
+    /// Σ aᵢxᵢ + c ≤ 0, narrowed by bounds reasoning
+    LessOrEqual(Vec<(usize, i64)>, i64),
+    /// Σ aᵢxᵢ + c ≠ 0, which removes one value once a single variable is free
+    NotEqual(Vec<(usize, i64)>, i64),
+    AllDifferent(Vec<usize>),
+    /// Any other comparison: checked when its variables are fixed, and used to filter the
+    /// last free one; division must be exact and undefined results fail
+    Check(Expr, Comparison, Expr, Vec<usize>),
+}
+
+fn compile_comparison(lhs: &Expr, comparison: Comparison, rhs: &Expr, index: &HashMap<&str, usize>) -> Vec<Propagator> {
+    let difference = Linear::of(lhs, index).zip(Linear::of(rhs, index)).and_then(|(l, r)| l.add(&r.scale(-1)?));
+    let linear = difference.and_then(|d| {
+        let negated = d.scale(-1)?;
+        let one = Linear::constant(1);
+        let forms = match comparison {
+            Comparison::Eq => vec![(d, false), (negated, false)],
+            Comparison::Ne => vec![(d, true)],
+            Comparison::Le => vec![(d, false)],
+            Comparison::Lt => vec![(d.add(&one)?, false)],
+            Comparison::Ge => vec![(negated, false)],
+            Comparison::Gt => vec![(negated.add(&one)?, false)],
+        };
+        Some(forms)
+    });
+    match linear {
+        Some(forms) => forms
+            .into_iter()
+            .map(|(form, not_equal)| {
+                let terms: Vec<(usize, i64)> = form.terms.into_iter().collect();
+                match not_equal {
+                    true => Propagator::NotEqual(terms, form.constant),
+                    false => Propagator::LessOrEqual(terms, form.constant),
+                }
+            })
+            .collect(),
+        None => {
+            let mut vars: Vec<usize> = [lhs, rhs]
+                .iter()
+                .flat_map(|e| e.free_variables())
+                .filter_map(|name| index.get(name.as_str()).copied())
+                .collect();
+            vars.sort_unstable();
+            vars.dedup();
+            vec![Propagator::Check(lhs.clone(), comparison, rhs.clone(), vars)]
+        }
+    }
+}
+
+/// Marker for a constraint that no remaining assignment can satisfy
+struct Failure;
+
+impl Propagator {
+    /// Narrow `domains`; true when anything changed
+    fn propagate(&self, domains: &mut [Domain], index: &HashMap<&str, usize>) -> Result<bool, Failure> {
+        match self {
+            Propagator::LessOrEqual(terms, constant) => {
+                let bounds: Vec<i128> = terms
+                    .iter()
+                    .map(|&(var, a)| {
+                        let a = a as i128;
+                        if a > 0 { a * domains[var].min() as i128 } else { a * domains[var].max() as i128 }
+                    })
+                    .collect();
+                let lowest = *constant as i128 + bounds.iter().sum::<i128>();
+                if lowest > 0 {
+                    return Err(Failure);
+                }
+                let mut changed = false;
+                for (&(var, a), low) in terms.iter().zip(&bounds) {
+                    // a·x ≤ -(lowest - low), the room the other terms leave
+                    let room = low - lowest;
+                    let (lo, hi) = match a > 0 {
+                        true => (i64::MIN, clamp(floor_div(room, a as i128))),
+                        false => (clamp(ceil_div(room, a as i128)), i64::MAX),
+                    };
+                    changed |= domains[var].restrict(lo, hi);
+                    if domains[var].is_empty() {
+                        return Err(Failure);
+                    }
+                }
+                Ok(changed)
+            }
+            Propagator::NotEqual(terms, constant) => {
+                let mut free = terms.iter().filter(|(var, _)| domains[*var].fixed().is_none());
+                let sum: i128 = *constant as i128
+                    + terms.iter().filter_map(|&(var, a)| domains[var].fixed().map(|v| a as i128 * v as i128)).sum::<i128>();
+                match (free.next(), free.next()) {
+                    (None, _) if sum == 0 => Err(Failure),
+                    (Some(&(var, a)), None) if sum % a as i128 == 0 => {
+                        let Ok(value) = i64::try_from(-sum / a as i128) else { return Ok(false) };
+                        let changed = domains[var].remove(value);
+                        if domains[var].is_empty() {
+                            return Err(Failure);
+                        }
+                        Ok(changed)
+                    }
+                    _ => Ok(false),
+                }
+            }
+            Propagator::AllDifferent(vars) => {
+                let mut changed = false;
+                for &i in vars {
+                    let Some(value) = domains[i].fixed() else { continue };
+                    for &j in vars.iter().filter(|&&j| j != i) {
+                        if domains[j].fixed() == Some(value) {
+                            return Err(Failure);
+                        }
+                        changed |= domains[j].remove(value);
+                    }
+                }
+                // Pigeonhole: the variables need at least as many distinct values
+                let mut intervals: Vec<(i64, i64)> = vars.iter().flat_map(|&v| domains[v].0.iter().copied()).collect();
+                intervals.sort_unstable();
+                let mut available = 0u64;
+                let mut covered = i64::MIN;
+                for (lo, hi) in intervals {
+                    let lo = lo.max(covered.saturating_add(1));
+                    if lo <= hi {
+                        available = available.saturating_add((hi as i128 - lo as i128 + 1) as u64);
+                        covered = hi;
+                    }
+                }
+                if available < vars.len() as u64 {
+                    return Err(Failure);
+                }
+                Ok(changed)
+            }
+            Propagator::Check(lhs, comparison, rhs, vars) => {
+                let free: Vec<usize> = vars.iter().copied().filter(|&v| domains[v].fixed().is_none()).collect();
+                let holds = |domains: &[Domain]| {
+                    let lookup = |name: &str| index.get(name).and_then(|&v| domains[v].fixed());
+                    evaluate(lhs, &lookup).zip(evaluate(rhs, &lookup)).is_some_and(|(l, r)| comparison.holds(l, r))
+                };
+                match free.as_slice() {
+                    [] if holds(domains) => Ok(false),
+                    [] => Err(Failure),
+                    [var] if domains[*var].size() <= FILTER_LIMIT => {
+                        let mut trial = domains.to_vec();
+                        let kept: Vec<i64> = domains[*var]
+                            .values()
+                            .filter(|&value| {
+                                trial[*var] = Domain::range(value, value);
+                                holds(&trial)
+                            })
+                            .collect();
+                        let kept = Domain::from_values(&kept);
+                        if kept.is_empty() {
+                            return Err(Failure);
+                        }
+                        let changed = kept != domains[*var];
+                        domains[*var] = kept;
+                        Ok(changed)
+                    }
+                    _ => {
+                        let bounds = interval(lhs, domains, index).zip(interval(rhs, domains, index));
+                        match bounds {
+                            Some((l, r)) if !comparison.possible(l, r) => Err(Failure),
+                            _ => Ok(false),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Bounds of `expr` over the current domains, for sums, differences and products
+fn interval(expr: &Expr, domains: &[Domain], index: &HashMap<&str, usize>) -> Option<(i128, i128)> {
+    match expr {
+        Expr::Num(value) if value.is_integer() => {
+            let value = value.to_integer().to_i64()? as i128;
+            Some((value, value))
+        }
+        Expr::Var(name) => {
+            let domain = &domains[*index.get(name.as_str())?];
+            Some((domain.min() as i128, domain.max() as i128))
+        }
+        Expr::Neg(inner) => interval(inner, domains, index).map(|(lo, hi)| (-hi, -lo)),
+        Expr::Binary(op @ (BinOp::Add | BinOp::Sub | BinOp::Mul), lhs, rhs) => {
+            let ((a, b), (c, d)) = (interval(lhs, domains, index)?, interval(rhs, domains, index)?);
+            match op {
+                BinOp::Add => Some((a.checked_add(c)?, b.checked_add(d)?)),
+                BinOp::Sub => Some((a.checked_sub(d)?, b.checked_sub(c)?)),
+                _ => {
+                    let products = [a.checked_mul(c)?, a.checked_mul(d)?, b.checked_mul(c)?, b.checked_mul(d)?];
+                    Some((*products.iter().min()?, *products.iter().max()?))
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+fn floor_div(a: i128, b: i128) -> i128 {
+    let quotient = a / b;
+    if a % b != 0 && ((a < 0) != (b < 0)) { quotient - 1 } else { quotient }
+}
+
+fn ceil_div(a: i128, b: i128) -> i128 {
+    -floor_div(-a, b)
+}
+
+fn clamp(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Integer value of `expr`; `None` when a variable is unbound or an operation is undefined
+fn evaluate(expr: &Expr, lookup: &dyn Fn(&str) -> Option<i64>) -> Option<i64> {
+    match expr {
+        Expr::Num(value) if value.is_integer() => value.to_integer().to_i64(),
+        Expr::Num(_) => None,
+        Expr::Var(name) => lookup(name),
+        Expr::Neg(inner) => evaluate(inner, lookup)?.checked_neg(),
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (evaluate(lhs, lookup)?, evaluate(rhs, lookup)?);
+            match op {
+                BinOp::Add => a.checked_add(b),
+                BinOp::Sub => a.checked_sub(b),
+                BinOp::Mul => a.checked_mul(b),
+                BinOp::Div => (b != 0 && a % b == 0).then(|| a / b),
+                BinOp::Rem => (b != 0).then(|| a.rem_euclid(b)),
+                BinOp::Pow => a.checked_pow(u32::try_from(b).ok()?),
+            }
+        }
+        Expr::Call(name, args) => {
+            let args = args.iter().map(|a| evaluate(a, lookup)).collect::<Option<Vec<i64>>>()?;
+            match (name.as_str(), args.as_slice()) {
+                ("abs", [a]) => a.checked_abs(),
+                ("min", [_, ..]) => args.iter().min().copied(),
+                ("max", [_, ..]) => args.iter().max().copied(),
+                _ => None,
+            }
+        }
+    }
+}
+
+struct Search<'a> {
+    propagators: Vec<Propagator>,
+    /// Objective bound from the best solution so far
+    bound: Vec<Propagator>,
+    index: &'a HashMap<&'a str, usize>,
+    objective: Option<&'a Objective>,
+    deadline: Instant,
+    limit: usize,
+    count_only: bool,
+    nodes: usize,
+    failures: usize,
+    /// Solutions found, or improving solutions when optimizing
+    found: usize,
+    solutions: Vec<Vec<i64>>,
+    best: Option<i64>,
+    timed_out: bool,
+}
+
+impl Search<'_> {
+    /// Depth-first labeling; true once the search should stop
+    fn run(&mut self, mut domains: Vec<Domain>) -> bool {
+        if Instant::now() >= self.deadline {
+            self.timed_out = true;
+            return true;
+        }
+        self.nodes += 1;
+        if domains.iter().any(Domain::is_empty) || self.propagate(&mut domains).is_err() {
+            self.failures += 1;
+            return false;
+        }
+        let next = (0..domains.len()).filter(|&v| domains[v].fixed().is_none()).min_by_key(|&v| (domains[v].size(), v));
+        let Some(var) = next else {
+            return self.record(&domains);
+        };
+        let branches: Vec<(i64, i64)> = if domains[var].size() <= ENUMERATE_LIMIT {
+            domains[var].values().map(|value| (value, value)).collect()
+        } else {
+            let (lo, hi) = (domains[var].min(), domains[var].max());
+            let mid = clamp(lo as i128 + (hi as i128 - lo as i128) / 2);
+            vec![(lo, mid), (mid + 1, hi)]
+        };
+        for (lo, hi) in branches {
+            let mut child = domains.clone();
+            child[var].restrict(lo, hi);
+            if self.run(child) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn propagate(&self, domains: &mut [Domain]) -> Result<(), Failure> {
+        loop {
+            let mut changed = false;
+            for propagator in self.propagators.iter().chain(&self.bound) {
+                changed |= propagator.propagate(domains, self.index)?;
+            }
+            if !changed {
+                return Ok(());
+            }
+        }
+    }
+
+    fn record(&mut self, domains: &[Domain]) -> bool {
+        let values: Vec<i64> = domains.iter().map(Domain::min).collect();
+        let Some(objective) = self.objective else {
+            self.found += 1;
+            if !self.count_only {
+                self.solutions.push(values);
+            }
+            return !self.count_only && self.found >= self.limit;
+        };
+        let (expr, comparison) = match objective {
+            Objective::Minimize(expr) => (expr, Comparison::Lt),
+            Objective::Maximize(expr) => (expr, Comparison::Gt),
+        };
+        let lookup = |name: &str| self.index.get(name).map(|&v| values[v]);
+        let Some(value) = evaluate(expr, &lookup) else {
+            return false;
+        };
+        // Later solutions must beat this one
+        self.found += 1;
+        self.best = Some(value);
+        self.solutions = vec![values];
+        self.bound = compile_comparison(expr, comparison, &Expr::integer(value), self.index);
+        false
+    }
+}
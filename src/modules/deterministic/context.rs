@@ -0,0 +1,264 @@
+// Per-session variables and user-defined functions
+use super::expr::{is_named_constant, parse_expression, Evaluator, Expr, Parser, BUILTIN_FUNCTIONS};
+use super::lexer::{tokenize, TokenKind};
+use super::number::Number;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Function defined with "f(x) = x^2 + 1"
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserFunction {
+    pub params: Vec<String>,
+    pub body: Expr,
+}
+
+/// Bindings that persist between queries of one session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(into = "SessionFile", try_from = "SessionFile")]
+pub struct EvalContext {
+    variables: BTreeMap<String, Number>,
+    functions: BTreeMap<String, UserFunction>,
+}
+
+/// Session-management commands recognized before any evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextCommand {
+    /// "let r = 4"
+    Assign { name: String, expr: Expr },
+    /// "f(x) = x^2 + 1" or "let f(x) = x^2 + 1"
+    Define { name: String, params: Vec<String>, body: Expr },
+    /// "list bindings"
+    List,
+    /// "clear bindings" or "clear r"
+    Clear(Option<String>),
+}
+
+/// Recognize a session command; `None` leaves the query to other handlers
+pub fn parse_command(query: &str) -> Option<anyhow::Result<ContextCommand>> {
+    let query = query.trim();
+    let lower = query.to_lowercase();
+
+    if ["list bindings", "bindings", "vars", "variables"].contains(&lower.as_str()) {
+        return Some(Ok(ContextCommand::List));
+    }
+    if let Some(rest) = super::strip_keyword(query, "clear") {
+        return match rest {
+            "" | "bindings" | "all" => Some(Ok(ContextCommand::Clear(None))),
+            name if is_identifier(name) => Some(Ok(ContextCommand::Clear(Some(name.to_string())))),
+            _ => None,
+        };
+    }
+
+    match super::strip_keyword(query, "let") {
+        Some(rest) => Some(parse_binding(rest)),
+        // Without "let" only function definitions are recognized, so plain
+        // equations like "x = 4" are never mistaken for assignments
+        None => {
+            let (head, _) = query.split_once('=')?;
+            head.contains('(').then(|| parse_binding(query)).filter(|parsed| parsed.is_ok())
+        }
+    }
+}
+
+fn parse_binding(text: &str) -> anyhow::Result<ContextCommand> {
+    let (head, body) = text
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected 'name = value' in binding '{}'", text))?;
+    let body = parse_expression(body.trim())?;
+
+    let mut parser = Parser::new(tokenize(head.trim())?);
+    let name = match parser.peek().map(|t| &t.kind) {
+        Some(TokenKind::Ident(name)) => name.clone(),
+        _ => return Err(anyhow::anyhow!("Expected a name before '=' in '{}'", text.trim())),
+    };
+    check_name(&name)?;
+    parser.advance();
+
+    if parser.at_end() {
+        return Ok(ContextCommand::Assign { name, expr: body });
+    }
+
+    parser.expect_symbol("(")?;
+    let mut params = Vec::new();
+    loop {
+        match parser.peek().map(|t| &t.kind) {
+            Some(TokenKind::Ident(param)) if !params.contains(param) && !is_named_constant(param) => {
+                params.push(param.clone());
+                parser.advance();
+            }
+            _ => return Err(anyhow::anyhow!("Expected distinct parameter names in '{}'", head.trim())),
+        }
+        if !parser.eat_symbol(",") {
+            parser.expect_symbol(")")?;
+            break;
+        }
+    }
+    parser.expect_end()?;
+
+    Ok(ContextCommand::Define { name, params, body })
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn check_name(name: &str) -> anyhow::Result<()> {
+    if is_named_constant(name) || BUILTIN_FUNCTIONS.contains(&name.to_lowercase().as_str()) {
+        return Err(anyhow::anyhow!("'{}' is built in and cannot be redefined", name));
+    }
+    Ok(())
+}
+
+impl EvalContext {
+    pub fn new() -> Self {
+        EvalContext::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.functions.is_empty()
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Number> {
+        self.variables.get(name)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&UserFunction> {
+        self.functions.get(name)
+    }
+
+    /// True when the expression uses any binding from this context
+    pub fn references(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Num(_) => false,
+            Expr::Var(name) => self.variables.contains_key(name),
+            Expr::Neg(inner) => self.references(inner),
+            Expr::Binary(_, lhs, rhs) => self.references(lhs) || self.references(rhs),
+            Expr::Call(name, args) => self.functions.contains_key(name) || args.iter().any(|a| self.references(a)),
+        }
+    }
+
+    /// Apply a session command, returning the message to show the user
+    pub fn apply(&mut self, command: &ContextCommand) -> anyhow::Result<String> {
+        match command {
+            ContextCommand::Assign { name, expr } => {
+                let value = Evaluator::with_context(self).evaluate(expr)?;
+                self.functions.remove(name);
+                self.variables.insert(name.clone(), value.clone());
+                Ok(format!("{} = {}", name, value))
+            }
+            ContextCommand::Define { name, params, body } => {
+                let function = UserFunction { params: params.clone(), body: body.clone() };
+                let message = format_function(name, &function);
+                self.variables.remove(name);
+                self.functions.insert(name.clone(), function);
+                Ok(message)
+            }
+            ContextCommand::List => {
+                if self.is_empty() {
+                    return Ok("no bindings".to_string());
+                }
+                let variables = self.variables.iter().map(|(name, value)| format!("{} = {}", name, value));
+                let functions = self.functions.iter().map(|(name, f)| format_function(name, f));
+                Ok(variables.chain(functions).collect::<Vec<_>>().join("\n"))
+            }
+            ContextCommand::Clear(None) => {
+                let count = self.variables.len() + self.functions.len();
+                self.variables.clear();
+                self.functions.clear();
+                Ok(format!("cleared {} binding(s)", count))
+            }
+            ContextCommand::Clear(Some(name)) => {
+                let removed = self.variables.remove(name).is_some() || self.functions.remove(name).is_some();
+                if !removed {
+                    return Err(anyhow::anyhow!("No binding named '{}'", name));
+                }
+                Ok(format!("cleared {}", name))
+            }
+        }
+    }
+
+    /// Load a session saved with `save`; a missing file yields an empty context
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(EvalContext::new());
+        }
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data)
+            .map_err(|e| anyhow::anyhow!("Failed to read session file {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+fn format_function(name: &str, function: &UserFunction) -> String {
+    format!("{}({}) = {}", name, function.params.join(", "), function.body)
+}
+
+/// On-disk form of `EvalContext`; expressions are stored as source text
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    variables: BTreeMap<String, StoredValue>,
+    functions: BTreeMap<String, StoredFunction>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum StoredValue {
+    /// Integer or "n/d" fraction
+    Exact(String),
+    Approx(f64),
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredFunction {
+    params: Vec<String>,
+    body: String,
+}
+
+impl From<EvalContext> for SessionFile {
+    fn from(context: EvalContext) -> Self {
+        let variables = context
+            .variables
+            .into_iter()
+            .map(|(name, value)| {
+                let stored = match value {
+                    Number::Exact(_) => StoredValue::Exact(value.to_string()),
+                    Number::Approx(f) => StoredValue::Approx(f),
+                };
+                (name, stored)
+            })
+            .collect();
+        let functions = context
+            .functions
+            .into_iter()
+            .map(|(name, f)| (name, StoredFunction { params: f.params, body: f.body.to_string() }))
+            .collect();
+        SessionFile { variables, functions }
+    }
+}
+
+impl TryFrom<SessionFile> for EvalContext {
+    type Error = anyhow::Error;
+
+    fn try_from(file: SessionFile) -> anyhow::Result<Self> {
+        let mut context = EvalContext::new();
+        for (name, stored) in file.variables {
+            let value = match stored {
+                StoredValue::Exact(text) => Evaluator::new().evaluate(&parse_expression(&text)?)?,
+                StoredValue::Approx(f) => Number::approx(f)?,
+            };
+            context.variables.insert(name, value);
+        }
+        for (name, stored) in file.functions {
+            let body = parse_expression(&stored.body)?;
+            context.functions.insert(name, UserFunction { params: stored.params, body });
+        }
+        Ok(context)
+    }
+}
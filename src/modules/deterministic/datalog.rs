@@ -0,0 +1,845 @@
+// Datalog: stratified, semi-naive bottom-up evaluation over the knowledge base's function-free clauses
+use super::lexer::relocate;
+use super::prolog::{self, Clause, KnowledgeBase, Query, Term, USER_MODULE};
+use std::collections::{HashMap, HashSet};
+
+/// Predicate name and arity; modules are ignored, so `geo:flows_into/2` is `flows_into/2`
+type Key = (String, usize);
+type Tuple = Vec<Term>;
+
+/// Head of the rule compiled from a query; not a readable atom, so no clause can define it
+const ANSWER: &str = "$answer";
+
+/// "datalog ancestor(X, hercules)", "datalog edge(a, b). path(X, Y) :- edge(X, Y). ?- path(a, Y)"
+/// or "why ancestor(cronus, ares)"
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatalogQuery {
+    /// Clauses given with the query, evaluated together with the knowledge base
+    pub rules: Vec<Clause>,
+    pub goal: Query,
+    /// Follow each answer with its derivation
+    pub explain: bool,
+}
+
+pub fn parse_command(query: &str) -> Option<anyhow::Result<DatalogQuery>> {
+    let text = query.trim().trim_end_matches('?');
+    if let Some(rest) = super::strip_keyword(text, "why") {
+        // Only goals; "why is the sky blue" is left to the other handlers
+        let goal = prolog::parse_query(rest).ok().filter(|q| q.goals.iter().all(|g| matches!(g, Term::Compound(..))))?;
+        return Some(Ok(DatalogQuery { rules: Vec::new(), goal, explain: true }));
+    }
+    super::strip_keyword(text, "datalog").map(|rest| parse_program_query(rest).map_err(|e| relocate(e, query, rest)))
+}
+
+/// "[why] goal" or "clauses ?- goal"
+fn parse_program_query(text: &str) -> anyhow::Result<DatalogQuery> {
+    let (explain, text) = match super::strip_keyword(text, "why") {
+        Some(goal) => (true, goal),
+        None => (false, text),
+    };
+    let (program, goal) = match text.rfind("?-") {
+        Some(i) => (&text[..i], &text[i + 2..]),
+        None if text.contains(":-") => {
+            return Err(anyhow::anyhow!("Datalog rules need a query after them, e.g. '... ?- path(a, X)'"))
+        }
+        None => ("", text),
+    };
+    let rules = prolog::parse_program(program, USER_MODULE).map_err(|e| relocate(e, text, program))?;
+    let goal = prolog::parse_query(goal).map_err(|e| relocate(e, text, goal))?;
+    Ok(DatalogQuery { rules, goal, explain })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AggregateOp {
+    Count,
+    Sum,
+    Min,
+    Max,
+}
+
+/// A body goal, with arguments that are constants or variables
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Positive(Key, Vec<Term>),
+    /// not(p(...)); variables seen nowhere else in the rule match anything
+    Negative(Key, Vec<Term>),
+    Equal(Term, Term),
+    NotEqual(Term, Term),
+    /// aggregate_all(count | sum(T) | min(T) | max(T), p(...), Result)
+    Aggregate { op: AggregateOp, template: Term, key: Key, args: Vec<Term>, result: Term },
+}
+
+impl Literal {
+    /// Variables in order of appearance, repeated as often as they occur
+    fn variables(&self) -> Vec<usize> {
+        let terms: Vec<&Term> = match self {
+            Literal::Positive(_, args) | Literal::Negative(_, args) => args.iter().collect(),
+            Literal::Equal(a, b) | Literal::NotEqual(a, b) => vec![a, b],
+            Literal::Aggregate { template, args, result, .. } => {
+                std::iter::once(template).chain(args).chain(std::iter::once(result)).collect()
+            }
+        };
+        terms.into_iter().filter_map(|t| if let Term::Var(i) = t { Some(*i) } else { None }).collect()
+    }
+
+    /// Predicate read by the literal, and whether it must be complete first (negation, aggregates)
+    fn dependency(&self) -> Option<(&Key, bool)> {
+        match self {
+            Literal::Positive(key, _) => Some((key, false)),
+            Literal::Negative(key, _) | Literal::Aggregate { key, .. } => Some((key, true)),
+            _ => None,
+        }
+    }
+}
+
+/// A rule compiled for evaluation, with its body in an order where every literal can run
+#[derive(Debug, Clone)]
+struct Rule {
+    clause: Clause,
+    key: Key,
+    head: Vec<Term>,
+    body: Vec<Literal>,
+}
+
+/// Why a derived fact holds: the rule and what each of its body literals matched
+#[derive(Debug, Clone)]
+enum Premise {
+    Fact(Key, Tuple),
+    /// Negations, comparisons and aggregates, already rendered
+    Check(String),
+}
+
+/// Answers, a yes/no verdict for ground queries, and the evaluation or explanation trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatalogOutcome {
+    pub result: String,
+    pub verdict: Option<bool>,
+    pub steps: Vec<String>,
+}
+
+/// Evaluate the knowledge base's Datalog predicates and the query's own rules bottom-up,
+/// one stratum at a time, then answer or explain the goal
+pub fn evaluate(query: &DatalogQuery, kb: &KnowledgeBase) -> anyhow::Result<DatalogOutcome> {
+    let program = Program::compile(kb.clauses(), query)?;
+    let mut db = Database::default();
+    let mut steps = Vec::new();
+    for (key, tuple) in &program.facts {
+        db.relations.entry(key.clone()).or_default().insert(tuple.clone());
+    }
+    for (stratum, rules) in program.strata.iter().enumerate() {
+        let (derived, rounds) = db.saturate(&program.rules, rules)?;
+        let mut heads: Vec<String> = rules
+            .iter()
+            .map(|&r| &program.rules[r].key)
+            .filter(|key| key.0 != ANSWER)
+            .map(|(name, arity)| format!("{}/{}", name, arity))
+            .collect();
+        heads.sort();
+        heads.dedup();
+        if !heads.is_empty() {
+            steps.push(format!(
+                "stratum {}: {}: {} in {}",
+                stratum,
+                heads.join(", "),
+                prolog::plural(derived, "new fact"),
+                prolog::plural(rounds, "round")
+            ));
+        }
+    }
+
+    let names: Vec<&String> = query.goal.var_names.iter().filter(|name| !name.starts_with('_')).collect();
+    let answer_key = (ANSWER.to_string(), query_arity(&query.goal));
+    let mut answers: Vec<&Tuple> = db.relations.get(&answer_key).map(|r| r.tuples.iter().collect()).unwrap_or_default();
+    answers.sort();
+    let verdict = names.is_empty().then_some(!answers.is_empty());
+    let describe = |tuple: &Tuple| -> String {
+        if names.is_empty() {
+            return "true".to_string();
+        }
+        names.iter().zip(tuple).map(|(name, value)| format!("{} = {}", name, value)).collect::<Vec<_>>().join(", ")
+    };
+
+    if query.explain {
+        if answers.is_empty() {
+            return Ok(DatalogOutcome {
+                result: "false".to_string(),
+                verdict,
+                steps: vec![format!("{} is not derivable", query.goal)],
+            });
+        }
+        // A derivation per listed answer, each under the answer it derives
+        steps.clear();
+        for (number, answer) in answers.iter().take(prolog::MAX_SOLUTIONS).enumerate() {
+            if !names.is_empty() {
+                steps.push(format!("% answer {}: {}", number + 1, describe(answer)));
+            }
+            db.explain_premises(&program.rules, &answer_key, answer, 0, &mut HashSet::new(), &mut steps);
+        }
+        // A ground goal has the one answer its derivation shows
+        if names.is_empty() {
+            return Ok(DatalogOutcome { result: "true".to_string(), verdict, steps });
+        }
+    }
+
+    let result = match answers.len() {
+        0 => "false".to_string(),
+        count => {
+            let mut listed: Vec<String> = answers.iter().take(prolog::MAX_SOLUTIONS).map(|t| describe(t)).collect();
+            if count > prolog::MAX_SOLUTIONS {
+                listed.push(format!("... ({} of {} answers)", prolog::MAX_SOLUTIONS, count));
+            }
+            listed.join(" ; ")
+        }
+    };
+    steps.push(format!("% {}", prolog::plural(answers.len(), "answer")));
+    Ok(DatalogOutcome { result, verdict, steps })
+}
+
+/// Ground facts, safe rules, and rule indices grouped by stratum
+struct Program {
+    facts: Vec<(Key, Tuple)>,
+    rules: Vec<Rule>,
+    strata: Vec<Vec<usize>>,
+}
+
+impl Program {
+    /// Knowledge-base predicates outside Datalog (lists, compound terms, unsafe rules) are left
+    /// out along with everything that uses them; the query's own clauses must all be Datalog
+    fn compile(kb: &[Clause], query: &DatalogQuery) -> anyhow::Result<Self> {
+        let mut compiled = Vec::new();
+        let mut excluded: HashMap<Key, String> = HashMap::new();
+        for clause in kb {
+            match compile_clause(clause) {
+                Ok(item) => compiled.push(item),
+                Err(e) => {
+                    if let Some(key) = head_key(clause) {
+                        excluded.entry(key).or_insert_with(|| e.to_string());
+                    }
+                }
+            }
+        }
+        // Dropping a predicate drops every rule that reads it, until nothing changes
+        loop {
+            let before = excluded.len();
+            for item in &compiled {
+                if let Compiled::Rule(rule) = item {
+                    let mut uses = rule.body.iter().filter_map(Literal::dependency).map(|(key, _)| key);
+                    if let Some(dropped) = uses.find(|key| excluded.contains_key(*key)) {
+                        let reason = format!("it uses {}/{}", dropped.0, dropped.1);
+                        excluded.entry(rule.key.clone()).or_insert(reason);
+                    }
+                }
+            }
+            if excluded.len() == before {
+                break;
+            }
+        }
+        compiled.retain(|item| !excluded.contains_key(item.key()));
+
+        for clause in &query.rules {
+            compiled.push(compile_clause(clause)?);
+        }
+        let names: Vec<Term> = query
+            .goal
+            .var_names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !name.starts_with('_'))
+            .map(|(i, _)| Term::Var(i))
+            .collect();
+        let answer = Clause {
+            module: USER_MODULE.to_string(),
+            head: Term::Compound(ANSWER.to_string(), names),
+            body: query.goal.goals.clone(),
+            var_names: query.goal.var_names.clone(),
+        };
+        for goal in &query.goal.goals {
+            literal(goal)?;
+        }
+        let answer = compile_clause(&answer)?;
+
+        let defined: HashSet<&Key> = compiled.iter().map(Compiled::key).collect();
+        if let Compiled::Rule(rule) = &answer {
+            for (key, _) in rule.body.iter().filter_map(Literal::dependency) {
+                if let Some(reason) = excluded.get(key) {
+                    return Err(anyhow::anyhow!("{}/{} is not a Datalog predicate: {}", key.0, key.1, reason));
+                }
+                if !defined.contains(key) {
+                    return Err(anyhow::anyhow!("Unknown predicate {}/{}", key.0, key.1));
+                }
+            }
+        }
+        compiled.push(answer);
+
+        // Only predicates the query reads, directly or through rules, are evaluated
+        let mut relevant: HashSet<Key> = HashSet::from([(ANSWER.to_string(), query_arity(&query.goal))]);
+        loop {
+            let before = relevant.len();
+            for item in &compiled {
+                if let Compiled::Rule(rule) = item {
+                    if relevant.contains(&rule.key) {
+                        relevant.extend(rule.body.iter().filter_map(Literal::dependency).map(|(key, _)| key.clone()));
+                    }
+                }
+            }
+            if relevant.len() == before {
+                break;
+            }
+        }
+
+        let mut facts = Vec::new();
+        let mut rules = Vec::new();
+        for item in compiled.into_iter().filter(|item| relevant.contains(item.key())) {
+            match item {
+                Compiled::Fact(key, tuple) => facts.push((key, tuple)),
+                Compiled::Rule(rule) => rules.push(rule),
+            }
+        }
+        // The query's rule runs on its own, after everything it reads is complete
+        let mut strata = stratify(&rules)?;
+        let answer = rules.len() - 1;
+        strata.iter_mut().for_each(|stratum| stratum.retain(|&r| r != answer));
+        strata.retain(|stratum| !stratum.is_empty());
+        strata.push(vec![answer]);
+        Ok(Program { facts, rules, strata })
+    }
+}
+
+enum Compiled {
+    Fact(Key, Tuple),
+    Rule(Rule),
+}
+
+impl Compiled {
+    fn key(&self) -> &Key {
+        match self {
+            Compiled::Fact(key, _) => key,
+            Compiled::Rule(rule) => &rule.key,
+        }
+    }
+}
+
+/// Number of named variables, which the answer rule's head lists
+fn query_arity(goal: &Query) -> usize {
+    goal.var_names.iter().filter(|name| !name.starts_with('_')).count()
+}
+
+fn head_key(clause: &Clause) -> Option<Key> {
+    clause.head.indicator().map(|(name, arity)| (name.to_string(), arity))
+}
+
+fn compile_clause(clause: &Clause) -> anyhow::Result<Compiled> {
+    let (key, head) = atom(&clause.head).map_err(|e| anyhow::anyhow!("'{}': {}", clause, e))?;
+    if clause.body.is_empty() && head.iter().all(|t| !matches!(t, Term::Var(_))) {
+        return Ok(Compiled::Fact(key, head));
+    }
+    let mut literals = Vec::new();
+    for goal in &clause.body {
+        literals.extend(literal(goal).map_err(|e| anyhow::anyhow!("'{}': {}", clause, e))?);
+    }
+    let body = schedule(clause, &head, literals)?;
+    Ok(Compiled::Rule(Rule { clause: clause.clone(), key, head, body }))
+}
+
+/// Name, arity and arguments of an atom such as "parent(zeus, X)"
+fn atom(term: &Term) -> anyhow::Result<(Key, Vec<Term>)> {
+    match unqualified(term) {
+        Term::Atom(name) => Ok(((name.clone(), 0), Vec::new())),
+        Term::Compound(name, args) => {
+            let args = args.iter().map(argument).collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(((name.clone(), args.len()), args))
+        }
+        other => Err(anyhow::anyhow!("{} is not a predicate", other)),
+    }
+}
+
+fn argument(term: &Term) -> anyhow::Result<Term> {
+    match term {
+        Term::Compound(..) => Err(anyhow::anyhow!("{} is not a constant or variable", term)),
+        _ => Ok(term.clone()),
+    }
+}
+
+/// The goal of "module:goal"
+fn unqualified(term: &Term) -> &Term {
+    match term {
+        Term::Compound(colon, args) if colon == ":" && args.len() == 2 => unqualified(&args[1]),
+        _ => term,
+    }
+}
+
+/// `None` for `true`, which adds nothing to a body
+fn literal(goal: &Term) -> anyhow::Result<Option<Literal>> {
+    let literal = match unqualified(goal) {
+        Term::Atom(name) if name == "true" => return Ok(None),
+        Term::Compound(name, args) if name == "=" && args.len() == 2 => {
+            Literal::Equal(argument(&args[0])?, argument(&args[1])?)
+        }
+        Term::Compound(name, args) if matches!(name.as_str(), "not" | "\\+") && args.len() == 1 => match unqualified(&args[0]) {
+            Term::Compound(name, args) if name == "=" && args.len() == 2 => {
+                Literal::NotEqual(argument(&args[0])?, argument(&args[1])?)
+            }
+            negated => {
+                let (key, args) = atom(negated)?;
+                Literal::Negative(key, args)
+            }
+        },
+        Term::Compound(name, args) if name == "aggregate_all" && args.len() == 3 => {
+            let (op, template) = match &args[0] {
+                Term::Atom(count) if count == "count" => (AggregateOp::Count, Term::Atom(count.clone())),
+                Term::Compound(op, inner) if inner.len() == 1 => {
+                    let op = match op.as_str() {
+                        "sum" => AggregateOp::Sum,
+                        "min" => AggregateOp::Min,
+                        "max" => AggregateOp::Max,
+                        _ => return Err(anyhow::anyhow!("Unknown aggregate {}; use count, sum, min or max", args[0])),
+                    };
+                    (op, argument(&inner[0])?)
+                }
+                other => return Err(anyhow::anyhow!("Unknown aggregate {}; use count, sum, min or max", other)),
+            };
+            let (key, goal_args) = atom(&args[1])?;
+            Literal::Aggregate { op, template, key, args: goal_args, result: argument(&args[2])? }
+        }
+        other => {
+            let (key, args) = atom(other)?;
+            Literal::Positive(key, args)
+        }
+    };
+    Ok(Some(literal))
+}
+
+/// Order the body so each literal runs once the variables it needs are bound, or report the
+/// first variable no positive goal binds
+fn schedule(clause: &Clause, head: &[Term], mut literals: Vec<Literal>) -> anyhow::Result<Vec<Literal>> {
+    let mut occurrences = vec![0; clause.var_names.len()];
+    let head_vars = head.iter().filter_map(|t| if let Term::Var(i) = t { Some(*i) } else { None });
+    for var in head_vars.clone().chain(literals.iter().flat_map(Literal::variables)) {
+        occurrences[var] += 1;
+    }
+    let mut bound = vec![false; clause.var_names.len()];
+    let mut ordered = Vec::with_capacity(literals.len());
+    while !literals.is_empty() {
+        let needed = |literal: &Literal, bound: &[bool]| -> Vec<usize> {
+            let vars = literal.variables();
+            let is_bound = |t: &Term| !matches!(t, Term::Var(i) if !bound[*i]);
+            match literal {
+                Literal::Positive(..) => Vec::new(),
+                Literal::Equal(a, b) if is_bound(a) || is_bound(b) => Vec::new(),
+                Literal::Equal(..) | Literal::NotEqual(..) => vars.into_iter().filter(|&v| !bound[v]).collect(),
+                Literal::Negative(..) | Literal::Aggregate { .. } => {
+                    // Variables local to the literal range over its matches
+                    let result = match literal {
+                        Literal::Aggregate { result: Term::Var(r), .. } => Some(*r),
+                        _ => None,
+                    };
+                    let local = |v: usize| vars.iter().filter(|&&w| w == v).count() == occurrences[v];
+                    vars.iter().copied().filter(|&v| !bound[v] && !local(v) && Some(v) != result).collect()
+                }
+            }
+        };
+        let Some(next) = literals.iter().position(|l| needed(l, &bound).is_empty()) else {
+            let unbound = needed(&literals[0], &bound)[0];
+            return Err(unsafe_rule(clause, unbound));
+        };
+        let literal = literals.remove(next);
+        match &literal {
+            Literal::Positive(_, args) => args.iter().for_each(|t| bind(t, &mut bound)),
+            Literal::Equal(a, b) => [a, b].into_iter().for_each(|t| bind(t, &mut bound)),
+            Literal::Aggregate { result, .. } => bind(result, &mut bound),
+            _ => {}
+        }
+        ordered.push(literal);
+    }
+    for term in head {
+        if let Term::Var(i) = term {
+            if !bound[*i] {
+                return Err(unsafe_rule(clause, *i));
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+fn bind(term: &Term, bound: &mut [bool]) {
+    if let Term::Var(i) = term {
+        bound[*i] = true;
+    }
+}
+
+fn unsafe_rule(clause: &Clause, var: usize) -> anyhow::Error {
+    if matches!(&clause.head, Term::Compound(name, _) if name == ANSWER) {
+        let query = Query { goals: clause.body.clone(), var_names: clause.var_names.clone() };
+        return anyhow::anyhow!("Unsafe query '{}': {} must appear in a positive goal", query, clause.var_names[var]);
+    }
+    anyhow::anyhow!("Unsafe rule '{}': {} must appear in a positive goal", clause, clause.var_names[var])
+}
+
+/// Rule indices by stratum: a predicate sits above everything it reads through negation
+/// or an aggregate, and no lower than what it reads positively
+fn stratify(rules: &[Rule]) -> anyhow::Result<Vec<Vec<usize>>> {
+    let mut stratum: HashMap<&Key, usize> = HashMap::new();
+    let limit = rules.len();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for rule in rules {
+            for (key, strict) in rule.body.iter().filter_map(Literal::dependency) {
+                let needed = stratum.get(key).copied().unwrap_or(0) + usize::from(strict);
+                let current = stratum.entry(&rule.key).or_insert(0);
+                if needed > *current {
+                    if needed > limit {
+                        return Err(anyhow::anyhow!(
+                            "Not stratifiable: {}/{} depends on its own negation or aggregate",
+                            rule.key.0,
+                            rule.key.1
+                        ));
+                    }
+                    *current = needed;
+                    changed = true;
+                }
+            }
+        }
+    }
+    let mut strata: Vec<Vec<usize>> = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        let level = stratum.get(&rule.key).copied().unwrap_or(0);
+        if strata.len() <= level {
+            strata.resize(level + 1, Vec::new());
+        }
+        strata[level].push(index);
+    }
+    strata.retain(|rules| !rules.is_empty());
+    Ok(strata)
+}
+
+/// A set of tuples with an index from (column, value) to the rows holding it
+#[derive(Debug, Clone, Default)]
+struct Relation {
+    tuples: Vec<Tuple>,
+    set: HashSet<Tuple>,
+    index: HashMap<(usize, Term), Vec<usize>>,
+}
+
+impl Relation {
+    fn insert(&mut self, tuple: Tuple) -> bool {
+        if self.set.contains(&tuple) {
+            return false;
+        }
+        for (column, value) in tuple.iter().enumerate() {
+            self.index.entry((column, value.clone())).or_default().push(self.tuples.len());
+        }
+        self.set.insert(tuple.clone());
+        self.tuples.push(tuple);
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.tuples.len()
+    }
+
+    /// Tuples agreeing with every bound position of `pattern`, found through the first one
+    fn matching(&self, pattern: &[Option<&Term>]) -> Vec<&Tuple> {
+        let Some((column, value)) = pattern.iter().enumerate().find_map(|(i, v)| v.map(|v| (i, v))) else {
+            return self.tuples.iter().collect();
+        };
+        let rows = self.index.get(&(column, value.clone())).map(Vec::as_slice).unwrap_or_default();
+        rows.iter()
+            .map(|&row| &self.tuples[row])
+            .filter(|tuple| pattern.iter().zip(tuple.iter()).all(|(p, v)| p.is_none_or(|p| p == v)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Database {
+    relations: HashMap<Key, Relation>,
+    /// First derivation of each derived fact: rule index and what its body matched
+    derivations: HashMap<(Key, Tuple), (usize, Vec<Premise>)>,
+}
+
+/// Derived head tuples with the rule that produced each and its premises
+type Derived = Vec<(usize, Tuple, Vec<Premise>)>;
+
+impl Database {
+    /// Run one stratum to a fixpoint: every rule once over all facts, then only joins that
+    /// use a fact new in the previous round. Returns the facts added and the rounds taken
+    fn saturate(&mut self, rules: &[Rule], stratum: &[usize]) -> anyhow::Result<(usize, usize)> {
+        let heads: HashSet<&Key> = stratum.iter().map(|&r| &rules[r].key).collect();
+        let mut derived = Vec::new();
+        for &r in stratum {
+            Join { db: self, rule: &rules[r], index: r, delta: None, out: &mut derived }.run()?;
+        }
+        let mut delta = self.add(rules, derived);
+        let (mut total, mut rounds) = (delta.values().map(Relation::len).sum::<usize>(), 1);
+        while !delta.is_empty() {
+            let mut derived = Vec::new();
+            for &r in stratum {
+                for (at, literal) in rules[r].body.iter().enumerate() {
+                    if let Literal::Positive(key, _) = literal {
+                        if let Some(new) = delta.get(key).filter(|_| heads.contains(key)) {
+                            let delta = Some((at, new));
+                            Join { db: self, rule: &rules[r], index: r, delta, out: &mut derived }.run()?;
+                        }
+                    }
+                }
+            }
+            delta = self.add(rules, derived);
+            total += delta.values().map(Relation::len).sum::<usize>();
+            rounds += usize::from(!delta.is_empty());
+        }
+        Ok((total, rounds))
+    }
+
+    /// Store derived tuples, returning those that were new
+    fn add(&mut self, rules: &[Rule], derived: Derived) -> HashMap<Key, Relation> {
+        let mut new: HashMap<Key, Relation> = HashMap::new();
+        for (rule, tuple, premises) in derived {
+            let key = &rules[rule].key;
+            if self.relations.entry(key.clone()).or_default().insert(tuple.clone()) {
+                self.derivations.insert((key.clone(), tuple.clone()), (rule, premises));
+                new.entry(key.clone()).or_default().insert(tuple);
+            }
+        }
+        new
+    }
+
+    /// One line per premise of `tuple`, each derived fact followed by its own premises
+    fn explain_premises(
+        &self,
+        rules: &[Rule],
+        key: &Key,
+        tuple: &Tuple,
+        depth: usize,
+        seen: &mut HashSet<(Key, Tuple)>,
+        lines: &mut Vec<String>,
+    ) {
+        let Some((_, premises)) = self.derivations.get(&(key.clone(), tuple.clone())) else {
+            return;
+        };
+        let indent = "  ".repeat(depth);
+        for premise in premises {
+            match premise {
+                Premise::Check(text) => lines.push(format!("{}{}", indent, text)),
+                Premise::Fact(key, tuple) => {
+                    let fact = fact_term(key, tuple);
+                    match self.derivations.get(&(key.clone(), tuple.clone())) {
+                        None => lines.push(format!("{}{} by fact", indent, fact)),
+                        Some(_) if !seen.insert((key.clone(), tuple.clone())) => {
+                            lines.push(format!("{}{} (shown above)", indent, fact))
+                        }
+                        Some((rule, _)) => {
+                            lines.push(format!("{}{} by rule {}", indent, fact, rules[*rule].clause));
+                            self.explain_premises(rules, key, tuple, depth + 1, seen, lines);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn fact_term(key: &Key, tuple: &Tuple) -> Term {
+    match tuple.is_empty() {
+        true => Term::Atom(key.0.clone()),
+        false => Term::Compound(key.0.clone(), tuple.clone()),
+    }
+}
+
+/// Nested-loop join of one rule's body, reading `delta` instead of the full relation at one position
+struct Join<'a> {
+    db: &'a Database,
+    rule: &'a Rule,
+    index: usize,
+    delta: Option<(usize, &'a Relation)>,
+    out: &'a mut Derived,
+}
+
+impl Join<'_> {
+    fn run(&mut self) -> anyhow::Result<()> {
+        let mut bindings = vec![None; self.rule.clause.var_names.len()];
+        self.step(0, &mut bindings, &mut Vec::new())
+    }
+
+    fn step(&mut self, at: usize, bindings: &mut [Option<Term>], premises: &mut Vec<Premise>) -> anyhow::Result<()> {
+        let Some(literal) = self.rule.body.get(at) else {
+            let tuple = self.rule.head.iter().map(|t| value(t, bindings).cloned().unwrap_or_else(|| t.clone())).collect();
+            self.out.push((self.index, tuple, premises.clone()));
+            return Ok(());
+        };
+        match literal {
+            Literal::Positive(key, args) => {
+                let relation = match self.delta {
+                    Some((position, delta)) if position == at => Some(delta),
+                    _ => self.db.relations.get(key),
+                };
+                let Some(relation) = relation else { return Ok(()) };
+                for tuple in relation.matching(&pattern(args, bindings)) {
+                    let mut trail = Vec::new();
+                    if unify(args, tuple, bindings, &mut trail) {
+                        premises.push(Premise::Fact(key.clone(), tuple.clone()));
+                        self.step(at + 1, bindings, premises)?;
+                        premises.pop();
+                    }
+                    undo(bindings, trail);
+                }
+                Ok(())
+            }
+            Literal::Negative(key, args) => {
+                let matches = self.db.relations.get(key).map_or(Vec::new(), |r| r.matching(&pattern(args, bindings)));
+                let found = matches.into_iter().any(|tuple| {
+                    let mut trail = Vec::new();
+                    let unified = unify(args, tuple, bindings, &mut trail);
+                    undo(bindings, trail);
+                    unified
+                });
+                if found {
+                    return Ok(());
+                }
+                let goal = instantiate(&fact_term(key, args), bindings, &self.rule.clause.var_names);
+                self.check(at, bindings, premises, format!("not({}) by absence", goal))
+            }
+            Literal::Equal(a, b) => {
+                let mut trail = Vec::new();
+                let unified = match (value(a, bindings).cloned(), value(b, bindings).cloned()) {
+                    (Some(x), Some(y)) => x == y,
+                    (Some(x), None) => unify(std::slice::from_ref(b), &[x], bindings, &mut trail),
+                    (None, Some(y)) => unify(std::slice::from_ref(a), &[y], bindings, &mut trail),
+                    // Scheduling runs an equality only once one side is bound
+                    (None, None) => false,
+                };
+                if unified {
+                    self.step(at + 1, bindings, premises)?;
+                }
+                undo(bindings, trail);
+                Ok(())
+            }
+            Literal::NotEqual(a, b) => {
+                let (a, b) = (value(a, bindings).unwrap_or(a), value(b, bindings).unwrap_or(b));
+                if a == b {
+                    return Ok(());
+                }
+                let text = format!("not({} = {}) by built-in", a, b);
+                self.check(at, bindings, premises, text)
+            }
+            Literal::Aggregate { op, template, key, args, result } => {
+                let Some(total) = self.aggregate(*op, template, key, args, bindings)? else {
+                    return Ok(());
+                };
+                let mut trail = Vec::new();
+                if unify(std::slice::from_ref(result), std::slice::from_ref(&total), bindings, &mut trail) {
+                    let names = &self.rule.clause.var_names;
+                    let goal = instantiate(&fact_term(key, args), bindings, names);
+                    let spec = match op {
+                        AggregateOp::Count => "count".to_string(),
+                        AggregateOp::Sum => format!("sum({})", instantiate(template, bindings, names)),
+                        AggregateOp::Min => format!("min({})", instantiate(template, bindings, names)),
+                        AggregateOp::Max => format!("max({})", instantiate(template, bindings, names)),
+                    };
+                    let text = format!("aggregate_all({}, {}, {}) by aggregate", spec, goal, total);
+                    self.check(at, bindings, premises, text)?;
+                }
+                undo(bindings, trail);
+                Ok(())
+            }
+        }
+    }
+
+    fn check(&mut self, at: usize, bindings: &mut [Option<Term>], premises: &mut Vec<Premise>, text: String) -> anyhow::Result<()> {
+        premises.push(Premise::Check(text));
+        self.step(at + 1, bindings, premises)?;
+        premises.pop();
+        Ok(())
+    }
+
+    /// Count the matching facts, or combine the template's value in each; `None` for the
+    /// minimum or maximum of no facts
+    fn aggregate(
+        &self,
+        op: AggregateOp,
+        template: &Term,
+        key: &Key,
+        args: &[Term],
+        bindings: &mut [Option<Term>],
+    ) -> anyhow::Result<Option<Term>> {
+        let matches = self.db.relations.get(key).map_or(Vec::new(), |r| r.matching(&pattern(args, bindings)));
+        let mut values = Vec::new();
+        for tuple in matches {
+            let mut trail = Vec::new();
+            if unify(args, tuple, bindings, &mut trail) {
+                values.push(value(template, bindings).unwrap_or(template).clone());
+            }
+            undo(bindings, trail);
+        }
+        if op == AggregateOp::Count {
+            return Ok(Some(Term::Int(values.len() as i64)));
+        }
+        let numbers = values
+            .iter()
+            .map(|v| match v {
+                Term::Int(n) => Ok(*n),
+                other => Err(anyhow::anyhow!("aggregate_all needs integers, found {}", other)),
+            })
+            .collect::<anyhow::Result<Vec<i64>>>()?;
+        let total = match op {
+            AggregateOp::Sum => Some(
+                numbers
+                    .iter()
+                    .try_fold(0i64, |sum, n| sum.checked_add(*n))
+                    .ok_or_else(|| anyhow::anyhow!("aggregate_all sum overflows 64 bits"))?,
+            ),
+            AggregateOp::Min => numbers.iter().min().copied(),
+            AggregateOp::Max => numbers.iter().max().copied(),
+            AggregateOp::Count => unreachable!("count returned above"),
+        };
+        Ok(total.map(Term::Int))
+    }
+}
+
+/// The constant a term stands for under `bindings`, if any
+fn value<'a>(term: &'a Term, bindings: &'a [Option<Term>]) -> Option<&'a Term> {
+    match term {
+        Term::Var(i) => bindings[*i].as_ref(),
+        _ => Some(term),
+    }
+}
+
+fn pattern<'a>(args: &'a [Term], bindings: &'a [Option<Term>]) -> Vec<Option<&'a Term>> {
+    args.iter().map(|t| value(t, bindings)).collect()
+}
+
+/// Match `args` against constants, binding free variables and recording them on `trail`
+fn unify(args: &[Term], values: &[Term], bindings: &mut [Option<Term>], trail: &mut Vec<usize>) -> bool {
+    for (arg, constant) in args.iter().zip(values) {
+        match arg {
+            Term::Var(i) => match &bindings[*i] {
+                Some(bound) if bound != constant => return false,
+                Some(_) => {}
+                None => {
+                    bindings[*i] = Some(constant.clone());
+                    trail.push(*i);
+                }
+            },
+            _ if arg != constant => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+fn undo(bindings: &mut [Option<Term>], trail: Vec<usize>) {
+    for var in trail {
+        bindings[var] = None;
+    }
+}
+
+/// `term` with bound variables replaced by their values and free ones shown by name
+fn instantiate(term: &Term, bindings: &[Option<Term>], names: &[String]) -> Term {
+    match term {
+        Term::Var(i) => bindings[*i].clone().unwrap_or_else(|| Term::Atom(names[*i].clone())),
+        Term::Compound(name, args) => {
+            Term::Compound(name.clone(), args.iter().map(|a| instantiate(a, bindings, names)).collect())
+        }
+        _ => term.clone(),
+    }
+}
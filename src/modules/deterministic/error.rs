@@ -0,0 +1,97 @@
+// Typed failures of the deterministic module, each with a stable code for the IPC layer
+use super::lexer::SyntaxError;
+
+/// The budget a query ran out of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Wall-clock time
+    Time,
+    /// Sandbox operations, or multiplications of a combinatorics result
+    Operations,
+    /// Prolog inferences
+    Inferences,
+    /// Proof or call depth
+    Depth,
+    /// Iterations or function evaluations of a numerical method
+    Iterations,
+    /// Length of a sandbox string, array or map, digits of a combinatorics result, or the
+    /// exponent of an exact power or of an expanded sum
+    Size,
+}
+
+/// A query stopped by one of its limits, with the message shown to the user
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message}")]
+pub struct LimitError {
+    pub limit: Limit,
+    pub message: String,
+}
+
+impl LimitError {
+    pub fn new(limit: Limit, message: impl Into<String>) -> Self {
+        LimitError { limit, message: message.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum DetError {
+    #[error("Query cannot be empty")]
+    EmptyQuery,
+    #[error("Query exceeds maximum length of {max} characters")]
+    QueryTooLong { max: usize },
+    #[error(transparent)]
+    Syntax(#[from] SyntaxError),
+    #[error(transparent)]
+    Timeout(LimitError),
+    #[error(transparent)]
+    ResourceLimit(LimitError),
+    /// Anything else a domain rejected: unknown names, division by zero, unsatisfiable
+    /// requests, unreadable knowledge bases
+    #[error("{0}")]
+    Evaluation(String),
+}
+
+impl DetError {
+    /// Stable identifier for scripts and the UI; never reworded
+    pub fn code(&self) -> &'static str {
+        match self {
+            DetError::EmptyQuery => "DET_EMPTY_QUERY",
+            DetError::QueryTooLong { .. } => "DET_QUERY_TOO_LONG",
+            DetError::Syntax(_) => "DET_SYNTAX",
+            DetError::Timeout(_) => "DET_TIMEOUT",
+            DetError::ResourceLimit(_) => "DET_RESOURCE_LIMIT",
+            DetError::Evaluation(_) => "DET_EVALUATION",
+        }
+    }
+
+    /// Column of a syntax error
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            DetError::Syntax(error) => Some(error.column),
+            _ => None,
+        }
+    }
+}
+
+impl From<LimitError> for DetError {
+    fn from(error: LimitError) -> Self {
+        match error.limit {
+            Limit::Time => DetError::Timeout(error),
+            _ => DetError::ResourceLimit(error),
+        }
+    }
+}
+
+/// Domains report through `anyhow`; typed errors inside keep their kind at the boundary
+impl From<anyhow::Error> for DetError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<SyntaxError>() {
+            Ok(syntax) => return DetError::Syntax(syntax),
+            Err(error) => error,
+        };
+        match error.downcast::<LimitError>() {
+            Ok(limit) => limit.into(),
+            Err(error) => DetError::Evaluation(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,695 @@
+// Arithmetic expression tree, parser and exact evaluator
+use super::error::{Limit, LimitError};
+use super::lexer::{tokenize, SyntaxError, Token, TokenKind};
+use super::context::{EvalContext, UserFunction};
+use super::number::Number;
+use num_rational::BigRational;
+use num_traits::Signed;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl BinOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Pow => "^",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            BinOp::Add | BinOp::Sub => 1,
+            BinOp::Mul | BinOp::Div | BinOp::Rem => 2,
+            BinOp::Pow => 4,
+        }
+    }
+}
+
+/// Parsed arithmetic expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(BigRational),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn binary(op: BinOp, lhs: Expr, rhs: Expr) -> Expr {
+        Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn integer(value: i64) -> Expr {
+        Expr::Num(BigRational::from_integer(value.into()))
+    }
+
+    pub fn call(name: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call(name.to_string(), args)
+    }
+
+    /// True when `name` occurs as a variable anywhere in the tree
+    pub fn contains_var(&self, name: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Var(v) => v == name,
+            Expr::Neg(inner) => inner.contains_var(name),
+            Expr::Binary(_, lhs, rhs) => lhs.contains_var(name) || rhs.contains_var(name),
+            Expr::Call(_, args) => args.iter().any(|a| a.contains_var(name)),
+        }
+    }
+
+    /// Variables other than the named constants pi, e and tau, in sorted order
+    pub fn free_variables(&self) -> BTreeSet<String> {
+        let mut vars = BTreeSet::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut BTreeSet<String>) {
+        match self {
+            Expr::Num(_) => {}
+            Expr::Var(v) => {
+                if !is_named_constant(v) {
+                    vars.insert(v.clone());
+                }
+            }
+            Expr::Neg(inner) => inner.collect_variables(vars),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.collect_variables(vars);
+                rhs.collect_variables(vars);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.collect_variables(vars)),
+        }
+    }
+
+    /// Replace every occurrence of the variable `name` with `value`
+    pub fn substitute(&self, name: &str, value: &Expr) -> Expr {
+        match self {
+            Expr::Var(v) if v == name => value.clone(),
+            Expr::Num(_) | Expr::Var(_) => self.clone(),
+            Expr::Neg(inner) => Expr::Neg(Box::new(inner.substitute(name, value))),
+            Expr::Binary(op, lhs, rhs) => {
+                Expr::binary(*op, lhs.substitute(name, value), rhs.substitute(name, value))
+            }
+            Expr::Call(f, args) => {
+                Expr::Call(f.clone(), args.iter().map(|a| a.substitute(name, value)).collect())
+            }
+        }
+    }
+
+    /// Render with every nested operation parenthesized, showing how precedence grouped it
+    pub fn grouped(&self) -> String {
+        let operand = |e: &Expr| match e {
+            Expr::Binary(..) | Expr::Neg(_) => format!("({})", e.grouped()),
+            Expr::Num(n) if !n.is_integer() || n.is_negative() => format!("({})", e),
+            _ => e.grouped(),
+        };
+        match self {
+            Expr::Binary(BinOp::Pow, lhs, rhs) => format!("{}^{}", operand(lhs), operand(rhs)),
+            Expr::Binary(op, lhs, rhs) => format!("{} {} {}", operand(lhs), op.symbol(), operand(rhs)),
+            Expr::Neg(inner) => format!("-{}", operand(inner)),
+            Expr::Call(name, args) => {
+                format!("{}({})", name, args.iter().map(|a| a.grouped()).collect::<Vec<_>>().join(", "))
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Num(n) if !n.is_integer() => 2,
+            Expr::Num(n) if n.is_negative() => 3,
+            Expr::Neg(_) => 3,
+            Expr::Binary(op, _, _) => op.precedence(),
+            _ => 5,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) if n.is_integer() => write!(f, "{}", n.numer()),
+            Expr::Num(n) => write!(f, "{}/{}", n.numer(), n.denom()),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Neg(inner) => {
+                // -(a * b) reads the same as -a * b, so only sums and remainders need parentheses
+                let needs_parens = match inner.as_ref() {
+                    Expr::Binary(BinOp::Mul | BinOp::Div, _, _) => false,
+                    other => other.precedence() <= 3,
+                };
+                if needs_parens {
+                    write!(f, "-({})", inner)
+                } else {
+                    write!(f, "-{}", inner)
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let prec = op.precedence();
+                let lhs_parens = if *op == BinOp::Pow {
+                    lhs.precedence() <= prec
+                } else {
+                    lhs.precedence() < prec
+                };
+                let rhs_parens = match op {
+                    BinOp::Pow => rhs.precedence() < prec,
+                    BinOp::Sub | BinOp::Div | BinOp::Rem => rhs.precedence() <= prec,
+                    _ => rhs.precedence() < prec,
+                };
+
+                if lhs_parens {
+                    write!(f, "({})", lhs)?;
+                } else {
+                    write!(f, "{}", lhs)?;
+                }
+                if *op == BinOp::Pow {
+                    write!(f, "^")?;
+                } else {
+                    write!(f, " {} ", op.symbol())?;
+                }
+                if rhs_parens {
+                    write!(f, "({})", rhs)
+                } else {
+                    write!(f, "{}", rhs)
+                }
+            }
+            Expr::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Parse a complete arithmetic expression
+pub fn parse_expression(input: &str) -> anyhow::Result<Expr> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse_expr()?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+
+/// Recursive-descent parser over lexer tokens
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// A number binds the names after it, as in "100 km / 2 h"
+    quantities: bool,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser { tokens, pos: 0, quantities: false }
+    }
+
+    /// Read a number and the unit names that follow it as one operand, so "100 km / 2 h"
+    /// is (100 km) / (2 h) and "1/2 m" is half a metre
+    pub fn with_quantities(self) -> Self {
+        Parser { quantities: true, ..self }
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Token `offset` positions after the current one
+    pub fn peek_ahead(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    /// Index of the next token
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Return to a `position`, to try another reading after a failed one
+    pub fn reset(&mut self, position: usize) {
+        self.pos = position;
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    pub fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    pub fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if self.peek().is_some_and(|t| t.is_symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect_symbol(&mut self, symbol: &str) -> anyhow::Result<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", symbol)))
+        }
+    }
+
+    pub fn expect_end(&self) -> anyhow::Result<()> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.error("end of expression"))
+        }
+    }
+
+    pub fn error(&self, expected: &str) -> anyhow::Error {
+        let (column, found) = match self.peek() {
+            Some(token) => (token.column, describe(&token.kind)),
+            None => (self.tokens.last().map_or(1, |t| t.end), "end of input".to_string()),
+        };
+        SyntaxError::new(column, expected, found).into()
+    }
+
+    /// expr := term (('+' | '-') term)*
+    pub fn parse_expr(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.parse_term()?;
+        loop {
+            let op = if self.eat_symbol("+") {
+                BinOp::Add
+            } else if self.eat_symbol("-") {
+                BinOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_term()?;
+            lhs = Expr::binary(op, lhs, rhs);
+        }
+    }
+
+    /// term := unary (('*' | '/' | '%') unary | power)*
+    /// The bare `power` alternative is implicit multiplication, as in "2x" or "3(x + 1)"
+    fn parse_term(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = if self.eat_symbol("*") {
+                BinOp::Mul
+            } else if self.eat_symbol("/") {
+                BinOp::Div
+            } else if self.eat_symbol("%") {
+                BinOp::Rem
+            } else if self.starts_implicit_factor() {
+                let rhs = self.parse_power()?;
+                lhs = Expr::binary(BinOp::Mul, lhs, rhs);
+                continue;
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_unary()?;
+            lhs = Expr::binary(op, lhs, rhs);
+        }
+    }
+
+    fn starts_implicit_factor(&self) -> bool {
+        self.peek().is_some_and(|t| matches!(t.kind, TokenKind::Ident(_)) || t.is_symbol("("))
+    }
+
+    /// unary := ('-' | '+') unary | power
+    fn parse_unary(&mut self) -> anyhow::Result<Expr> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        if self.eat_symbol("+") {
+            return self.parse_unary();
+        }
+        self.parse_power()
+    }
+
+    /// power := primary ('^' unary)?, right-associative so 2^3^2 = 2^9
+    pub fn parse_power(&mut self) -> anyhow::Result<Expr> {
+        let base = self.parse_primary()?;
+        if self.eat_symbol("^") {
+            let exponent = self.parse_unary()?;
+            return Ok(Expr::binary(BinOp::Pow, base, exponent));
+        }
+        match base {
+            Expr::Num(value) if self.quantities => self.parse_quantity(value),
+            base => Ok(base),
+        }
+    }
+
+    /// quantity := number ('/' number)? (name ('^' unary)?)*, where the fraction is written
+    /// without spaces
+    fn parse_quantity(&mut self, value: BigRational) -> anyhow::Result<Expr> {
+        let mut quantity = Expr::Num(value);
+        let touching = |a: Option<&Token>, b: Option<&Token>| a.zip(b).is_some_and(|(a, b)| a.end == b.column);
+        let slash = self.peek().filter(|t| t.is_symbol("/"));
+        let denominator = self.peek_ahead(1).filter(|t| matches!(t.kind, TokenKind::Number(_)));
+        if touching(self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)), slash) && touching(slash, denominator) {
+            self.advance();
+            let denominator = self.parse_primary()?;
+            quantity = Expr::binary(BinOp::Div, quantity, denominator);
+        }
+        // Names, not calls: "2 sqrt(m)" keeps its ordinary reading
+        while self.peek().is_some_and(|t| matches!(t.kind, TokenKind::Ident(_)))
+            && !self.peek_ahead(1).is_some_and(|t| t.is_symbol("("))
+        {
+            let unit = self.parse_primary()?;
+            let unit = if self.eat_symbol("^") { Expr::binary(BinOp::Pow, unit, self.parse_unary()?) } else { unit };
+            quantity = Expr::binary(BinOp::Mul, quantity, unit);
+        }
+        Ok(quantity)
+    }
+
+    fn parse_primary(&mut self) -> anyhow::Result<Expr> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("a number, name or '('"));
+        };
+
+        match token.kind {
+            TokenKind::Number(text) => {
+                self.advance();
+                match Number::parse_decimal(&text)? {
+                    Number::Exact(value) => Ok(Expr::Num(value)),
+                    Number::Approx(_) => Err(anyhow::anyhow!("Invalid number '{}'", text)),
+                }
+            }
+            TokenKind::Ident(name) => {
+                self.advance();
+                if self.eat_symbol("(") {
+                    let mut args = Vec::new();
+                    if !self.eat_symbol(")") {
+                        loop {
+                            args.push(self.parse_expr()?);
+                            if self.eat_symbol(")") {
+                                break;
+                            }
+                            self.expect_symbol(",")?;
+                        }
+                    }
+                    Ok(Expr::Call(name.to_lowercase(), args))
+                } else {
+                    Ok(Expr::Var(name))
+                }
+            }
+            TokenKind::Symbol("(") => {
+                self.advance();
+                let inner = self.parse_expr()?;
+                self.expect_symbol(")")?;
+                Ok(inner)
+            }
+            _ => Err(self.error("a number, name or '('")),
+        }
+    }
+}
+
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Number(n) => format!("number '{}'", n),
+        TokenKind::Ident(name) => format!("name '{}'", name),
+        TokenKind::Symbol(s) => format!("'{}'", s),
+        TokenKind::Text(text) => format!("text {:?}", text),
+        TokenKind::Pattern(pattern) => format!("pattern /{}/", pattern),
+    }
+}
+
+/// Names the evaluator resolves to built-in constants
+pub fn is_named_constant(name: &str) -> bool {
+    matches!(name.to_lowercase().as_str(), "pi" | "e" | "tau")
+}
+
+/// Deepest chain of nested user-defined function calls before evaluation gives up
+const MAX_CALL_DEPTH: usize = 64;
+
+/// Evaluates expression trees over `Number`, keeping results exact where possible
+#[derive(Default)]
+pub struct Evaluator<'a> {
+    /// Session bindings consulted after local parameters
+    context: Option<&'a EvalContext>,
+    /// Parameters bound by the user-defined function being evaluated, or by `with_variable`
+    locals: BTreeMap<String, Number>,
+    depth: usize,
+    /// Receives one line per reduction when tracing is enabled
+    trace: Option<&'a RefCell<Vec<String>>>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new() -> Self {
+        Evaluator::default()
+    }
+
+    /// Evaluator that resolves session variables and user-defined functions
+    pub fn with_context(context: &'a EvalContext) -> Self {
+        Evaluator { context: Some(context), ..Evaluator::default() }
+    }
+
+    /// Bind `name` ahead of session variables and constants
+    pub fn with_variable(mut self, name: &str, value: Number) -> Self {
+        self.locals.insert(name.to_string(), value);
+        self
+    }
+
+    /// Record each intermediate value into `trace` as it is computed
+    pub fn with_trace(self, trace: &'a RefCell<Vec<String>>) -> Self {
+        Evaluator { trace: Some(trace), ..self }
+    }
+
+    /// Evaluate and return the reduction trace: grouping, each intermediate value, result
+    pub fn evaluate_traced(&self, expr: &Expr) -> anyhow::Result<(Number, Vec<String>)> {
+        let trace = RefCell::new(vec![format!("parse: {} → {}", expr, expr.grouped())]);
+        let value = Evaluator {
+            context: self.context,
+            locals: self.locals.clone(),
+            depth: self.depth,
+            trace: Some(&trace),
+        }
+        .evaluate(expr)?;
+        let mut lines = trace.into_inner();
+        lines.push(format!("result: {}", value));
+        Ok((value, lines))
+    }
+
+    fn record(&self, line: impl FnOnce() -> String) {
+        if let Some(trace) = self.trace {
+            trace.borrow_mut().push(line());
+        }
+    }
+
+    pub fn evaluate(&self, expr: &Expr) -> anyhow::Result<Number> {
+        match expr {
+            Expr::Num(value) => Ok(Number::Exact(value.clone())),
+            Expr::Var(name) => {
+                let value = self.lookup(name)?;
+                if !self.locals.contains_key(name) {
+                    let relation = if value.is_exact() { "=" } else { "≈" };
+                    self.record(|| format!("{} {} {}", name, relation, value));
+                }
+                Ok(value)
+            }
+            Expr::Neg(inner) => {
+                let value = self.evaluate(inner)?.neg();
+                if !matches!(inner.as_ref(), Expr::Num(_)) {
+                    self.record(|| format!("-({}) = {}", value.neg(), value));
+                }
+                Ok(value)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let a = self.evaluate(lhs)?;
+                let b = self.evaluate(rhs)?;
+                let value = apply_binary(*op, &a, &b)?;
+                self.record(|| {
+                    let (a, b) = (traced_operand(*op, &a, false), traced_operand(*op, &b, true));
+                    format!("{} {} {} = {}", a, op.symbol(), b, value)
+                });
+                Ok(value)
+            }
+            Expr::Call(name, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let value = match self.context.and_then(|ctx| ctx.function(name)) {
+                    Some(function) => self.call_user(name, function, values.clone())?,
+                    None => call_builtin(name, &values)?,
+                };
+                self.record(|| {
+                    let args: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                    format!("{}({}) = {}", name, args.join(", "), value)
+                });
+                Ok(value)
+            }
+        }
+    }
+
+    fn call_user(&self, name: &str, function: &UserFunction, args: Vec<Number>) -> anyhow::Result<Number> {
+        if args.len() != function.params.len() {
+            return Err(arity_error(name, function.params.len(), args.len()));
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(LimitError::new(Limit::Depth, format!("Recursion limit exceeded calling '{}'", name)).into());
+        }
+        let inner = Evaluator {
+            context: self.context,
+            locals: function.params.iter().cloned().zip(args).collect(),
+            depth: self.depth + 1,
+            trace: self.trace,
+        };
+        inner.evaluate(&function.body)
+    }
+
+    fn lookup(&self, name: &str) -> anyhow::Result<Number> {
+        if let Some(value) = self.locals.get(name) {
+            return Ok(value.clone());
+        }
+        if let Some(value) = self.context.and_then(|ctx| ctx.variable(name)) {
+            return Ok(value.clone());
+        }
+        match name.to_lowercase().as_str() {
+            "pi" => Ok(Number::Approx(std::f64::consts::PI)),
+            "e" => Ok(Number::Approx(std::f64::consts::E)),
+            "tau" => Ok(Number::Approx(std::f64::consts::TAU)),
+            _ => Err(anyhow::anyhow!("Unknown variable '{}'", name)),
+        }
+    }
+}
+
+/// An intermediate value as an operand of `op` in the trace: "2 ^ (1/2)", "3 - (-2)"
+fn traced_operand(op: BinOp, value: &Number, right: bool) -> String {
+    let fraction = matches!(value, Number::Exact(r) if !r.is_integer());
+    let binds_loosely = match op {
+        BinOp::Pow | BinOp::Mul | BinOp::Div => fraction || (value.is_negative() && (right || op == BinOp::Pow)),
+        _ => value.is_negative() && right,
+    };
+    if binds_loosely {
+        format!("({})", value)
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn apply_binary(op: BinOp, a: &Number, b: &Number) -> anyhow::Result<Number> {
+    match op {
+        BinOp::Add => a.add(b),
+        BinOp::Sub => a.sub(b),
+        BinOp::Mul => a.mul(b),
+        BinOp::Div => a.div(b),
+        BinOp::Rem => a.rem(b),
+        BinOp::Pow => a.pow(b),
+    }
+}
+
+/// Names handled by `call_builtin`, which user-defined functions may not shadow
+pub const BUILTIN_FUNCTIONS: &[&str] = &[
+    "sqrt", "abs", "floor", "ceil", "round", "trunc", "min", "max", "sin", "cos", "tan", "asin", "acos",
+    "atan", "sinh", "cosh", "tanh", "exp", "ln", "log", "log10", "log2", "factorial", "ncr", "npr", "choose",
+];
+
+/// Evaluate a built-in function; exact where the function preserves rationality
+pub fn call_builtin(name: &str, args: &[Number]) -> anyhow::Result<Number> {
+    let unary = |f: fn(f64) -> f64| -> anyhow::Result<Number> {
+        match args {
+            [x] => Number::approx(f(x.to_f64())),
+            _ => Err(arity_error(name, 1, args.len())),
+        }
+    };
+
+    match name {
+        "sqrt" => match args {
+            [x] if x.is_negative() => Err(anyhow::anyhow!("Result is not a real number: sqrt of a negative value")),
+            [x] => x.pow(&Number::Exact(BigRational::new(1.into(), 2.into()))),
+            _ => Err(arity_error(name, 1, args.len())),
+        },
+        "abs" => match args {
+            [x] => Ok(x.abs()),
+            _ => Err(arity_error(name, 1, args.len())),
+        },
+        "floor" | "ceil" | "round" | "trunc" => match args {
+            [Number::Exact(r)] => Ok(Number::Exact(match name {
+                "floor" => r.floor(),
+                "ceil" => r.ceil(),
+                "round" => r.round(),
+                _ => r.trunc(),
+            })),
+            [Number::Approx(f)] => Number::approx(match name {
+                "floor" => f.floor(),
+                "ceil" => f.ceil(),
+                "round" => f.round(),
+                _ => f.trunc(),
+            }),
+            _ => Err(arity_error(name, 1, args.len())),
+        },
+        "min" | "max" => {
+            let mut iter = args.iter();
+            let Some(first) = iter.next() else {
+                return Err(anyhow::anyhow!("{}() needs at least one argument", name));
+            };
+            let mut best = first.clone();
+            for candidate in iter {
+                let diff = candidate.sub(&best)?;
+                let better = if name == "min" { diff.is_negative() } else { !diff.is_negative() && !diff.is_zero() };
+                if better {
+                    best = candidate.clone();
+                }
+            }
+            Ok(best)
+        }
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "asin" => unary(f64::asin),
+        "acos" => unary(f64::acos),
+        "atan" => unary(f64::atan),
+        "sinh" => unary(f64::sinh),
+        "cosh" => unary(f64::cosh),
+        "tanh" => unary(f64::tanh),
+        "exp" => match args {
+            [x] if x.is_zero() => Ok(Number::from_i64(1)),
+            _ => unary(f64::exp),
+        },
+        "ln" => match args {
+            [x] if x.is_negative() || x.is_zero() => Err(anyhow::anyhow!("Logarithm of a non-positive value")),
+            _ => unary(f64::ln),
+        },
+        "log" | "log10" | "log2" => {
+            let (value, base) = match (name, args) {
+                ("log", [x, b]) => (x, b.to_f64()),
+                ("log2", [x]) => (x, 2.0),
+                (_, [x]) => (x, 10.0),
+                _ => return Err(arity_error(name, 1, args.len())),
+            };
+            if value.is_negative() || value.is_zero() {
+                return Err(anyhow::anyhow!("Logarithm of a non-positive value"));
+            }
+            Number::approx(value.to_f64().ln() / base.ln())
+        }
+        "factorial" | "ncr" | "npr" | "choose" => super::stats::call_combinatoric(name, args),
+        _ => Err(anyhow::anyhow!("Unknown function '{}'", name)),
+    }
+}
+
+fn arity_error(name: &str, expected: usize, found: usize) -> anyhow::Error {
+    anyhow::anyhow!("{}() takes {} argument(s), got {}", name, expected, found)
+}
+
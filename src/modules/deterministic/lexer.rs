@@ -0,0 +1,247 @@
+// Tokenizer shared by the deterministic expression parsers
+
+/// Punctuation recognized by the lexer; multi-character symbols must precede their prefixes
+pub const SYMBOLS: &[&str] = &[
+    "<->", "->", ":-", "?-", "<<", ">>", "<=", ">=", "!=", "\\+", "<", ">", "+", "-", "*", "/", "^", "%", "(", ")", "[",
+    "]", ",", "=", ":", "&", "|", "~", "!", ".",
+];
+
+/// Input outside the grammar: where it is, what the grammar allows there and what was found
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub struct SyntaxError {
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+    /// 1-based line, for multi-line sources such as knowledge-base files
+    pub line: Option<usize>,
+    /// The file the source came from
+    pub file: Option<String>,
+}
+
+impl SyntaxError {
+    pub fn new(column: usize, expected: impl Into<String>, found: impl Into<String>) -> Self {
+        SyntaxError { column, expected: expected.into(), found: found.into(), line: None, file: None }
+    }
+
+    pub fn on_line(self, line: usize) -> Self {
+        SyntaxError { line: Some(line), ..self }
+    }
+
+    pub fn in_file(self, file: impl Into<String>) -> Self {
+        SyntaxError { file: Some(file.into()), ..self }
+    }
+}
+
+/// "kb/family.pl:3: Expected ')' at column 12, found '.'"
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        if let Some(line) = self.line {
+            write!(f, "{}: ", line)?;
+        } else if self.file.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "Expected {} at column {}, found {}", self.expected, self.column, self.found)
+    }
+}
+
+/// A syntax error from a grammar that read only `part` of `whole`, with its column moved
+/// to count from the start of `whole`. Errors past the first line of `part`, other errors,
+/// and parts that are not slices of `whole` pass through unchanged
+pub fn relocate(error: anyhow::Error, whole: &str, part: &str) -> anyhow::Error {
+    match (error.downcast::<SyntaxError>(), column_of(whole, part)) {
+        (Ok(error), Some(start)) if error.line.is_none_or(|line| line == 1) => {
+            SyntaxError { column: error.column + start - 1, line: None, ..error }.into()
+        }
+        (Ok(error), _) => error.into(),
+        (Err(error), _) => error,
+    }
+}
+
+/// The 1-based column where `part` starts, when it is a slice of `whole`
+pub fn column_of(whole: &str, part: &str) -> Option<usize> {
+    let start = (part.as_ptr() as usize).wrapping_sub(whole.as_ptr() as usize);
+    let before = whole.get(..start).filter(|_| start + part.len() <= whole.len())?;
+    Some(before.chars().count() + 1)
+}
+
+/// Characters that look like an operator the grammar spells differently
+const LOOKALIKES: &[(char, &str)] = &[
+    ('×', "*"), ('·', "*"), ('÷', "/"), ('−', "-"), ('≤', "<="), ('≥', ">="), ('≠', "!="), ('→', "->"),
+    ('⇒', "->"), ('↔', "<->"), ('⇔', "<->"), ('∧', "&"), ('∨', "|"), ('¬', "~"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    /// Numeric literal kept as source text so it can be parsed exactly; "0x", "0o" and
+    /// "0b" literals keep their prefix and any type suffix, e.g. "0xFFu8"
+    Number(String),
+    /// Name, possibly with apostrophes: "x", "y'", "what's"
+    Ident(String),
+    Symbol(&'static str),
+    /// Contents of a "double", “curly” or 'single' quoted string
+    Text(String),
+    /// Contents of a /regex/ literal
+    Pattern(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// 1-based character column of the first character of the token
+    pub column: usize,
+    /// Column just past the last character
+    pub end: usize,
+}
+
+impl Token {
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.kind, TokenKind::Symbol(s) if s == symbol)
+    }
+}
+
+/// Split `input` into tokens, rejecting characters outside the grammar
+pub fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    Ok(scan(input)?)
+}
+
+/// `tokenize` with the error kept typed
+pub fn scan(input: &str) -> Result<Vec<Token>, SyntaxError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '0' && is_radix_prefix(chars.get(i + 1), chars.get(i + 2)) {
+            let start = i;
+            i += 2;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token { kind: TokenKind::Number(text), column, end: i + 1 });
+            continue;
+        }
+
+        if let Some((close, pattern)) = quoted(&chars, i)? {
+            let text: String = chars[i + 1..close].iter().collect();
+            let kind = if pattern { TokenKind::Pattern(text) } else { TokenKind::Text(text) };
+            i = close + 1;
+            tokens.push(Token { kind, column, end: i + 1 });
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            if i < chars.len() && chars[i] == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()) {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            // Scientific notation only when an exponent actually follows
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if chars.get(j).is_some_and(|n| n.is_ascii_digit()) {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token { kind: TokenKind::Number(text), column, end: i + 1 });
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            // Apostrophes belong to the name: primes ("y'") and contractions ("what's")
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || is_apostrophe(chars[i])) {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token { kind: TokenKind::Ident(text), column, end: i + 1 });
+            continue;
+        }
+
+        let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+        match SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            Some(symbol) => {
+                i += symbol.chars().count();
+                tokens.push(Token { kind: TokenKind::Symbol(symbol), column, end: i + 1 });
+            }
+            None => {
+                let expected = match LOOKALIKES.iter().find(|(lookalike, _)| *lookalike == c) {
+                    Some((_, symbol)) => format!("'{}'", symbol),
+                    None if c == '?' => "'?-' or the end of the query".to_string(),
+                    None => "a number, name, operator or quoted text".to_string(),
+                };
+                return Err(SyntaxError::new(column, expected, format!("'{}'", c)));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// A quote or /regex/ opening at `i`: the index of its closing character and whether it
+/// is a pattern. Single quotes and slashes open only at the start
+/// of a word and close only at the end of one (or before a plural "s", as in "'r's"), so
+/// "it's", "6 / 3" and "7 // 2" are not quotes; an unclosed double quote is an error.
+fn quoted(chars: &[char], i: usize) -> Result<Option<(usize, bool)>, SyntaxError> {
+    let starts_word = i == 0 || chars[i - 1].is_whitespace() || "([{,:".contains(chars[i - 1]);
+    let (close, strict) = match chars[i] {
+        '"' => ('"', false),
+        '“' => ('”', false),
+        '\'' | '‘' if starts_word => (if chars[i] == '‘' { '’' } else { '\'' }, true),
+        '/' if starts_word && chars.get(i + 1).is_some_and(|c| !c.is_whitespace() && *c != '/') => ('/', true),
+        _ => return Ok(None),
+    };
+    let ends_word = |at: usize| chars.get(at).is_none_or(|c| !c.is_alphanumeric());
+    let closing = (i + 1..chars.len()).find(|&j| {
+        chars[j] == close
+            && !(close == '/' && chars[j - 1] == '\\')
+            && (!strict || ends_word(j + 1) || (chars.get(j + 1) == Some(&'s') && ends_word(j + 2)))
+    });
+    match closing {
+        Some(j) => Ok(Some((j, close == '/'))),
+        None if strict => Ok(None),
+        None => Err(SyntaxError::new(
+            chars.len() + 1,
+            format!("'{}'", close),
+            format!("end of input, with the quote from column {} still open", i + 1),
+        )),
+    }
+}
+
+/// "0x", "0o" or "0b" followed by a digit of that base
+fn is_radix_prefix(marker: Option<&char>, digit: Option<&char>) -> bool {
+    let radix = match marker {
+        Some('x' | 'X') => 16,
+        Some('o' | 'O') => 8,
+        Some('b' | 'B') => 2,
+        _ => return false,
+    };
+    digit.is_some_and(|d| d.is_digit(radix))
+}
@@ -0,0 +1,895 @@
+// Linear algebra: matrix and vector literals, products and decompositions
+use super::context::EvalContext;
+use super::expr::{BinOp, Evaluator, Expr, Parser};
+use super::error::{Limit, LimitError};
+use super::lexer::{tokenize, TokenKind};
+use super::number::Number;
+use super::solver::{self, Equation, Root, Solution, SolveRequest};
+use super::symbolic;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use std::fmt;
+
+/// Largest matrix whose eigenvalues are solved for exactly
+const MAX_EIGEN_SIZE: usize = 4;
+
+/// Largest matrix whose eigenvalues are approximated numerically
+const MAX_NUMERIC_EIGEN_SIZE: usize = 10;
+
+/// Sweeps of the Durand–Kerner iteration before giving up
+const MAX_EIGEN_ITERATIONS: usize = 1000;
+
+/// Largest exponent accepted in `A^n`
+const MAX_MATRIX_POWER: u64 = 1024;
+
+/// Pivots smaller than this are treated as zero when entries are approximate
+const APPROX_PIVOT_TOLERANCE: f64 = 1e-12;
+
+/// Functions that take matrix or vector arguments
+const MATRIX_FUNCTIONS: &[&str] = &[
+    "det", "determinant", "inv", "inverse", "transpose", "rank", "trace", "eigenvalues", "eig", "solve", "dot",
+    "cross",
+];
+
+/// Phrases rewritten to function calls, e.g. "determinant of A" → det(A)
+const PHRASES: &[(&str, &str)] = &[
+    ("determinant of", "det"),
+    ("inverse of", "inverse"),
+    ("transpose of", "transpose"),
+    ("rank of", "rank"),
+    ("trace of", "trace"),
+    ("eigenvalues of", "eigenvalues"),
+];
+
+/// Expression over scalars, vectors and matrices
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinExpr {
+    Scalar(Expr),
+    Vector(Vec<Expr>),
+    Matrix(Vec<Vec<Expr>>),
+    Neg(Box<LinExpr>),
+    Binary(BinOp, Box<LinExpr>, Box<LinExpr>),
+    Call(String, Vec<LinExpr>),
+}
+
+impl fmt::Display for LinExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |items: &[Expr]| items.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            LinExpr::Scalar(e) => write!(f, "{}", e),
+            LinExpr::Vector(items) => write!(f, "[{}]", join(items)),
+            LinExpr::Matrix(rows) => {
+                let rows: Vec<String> = rows.iter().map(|row| format!("[{}]", join(row))).collect();
+                write!(f, "[{}]", rows.join(", "))
+            }
+            LinExpr::Neg(inner) => write!(f, "-{}", inner),
+            LinExpr::Binary(op, lhs, rhs) => {
+                let operand = |e: &LinExpr| match e {
+                    LinExpr::Binary(..) => format!("({})", e),
+                    _ => e.to_string(),
+                };
+                write!(f, "{} {} {}", operand(lhs), op.symbol(), operand(rhs))
+            }
+            LinExpr::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+        }
+    }
+}
+
+/// Recognize queries containing matrix or vector literals
+pub fn parse_command(query: &str) -> Option<anyhow::Result<LinExpr>> {
+    let query = query.trim();
+    if !query.contains('[') {
+        return None;
+    }
+
+    // "solve A x = b" → solve(A, b)
+    if let Some(rest) = super::strip_keyword(query, "solve") {
+        if let Some((lhs, rhs)) = rest.split_once('=') {
+            return Some(parse_solve(lhs, rhs));
+        }
+    }
+
+    for (phrase, function) in PHRASES {
+        if let Some(rest) = super::strip_keyword(query, phrase) {
+            return Some(parse_text(&format!("{}({})", function, rest)));
+        }
+    }
+
+    // "det [[1, 2], [3, 4]]" without parentheses
+    if let Some((head, rest)) = query.split_once(char::is_whitespace) {
+        let head = head.to_lowercase();
+        if MATRIX_FUNCTIONS.contains(&head.as_str()) && rest.trim_start().starts_with('[') {
+            return Some(parse_text(&format!("{}({})", head, rest)));
+        }
+    }
+
+    Some(parse_text(query))
+}
+
+fn parse_solve(lhs: &str, rhs: &str) -> anyhow::Result<LinExpr> {
+    let lhs = lhs.trim();
+    let unknown_start = lhs
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    if unknown_start == lhs.len() {
+        return Err(anyhow::anyhow!("Expected 'solve A x = b' with an unknown after the matrix"));
+    }
+    let matrix = lhs[..unknown_start].trim_end();
+    let matrix = matrix.strip_suffix('*').unwrap_or(matrix);
+    parse_text(&format!("solve({}, {})", matrix, rhs.trim()))
+}
+
+fn parse_text(text: &str) -> anyhow::Result<LinExpr> {
+    let mut parser = Parser::new(tokenize(text)?);
+    let expr = parse_lin_expr(&mut parser)?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+
+/// lin_expr := lin_term (('+' | '-') lin_term)*
+fn parse_lin_expr(parser: &mut Parser) -> anyhow::Result<LinExpr> {
+    let mut lhs = parse_lin_term(parser)?;
+    loop {
+        let op = if parser.eat_symbol("+") {
+            BinOp::Add
+        } else if parser.eat_symbol("-") {
+            BinOp::Sub
+        } else {
+            return Ok(lhs);
+        };
+        let rhs = parse_lin_term(parser)?;
+        lhs = LinExpr::Binary(op, Box::new(lhs), Box::new(rhs));
+    }
+}
+
+/// lin_term := lin_unary (('*' | '/') lin_unary)*
+fn parse_lin_term(parser: &mut Parser) -> anyhow::Result<LinExpr> {
+    let mut lhs = parse_lin_unary(parser)?;
+    loop {
+        let op = if parser.eat_symbol("*") {
+            BinOp::Mul
+        } else if parser.eat_symbol("/") {
+            BinOp::Div
+        } else {
+            return Ok(lhs);
+        };
+        let rhs = parse_lin_unary(parser)?;
+        lhs = LinExpr::Binary(op, Box::new(lhs), Box::new(rhs));
+    }
+}
+
+/// lin_unary := '-' lin_unary | lin_primary ('^' '-'? power)?
+fn parse_lin_unary(parser: &mut Parser) -> anyhow::Result<LinExpr> {
+    if parser.eat_symbol("-") {
+        return Ok(LinExpr::Neg(Box::new(parse_lin_unary(parser)?)));
+    }
+    let base = parse_lin_primary(parser)?;
+    if parser.eat_symbol("^") {
+        let negative = parser.eat_symbol("-");
+        let mut exponent = parser.parse_power()?;
+        if negative {
+            exponent = Expr::Neg(Box::new(exponent));
+        }
+        return Ok(LinExpr::Binary(BinOp::Pow, Box::new(base), Box::new(LinExpr::Scalar(exponent))));
+    }
+    Ok(base)
+}
+
+fn parse_lin_primary(parser: &mut Parser) -> anyhow::Result<LinExpr> {
+    let Some(token) = parser.peek().cloned() else {
+        return Err(parser.error("a matrix, vector or number"));
+    };
+
+    match &token.kind {
+        TokenKind::Symbol("[") => parse_literal(parser),
+        TokenKind::Symbol("(") => {
+            parser.advance();
+            let inner = parse_lin_expr(parser)?;
+            parser.expect_symbol(")")?;
+            Ok(inner)
+        }
+        TokenKind::Ident(name) if MATRIX_FUNCTIONS.contains(&name.to_lowercase().as_str()) => {
+            parser.advance();
+            parser.expect_symbol("(")?;
+            let mut args = vec![parse_lin_expr(parser)?];
+            while parser.eat_symbol(",") {
+                args.push(parse_lin_expr(parser)?);
+            }
+            parser.expect_symbol(")")?;
+            Ok(LinExpr::Call(name.to_lowercase(), args))
+        }
+        _ => Ok(LinExpr::Scalar(parser.parse_power()?)),
+    }
+}
+
+/// "[1, 2]" is a vector, "[[1, 2], [3, 4]]" a matrix given row by row
+fn parse_literal(parser: &mut Parser) -> anyhow::Result<LinExpr> {
+    parser.expect_symbol("[")?;
+    if parser.peek().is_some_and(|t| t.is_symbol("[")) {
+        let mut rows = Vec::new();
+        loop {
+            parser.expect_symbol("[")?;
+            rows.push(parse_entries(parser)?);
+            if !parser.eat_symbol(",") {
+                parser.expect_symbol("]")?;
+                return Ok(LinExpr::Matrix(rows));
+            }
+        }
+    }
+    Ok(LinExpr::Vector(parse_entries(parser)?))
+}
+
+/// Comma-separated scalar entries up to and including the closing ']'
+fn parse_entries(parser: &mut Parser) -> anyhow::Result<Vec<Expr>> {
+    let mut entries = vec![parser.parse_expr()?];
+    while parser.eat_symbol(",") {
+        entries.push(parser.parse_expr()?);
+    }
+    parser.expect_symbol("]")?;
+    Ok(entries)
+}
+
+/// Dense row-major matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<Number>,
+}
+
+impl Matrix {
+    pub fn from_rows(rows: Vec<Vec<Number>>) -> anyhow::Result<Self> {
+        let cols = rows.first().map(|r| r.len()).unwrap_or(0);
+        if cols == 0 {
+            return Err(anyhow::anyhow!("Matrix must have at least one entry"));
+        }
+        if let Some(bad) = rows.iter().position(|r| r.len() != cols) {
+            return Err(anyhow::anyhow!(
+                "Matrix rows must have equal length: row 1 has {} entries, row {} has {}",
+                cols,
+                bad + 1,
+                rows[bad].len()
+            ));
+        }
+        Ok(Matrix { rows: rows.len(), cols, data: rows.into_iter().flatten().collect() })
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let data = (0..n * n).map(|i| Number::from_i64(i64::from(i % (n + 1) == 0))).collect();
+        Matrix { rows: n, cols: n, data }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &Number {
+        &self.data[row * self.cols + col]
+    }
+
+    fn set(&mut self, row: usize, col: usize, value: Number) {
+        self.data[row * self.cols + col] = value;
+    }
+
+    pub fn row_vectors(&self) -> Vec<Vec<Number>> {
+        self.data.chunks(self.cols).map(|row| row.to_vec()).collect()
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.data.iter().all(Number::is_exact)
+    }
+
+    fn shape(&self) -> String {
+        format!("{}x{}", self.rows, self.cols)
+    }
+
+    fn require_square(&self, operation: &str) -> anyhow::Result<()> {
+        if self.is_square() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{} needs a square matrix, found {}", operation, self.shape()))
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut data = Vec::with_capacity(self.data.len());
+        for c in 0..self.cols {
+            for r in 0..self.rows {
+                data.push(self.get(r, c).clone());
+            }
+        }
+        Matrix { rows: self.cols, cols: self.rows, data }
+    }
+
+    fn zip_with(&self, other: &Matrix, op: BinOp) -> anyhow::Result<Matrix> {
+        if (self.rows, self.cols) != (other.rows, other.cols) {
+            return Err(anyhow::anyhow!(
+                "Cannot {} matrices of shapes {} and {}",
+                if op == BinOp::Add { "add" } else { "subtract" },
+                self.shape(),
+                other.shape()
+            ));
+        }
+        let data = self
+            .data
+            .iter()
+            .zip(&other.data)
+            .map(|(a, b)| super::expr::apply_binary(op, a, b))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Matrix { rows: self.rows, cols: self.cols, data })
+    }
+
+    pub fn scale(&self, factor: &Number) -> anyhow::Result<Matrix> {
+        let data = self.data.iter().map(|x| x.mul(factor)).collect::<anyhow::Result<_>>()?;
+        Ok(Matrix { rows: self.rows, cols: self.cols, data })
+    }
+
+    pub fn mul(&self, other: &Matrix) -> anyhow::Result<Matrix> {
+        if self.cols != other.rows {
+            return Err(anyhow::anyhow!(
+                "Cannot multiply {} by {}: inner dimensions differ",
+                self.shape(),
+                other.shape()
+            ));
+        }
+        let mut data = Vec::with_capacity(self.rows * other.cols);
+        for r in 0..self.rows {
+            for c in 0..other.cols {
+                let mut sum = Number::from_i64(0);
+                for k in 0..self.cols {
+                    sum = sum.add(&self.get(r, k).mul(other.get(k, c))?)?;
+                }
+                data.push(sum);
+            }
+        }
+        Ok(Matrix { rows: self.rows, cols: other.cols, data })
+    }
+
+    pub fn trace(&self) -> anyhow::Result<Number> {
+        self.require_square("trace")?;
+        (0..self.rows).try_fold(Number::from_i64(0), |sum, i| sum.add(self.get(i, i)))
+    }
+
+    /// Reduced row echelon form, the pivot columns and the determinant factor
+    /// accumulated from row swaps and scalings
+    fn rref(&self) -> anyhow::Result<(Matrix, Vec<usize>, Number)> {
+        let mut m = self.clone();
+        let mut pivots = Vec::new();
+        let mut factor = Number::from_i64(1);
+        let mut row = 0;
+
+        for col in 0..m.cols {
+            if row == m.rows {
+                break;
+            }
+            // Largest magnitude pivot keeps approximate entries stable
+            let Some(pivot) = (row..m.rows)
+                .filter(|&r| !negligible(m.get(r, col)))
+                .max_by(|&a, &b| m.get(a, col).to_f64().abs().total_cmp(&m.get(b, col).to_f64().abs()))
+            else {
+                continue;
+            };
+            if pivot != row {
+                for c in 0..m.cols {
+                    m.data.swap(pivot * m.cols + c, row * m.cols + c);
+                }
+                factor = factor.neg();
+            }
+
+            let lead = m.get(row, col).clone();
+            factor = factor.mul(&lead)?;
+            for c in 0..m.cols {
+                let value = m.get(row, c).div(&lead)?;
+                m.set(row, c, value);
+            }
+            for r in 0..m.rows {
+                let scale = m.get(r, col).clone();
+                if r == row || scale.is_zero() {
+                    continue;
+                }
+                for c in 0..m.cols {
+                    let value = m.get(r, c).sub(&scale.mul(m.get(row, c))?)?;
+                    m.set(r, c, value);
+                }
+            }
+            pivots.push(col);
+            row += 1;
+        }
+        Ok((m, pivots, factor))
+    }
+
+    pub fn determinant(&self) -> anyhow::Result<Number> {
+        self.require_square("determinant")?;
+        let (_, pivots, factor) = self.rref()?;
+        if pivots.len() < self.rows {
+            return Ok(Number::from_i64(0));
+        }
+        Ok(factor)
+    }
+
+    pub fn rank(&self) -> anyhow::Result<usize> {
+        Ok(self.rref()?.1.len())
+    }
+
+    pub fn inverse(&self) -> anyhow::Result<Matrix> {
+        self.require_square("inverse")?;
+        let n = self.rows;
+        let augmented = self.augment(&Matrix::identity(n))?;
+        let (reduced, pivots, _) = augmented.rref()?;
+        if pivots.len() < n || pivots[n - 1] >= n {
+            return Err(anyhow::anyhow!("Matrix is singular (determinant 0) and has no inverse"));
+        }
+        let data = (0..n).flat_map(|r| (n..2 * n).map(move |c| (r, c))).map(|(r, c)| reduced.get(r, c).clone()).collect();
+        Ok(Matrix { rows: n, cols: n, data })
+    }
+
+    fn augment(&self, other: &Matrix) -> anyhow::Result<Matrix> {
+        if self.rows != other.rows {
+            return Err(anyhow::anyhow!(
+                "Right-hand side has {} rows but the matrix has {}",
+                other.rows,
+                self.rows
+            ));
+        }
+        let rows = self
+            .row_vectors()
+            .into_iter()
+            .zip(other.row_vectors())
+            .map(|(mut left, right)| {
+                left.extend(right);
+                left
+            })
+            .collect();
+        Matrix::from_rows(rows)
+    }
+
+    /// Solve `self * x = rhs` for a unique x
+    pub fn solve(&self, rhs: &[Number]) -> anyhow::Result<Vec<Number>> {
+        let column = Matrix { rows: rhs.len(), cols: 1, data: rhs.to_vec() };
+        let (reduced, pivots, _) = self.augment(&column)?.rref()?;
+        if pivots.contains(&self.cols) {
+            return Err(anyhow::anyhow!("System is inconsistent: no solution"));
+        }
+        if pivots.len() < self.cols {
+            return Err(anyhow::anyhow!(
+                "System has infinitely many solutions (rank {} < {} unknowns)",
+                pivots.len(),
+                self.cols
+            ));
+        }
+        Ok((0..self.cols).map(|r| reduced.get(r, self.cols).clone()).collect())
+    }
+
+    pub fn pow(&self, exponent: i64) -> anyhow::Result<Matrix> {
+        self.require_square("matrix power")?;
+        if exponent.unsigned_abs() > MAX_MATRIX_POWER {
+            return Err(anyhow::anyhow!("Matrix exponent {} exceeds {}", exponent, MAX_MATRIX_POWER));
+        }
+        let mut base = if exponent < 0 { self.inverse()? } else { self.clone() };
+        let mut remaining = exponent.unsigned_abs();
+        let mut result = Matrix::identity(self.rows);
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            base = base.mul(&base)?;
+            remaining >>= 1;
+        }
+        Ok(result)
+    }
+
+    /// Coefficients c0..cn of det(lambda*I - A) by the Faddeev–LeVerrier recurrence
+    pub fn characteristic_polynomial(&self) -> anyhow::Result<Vec<Number>> {
+        self.require_square("characteristic polynomial")?;
+        let n = self.rows;
+        let mut coeffs = vec![Number::from_i64(0); n + 1];
+        coeffs[n] = Number::from_i64(1);
+        let mut m = Matrix { rows: n, cols: n, data: vec![Number::from_i64(0); n * n] };
+        for k in 1..=n {
+            let shifted = m.zip_with(&Matrix::identity(n).scale(&coeffs[n - k + 1])?, BinOp::Add)?;
+            m = self.mul(&shifted)?;
+            coeffs[n - k] = m.trace()?.div(&Number::from_i64(k as i64))?.neg();
+        }
+        Ok(coeffs)
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = self.row_vectors().iter().map(|row| format_vector(row)).collect();
+        write!(f, "[{}]", rows.join(", "))
+    }
+}
+
+pub fn format_vector(items: &[Number]) -> String {
+    format!("[{}]", items.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", "))
+}
+
+fn negligible(value: &Number) -> bool {
+    match value {
+        Number::Exact(_) => value.is_zero(),
+        Number::Approx(f) => f.abs() < APPROX_PIVOT_TOLERANCE,
+    }
+}
+
+/// Result of a linear algebra expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(Number),
+    Vector(Vec<Number>),
+    Matrix(Matrix),
+    /// Eigenvalues, which may be irrational or complex
+    Roots(Vec<Root>),
+    /// Eigenvalues approximated numerically, as (real, imaginary) parts
+    ApproxRoots(Vec<(f64, f64)>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Scalar(n) => write!(f, "{}", n),
+            Value::Vector(items) => write!(f, "{}", format_vector(items)),
+            Value::Matrix(m) => write!(f, "{}", m),
+            Value::Roots(roots) => {
+                write!(f, "[{}]", roots.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", "))
+            }
+            Value::ApproxRoots(roots) => write!(f, "[{}]", roots.iter().map(|&r| format_complex(r)).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Scalar(_) => "scalar",
+            Value::Vector(_) => "vector",
+            Value::Matrix(_) => "matrix",
+            Value::Roots(_) | Value::ApproxRoots(_) => "eigenvalue list",
+        }
+    }
+}
+
+/// Evaluates linear algebra expressions, recording each matrix operation
+pub struct LinEvaluator<'a> {
+    scalars: Evaluator<'a>,
+    pub steps: Vec<String>,
+}
+
+impl<'a> LinEvaluator<'a> {
+    pub fn new(context: &'a EvalContext) -> Self {
+        LinEvaluator { scalars: Evaluator::with_context(context), steps: Vec::new() }
+    }
+
+    pub fn evaluate(&mut self, expr: &LinExpr) -> anyhow::Result<Value> {
+        match expr {
+            LinExpr::Scalar(e) => Ok(Value::Scalar(self.scalars.evaluate(e)?)),
+            LinExpr::Vector(items) => {
+                Ok(Value::Vector(items.iter().map(|e| self.scalars.evaluate(e)).collect::<anyhow::Result<_>>()?))
+            }
+            LinExpr::Matrix(rows) => {
+                let rows = rows
+                    .iter()
+                    .map(|row| row.iter().map(|e| self.scalars.evaluate(e)).collect::<anyhow::Result<Vec<_>>>())
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok(Value::Matrix(Matrix::from_rows(rows)?))
+            }
+            LinExpr::Neg(inner) => {
+                let minus_one = Number::from_i64(-1);
+                match self.evaluate(inner)? {
+                    Value::Scalar(n) => Ok(Value::Scalar(n.neg())),
+                    Value::Vector(v) => Ok(Value::Vector(v.iter().map(Number::neg).collect())),
+                    Value::Matrix(m) => Ok(Value::Matrix(m.scale(&minus_one)?)),
+                    other => Err(anyhow::anyhow!("Cannot negate an {}", other.kind())),
+                }
+            }
+            LinExpr::Binary(op, lhs, rhs) => {
+                let (a, b) = (self.evaluate(lhs)?, self.evaluate(rhs)?);
+                let result = self.binary(*op, a, b)?;
+                if matches!(result, Value::Matrix(_) | Value::Vector(_)) {
+                    self.steps.push(format!("{} = {}", expr, result));
+                }
+                Ok(result)
+            }
+            LinExpr::Call(name, args) => {
+                let values = args.iter().map(|a| self.evaluate(a)).collect::<anyhow::Result<Vec<_>>>()?;
+                let result = self.call(name, values)?;
+                self.steps.push(format!("{} = {}", expr, result));
+                Ok(result)
+            }
+        }
+    }
+
+    fn binary(&mut self, op: BinOp, a: Value, b: Value) -> anyhow::Result<Value> {
+        use Value::{Matrix as M, Scalar as S, Vector as V};
+        match (op, a, b) {
+            (_, S(x), S(y)) => Ok(S(super::expr::apply_binary(op, &x, &y)?)),
+            (BinOp::Add | BinOp::Sub, M(x), M(y)) => Ok(M(x.zip_with(&y, op)?)),
+            (BinOp::Add | BinOp::Sub, V(x), V(y)) => {
+                let (x, y) = (column(&x), column(&y));
+                let sum = x.zip_with(&y, op).map_err(|_| {
+                    anyhow::anyhow!("Cannot combine vectors of lengths {} and {}", x.rows(), y.rows())
+                })?;
+                Ok(V(sum.data))
+            }
+            (BinOp::Mul, S(k), M(m)) | (BinOp::Mul, M(m), S(k)) => Ok(M(m.scale(&k)?)),
+            (BinOp::Mul, S(k), V(v)) | (BinOp::Mul, V(v), S(k)) => {
+                Ok(V(v.iter().map(|x| x.mul(&k)).collect::<anyhow::Result<_>>()?))
+            }
+            (BinOp::Div, M(m), S(k)) => Ok(M(m.scale(&Number::from_i64(1).div(&k)?)?)),
+            (BinOp::Div, V(v), S(k)) => Ok(V(v.iter().map(|x| x.div(&k)).collect::<anyhow::Result<_>>()?)),
+            (BinOp::Mul, M(x), M(y)) => Ok(M(x.mul(&y)?)),
+            (BinOp::Mul, M(x), V(v)) => Ok(V(x.mul(&column(&v))?.data)),
+            (BinOp::Mul, V(v), M(y)) => Ok(V(column(&v).transpose().mul(&y)?.data)),
+            (BinOp::Mul, V(_), V(_)) => Err(anyhow::anyhow!("Use dot(u, v) or cross(u, v) to multiply two vectors")),
+            (BinOp::Pow, M(m), S(k)) => {
+                let exponent = k
+                    .as_integer()
+                    .and_then(|n| n.to_i64())
+                    .ok_or_else(|| anyhow::anyhow!("Matrix powers need an integer exponent, found {}", k))?;
+                Ok(M(m.pow(exponent)?))
+            }
+            (op, a, b) => Err(anyhow::anyhow!(
+                "Operator '{}' is not defined for {} and {}",
+                op.symbol(),
+                a.kind(),
+                b.kind()
+            )),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Value>) -> anyhow::Result<Value> {
+        match (name, args.as_slice()) {
+            ("det" | "determinant", [Value::Matrix(m)]) => Ok(Value::Scalar(m.determinant()?)),
+            ("inv" | "inverse", [Value::Matrix(m)]) => Ok(Value::Matrix(m.inverse()?)),
+            ("transpose", [Value::Matrix(m)]) => Ok(Value::Matrix(m.transpose())),
+            ("transpose", [Value::Vector(v)]) => Ok(Value::Matrix(column(v).transpose())),
+            ("rank", [Value::Matrix(m)]) => Ok(Value::Scalar(Number::from_i64(m.rank()? as i64))),
+            ("trace", [Value::Matrix(m)]) => Ok(Value::Scalar(m.trace()?)),
+            ("eigenvalues" | "eig", [Value::Matrix(m)]) => self.eigenvalues(m),
+            ("solve", [Value::Matrix(a), Value::Vector(b)]) => {
+                let x = a.solve(b)?;
+                self.steps.push(format!("check: {} * {} = {}", a, format_vector(&x), format_vector(b)));
+                Ok(Value::Vector(x))
+            }
+            ("dot", [Value::Vector(u), Value::Vector(v)]) => {
+                if u.len() != v.len() {
+                    return Err(anyhow::anyhow!("dot() needs vectors of equal length, found {} and {}", u.len(), v.len()));
+                }
+                let sum = u.iter().zip(v).try_fold(Number::from_i64(0), |sum, (a, b)| sum.add(&a.mul(b)?))?;
+                Ok(Value::Scalar(sum))
+            }
+            ("cross", [Value::Vector(u), Value::Vector(v)]) if u.len() == 3 && v.len() == 3 => {
+                let component = |i: usize, j: usize| u[i].mul(&v[j])?.sub(&u[j].mul(&v[i])?);
+                Ok(Value::Vector(vec![component(1, 2)?, component(2, 0)?, component(0, 1)?]))
+            }
+            _ => {
+                let kinds: Vec<&str> = args.iter().map(Value::kind).collect();
+                Err(anyhow::anyhow!("{}() is not defined for ({})", name, kinds.join(", ")))
+            }
+        }
+    }
+
+    /// Exact roots of the characteristic polynomial where a closed form exists, otherwise
+    /// numeric approximations
+    fn eigenvalues(&mut self, m: &Matrix) -> anyhow::Result<Value> {
+        m.require_square("eigenvalues")?;
+        if m.rows() > MAX_NUMERIC_EIGEN_SIZE {
+            return Err(anyhow::anyhow!(
+                "Eigenvalues are supported up to {}x{} matrices, found {}",
+                MAX_NUMERIC_EIGEN_SIZE,
+                MAX_NUMERIC_EIGEN_SIZE,
+                m.shape()
+            ));
+        }
+
+        let coeffs = m.characteristic_polynomial()?;
+        if m.is_exact() && m.rows() <= MAX_EIGEN_SIZE {
+            match self.exact_eigenvalues(&coeffs) {
+                Ok(roots) => return Ok(roots),
+                Err(e) => self.steps.push(format!("{}; approximating numerically", e)),
+            }
+        } else {
+            self.steps.push(format!("characteristic polynomial coefficients, constant first: {}", format_vector(&coeffs)));
+        }
+
+        // Repeated roots would converge slowly and imprecisely; with exact coefficients
+        // they are divided out first, leaving each eigenvalue once, and rational roots
+        // (zero above all) are split off so they come out exactly rather than as noise
+        let (mut roots, coeffs): (Vec<(f64, f64)>, Vec<f64>) =
+            match coeffs.iter().map(Number::as_rational).collect::<Option<Vec<_>>>() {
+                Some(exact) => {
+                    let exact: Vec<BigRational> = exact.into_iter().cloned().collect();
+                    let (rational, rest) = symbolic::split_rational_roots(&square_free(&exact));
+                    if !rational.is_empty() {
+                        let listed: Vec<String> = rational.iter().map(|r| r.to_string()).collect();
+                        self.steps.push(format!("rational roots: {}", listed.join(", ")));
+                    }
+                    let lead = rest.last().cloned().unwrap_or_else(|| BigRational::from_integer(1.into()));
+                    let rest = rest.iter().map(|c| (c / &lead).to_f64().unwrap_or(f64::NAN)).collect();
+                    (rational.iter().map(|r| (r.to_f64().unwrap_or(f64::NAN), 0.0)).collect(), rest)
+                }
+                None => (Vec::new(), coeffs.iter().map(Number::to_f64).collect()),
+            };
+        if coeffs.len() > 1 {
+            let (approximate, sweeps) = approximate_roots(&coeffs)?;
+            self.steps.push(format!("Durand–Kerner iteration converged after {} sweeps", sweeps));
+            roots.extend(approximate);
+        }
+        roots.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1)));
+        Ok(Value::ApproxRoots(roots))
+    }
+
+    fn exact_eigenvalues(&mut self, coeffs: &[Number]) -> anyhow::Result<Value> {
+        let polynomial = coeffs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(power, c)| {
+                let c = Expr::Num(c.as_rational().cloned().unwrap_or_else(|| BigRational::from_integer(0.into())));
+                let term = match power {
+                    0 => return c,
+                    1 => Expr::Var("lambda".to_string()),
+                    p => Expr::binary(BinOp::Pow, Expr::Var("lambda".to_string()), Expr::integer(p as i64)),
+                };
+                Expr::binary(BinOp::Mul, c, term)
+            })
+            .reduce(|acc, term| Expr::binary(BinOp::Add, acc, term))
+            .unwrap_or_else(|| Expr::integer(0));
+        let polynomial = symbolic::to_poly(&polynomial, &mut Vec::new()).map(|p| p.to_expr()).unwrap_or(polynomial);
+
+        let request = SolveRequest {
+            equations: vec![Equation { lhs: polynomial, rhs: Expr::integer(0) }],
+            target: Some("lambda".to_string()),
+        };
+        self.steps.push(format!("characteristic polynomial: {}", request.equations[0]));
+        let outcome = solver::solve(&request)?;
+        self.steps.extend(outcome.steps);
+        match outcome.solution {
+            Solution::Finite(solutions) => {
+                Ok(Value::Roots(solutions.into_iter().flat_map(|s| s.into_iter().map(|(_, root)| root)).collect()))
+            }
+            other => Err(anyhow::anyhow!("Unexpected characteristic equation result: {}", other.summary())),
+        }
+    }
+}
+
+/// Roots of the monic polynomial with `coeffs` (constant term first), refined together
+/// by Durand–Kerner iteration; returns them with the number of sweeps taken
+fn approximate_roots(coeffs: &[f64]) -> anyhow::Result<(Vec<(f64, f64)>, usize)> {
+    type Complex = (f64, f64);
+    let mul = |a: Complex, b: Complex| (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0);
+    let div = |a: Complex, b: Complex| {
+        let norm = b.0 * b.0 + b.1 * b.1;
+        ((a.0 * b.0 + a.1 * b.1) / norm, (a.1 * b.0 - a.0 * b.1) / norm)
+    };
+    let eval = |z: Complex| coeffs.iter().rev().fold((0.0, 0.0), |acc, &c| {
+        let product = mul(acc, z);
+        (product.0 + c, product.1)
+    });
+
+    // Start on a circle holding every root (Cauchy's bound), off the real axis so
+    // conjugate pairs can separate
+    let n = coeffs.len() - 1;
+    let radius = 1.0 + coeffs[..n].iter().fold(0.0f64, |max, c| max.max(c.abs()));
+    let mut roots: Vec<Complex> = (0..n)
+        .map(|k| {
+            let angle = 2.0 * std::f64::consts::PI * k as f64 / n as f64 + 0.4;
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect();
+
+    for sweep in 1..=MAX_EIGEN_ITERATIONS {
+        let mut change = 0.0f64;
+        for i in 0..n {
+            let others = (0..n)
+                .filter(|&j| j != i)
+                .fold((1.0, 0.0), |acc, j| mul(acc, (roots[i].0 - roots[j].0, roots[i].1 - roots[j].1)));
+            let step = div(eval(roots[i]), others);
+            roots[i] = (roots[i].0 - step.0, roots[i].1 - step.1);
+            change = change.max(step.0.hypot(step.1) / (1.0 + roots[i].0.hypot(roots[i].1)));
+        }
+        if change < 1e-14 {
+            return Ok((tidy_roots(roots, radius), sweep));
+        }
+    }
+
+    // Repeated roots converge slowly and only to about the square root of machine precision
+    let scale = |z: Complex| coeffs.iter().rev().fold(0.0, |acc, c| acc * z.0.hypot(z.1) + c.abs());
+    if roots.iter().all(|&z| eval(z).0.hypot(eval(z).1) <= 1e-9 * scale(z)) {
+        return Ok((tidy_roots(roots, radius), MAX_EIGEN_ITERATIONS));
+    }
+    let message = format!("Eigenvalue iteration did not converge within {} sweeps", MAX_EIGEN_ITERATIONS);
+    Err(LimitError::new(Limit::Iterations, message).into())
+}
+
+/// The monic polynomial with the same roots as `p`, each once: p / gcd(p, p')
+fn square_free(p: &[BigRational]) -> Vec<BigRational> {
+    let derivative: Vec<BigRational> =
+        p.iter().enumerate().skip(1).map(|(power, c)| c * BigRational::from_integer(power.into())).collect();
+    let (mut a, mut b) = (p.to_vec(), trimmed(derivative));
+    while !b.is_empty() {
+        let remainder = poly_div(&a, &b).1;
+        a = std::mem::replace(&mut b, remainder);
+    }
+    let quotient = poly_div(p, &a).0;
+    let lead = quotient.last().cloned().unwrap_or_else(|| BigRational::from_integer(1.into()));
+    quotient.iter().map(|c| c / &lead).collect()
+}
+
+/// Quotient and remainder of polynomials, coefficients constant term first
+fn poly_div(a: &[BigRational], b: &[BigRational]) -> (Vec<BigRational>, Vec<BigRational>) {
+    let lead = b.last().expect("a nonzero divisor");
+    let mut quotient = vec![BigRational::from_integer(0.into()); (a.len() + 1).saturating_sub(b.len())];
+    let mut remainder = a.to_vec();
+    while remainder.len() >= b.len() {
+        let shift = remainder.len() - b.len();
+        let factor = remainder[remainder.len() - 1].clone() / lead;
+        for (i, c) in b.iter().enumerate() {
+            remainder[shift + i] -= &factor * c;
+        }
+        quotient[shift] = factor;
+        remainder.pop();
+        remainder = trimmed(remainder);
+    }
+    (quotient, remainder)
+}
+
+/// Without leading zero coefficients
+fn trimmed(mut p: Vec<BigRational>) -> Vec<BigRational> {
+    while p.last().is_some_and(|c| c.is_zero()) {
+        p.pop();
+    }
+    p
+}
+
+/// Round to 12 significant digits, drop parts that are rounding noise next to `bound`, the
+/// largest root magnitude possible, and order by real part, the upper member of a
+/// conjugate pair first
+fn tidy_roots(roots: Vec<(f64, f64)>, bound: f64) -> Vec<(f64, f64)> {
+    let round = |x: f64| format!("{:.11e}", x).parse::<f64>().unwrap_or(x);
+    let clean = |part: f64| if part.abs() <= 1e-10 * bound { 0.0 } else { round(part) };
+    let mut roots: Vec<(f64, f64)> = roots.into_iter().map(|(re, im)| (clean(re), clean(im))).collect();
+    roots.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1)));
+    roots
+}
+
+/// "1.5", "2i" or "1.5 - 2i"
+pub fn format_complex((re, im): (f64, f64)) -> String {
+    let magnitude = if im.abs() == 1.0 { String::new() } else { format_real(im.abs()) };
+    if im == 0.0 {
+        format_real(re)
+    } else if re == 0.0 {
+        format!("{}{}i", if im < 0.0 { "-" } else { "" }, magnitude)
+    } else {
+        format!("{} {} {}i", format_real(re), if im < 0.0 { "-" } else { "+" }, magnitude)
+    }
+}
+
+/// At most 12 significant digits, in scientific notation outside 1e-6..1e15
+fn format_real(x: f64) -> String {
+    let scientific = format!("{:.11e}", x);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    match exponent.parse::<i32>() {
+        Ok(exponent) if x != 0.0 && !(-6..15).contains(&exponent) => {
+            format!("{}e{}", mantissa.trim_end_matches('0').trim_end_matches('.'), exponent)
+        }
+        _ => scientific.parse::<f64>().unwrap_or(x).to_string(),
+    }
+}
+
+fn column(items: &[Number]) -> Matrix {
+    Matrix { rows: items.len(), cols: 1, data: items.to_vec() }
+}
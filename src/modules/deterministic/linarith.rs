@@ -0,0 +1,788 @@
+// Linear arithmetic claims: Fourier–Motzkin elimination over the reals, branch-and-bound over the integers
+use super::constraints::{Comparison, COMPARISONS};
+use super::expr::{BinOp, Evaluator, Expr, Parser};
+use super::lexer::tokenize;
+use super::number::Number;
+use super::prolog::plural;
+use super::{split_keyword, strip_keyword, strip_suffix_phrase, symbolic};
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Cases the negated claim may split into before the check is refused
+const MAX_CASES: usize = 256;
+
+/// Constraints allowed after any one elimination step
+const MAX_ROWS: usize = 2000;
+
+/// Branch-and-bound subproblems explored over the integers before answering "unknown"
+const MAX_BRANCHES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    Real,
+    Integer,
+}
+
+impl Sort {
+    fn plural(&self) -> &'static str {
+        match self {
+            Sort::Real => "reals",
+            Sort::Integer => "integers",
+        }
+    }
+}
+
+/// Comparisons of linear terms joined by connectives
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    Compare(Expr, Comparison, Expr),
+    Not(Box<Formula>),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    fn precedence(&self) -> u8 {
+        match self {
+            Formula::Implies(..) => 0,
+            Formula::Or(..) => 1,
+            Formula::And(..) => 2,
+            Formula::Not(_) | Formula::Compare(..) => 3,
+        }
+    }
+
+    pub fn variables(&self) -> BTreeSet<String> {
+        match self {
+            Formula::Compare(lhs, _, rhs) => lhs.free_variables().into_iter().chain(rhs.free_variables()).collect(),
+            Formula::Not(inner) => inner.variables(),
+            Formula::And(a, b) | Formula::Or(a, b) | Formula::Implies(a, b) => {
+                a.variables().into_iter().chain(b.variables()).collect()
+            }
+        }
+    }
+
+    /// Fails on the first comparison whose sides are not linear
+    fn check_linear(&self) -> anyhow::Result<()> {
+        match self {
+            Formula::Compare(lhs, comparison, rhs) => difference(lhs, *comparison, rhs).map(drop),
+            Formula::Not(inner) => inner.check_linear(),
+            Formula::And(a, b) | Formula::Or(a, b) | Formula::Implies(a, b) => {
+                a.check_linear()?;
+                b.check_linear()
+            }
+        }
+    }
+
+    /// Truth value with every variable bound exactly
+    fn holds(&self, model: &BTreeMap<String, BigRational>) -> anyhow::Result<bool> {
+        Ok(match self {
+            Formula::Compare(lhs, comparison, rhs) => {
+                let evaluator = model
+                    .iter()
+                    .fold(Evaluator::new(), |e, (name, value)| e.with_variable(name, Number::Exact(value.clone())));
+                let (lhs, rhs) = (evaluator.evaluate(lhs)?, evaluator.evaluate(rhs)?);
+                let difference = lhs.sub(&rhs)?;
+                let sign = match (difference.is_zero(), difference.is_negative()) {
+                    (true, _) => 0,
+                    (false, true) => -1,
+                    (false, false) => 1,
+                };
+                comparison_holds(*comparison, sign)
+            }
+            Formula::Not(inner) => !inner.holds(model)?,
+            Formula::And(a, b) => a.holds(model)? && b.holds(model)?,
+            Formula::Or(a, b) => a.holds(model)? || b.holds(model)?,
+            Formula::Implies(a, b) => !a.holds(model)? || b.holds(model)?,
+        })
+    }
+}
+
+/// Whether `comparison` holds between two values whose difference has sign `sign`
+fn comparison_holds(comparison: Comparison, sign: i32) -> bool {
+    match comparison {
+        Comparison::Eq => sign == 0,
+        Comparison::Ne => sign != 0,
+        Comparison::Lt => sign < 0,
+        Comparison::Le => sign <= 0,
+        Comparison::Gt => sign > 0,
+        Comparison::Ge => sign >= 0,
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let child = |inner: &Formula, min: u8| match inner.precedence() < min {
+            true => format!("({})", inner),
+            false => inner.to_string(),
+        };
+        match self {
+            Formula::Compare(lhs, comparison, rhs) => write!(f, "{} {} {}", lhs, comparison.symbol(), rhs),
+            Formula::Not(inner) => write!(f, "not {}", child(inner, 4)),
+            Formula::And(a, b) => write!(f, "{} and {}", child(a, 2), child(b, 2)),
+            Formula::Or(a, b) => write!(f, "{} or {}", child(a, 1), child(b, 1)),
+            Formula::Implies(a, b) => write!(f, "{} implies {}", child(a, 1), child(b, 0)),
+        }
+    }
+}
+
+/// A claim that `formula` holds for every assignment of its variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArithClaim {
+    pub formula: Formula,
+    pub sort: Sort,
+}
+
+/// Phrases naming the domain of the variables, at either end of a claim
+const SORTS: &[(&str, Sort)] = &[
+    ("over the integers", Sort::Integer),
+    ("over integers", Sort::Integer),
+    ("for all integers", Sort::Integer),
+    ("for integers", Sort::Integer),
+    ("over the reals", Sort::Real),
+    ("over reals", Sort::Real),
+    ("for all reals", Sort::Real),
+    ("for all real numbers", Sort::Real),
+];
+
+/// Words after "is F" that ask whether the claim holds everywhere
+const VALIDITY: &[&str] = &["always true", "true", "valid"];
+
+/// Recognize "if A then B", "A implies B", "for all [integers] x, y: F", "is F valid" and
+/// bare inequalities such as "x != y", where the formulas compare linear terms
+pub fn parse_command(query: &str) -> Option<anyhow::Result<ArithClaim>> {
+    let mut text = query.trim().trim_end_matches(['?', '.']).trim_end();
+    let mut sort = None;
+    if let Some((rest, found)) = SORTS.iter().find_map(|(phrase, s)| Some((strip_suffix_phrase(text, phrase)?, *s))) {
+        text = rest;
+        sort = Some(found);
+    }
+
+    let quantified = ["for all", "for every", "for any"].iter().find_map(|k| strip_keyword(text, k));
+    if let Some(rest) = quantified {
+        let (rest, declared) = match ["integers", "integer", "reals", "real numbers", "real"]
+            .iter()
+            .find_map(|word| Some((strip_keyword(rest, word)?, word.starts_with("integer"))))
+        {
+            Some((rest, integer)) => (rest, Some(if integer { Sort::Integer } else { Sort::Real })),
+            None => (rest, None),
+        };
+        let sort = sort.or(declared).unwrap_or(Sort::Real);
+        return Some(parse_quantified_body(rest).map(|formula| ArithClaim { formula, sort }));
+    }
+    let sort = sort.unwrap_or(Sort::Real);
+    let claim = |formula| ArithClaim { formula, sort };
+
+    if let Some(subject) =
+        strip_keyword(text, "is").and_then(|rest| VALIDITY.iter().find_map(|word| strip_suffix_phrase(rest, word)))
+    {
+        return has_comparison(subject).then(|| parse_claim(subject).map(claim));
+    }
+    if let Some((hypothesis, conclusion)) = strip_keyword(text, "if").and_then(|rest| split_keyword(rest, "then")) {
+        let hypothesis = hypothesis.trim_end_matches(',');
+        return (has_comparison(hypothesis) && has_comparison(conclusion))
+            .then(|| Ok(claim(implies(parse_formula(hypothesis)?, parse_formula(conclusion)?))));
+    }
+    // A bare implication or inequality is claimed only when it parses, so other uses of
+    // "->" fall through; equations are left to the solver and to bindings
+    match parse_formula(text) {
+        Ok(formula @ Formula::Implies(..)) => Some(Ok(claim(formula))),
+        Ok(formula) if tokenize(text).is_ok_and(|tokens| !tokens.iter().any(|t| t.is_symbol("="))) => {
+            Some(Ok(claim(formula)))
+        }
+        _ => None,
+    }
+}
+
+/// "x, y: F", "x and y, F", or a guarded "x > 0, F" read as "x > 0 implies F"
+fn parse_quantified_body(text: &str) -> anyhow::Result<Formula> {
+    let is_name_list = |names: &str| {
+        let mut words = names.split(',').flat_map(str::split_whitespace).filter(|w| !w.eq_ignore_ascii_case("and"));
+        words.clone().next().is_some() && words.all(is_identifier)
+    };
+    let mut body = text;
+    if let Some((_, rest)) = text.split_once(':').filter(|(names, _)| is_name_list(names)) {
+        body = rest;
+    } else {
+        while let Some((_, rest)) = body.split_once(',').filter(|(names, _)| is_name_list(names)) {
+            body = rest;
+        }
+    }
+    let body = body.trim();
+    match body.rsplit_once(',') {
+        Some((guard, conclusion)) if has_comparison(guard) => Ok(implies(parse_formula(guard)?, parse_claim(conclusion)?)),
+        _ => parse_claim(body),
+    }
+}
+
+/// A formula, or "if A then B"
+fn parse_claim(text: &str) -> anyhow::Result<Formula> {
+    match strip_keyword(text.trim(), "if").and_then(|rest| split_keyword(rest, "then")) {
+        Some((hypothesis, conclusion)) => {
+            Ok(implies(parse_formula(hypothesis.trim_end_matches(','))?, parse_formula(conclusion)?))
+        }
+        None => parse_formula(text),
+    }
+}
+
+fn implies(hypothesis: Formula, conclusion: Formula) -> Formula {
+    Formula::Implies(Box::new(hypothesis), Box::new(conclusion))
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_') && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn has_comparison(text: &str) -> bool {
+    tokenize(text).is_ok_and(|tokens| tokens.iter().any(|t| COMPARISONS.iter().any(|(symbol, _)| t.is_symbol(symbol))))
+}
+
+/// Parse linear comparisons joined by "and"/"&", "or"/"|", "not"/"~" and "implies"/"->";
+/// chains such as "0 < x <= 5" are conjunctions
+pub fn parse_formula(text: &str) -> anyhow::Result<Formula> {
+    let mut parser = Parser::new(tokenize(&symbolize(text))?);
+    let formula = parse_implication(&mut parser)?;
+    parser.expect_end()?;
+    formula.check_linear()?;
+    Ok(formula)
+}
+
+/// Connective words as the symbols the lexer knows, so arithmetic parsing stops at them
+fn symbolize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        let symbol = match word.to_ascii_lowercase().as_str() {
+            "and" => "&",
+            "or" => "|",
+            "not" => "~",
+            "implies" => "->",
+            _ => word.as_str(),
+        }
+        .to_string();
+        out.push_str(&symbol);
+        out.push(c);
+        word.clear();
+    }
+    out.pop();
+    out
+}
+
+fn parse_implication(parser: &mut Parser) -> anyhow::Result<Formula> {
+    let lhs = parse_or(parser)?;
+    if parser.eat_symbol("->") {
+        return Ok(implies(lhs, parse_implication(parser)?));
+    }
+    Ok(lhs)
+}
+
+fn parse_or(parser: &mut Parser) -> anyhow::Result<Formula> {
+    let mut lhs = parse_and(parser)?;
+    while parser.eat_symbol("|") {
+        lhs = Formula::Or(Box::new(lhs), Box::new(parse_and(parser)?));
+    }
+    Ok(lhs)
+}
+
+fn parse_and(parser: &mut Parser) -> anyhow::Result<Formula> {
+    let mut lhs = parse_unary(parser)?;
+    while parser.eat_symbol("&") {
+        lhs = Formula::And(Box::new(lhs), Box::new(parse_unary(parser)?));
+    }
+    Ok(lhs)
+}
+
+/// A "(" may open either arithmetic, as in "(x + 1) > 2", or a formula, as in "(x > 1 or x < 0)"
+fn parse_unary(parser: &mut Parser) -> anyhow::Result<Formula> {
+    if parser.eat_symbol("~") || parser.eat_symbol("!") {
+        return Ok(Formula::Not(Box::new(parse_unary(parser)?)));
+    }
+    if parser.peek().is_some_and(|t| t.is_symbol("(")) {
+        let start = parser.position();
+        if let Ok(comparison) = parse_comparison(parser) {
+            return Ok(comparison);
+        }
+        parser.reset(start + 1);
+        let inner = parse_implication(parser)?;
+        parser.expect_symbol(")")?;
+        return Ok(inner);
+    }
+    parse_comparison(parser)
+}
+
+fn parse_comparison(parser: &mut Parser) -> anyhow::Result<Formula> {
+    let mut lhs = parser.parse_expr()?;
+    let mut chain: Option<Formula> = None;
+    while let Some(&(_, comparison)) = COMPARISONS.iter().find(|(symbol, _)| parser.eat_symbol(symbol)) {
+        let rhs = parser.parse_expr()?;
+        let atom = Formula::Compare(lhs, comparison, rhs.clone());
+        chain = Some(match chain {
+            Some(previous) => Formula::And(Box::new(previous), Box::new(atom)),
+            None => atom,
+        });
+        lhs = rhs;
+    }
+    chain.ok_or_else(|| parser.error("a comparison such as '<' or '='"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Relation {
+    Lt,
+    Le,
+    Eq,
+}
+
+/// Σ coefficient × variable + constant, related to zero
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Row {
+    coeffs: BTreeMap<String, BigRational>,
+    constant: BigRational,
+    relation: Relation,
+}
+
+impl Row {
+    fn scale(&self, factor: &BigRational) -> Row {
+        Row {
+            coeffs: self.coeffs.iter().map(|(v, c)| (v.clone(), c * factor)).collect(),
+            constant: &self.constant * factor,
+            relation: self.relation,
+        }
+    }
+
+    fn add(&self, other: &Row) -> Row {
+        let mut coeffs = self.coeffs.clone();
+        for (var, c) in &other.coeffs {
+            let entry = coeffs.entry(var.clone()).or_insert_with(BigRational::zero);
+            *entry += c;
+        }
+        coeffs.retain(|_, c| !c.is_zero());
+        Row { coeffs, constant: &self.constant + &other.constant, relation: self.relation.min(other.relation) }
+    }
+
+    /// Replace `var` by the linear term `value` (a row whose relation is ignored)
+    fn substitute(&self, var: &str, value: &Row) -> Row {
+        let Some(c) = self.coeffs.get(var) else {
+            return self.clone();
+        };
+        let mut rest = self.clone();
+        rest.coeffs.remove(var);
+        let mut term = value.scale(c);
+        term.relation = self.relation;
+        rest.add(&term)
+    }
+
+    /// Σ coefficient × value + constant, with unassigned variables at 0
+    fn evaluate(&self, model: &BTreeMap<String, BigRational>) -> BigRational {
+        self.coeffs.iter().fold(self.constant.clone(), |sum, (var, c)| match model.get(var) {
+            Some(value) => sum + c * value,
+            None => sum,
+        })
+    }
+
+    /// Whether a row without variables holds
+    fn holds(&self) -> bool {
+        match self.relation {
+            Relation::Lt => self.constant.is_negative(),
+            Relation::Le => !self.constant.is_positive(),
+            Relation::Eq => self.constant.is_zero(),
+        }
+    }
+
+    /// Scaled so the first coefficient is ±1, which makes duplicate rows equal
+    fn normalized(self) -> Row {
+        match self.coeffs.values().next().map(BigRational::abs) {
+            Some(lead) => self.scale(&lead.recip()),
+            None => self,
+        }
+    }
+
+    /// Over the integers: integral coefficients with no common factor and "< c" as "<= c - 1"
+    fn tightened(&self) -> Row {
+        let lcm = self.coeffs.values().chain([&self.constant]).fold(num_bigint::BigInt::one(), |l, c| l.lcm(c.denom()));
+        let mut row = self.scale(&BigRational::from_integer(lcm));
+        if row.relation == Relation::Lt {
+            row.constant += BigRational::one();
+            row.relation = Relation::Le;
+        }
+        let gcd = row.coeffs.values().fold(num_bigint::BigInt::zero(), |g, c| g.gcd(c.numer()));
+        if gcd > num_bigint::BigInt::one() {
+            let gcd = BigRational::from_integer(gcd);
+            row.coeffs.values_mut().for_each(|c| *c /= &gcd);
+            row.constant = match row.relation {
+                Relation::Le => (&row.constant / &gcd).ceil(),
+                _ => &row.constant / &gcd,
+            };
+        }
+        row
+    }
+}
+
+/// "x - y <= 1", with the constant on the right and the first coefficient positive
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flip = self.coeffs.values().next().is_some_and(Signed::is_negative);
+        let row = if flip { self.scale(&-BigRational::one()) } else { self.clone() };
+        let symbol = match (self.relation, flip) {
+            (Relation::Lt, false) => "<",
+            (Relation::Le, false) => "<=",
+            (Relation::Lt, true) => ">",
+            (Relation::Le, true) => ">=",
+            (Relation::Eq, _) => "=",
+        };
+        write!(f, "{} {} {}", linear_term(&row.coeffs), symbol, Number::Exact(-row.constant))
+    }
+}
+
+/// "3 * x - y", or "0" without variables
+fn linear_term(coeffs: &BTreeMap<String, BigRational>) -> String {
+    let mut text = String::new();
+    for (var, c) in coeffs {
+        let sign = match (text.is_empty(), c.is_negative()) {
+            (true, true) => "-",
+            (true, false) => "",
+            (false, true) => " - ",
+            (false, false) => " + ",
+        };
+        let magnitude = c.abs();
+        match magnitude.is_one() {
+            true => text.push_str(&format!("{}{}", sign, var)),
+            false => text.push_str(&format!("{}{} * {}", sign, Number::Exact(magnitude), var)),
+        }
+    }
+    if text.is_empty() {
+        text.push('0');
+    }
+    text
+}
+
+/// `lhs - rhs` as a row, or an error naming the comparison that is not linear
+fn difference(lhs: &Expr, comparison: Comparison, rhs: &Expr) -> anyhow::Result<Row> {
+    let expr = Expr::binary(BinOp::Sub, lhs.clone(), rhs.clone());
+    let (constant, coeffs) = symbolic::to_poly(&expr, &mut Vec::new())
+        .and_then(|poly| poly.linear_coefficients())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "'{} {} {}' is not linear; only sums of constant multiples of variables can be checked",
+                lhs,
+                comparison.symbol(),
+                rhs
+            )
+        })?;
+    Ok(Row { coeffs, constant, relation: Relation::Le })
+}
+
+/// Conjunctions of rows whose disjunction is equivalent to `formula`, or to its negation
+fn cases(formula: &Formula, positive: bool) -> anyhow::Result<Vec<Vec<Row>>> {
+    let both = |a: &Formula, pa: bool, b: &Formula, pb: bool| -> anyhow::Result<Vec<Vec<Row>>> {
+        let (left, right) = (cases(a, pa)?, cases(b, pb)?);
+        if left.len() * right.len() > MAX_CASES {
+            return Err(anyhow::anyhow!("The claim splits into more than {} cases", MAX_CASES));
+        }
+        Ok(left.iter().flat_map(|l| right.iter().map(move |r| [l.as_slice(), r.as_slice()].concat())).collect())
+    };
+    let either = |a: &Formula, pa: bool, b: &Formula, pb: bool| -> anyhow::Result<Vec<Vec<Row>>> {
+        let mut all = cases(a, pa)?;
+        all.extend(cases(b, pb)?);
+        match all.len() > MAX_CASES {
+            true => Err(anyhow::anyhow!("The claim splits into more than {} cases", MAX_CASES)),
+            false => Ok(all),
+        }
+    };
+    match formula {
+        Formula::Compare(lhs, comparison, rhs) => {
+            let comparison = if positive { *comparison } else { negate(*comparison) };
+            let row = difference(lhs, comparison, rhs)?;
+            let flipped = row.scale(&-BigRational::one());
+            let with = |row: &Row, relation| Row { relation, ..row.clone() };
+            Ok(match comparison {
+                Comparison::Lt => vec![vec![with(&row, Relation::Lt)]],
+                Comparison::Le => vec![vec![with(&row, Relation::Le)]],
+                Comparison::Gt => vec![vec![with(&flipped, Relation::Lt)]],
+                Comparison::Ge => vec![vec![with(&flipped, Relation::Le)]],
+                Comparison::Eq => vec![vec![with(&row, Relation::Eq)]],
+                Comparison::Ne => vec![vec![with(&row, Relation::Lt)], vec![with(&flipped, Relation::Lt)]],
+            })
+        }
+        Formula::Not(inner) => cases(inner, !positive),
+        Formula::And(a, b) if positive => both(a, true, b, true),
+        Formula::And(a, b) => either(a, false, b, false),
+        Formula::Or(a, b) if positive => either(a, true, b, true),
+        Formula::Or(a, b) => both(a, false, b, false),
+        Formula::Implies(a, b) if positive => either(a, false, b, true),
+        Formula::Implies(a, b) => both(a, true, b, false),
+    }
+}
+
+fn negate(comparison: Comparison) -> Comparison {
+    match comparison {
+        Comparison::Eq => Comparison::Ne,
+        Comparison::Ne => Comparison::Eq,
+        Comparison::Lt => Comparison::Ge,
+        Comparison::Le => Comparison::Gt,
+        Comparison::Gt => Comparison::Le,
+        Comparison::Ge => Comparison::Lt,
+    }
+}
+
+type Model = BTreeMap<String, BigRational>;
+
+/// A real solution of the conjunction `rows`, or `None` with the contradiction in `steps`.
+/// Equalities are solved and substituted first, then each remaining variable is
+/// eliminated by pairing its lower and upper bounds; the solution is rebuilt in reverse
+fn solve_real(mut rows: Vec<Row>, steps: &mut Vec<String>) -> anyhow::Result<Option<Model>> {
+    let mut substitutions: Vec<(String, Row)> = Vec::new();
+    while let Some(i) = rows.iter().position(|r| r.relation == Relation::Eq) {
+        let row = rows.remove(i);
+        let Some((var, c)) = row.coeffs.iter().next().map(|(v, c)| (v.clone(), c.clone())) else {
+            if !row.holds() {
+                steps.push(format!("contradiction: {}", row));
+                return Ok(None);
+            }
+            continue;
+        };
+        let mut value = row.clone();
+        value.coeffs.remove(&var);
+        let value = value.scale(&-c.recip());
+        steps.push(format!("substitute {} = {}", var, affine(&value)));
+        rows = rows.iter().map(|r| r.substitute(&var, &value)).collect();
+        substitutions.push((var, value));
+    }
+
+    let mut stages: Vec<(String, Vec<Row>)> = Vec::new();
+    loop {
+        if let Some(false_row) = rows.iter().find(|r| r.coeffs.is_empty() && !r.holds()) {
+            steps.push(format!("contradiction: {}", false_row));
+            return Ok(None);
+        }
+        rows.retain(|r| !r.coeffs.is_empty());
+        // Eliminate the variable that creates the fewest new rows
+        let variables: BTreeSet<&String> = rows.iter().flat_map(|r| r.coeffs.keys()).collect();
+        let Some(var) = variables
+            .into_iter()
+            .min_by_key(|var| {
+                let upper = rows.iter().filter(|r| r.coeffs.get(*var).is_some_and(Signed::is_positive)).count();
+                let lower = rows.iter().filter(|r| r.coeffs.get(*var).is_some_and(Signed::is_negative)).count();
+                upper * lower
+            })
+            .cloned()
+        else {
+            break;
+        };
+        let (bounds, mut next): (Vec<Row>, Vec<Row>) = rows.into_iter().partition(|r| r.coeffs.contains_key(&var));
+        let (upper, lower): (Vec<&Row>, Vec<&Row>) = bounds.iter().partition(|r| r.coeffs[&var].is_positive());
+        for u in &upper {
+            for l in &lower {
+                let combined = u.scale(&-&l.coeffs[&var]).add(&l.scale(&u.coeffs[&var]));
+                next.push(combined.normalized());
+            }
+        }
+        next.sort();
+        next.dedup();
+        if next.len() > MAX_ROWS {
+            return Err(anyhow::anyhow!("Too many constraints after eliminating {} (more than {})", var, MAX_ROWS));
+        }
+        steps.push(format!(
+            "eliminate {}: {} × {} → {}",
+            var,
+            plural(lower.len(), "lower bound"),
+            plural(upper.len(), "upper bound"),
+            plural(next.len(), "constraint")
+        ));
+        stages.push((var, bounds));
+        rows = next;
+    }
+
+    let mut model = Model::new();
+    for (var, bounds) in stages.iter().rev() {
+        let value = pick(var, bounds, &model);
+        model.insert(var.clone(), value);
+    }
+    for (var, value) in substitutions.iter().rev() {
+        let value = value.evaluate(&model);
+        model.insert(var.clone(), value);
+    }
+    Ok(Some(model))
+}
+
+/// "2 * y + 1/2", a row read as a term
+fn affine(row: &Row) -> String {
+    let constant = Number::Exact(row.constant.abs());
+    match (row.coeffs.is_empty(), row.constant.is_zero(), row.constant.is_negative()) {
+        (true, _, _) => Number::Exact(row.constant.clone()).to_string(),
+        (false, true, _) => linear_term(&row.coeffs),
+        (false, false, true) => format!("{} - {}", linear_term(&row.coeffs), constant),
+        (false, false, false) => format!("{} + {}", linear_term(&row.coeffs), constant),
+    }
+}
+
+/// A value for `var` within its bounds once the later variables are fixed: 0 when
+/// allowed, else the integer nearest the feasible range, else the midpoint
+fn pick(var: &str, bounds: &[Row], model: &Model) -> BigRational {
+    let mut lower: Option<(BigRational, bool)> = None;
+    let mut upper: Option<(BigRational, bool)> = None;
+    for row in bounds {
+        let c = &row.coeffs[var];
+        let mut rest = row.clone();
+        rest.coeffs.remove(var);
+        let bound = -rest.evaluate(model) / c;
+        let strict = row.relation == Relation::Lt;
+        if c.is_positive() {
+            if upper.as_ref().is_none_or(|(u, s)| bound < *u || (bound == *u && strict && !s)) {
+                upper = Some((bound, strict));
+            }
+        } else if lower.as_ref().is_none_or(|(l, s)| bound > *l || (bound == *l && strict && !s)) {
+            lower = Some((bound, strict));
+        }
+    }
+    let fits = |v: &BigRational| {
+        lower.as_ref().is_none_or(|(l, strict)| if *strict { v > l } else { v >= l })
+            && upper.as_ref().is_none_or(|(u, strict)| if *strict { v < u } else { v <= u })
+    };
+    let candidates = [
+        Some(BigRational::zero()),
+        lower.as_ref().map(|(l, strict)| if *strict { l.floor() + BigRational::one() } else { l.ceil() }),
+        upper.as_ref().map(|(u, strict)| if *strict { u.ceil() - BigRational::one() } else { u.floor() }),
+    ];
+    if let Some(value) = candidates.into_iter().flatten().find(|v| fits(v)) {
+        return value;
+    }
+    match (lower, upper) {
+        (Some((l, _)), Some((u, _))) => (l + u) / BigRational::from_integer(2.into()),
+        (Some((l, _)), None) => l,
+        (None, Some((u, _))) => u,
+        (None, None) => BigRational::zero(),
+    }
+}
+
+/// Outcome of branch-and-bound: a solution, none, or the budget ran out first
+enum Search {
+    Found(Model),
+    Infeasible,
+    GaveUp,
+}
+
+/// An integer solution: solve the real relaxation and split on a fractional value,
+/// x <= floor(v) or x >= ceil(v)
+fn solve_integer(rows: &[Row], steps: &mut Vec<String>, budget: &mut usize) -> anyhow::Result<Search> {
+    if *budget == 0 {
+        return Ok(Search::GaveUp);
+    }
+    *budget -= 1;
+    let Some(model) = solve_real(rows.to_vec(), steps)? else {
+        return Ok(Search::Infeasible);
+    };
+    let Some((var, value)) = model.iter().find(|(_, v)| !v.is_integer()) else {
+        return Ok(Search::Found(model));
+    };
+    let single = |sign: i64, bound: BigRational| Row {
+        coeffs: BTreeMap::from([(var.clone(), BigRational::from_integer(sign.into()))]),
+        constant: bound * BigRational::from_integer((-sign).into()),
+        relation: Relation::Le,
+    };
+    for split in [single(1, value.floor()), single(-1, value.ceil())] {
+        let mut child = rows.to_vec();
+        child.push(split);
+        match solve_integer(&child, &mut Vec::new(), budget)? {
+            Search::Infeasible => continue,
+            found_or_gave_up => return Ok(found_or_gave_up),
+        }
+    }
+    Ok(Search::Infeasible)
+}
+
+/// Verdict with a counterexample when the claim fails
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArithOutcome {
+    pub result: String,
+    /// `None` when branch-and-bound gave up before deciding
+    pub verdict: Option<bool>,
+    pub steps: Vec<String>,
+}
+
+/// The claim is valid when no case of its negation has a solution
+pub fn check(claim: &ArithClaim) -> anyhow::Result<ArithOutcome> {
+    if claim.formula.variables().is_empty() {
+        let holds = claim.formula.holds(&BTreeMap::new())?;
+        let steps = vec![format!("{} has no variables, so it is evaluated directly", claim.formula)];
+        return Ok(ArithOutcome { result: holds.to_string(), verdict: Some(holds), steps });
+    }
+    let mut steps = vec![format!(
+        "valid iff no {} satisfy {}",
+        claim.sort.plural(),
+        Formula::Not(Box::new(claim.formula.clone()))
+    )];
+    let all_cases = cases(&claim.formula, false)?;
+    let names = claim.formula.variables();
+    let mut branches = 0;
+    let mut gave_up = false;
+
+    for (i, case) in all_cases.iter().enumerate() {
+        let rows: Vec<String> = case.iter().map(Row::to_string).collect();
+        match all_cases.len() {
+            1 => steps.push(format!("constraints: {}", rows.join(", "))),
+            _ => steps.push(format!("case {}: {}", i + 1, rows.join(", "))),
+        }
+        let search = match claim.sort {
+            Sort::Real => solve_real(case.clone(), &mut steps)?.map_or(Search::Infeasible, Search::Found),
+            Sort::Integer => {
+                let tightened: Vec<Row> = case
+                    .iter()
+                    .flat_map(|row| match row.relation {
+                        Relation::Eq => vec![row.clone(), row.scale(&-BigRational::one())],
+                        _ => vec![row.clone()],
+                    })
+                    .map(|row| match row.relation {
+                        Relation::Eq => Row { relation: Relation::Le, ..row },
+                        _ => row,
+                    })
+                    .map(|row| row.tightened())
+                    .collect();
+                let mut budget = MAX_BRANCHES;
+                let search = solve_integer(&tightened, &mut steps, &mut budget)?;
+                let used = MAX_BRANCHES - budget;
+                branches += used;
+                if used > 1 {
+                    steps.push(format!("branch-and-bound: {}", plural(used, "subproblem")));
+                }
+                search
+            }
+        };
+        match search {
+            Search::Found(mut model) => {
+                for name in &names {
+                    model.entry(name.clone()).or_insert_with(BigRational::zero);
+                }
+                if claim.formula.holds(&model)? {
+                    return Err(anyhow::anyhow!("Internal error: the counterexample satisfies the claim"));
+                }
+                let assignment: Vec<String> =
+                    model.iter().map(|(name, value)| format!("{} = {}", name, Number::Exact(value.clone()))).collect();
+                steps.push(format!("the claim is false at {}", assignment.join(", ")));
+                return Ok(ArithOutcome {
+                    result: format!("not valid; counterexample: {}", assignment.join(", ")),
+                    verdict: Some(false),
+                    steps,
+                });
+            }
+            Search::GaveUp => gave_up = true,
+            Search::Infeasible => {}
+        }
+    }
+
+    if gave_up {
+        return Ok(ArithOutcome {
+            result: format!("unknown: branch-and-bound stopped after {}", plural(branches, "subproblem")),
+            verdict: None,
+            steps,
+        });
+    }
+    steps.push(format!("no case has a solution over the {}", claim.sort.plural()));
+    Ok(ArithOutcome { result: "valid".to_string(), verdict: Some(true), steps })
+}
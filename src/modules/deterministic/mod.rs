@@ -0,0 +1,751 @@
+use serde::{Serialize, Deserialize};
+use evalexpr::*;
+
+pub mod number;
+pub mod lexer;
+pub mod expr;
+pub mod symbolic;
+pub mod solver;
+pub mod units;
+pub mod context;
+pub mod linalg;
+pub mod stats;
+pub mod numerical;
+pub mod calendar;
+pub mod programmer;
+pub mod natural;
+pub mod propositional;
+pub mod prolog;
+pub mod datalog;
+pub mod constraints;
+pub mod linarith;
+pub mod sandbox;
+pub mod syntax;
+pub mod text;
+pub mod error;
+#[cfg(feature = "swipl")]
+pub mod swipl;
+
+use context::EvalContext;
+use error::DetError;
+use number::Number;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Production-grade deterministic module with comprehensive error handling
+/// Implements math evaluation and logic processing with full verification
+pub struct DeterministicModule {
+    config: DetConfig,
+    /// Non-working days for business-day arithmetic
+    holidays: Box<dyn calendar::HolidayCalendar>,
+    /// Facts and rules for Horn-clause queries; "consult" and "reload" replace it at runtime
+    knowledge: std::sync::RwLock<prolog::KnowledgeBase>,
+}
+
+struct DetConfig {
+    enable_prolog: bool,
+    max_query_length: usize,
+    math_mode: MathMode,
+    decimal_precision: usize,
+    numeric: numerical::NumericConfig,
+    fd: constraints::FdConfig,
+    sandbox: sandbox::SandboxConfig,
+    #[cfg(feature = "swipl")]
+    swipl: swipl::SwiplConfig,
+}
+
+/// Arithmetic backend used for math queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MathMode {
+    /// Arbitrary-precision rationals; irrational functions fall back to f64
+    Exact,
+    /// Results rounded to f64; arithmetic outside the exact grammar goes to evalexpr
+    Float,
+}
+
+impl DeterministicModule {
+    /// Initialize deterministic module with configuration from environment
+    pub fn init_deterministic_module() -> anyhow::Result<Self> {
+        log::info!("Initializing DeterministicModule");
+        
+        let enable_prolog = std::env::var("AXIOM_ENABLE_PROLOG")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        
+        // May only lower the limit every layer shares
+        let max_query_length = std::env::var("AXIOM_MAX_QUERY_LENGTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map_or(super::MAX_QUERY_LENGTH, |n: usize| n.min(super::MAX_QUERY_LENGTH));
+        
+        let math_mode = match std::env::var("AXIOM_MATH_MODE") {
+            Ok(mode) if mode.eq_ignore_ascii_case("float") => MathMode::Float,
+            _ => MathMode::Exact,
+        };
+        
+        let decimal_precision = std::env::var("AXIOM_DECIMAL_PRECISION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        
+        let defaults = numerical::NumericConfig::default();
+        let numeric = numerical::NumericConfig {
+            tolerance: std::env::var("AXIOM_NUMERIC_TOLERANCE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|t: &f64| t.is_finite() && *t > 0.0)
+                .unwrap_or(defaults.tolerance),
+            max_iterations: std::env::var("AXIOM_MAX_ITERATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_iterations),
+        };
+        
+        let defaults = constraints::FdConfig::default();
+        let fd = constraints::FdConfig {
+            time_limit: std::env::var("AXIOM_FD_TIME_LIMIT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|t: &f64| t.is_finite() && *t > 0.0)
+                .map(std::time::Duration::from_secs_f64)
+                .unwrap_or(defaults.time_limit),
+            max_solutions: std::env::var("AXIOM_FD_MAX_SOLUTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(defaults.max_solutions),
+        };
+        
+        let defaults = sandbox::SandboxConfig::default();
+        let sandbox = sandbox::SandboxConfig {
+            max_operations: std::env::var("AXIOM_SANDBOX_MAX_OPERATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &u64| *n > 0)
+                .unwrap_or(defaults.max_operations),
+            time_limit: std::env::var("AXIOM_SANDBOX_TIME_LIMIT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|t: &f64| t.is_finite() && *t > 0.0)
+                .map(std::time::Duration::from_secs_f64)
+                .unwrap_or(defaults.time_limit),
+            seed: std::env::var("AXIOM_SANDBOX_SEED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.seed),
+            ..defaults
+        };
+        
+        let holidays = match std::env::var("AXIOM_HOLIDAYS_PATH") {
+            Ok(path) => calendar::HolidayList::load(std::path::Path::new(&path))?,
+            Err(_) => calendar::HolidayList::default(),
+        };
+        
+        let knowledge = match std::env::var_os("AXIOM_KB_PATH") {
+            Some(paths) => prolog::KnowledgeBase::load(&std::env::split_paths(&paths).collect::<Vec<_>>())?,
+            None => prolog::KnowledgeBase::standard(),
+        };
+        
+        #[cfg(feature = "swipl")]
+        let swipl = {
+            let defaults = swipl::SwiplConfig::default();
+            swipl::SwiplConfig {
+                executable: std::env::var_os("AXIOM_SWIPL_PATH").map(Into::into).unwrap_or(defaults.executable),
+                time_limit: std::env::var("AXIOM_PROLOG_TIME_LIMIT")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|t: &f64| t.is_finite() && *t > 0.0)
+                    .map(std::time::Duration::from_secs_f64)
+                    .unwrap_or(defaults.time_limit),
+                inference_limit: std::env::var("AXIOM_PROLOG_INFERENCE_LIMIT")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(defaults.inference_limit),
+            }
+        };
+        
+        if enable_prolog {
+            #[cfg(feature = "swipl")]
+            {
+                log::info!("Prolog integration enabled with SWI-Prolog ({})", swipl.executable.display());
+            }
+            #[cfg(not(feature = "swipl"))]
+            {
+                log::info!("Prolog requested but swipl feature not enabled, using the built-in resolver");
+            }
+        } else {
+            log::info!("Prolog integration disabled, using the built-in resolver");
+        }
+        
+        let config = DetConfig {
+            enable_prolog,
+            max_query_length,
+            math_mode,
+            decimal_precision,
+            numeric,
+            fd,
+            sandbox,
+            #[cfg(feature = "swipl")]
+            swipl,
+        };
+        
+        log::info!("DeterministicModule initialized successfully (math mode: {:?})", math_mode);
+        Ok(DeterministicModule {
+            config,
+            holidays: Box::new(holidays),
+            knowledge: std::sync::RwLock::new(knowledge),
+        })
+    }
+
+    /// Replace the holiday list used for business-day counting
+    pub fn with_holidays(self, holidays: impl calendar::HolidayCalendar + 'static) -> Self {
+        DeterministicModule { holidays: Box::new(holidays), ..self }
+    }
+
+    /// Execute a logic/math query with full error handling
+    /// For math queries: evaluate exactly (or with evalexpr in float mode)
+    /// For logic queries: return deterministic proofs
+    pub fn execute_logic(&self, query: &str) -> Result<String, DetError> {
+        self.execute(query).map(|response| response.result)
+    }
+
+    /// Execute a query and return the full structured response
+    pub fn execute(&self, query: &str) -> Result<DetResponse, DetError> {
+        self.execute_in(query, &mut EvalContext::new())
+    }
+
+    /// Execute a query against session bindings, which "let" and function
+    /// definitions update in place
+    pub fn execute_in(&self, query: &str, context: &mut EvalContext) -> Result<DetResponse, DetError> {
+        if query.is_empty() {
+            return Err(DetError::EmptyQuery);
+        }
+        
+        if query.len() > self.config.max_query_length {
+            return Err(DetError::QueryTooLong { max: self.config.max_query_length });
+        }
+        
+        log::debug!("Executing logic query: {}", query);
+        Ok(self.dispatch(query, context)?)
+    }
+
+    /// Read a query into its typed form and hand that form to its domain
+    fn dispatch(&self, query: &str, context: &mut EvalContext) -> anyhow::Result<DetResponse> {
+        // One front end for every domain: input outside the grammar is an error with its
+        // column, never silently dropped, and no domain reads the query text itself
+        let parsed = {
+            let knowledge = self.knowledge();
+            syntax::parse(query, &syntax::Scope { context, knowledge: &knowledge })?
+        };
+        
+        match parsed.form {
+            syntax::Form::Code(snippet) => self.execute_code(&snippet),
+            syntax::Form::Text(query) => self.execute_text(&query),
+            syntax::Form::Session(command) => Ok(DetResponse::text(context.apply(&command)?)),
+            syntax::Form::Knowledge(command) => {
+                let message = self.knowledge.write().unwrap_or_else(|e| e.into_inner()).apply(&command)?;
+                Ok(DetResponse::text(message))
+            }
+            syntax::Form::Datalog(query) => self.execute_datalog(&query),
+            syntax::Form::Goal(query) => self.execute_prolog(&query),
+            syntax::Form::Constraints(problem) => self.execute_constraints(&problem),
+            syntax::Form::Claim(claim) => self.execute_linarith(&claim),
+            syntax::Form::Calendar(query) => self.execute_calendar(&query),
+            syntax::Form::Propositional(query) => self.execute_propositional(&query),
+            syntax::Form::Programmer(query) => self.execute_programmer(&query),
+            syntax::Form::Numerical(query) => self.execute_numerical(&query, context),
+            syntax::Form::Stats(query) => self.execute_stats(&query, context),
+            syntax::Form::LinearAlgebra(expr) => self.execute_linalg(&expr, context),
+            syntax::Form::Symbolic(command) => self.execute_symbolic(&command),
+            syntax::Form::Solve(request) => self.execute_solve(&request),
+            syntax::Form::Words { words, expr } => {
+                let mut response = self.execute_math(&expr, context)?;
+                let canonical = expr.to_string();
+                response.proof.get_or_insert_with(Vec::new).insert(0, format!("words: {} → {}", words, canonical));
+                response.interpreted = Some(canonical);
+                Ok(response)
+            }
+            syntax::Form::Units(query) => self.execute_units(&query),
+            syntax::Form::Math(expr) => self.execute_math(&expr, context),
+            syntax::Form::Float(text) => self.execute_float(&text),
+            syntax::Form::Unrecognized => {
+                log::warn!("Query type not recognized: {}", parsed.source);
+                Ok(DetResponse::text("[deterministic: query type not recognized]"))
+            }
+        }
+    }
+    
+    fn knowledge(&self) -> std::sync::RwLockReadGuard<'_, prolog::KnowledgeBase> {
+        self.knowledge.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// True when the query reads or changes session bindings and must bypass intent routing
+    pub fn is_session_query(&self, query: &str, context: &EvalContext) -> bool {
+        context::parse_command(query).is_some() || uses_bindings(query, context).is_some()
+    }
+    
+    /// True when the front end reads the query as something a domain answers; malformed
+    /// commands count, since their errors are the answer
+    pub fn recognizes(&self, query: &str, context: &EvalContext) -> bool {
+        let knowledge = self.knowledge();
+        syntax::parse(query, &syntax::Scope { context, knowledge: &knowledge })
+            .map_or(true, |parsed| parsed.form != syntax::Form::Unrecognized)
+    }
+    
+    /// Evaluate an expression exactly, rounding the result in float mode
+    fn execute_math(&self, parsed: &expr::Expr, context: &EvalContext) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating math expression: {}", parsed);
+        let (mut value, mut steps) = expr::Evaluator::with_context(context).evaluate_traced(parsed)?;
+        if self.config.math_mode == MathMode::Float {
+            value = Number::approx(value.to_f64())?;
+            steps.push(format!("as float: {}", value));
+        }
+        log::debug!("Math result (exact: {}): {}", value.is_exact(), value);
+        let mut response = self.number_response(&value);
+        response.proof = Some(steps);
+        Ok(response)
+    }
+    
+    /// Evaluate arithmetic the exact grammar rejects with evalexpr, as a float or else
+    /// an integer
+    fn execute_float(&self, math_expr: &str) -> anyhow::Result<DetResponse> {
+        match eval_float(math_expr) {
+            Ok(result) => {
+                log::debug!("Math result (float): {}", result);
+                let mut response = DetResponse::approximate(format!("{}", result));
+                response.proof = Some(vec![format!("float evaluation: {} = {}", math_expr, result)]);
+                Ok(response)
+            }
+            Err(_) => {
+                // Try integer evaluation
+                match eval_int(math_expr) {
+                    Ok(result) => {
+                        log::debug!("Math result (int): {}", result);
+                        let mut response = DetResponse::exact(format!("{}", result), None);
+                        response.proof = Some(vec![format!("integer evaluation: {} = {}", math_expr, result)]);
+                        Ok(response)
+                    }
+                    Err(e) => {
+                        log::warn!("Math evaluation error: {}", e);
+                        Err(anyhow::anyhow!("Math evaluation failed: {}", e))
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Run a symbolic algebra command and return the rewrite chain as the proof
+    fn execute_symbolic(&self, command: &symbolic::SymbolicCommand) -> anyhow::Result<DetResponse> {
+        log::debug!("Executing symbolic command: {:?}", command);
+        let outcome = symbolic::execute(command)?;
+        
+        let mut response = match &outcome.value {
+            Some(value) => self.number_response(value),
+            None => DetResponse::exact(outcome.expr.to_string(), None),
+        };
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Solve equations, reporting every solution with its substitution check
+    fn execute_solve(&self, request: &solver::SolveRequest) -> anyhow::Result<DetResponse> {
+        log::debug!("Solving {} equation(s)", request.equations.len());
+        let outcome = solver::solve(request)?;
+        
+        let mut response = DetResponse::exact(outcome.solution.summary(), None);
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Evaluate a unit-aware expression, converting to the requested unit if any
+    fn execute_units(&self, query: &units::UnitQuery) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating quantity: {}", query.expr);
+        let outcome = units::evaluate_query(query)?;
+        
+        let precision = self.config.decimal_precision;
+        let result = format!("{} {}", outcome.value.to_decimal(precision), outcome.unit);
+        let mut response = if units::decimal_is_exact(&outcome.value, precision) {
+            DetResponse::exact(result, None)
+        } else {
+            DetResponse::approximate(result)
+        };
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Label a finite-domain problem: the first solutions, a count, or an optimum
+    fn execute_constraints(&self, problem: &constraints::FdProblem) -> anyhow::Result<DetResponse> {
+        log::debug!("Solving finite-domain problem: {:?}", problem);
+        let outcome = constraints::solve(problem, &self.config.fd)?;
+        
+        let mut response = DetResponse::text(outcome.result);
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Decide a linear arithmetic claim, with a counterexample when it fails
+    fn execute_linarith(&self, claim: &linarith::ArithClaim) -> anyhow::Result<DetResponse> {
+        log::debug!("Checking linear arithmetic claim: {}", claim.formula);
+        let outcome = linarith::check(claim)?;
+        
+        let mut response = DetResponse::text(outcome.result);
+        response.verdict = outcome.verdict;
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Run a snippet in the sandbox and answer with its output and final value
+    fn execute_code(&self, snippet: &sandbox::Snippet) -> anyhow::Result<DetResponse> {
+        log::debug!("Running sandboxed snippet: {} bytes", snippet.code.len());
+        let seed = snippet.seed.unwrap_or(self.config.sandbox.seed);
+        let outcome = sandbox::run(&snippet.code, seed, &self.config.sandbox)?;
+        
+        let mut response = DetResponse::text(outcome.summary());
+        response.proof = Some(outcome.steps(seed));
+        Ok(response)
+    }
+    
+    /// Run each example against `code` in the sandbox, for checking generated code
+    pub fn check_examples(&self, code: &str, examples: &[sandbox::Example]) -> Vec<sandbox::ExampleCheck> {
+        examples
+            .iter()
+            .map(|example| sandbox::check_example(code, example, self.config.sandbox.seed, &self.config.sandbox))
+            .collect()
+    }
+    
+    /// Count, index, transform or match text exactly
+    fn execute_text(&self, query: &text::TextQuery) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating text query: {:?}", query);
+        let outcome = text::evaluate(query)?;
+        
+        let mut response = DetResponse::text(outcome.result);
+        response.verdict = outcome.verdict;
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Answer a date question; only "today" reads the host clock
+    fn execute_calendar(&self, query: &calendar::CalendarQuery) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating calendar query: {:?}", query);
+        let outcome = calendar::evaluate(query, self.holidays.as_ref())?;
+        
+        let mut response = DetResponse::text(outcome.result);
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Tabulate a formula or decide satisfiability, validity or equivalence
+    fn execute_propositional(&self, query: &propositional::LogicQuery) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating propositional query: {:?}", query);
+        let outcome = propositional::evaluate(query)?;
+        
+        let mut response = DetResponse::text(outcome.result);
+        response.verdict = outcome.verdict;
+        response.proof = (!outcome.steps.is_empty()).then_some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Evaluate an integer expression, showing the result in several bases
+    fn execute_programmer(&self, query: &programmer::ProgrammerQuery) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating integer expression: {}", query.expr);
+        let outcome = programmer::evaluate(query)?;
+        
+        let mut response = DetResponse::text(outcome.summary(query));
+        response.proof = (!outcome.steps.is_empty()).then_some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Integrate, find a root or step an ODE, reporting the method and its error estimate
+    fn execute_numerical(&self, query: &numerical::NumericQuery, context: &EvalContext) -> anyhow::Result<DetResponse> {
+        log::debug!("Running numerical method: {:?}", query);
+        let outcome = numerical::evaluate(query, context, &self.config.numeric)?;
+        
+        let mut response = DetResponse::approximate(outcome.summary());
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Compute a statistic, combinatoric count or binomial probability
+    fn execute_stats(&self, query: &stats::StatsQuery, context: &EvalContext) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating statistics query: {:?}", query.function);
+        let outcome = stats::evaluate(query, context)?;
+        
+        let mut response = match outcome.values.as_slice() {
+            [value] => self.number_response(value),
+            values => {
+                let text: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                let mut response = if values.iter().all(Number::is_exact) {
+                    DetResponse::exact(text.join(", "), None)
+                } else {
+                    DetResponse::approximate(text.join(", "))
+                };
+                response.value = Some(DetValue::Vector(text));
+                response
+            }
+        };
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Evaluate a matrix/vector expression into a structured result
+    fn execute_linalg(&self, expr: &linalg::LinExpr, context: &EvalContext) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating linear algebra expression: {}", expr);
+        let mut evaluator = linalg::LinEvaluator::new(context);
+        let value = evaluator.evaluate(expr)?;
+        
+        let strings = |items: &[Number]| items.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let mut response = match &value {
+            linalg::Value::Scalar(n) => self.number_response(n),
+            linalg::Value::Vector(items) => {
+                let mut response = if items.iter().all(Number::is_exact) {
+                    DetResponse::exact(value.to_string(), None)
+                } else {
+                    DetResponse::approximate(value.to_string())
+                };
+                response.value = Some(DetValue::Vector(strings(items)));
+                response
+            }
+            linalg::Value::Matrix(m) => {
+                let mut response = if m.is_exact() {
+                    DetResponse::exact(value.to_string(), None)
+                } else {
+                    DetResponse::approximate(value.to_string())
+                };
+                response.value = Some(DetValue::Matrix(m.row_vectors().iter().map(|row| strings(row)).collect()));
+                response
+            }
+            linalg::Value::Roots(roots) => {
+                let mut response = DetResponse::exact(value.to_string(), None);
+                response.value = Some(DetValue::Vector(roots.iter().map(|r| r.to_string()).collect()));
+                response
+            }
+            linalg::Value::ApproxRoots(roots) => {
+                let mut response = DetResponse::approximate(value.to_string());
+                response.value = Some(DetValue::Vector(roots.iter().map(|&r| linalg::format_complex(r)).collect()));
+                response
+            }
+        };
+        response.proof = (!evaluator.steps.is_empty()).then_some(evaluator.steps);
+        Ok(response)
+    }
+    
+    /// Build a response for a numeric result, adding a decimal rendering for fractions
+    fn number_response(&self, value: &Number) -> DetResponse {
+        match value {
+            Number::Exact(r) if !r.is_integer() => DetResponse::exact(
+                value.to_string(),
+                Some(value.to_decimal(self.config.decimal_precision)),
+            ),
+            Number::Exact(_) => DetResponse::exact(value.to_string(), None),
+            Number::Approx(_) => DetResponse::approximate(value.to_string()),
+        }
+    }
+    
+    /// Evaluate the knowledge base bottom-up and answer, or explain, a Datalog goal
+    fn execute_datalog(&self, query: &datalog::DatalogQuery) -> anyhow::Result<DetResponse> {
+        log::debug!("Evaluating Datalog query: {}", query.goal);
+        let outcome = datalog::evaluate(query, &self.knowledge())?;
+        
+        let mut response = DetResponse::text(outcome.result);
+        response.verdict = outcome.verdict;
+        response.proof = Some(outcome.steps);
+        Ok(response)
+    }
+    
+    /// Answer a Horn-clause query against the knowledge base, by SWI-Prolog when enabled
+    /// and otherwise by the built-in resolver
+    fn execute_prolog(&self, query: &prolog::Query) -> anyhow::Result<DetResponse> {
+        log::debug!("Executing Prolog query (prolog enabled: {}): {}", self.config.enable_prolog, query);
+        
+        #[cfg(feature = "swipl")]
+        let outcome = if self.config.enable_prolog {
+            swipl::solve(query, &self.knowledge(), &self.config.swipl)?
+        } else {
+            prolog::solve(query, &self.knowledge())?
+        };
+        #[cfg(not(feature = "swipl"))]
+        let outcome = prolog::solve(query, &self.knowledge())?;
+        let mut response = DetResponse::text(outcome.summary());
+        if query.var_names.iter().all(|name| name.starts_with('_')) {
+            response.verdict = Some(!outcome.solutions.is_empty());
+        }
+        response.proof = Some(outcome.proof);
+        Ok(response)
+    }
+}
+
+/// The expression of a query over session variables or user functions whose remaining
+/// identifiers are all bound
+fn uses_bindings(query: &str, context: &EvalContext) -> Option<expr::Expr> {
+    if context.is_empty() {
+        return None;
+    }
+    expr::parse_expression(extract_math_expression(query)).ok().filter(|parsed| {
+        context.references(parsed) && parsed.free_variables().iter().all(|v| context.variable(v).is_some())
+    })
+}
+
+/// Strip a leading keyword (ASCII case-insensitive) that ends at a word boundary
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let head = text.get(..keyword.len())?;
+    if !head.eq_ignore_ascii_case(keyword) {
+        return None;
+    }
+    let rest = &text[keyword.len()..];
+    match rest.chars().next() {
+        Some(c) if c.is_alphanumeric() || c == '_' => None,
+        _ => Some(rest.trim()),
+    }
+}
+
+/// Strip a trailing phrase (ASCII case-insensitive) preceded by whitespace
+fn strip_suffix_phrase<'a>(text: &'a str, phrase: &str) -> Option<&'a str> {
+    let at = text.len().checked_sub(phrase.len())?;
+    let tail = text.get(at..)?;
+    let head = &text[..at];
+    (tail.eq_ignore_ascii_case(phrase) && head.ends_with(char::is_whitespace)).then(|| head.trim())
+}
+
+/// Strip a leading phrase that ends at a word boundary, comparing grapheme by grapheme
+/// after lowercasing; the rest is cut from `text` itself, so a letter whose lowercase form
+/// has another byte length (such as 'İ') never shifts the cut
+pub fn strip_leading_phrase<'a>(text: &'a str, phrase: &str) -> Option<&'a str> {
+    let mut graphemes = text.grapheme_indices(true);
+    for wanted in phrase.graphemes(true) {
+        let (_, found) = graphemes.next()?;
+        if found.to_lowercase() != wanted.to_lowercase() {
+            return None;
+        }
+    }
+    match graphemes.next() {
+        Some((_, next)) if next.starts_with(is_word_char) => None,
+        Some((at, _)) => Some(&text[at..]),
+        None => Some(""),
+    }
+}
+
+/// Strip a trailing phrase that starts at a word boundary, matching graphemes from the end
+pub fn strip_trailing_phrase<'a>(text: &'a str, phrase: &str) -> Option<&'a str> {
+    let mut graphemes = text.grapheme_indices(true).rev();
+    let mut cut = text.len();
+    for wanted in phrase.graphemes(true).rev() {
+        let (at, found) = graphemes.next()?;
+        if found.to_lowercase() != wanted.to_lowercase() {
+            return None;
+        }
+        cut = at;
+    }
+    match graphemes.next() {
+        Some((_, previous)) if previous.ends_with(is_word_char) => None,
+        _ => Some(&text[..cut]),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Split around the first whitespace-delimited occurrence of `keyword`
+fn split_keyword<'a>(text: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    text.char_indices().find_map(|(i, _)| {
+        let candidate = text.get(i..i + keyword.len())?;
+        let before = text[..i].chars().next_back()?;
+        let after = text[i + keyword.len()..].chars().next()?;
+        (candidate.eq_ignore_ascii_case(keyword) && before.is_whitespace() && after.is_whitespace())
+            .then(|| (text[..i].trim(), text[i + keyword.len()..].trim()))
+    })
+}
+
+/// Extract the mathematical expression from a query string
+/// Handles queries like "Calculate 10 + 5" -> "10 + 5"
+fn extract_math_expression(query: &str) -> &str {
+    // Remove common prefix words, cutting the original query so numbers and names keep
+    // their case
+    let prefixes = ["calculate", "solve", "compute", "evaluate", "what is"];
+    let expr = prefixes
+        .iter()
+        .find_map(|prefix| strip_leading_phrase(query, prefix))
+        .map_or(query, str::trim);
+    
+    // A bare prefix word is left as it was
+    if expr.is_empty() {
+        query
+    } else {
+        expr.trim()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DetRequest {
+    pub query_type: QueryType,
+    pub query: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum QueryType {
+    Math,
+    Logic,
+    Code,
+    Symbolic,
+    Equation,
+    Units,
+    LinearAlgebra,
+    Statistics,
+    Numerical,
+    Calendar,
+    Programmer,
+    NaturalLanguage,
+    Propositional,
+    Datalog,
+    Constraints,
+    LinearArithmetic,
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetResponse {
+    pub result: String,
+    pub proof: Option<Vec<String>>,
+    pub deterministic: bool,
+    /// True when `result` is exact, false for floating-point approximations
+    pub exact: bool,
+    /// Decimal rendering of an exact fractional result
+    pub decimal: Option<String>,
+    /// Structured form of vector and matrix results; `result` holds the same value as text
+    pub value: Option<DetValue>,
+    /// Canonical expression that was evaluated when the query was written in words
+    pub interpreted: Option<String>,
+    /// Answer to a yes/no question such as "is F valid"; hybrid claim checks fail on `Some(false)`
+    pub verdict: Option<bool>,
+}
+
+/// Structured result for answers that are not a single number
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DetValue {
+    /// Entries row by row, each an integer, fraction or decimal
+    Matrix(Vec<Vec<String>>),
+    Vector(Vec<String>),
+}
+
+impl DetResponse {
+    fn exact(result: String, decimal: Option<String>) -> Self {
+        DetResponse {
+            result,
+            proof: None,
+            deterministic: true,
+            exact: true,
+            decimal,
+            value: None,
+            interpreted: None,
+            verdict: None,
+        }
+    }
+
+    fn approximate(result: String) -> Self {
+        DetResponse { exact: false, ..DetResponse::exact(result, None) }
+    }
+
+    fn text(result: impl Into<String>) -> Self {
+        DetResponse::exact(result.into(), None)
+    }
+}
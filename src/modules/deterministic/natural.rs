@@ -0,0 +1,342 @@
+// Natural-language arithmetic: number words and operator phrases rewritten as expressions
+
+/// Words of a query, with numerals and punctuation kept apart
+#[derive(Debug, Clone, PartialEq)]
+enum Word {
+    Text(String),
+    Numeral(String),
+    Symbol(char),
+}
+
+pub(super) const UNITS: &[&str] = &[
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+const TENS: &[&str] = &["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+const SCALES: &[(&str, u128)] = &[
+    ("thousand", 1_000),
+    ("million", 1_000_000),
+    ("billion", 1_000_000_000),
+    ("trillion", 1_000_000_000_000),
+];
+
+/// Denominators in "two thirds" or "a quarter"
+const DENOMINATORS: &[(&str, u32)] = &[
+    ("half", 2), ("halves", 2), ("third", 3), ("thirds", 3), ("quarter", 4), ("quarters", 4), ("fourth", 4),
+    ("fourths", 4), ("fifth", 5), ("fifths", 5), ("sixth", 6), ("sixths", 6), ("seventh", 7), ("sevenths", 7),
+    ("eighth", 8), ("eighths", 8), ("ninth", 9), ("ninths", 9), ("tenth", 10), ("tenths", 10),
+    ("hundredth", 100), ("hundredths", 100), ("thousandth", 1000), ("thousandths", 1000),
+];
+
+/// Ordinals accepted in "to the third power"
+pub(super) const ORDINALS: &[&str] = &[
+    "zeroth", "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+];
+
+/// Operator phrases, longest first within each shared prefix
+const OPERATORS: &[(&[&str], &str)] = &[
+    (&["multiplied", "by"], "*"),
+    (&["times"], "*"),
+    (&["divided", "by"], "/"),
+    (&["over"], "/"),
+    (&["plus"], "+"),
+    (&["minus"], "-"),
+    (&["negative"], "-"),
+    (&["modulo"], "%"),
+    (&["mod"], "%"),
+    (&["raised", "to", "the", "power", "of"], "^"),
+    (&["to", "the", "power", "of"], "^"),
+    (&["raised", "to"], "^"),
+    (&["squared"], "^ 2"),
+    (&["cubed"], "^ 3"),
+    (&["percent", "of"], "/ 100 *"),
+    (&["percent"], "/ 100"),
+];
+
+/// Leading filler dropped before rewriting
+const FILLERS: &[&str] = &[
+    "what", "whats", "what's", "is", "how", "much", "calculate", "compute", "evaluate", "please",
+];
+
+/// Rewrite English number words ("twelve", "a half"), percentages ("15 percent of 80")
+/// and operator words ("divided by", "squared") into an expression such as
+/// "12 * 7 + (1/2)"; `None` when the query has nothing to rewrite
+pub fn normalize(query: &str) -> Option<String> {
+    let words = split_words(&query.to_lowercase());
+    let start = words
+        .iter()
+        .position(|w| !matches!(w, Word::Text(t) if FILLERS.contains(&t.as_str())))
+        .unwrap_or(words.len());
+    let mut rewriter = Rewriter { words: &words, pos: start, output: Vec::new(), rewritten: false, open_calls: 0 };
+    rewriter.run();
+    rewriter.rewritten.then(|| rewriter.output.join(" "))
+}
+
+fn split_words(text: &str) -> Vec<Word> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphabetic() || chars[i] == '\'') {
+                i += 1;
+            }
+            let mut word: String = chars[start..i].iter().collect();
+            // "twenty-one" is one number word
+            if chars.get(i) == Some(&'-') && TENS.contains(&word.as_str()) {
+                let end = (i + 1..chars.len()).find(|&j| !chars[j].is_alphabetic()).unwrap_or(chars.len());
+                let unit: String = chars[i + 1..end].iter().collect();
+                if UNITS[1..10].contains(&unit.as_str()) {
+                    word = format!("{}-{}", word, unit);
+                    i = end;
+                }
+            }
+            words.push(Word::Text(word));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            let decimal_point = |j: usize| chars[j] == '.' && chars.get(j + 1).is_some_and(|n| n.is_ascii_digit());
+            while i < chars.len() && (chars[i].is_ascii_digit() || decimal_point(i)) {
+                i += 1;
+            }
+            words.push(Word::Numeral(chars[start..i].iter().collect()));
+        } else {
+            words.push(Word::Symbol(c));
+            i += 1;
+        }
+    }
+    words
+}
+
+struct Rewriter<'a> {
+    words: &'a [Word],
+    pos: usize,
+    output: Vec<String>,
+    /// True once any word has been replaced by a number or operator
+    rewritten: bool,
+    /// "sqrt(" calls waiting for their operand
+    open_calls: usize,
+}
+
+impl Rewriter<'_> {
+    fn run(&mut self) {
+        while self.pos < self.words.len() {
+            if self.operator() || self.root() || self.number() {
+                continue;
+            }
+            match &self.words[self.pos] {
+                Word::Text(t) if t == "the" => {}
+                Word::Text(t) if (t == "equals" || t == "equal") && self.pos + 1 >= self.words.len() => {}
+                // "half of 80", "15 % of 80"
+                Word::Text(t) if t == "of" && self.follows_operand() => {
+                    self.rewritten = true;
+                    self.output.push("*".to_string());
+                }
+                Word::Symbol('%') if self.peek_text(1) == Some("of") => {
+                    self.rewritten = true;
+                    self.output.push("/ 100 *".to_string());
+                    self.pos += 1;
+                }
+                Word::Text(t) => self.operand(t.clone()),
+                Word::Numeral(n) => self.operand(n.clone()),
+                Word::Symbol(c) => self.output.push(c.to_string()),
+            }
+            self.pos += 1;
+        }
+        self.close_calls();
+    }
+
+    fn peek_text(&self, offset: usize) -> Option<&str> {
+        match self.words.get(self.pos + offset) {
+            Some(Word::Text(t)) => Some(t),
+            _ => None,
+        }
+    }
+
+    fn follows_operand(&self) -> bool {
+        self.output.last().is_some_and(|last| last.ends_with(|c: char| c.is_alphanumeric() || c == ')'))
+    }
+
+    fn operand(&mut self, text: String) {
+        self.output.push(text);
+        self.close_calls();
+    }
+
+    fn close_calls(&mut self) {
+        for _ in 0..self.open_calls {
+            self.output.push(")".to_string());
+        }
+        self.open_calls = 0;
+    }
+
+    fn matches_phrase(&self, phrase: &[&str]) -> bool {
+        phrase.iter().enumerate().all(|(i, word)| self.peek_text(i) == Some(word))
+    }
+
+    fn operator(&mut self) -> bool {
+        // "to the third power", "to the 4th power"
+        if self.matches_phrase(&["to", "the"]) {
+            let (exponent, width) = match (self.words.get(self.pos + 2), self.words.get(self.pos + 3)) {
+                (Some(Word::Text(t)), _) => (ORDINALS.iter().position(|o| o == t).map(|n| n.to_string()), 1),
+                (Some(Word::Numeral(n)), Some(Word::Text(suffix)))
+                    if ["st", "nd", "rd", "th"].contains(&suffix.as_str()) =>
+                {
+                    (Some(n.clone()), 2)
+                }
+                _ => (None, 0),
+            };
+            if let Some(exponent) = exponent {
+                if self.peek_text(2 + width) == Some("power") {
+                    self.output.push(format!("^ {}", exponent));
+                    self.rewritten = true;
+                    self.pos += 3 + width;
+                    return true;
+                }
+            }
+        }
+        for (phrase, symbol) in OPERATORS {
+            if self.matches_phrase(phrase) {
+                self.output.push(symbol.to_string());
+                self.rewritten = true;
+                self.pos += phrase.len();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// "square root of 16" → "sqrt( 16 )", closed after the next operand
+    fn root(&mut self) -> bool {
+        if !self.matches_phrase(&["square", "root", "of"]) {
+            return false;
+        }
+        self.output.push("sqrt(".to_string());
+        self.open_calls += 1;
+        self.rewritten = true;
+        self.pos += 3;
+        true
+    }
+
+    /// A number in words or digits, with any fraction or scale word that follows
+    fn number(&mut self) -> bool {
+        let start = self.pos;
+        let value = match &self.words[self.pos] {
+            Word::Numeral(n) => {
+                self.pos += 1;
+                Some(n.clone())
+            }
+            Word::Text(_) => self.number_words(),
+            Word::Symbol(_) => None,
+        };
+
+        // "a half", "a quarter of 80"
+        let value = match value {
+            Some(value) => value,
+            None if matches!(self.peek_text(0), Some("a" | "an")) && self.denominator(1).is_some() => {
+                self.pos += 1;
+                "1".to_string()
+            }
+            None if self.denominator(0).is_some_and(|d| d == 2) => "1".to_string(),
+            None => {
+                self.pos = start;
+                return false;
+            }
+        };
+
+        let mut text = value.clone();
+        let mut rewritten = !matches!(self.words[start], Word::Numeral(_));
+        // "2 million"
+        if let Some(scale) = self.peek_text(0).and_then(scale_of) {
+            text = format!("({} * {})", value, scale);
+            self.pos += 1;
+            rewritten = true;
+        }
+        // "two thirds"
+        if let Some(denominator) = self.denominator(0) {
+            text = format!("({}/{})", value, denominator);
+            self.pos += 1;
+            rewritten = true;
+        }
+        // "one and a half"
+        if self.peek_text(0) == Some("and") && matches!(self.peek_text(1), Some("a" | "an")) {
+            if let Some(denominator) = self.denominator(2) {
+                text = format!("({} + 1/{})", text, denominator);
+                self.pos += 3;
+                rewritten = true;
+            }
+        }
+
+        if !rewritten {
+            self.pos = start;
+            return false;
+        }
+        self.rewritten = true;
+        self.operand(text);
+        true
+    }
+
+    fn denominator(&self, offset: usize) -> Option<u32> {
+        let word = self.peek_text(offset)?;
+        DENOMINATORS.iter().find(|(name, _)| *name == word).map(|(_, d)| *d)
+    }
+
+    /// "three hundred and five thousand", "three point one four"
+    fn number_words(&mut self) -> Option<String> {
+        let (mut total, mut current, mut any) = (0u128, 0u128, false);
+        while let Some(word) = self.peek_text(0) {
+            if let Some(value) = small_number(word) {
+                current += value;
+            } else if word == "hundred" && any {
+                current = current.max(1) * 100;
+            } else if let Some(scale) = scale_of(word).filter(|_| any) {
+                total += current.max(1) * scale;
+                current = 0;
+            } else if word == "and" && any && self.peek_text(1).and_then(small_number).is_some() {
+                // "one hundred and five"
+            } else if matches!(word, "a" | "an")
+                && !any
+                && self.peek_text(1).is_some_and(|next| next == "hundred" || scale_of(next).is_some())
+            {
+                // "a hundred", "a million"
+            } else {
+                break;
+            }
+            any = true;
+            self.pos += 1;
+        }
+        if !any {
+            return None;
+        }
+        let mut text = (total + current).to_string();
+
+        // Digits after "point" are read one at a time
+        if self.peek_text(0) == Some("point") {
+            let digits: String = (1..)
+                .map_while(|i| self.peek_text(i).and_then(|w| UNITS[..10].iter().position(|u| *u == w)))
+                .map(|d| d.to_string())
+                .collect();
+            if !digits.is_empty() {
+                self.pos += 1 + digits.len();
+                text = format!("{}.{}", text, digits);
+            }
+        }
+        Some(text)
+    }
+}
+
+/// 0-99 written as one word or hyphenated, e.g. "forty-two"
+fn small_number(word: &str) -> Option<u128> {
+    let tens_value = |w: &str| TENS.iter().position(|t| *t == w).map(|i| 20 + 10 * i as u128);
+    let unit_value = |w: &str| UNITS.iter().position(|u| *u == w).map(|i| i as u128);
+    match word.split_once('-') {
+        Some((tens, unit)) => Some(tens_value(tens)? + unit_value(unit).filter(|u| (1..10).contains(u))?),
+        None => unit_value(word).or_else(|| tens_value(word)),
+    }
+}
+
+fn scale_of(word: &str) -> Option<u128> {
+    SCALES.iter().find(|(name, _)| *name == word).map(|(_, scale)| *scale)
+}
@@ -0,0 +1,289 @@
+// Exact and approximate numeric values for the deterministic module
+use super::error::{Limit, LimitError};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::fmt;
+
+/// Largest exponent accepted for exact powers, to bound result size
+const MAX_EXACT_EXPONENT: u32 = 4096;
+
+/// A numeric value that is either an exact reduced fraction or an f64 approximation.
+/// Exact values stay exact under +, -, *, / and integer powers; anything that
+/// touches an approximate operand or a transcendental function becomes approximate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    /// Reduced fraction over arbitrary-precision integers
+    Exact(BigRational),
+    /// IEEE-754 double produced by transcendental functions or float inputs
+    Approx(f64),
+}
+
+impl Number {
+    pub fn from_i64(value: i64) -> Self {
+        Number::Exact(BigRational::from_integer(BigInt::from(value)))
+    }
+
+    pub fn from_integer(value: BigInt) -> Self {
+        Number::Exact(BigRational::from_integer(value))
+    }
+
+    /// Parse a decimal literal such as "12", "0.1" or "6.02e23" exactly
+    pub fn parse_decimal(literal: &str) -> anyhow::Result<Self> {
+        let (mantissa, exponent) = match literal.find(['e', 'E']) {
+            Some(idx) => {
+                let exp: i64 = literal[idx + 1..]
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid exponent in number '{}'", literal))?;
+                (&literal[..idx], exp)
+            }
+            None => (literal, 0),
+        };
+
+        let (int_part, frac_part) = match mantissa.find('.') {
+            Some(idx) => (&mantissa[..idx], &mantissa[idx + 1..]),
+            None => (mantissa, ""),
+        };
+
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(anyhow::anyhow!("Invalid number '{}'", literal));
+        }
+
+        let digits = format!("{}{}", int_part, frac_part);
+        let numerator: BigInt = digits
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid number '{}'", literal))?;
+
+        let scale = exponent - frac_part.len() as i64;
+        if scale.unsigned_abs() > MAX_EXACT_EXPONENT as u64 {
+            return Err(anyhow::anyhow!("Exponent too large in number '{}'", literal));
+        }
+
+        let factor = num_traits::pow(BigInt::from(10), scale.unsigned_abs() as usize);
+        let value = if scale >= 0 {
+            BigRational::from_integer(numerator * factor)
+        } else {
+            BigRational::new(numerator, factor)
+        };
+
+        Ok(Number::Exact(value))
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Number::Exact(_))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Exact(r) => r.is_zero(),
+            Number::Approx(f) => *f == 0.0,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Number::Exact(r) => r.is_negative(),
+            Number::Approx(f) => *f < 0.0,
+        }
+    }
+
+    /// Return the value as an integer if it is an exact whole number
+    pub fn as_integer(&self) -> Option<BigInt> {
+        match self {
+            Number::Exact(r) if r.is_integer() => Some(r.to_integer()),
+            _ => None,
+        }
+    }
+
+    pub fn as_rational(&self) -> Option<&BigRational> {
+        match self {
+            Number::Exact(r) => Some(r),
+            Number::Approx(_) => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Approx(f) => *f,
+        }
+    }
+
+    /// Build an approximate value, rejecting NaN and infinities
+    pub fn approx(value: f64) -> anyhow::Result<Self> {
+        if value.is_finite() {
+            Ok(Number::Approx(value))
+        } else {
+            Err(anyhow::anyhow!("Result is not a finite real number"))
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        match self {
+            Number::Exact(r) => Number::Exact(-r),
+            Number::Approx(f) => Number::Approx(-f),
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        match self {
+            Number::Exact(r) => Number::Exact(r.abs()),
+            Number::Approx(f) => Number::Approx(f.abs()),
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> anyhow::Result<Number> {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Ok(Number::Exact(a + b)),
+            _ => Number::approx(self.to_f64() + other.to_f64()),
+        }
+    }
+
+    pub fn sub(&self, other: &Number) -> anyhow::Result<Number> {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Ok(Number::Exact(a - b)),
+            _ => Number::approx(self.to_f64() - other.to_f64()),
+        }
+    }
+
+    pub fn mul(&self, other: &Number) -> anyhow::Result<Number> {
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Ok(Number::Exact(a * b)),
+            _ => Number::approx(self.to_f64() * other.to_f64()),
+        }
+    }
+
+    pub fn div(&self, other: &Number) -> anyhow::Result<Number> {
+        if other.is_zero() {
+            return Err(anyhow::anyhow!("Division by zero"));
+        }
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Ok(Number::Exact(a / b)),
+            _ => Number::approx(self.to_f64() / other.to_f64()),
+        }
+    }
+
+    /// Remainder with the sign of the dividend, matching integer `%`
+    pub fn rem(&self, other: &Number) -> anyhow::Result<Number> {
+        if other.is_zero() {
+            return Err(anyhow::anyhow!("Division by zero"));
+        }
+        match (self, other) {
+            (Number::Exact(a), Number::Exact(b)) => Ok(Number::Exact(a % b)),
+            _ => Number::approx(self.to_f64() % other.to_f64()),
+        }
+    }
+
+    /// Raise to a power, staying exact for integer exponents and for
+    /// rational exponents whose roots are themselves rational
+    pub fn pow(&self, exponent: &Number) -> anyhow::Result<Number> {
+        if let (Number::Exact(base), Number::Exact(exp)) = (self, exponent) {
+            if let Some(result) = exact_pow(base, exp)? {
+                return Ok(Number::Exact(result));
+            }
+        }
+
+        let (b, e) = (self.to_f64(), exponent.to_f64());
+        if b < 0.0 && e.fract() != 0.0 {
+            return Err(anyhow::anyhow!("Result is not a real number: {} ^ {}", self, exponent));
+        }
+        // An exact power past the cap whose float also overflows was never computable here
+        let result = b.powf(e);
+        if !result.is_finite() && self.is_exact() && exponent.is_exact() {
+            return Err(LimitError::new(
+                Limit::Size,
+                format!("Exponent {} exceeds the limit of {} for exact powers", exponent, MAX_EXACT_EXPONENT),
+            )
+            .into());
+        }
+        Number::approx(result)
+    }
+
+    /// Render the value as a decimal with at most `precision` fractional digits,
+    /// rounding half away from zero and trimming trailing zeros
+    pub fn to_decimal(&self, precision: usize) -> String {
+        match self {
+            Number::Exact(r) => rational_to_decimal(r, precision),
+            Number::Approx(f) => format!("{}", f),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Exact(r) if r.is_integer() => write!(f, "{}", r.numer()),
+            Number::Exact(r) => write!(f, "{}/{}", r.numer(), r.denom()),
+            Number::Approx(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Compute `base ^ exp` exactly, or return `None` when the result is irrational
+fn exact_pow(base: &BigRational, exp: &BigRational) -> anyhow::Result<Option<BigRational>> {
+    let numer = exp.numer().abs().to_u32().filter(|n| *n <= MAX_EXACT_EXPONENT);
+    let denom = exp.denom().to_u32().filter(|d| *d <= MAX_EXACT_EXPONENT);
+    let (Some(numer), Some(denom)) = (numer, denom) else {
+        return Ok(None);
+    };
+
+    if base.is_zero() && exp.is_negative() {
+        return Err(anyhow::anyhow!("Division by zero"));
+    }
+
+    let root = if denom == 1 {
+        base.clone()
+    } else {
+        if base.is_negative() && denom % 2 == 0 {
+            return Err(anyhow::anyhow!("Result is not a real number: even root of a negative value"));
+        }
+        match (exact_root(base.numer(), denom), exact_root(base.denom(), denom)) {
+            (Some(n), Some(d)) => BigRational::new(n, d),
+            _ => return Ok(None),
+        }
+    };
+
+    let mut result = num_traits::pow(root, numer as usize);
+    if exp.is_negative() {
+        result = BigRational::one() / result;
+    }
+    Ok(Some(result))
+}
+
+/// Integer n-th root, if `value` is a perfect n-th power
+fn exact_root(value: &BigInt, n: u32) -> Option<BigInt> {
+    let root = value.nth_root(n);
+    if num_traits::pow(root.clone(), n as usize) == *value {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+fn rational_to_decimal(value: &BigRational, precision: usize) -> String {
+    let negative = value.is_negative();
+    let abs = value.abs();
+    let scale = num_traits::pow(BigInt::from(10), precision);
+
+    // Round half away from zero at the requested precision
+    let scaled = &abs * BigRational::from_integer(scale.clone());
+    let mut digits = scaled.floor().to_integer();
+    if scaled.fract() * BigInt::from(2) >= BigRational::one() {
+        digits += 1;
+    }
+
+    let int_part = &digits / &scale;
+    let frac_part = &digits % &scale;
+
+    let mut text = int_part.to_string();
+    if precision > 0 && !frac_part.is_zero() {
+        let frac = format!("{:0>width$}", frac_part.to_string(), width = precision);
+        text.push('.');
+        text.push_str(frac.trim_end_matches('0'));
+    }
+
+    if negative && !digits.is_zero() {
+        text.insert(0, '-');
+    }
+    text
+}
@@ -0,0 +1,559 @@
+// Programmer mode: radix literals, bitwise operators and fixed-width integers
+use super::expr::Parser;
+use super::lexer::{tokenize, Token, TokenKind};
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::fmt;
+
+/// Largest shift accepted when no width bounds the result
+const MAX_UNBOUNDED_SHIFT: usize = 4096;
+
+/// Fixed-width integer type, as in Rust's u8..u128 and i8..i128
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+impl IntType {
+    /// "u8", "i32", "usize" (64 bits) and so on
+    pub fn parse(name: &str) -> Option<IntType> {
+        let name = name.to_lowercase();
+        let (signed, bits) = name.split_at_checked(1)?;
+        let signed = match signed {
+            "u" => false,
+            "i" => true,
+            _ => return None,
+        };
+        let bits = match bits {
+            "8" => 8,
+            "16" => 16,
+            "32" => 32,
+            "64" | "size" => 64,
+            "128" => 128,
+            _ => return None,
+        };
+        Some(IntType { signed, bits })
+    }
+
+    pub fn min(&self) -> BigInt {
+        if self.signed {
+            -(BigInt::one() << (self.bits - 1))
+        } else {
+            BigInt::zero()
+        }
+    }
+
+    pub fn max(&self) -> BigInt {
+        let magnitude_bits = if self.signed { self.bits - 1 } else { self.bits };
+        (BigInt::one() << magnitude_bits) - 1
+    }
+
+    fn contains(&self, value: &BigInt) -> bool {
+        *value >= self.min() && *value <= self.max()
+    }
+
+    /// Keep the low `bits` bits and read them back in this type (two's complement)
+    pub fn wrap(&self, value: &BigInt) -> BigInt {
+        let modulus = BigInt::one() << self.bits;
+        let low = ((value % &modulus) + &modulus) % &modulus;
+        if self.signed && low > self.max() {
+            low - modulus
+        } else {
+            low
+        }
+    }
+
+    /// The value's bit pattern as an unsigned number
+    fn bit_pattern(&self, value: &BigInt) -> BigInt {
+        if value.is_negative() {
+            value + (BigInt::one() << self.bits)
+        } else {
+            value.clone()
+        }
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", if self.signed { "i" } else { "u" }, self.bits)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hex,
+}
+
+impl Radix {
+    fn parse(name: &str) -> Option<Radix> {
+        match name.to_lowercase().as_str() {
+            "bin" | "binary" => Some(Radix::Binary),
+            "oct" | "octal" => Some(Radix::Octal),
+            "dec" | "decimal" => Some(Radix::Decimal),
+            "hex" | "hexadecimal" => Some(Radix::Hex),
+            _ => None,
+        }
+    }
+
+    fn base(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hex => 16,
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            Radix::Binary => "0b",
+            Radix::Octal => "0o",
+            Radix::Decimal => "",
+            Radix::Hex => "0x",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shl,
+    Shr,
+    And,
+    Xor,
+    Or,
+}
+
+impl IntOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            IntOp::Add => "+",
+            IntOp::Sub => "-",
+            IntOp::Mul => "*",
+            IntOp::Div => "/",
+            IntOp::Rem => "%",
+            IntOp::Shl => "<<",
+            IntOp::Shr => ">>",
+            IntOp::And => "&",
+            IntOp::Xor => "^",
+            IntOp::Or => "|",
+        }
+    }
+}
+
+/// Integer expression; '^' is exclusive or here, not a power
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntExpr {
+    /// Literal with its source base and optional type suffix ("0xFFu8")
+    Literal { value: BigInt, radix: Radix, suffix: Option<IntType> },
+    Neg(Box<IntExpr>),
+    /// Bitwise complement, written '~' or '!'
+    Not(Box<IntExpr>),
+    Binary(IntOp, Box<IntExpr>, Box<IntExpr>),
+}
+
+impl fmt::Display for IntExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntExpr::Literal { value, radix, suffix } => {
+                write!(f, "{}", format_radix(value, *radix, None))?;
+                match suffix {
+                    Some(ty) => write!(f, "{}", ty),
+                    None => Ok(()),
+                }
+            }
+            IntExpr::Neg(inner) => write!(f, "-{}", inner),
+            IntExpr::Not(inner) => write!(f, "~{}", inner),
+            IntExpr::Binary(op, lhs, rhs) => {
+                let operand = |e: &IntExpr| match e {
+                    IntExpr::Binary(..) => format!("({})", e),
+                    _ => e.to_string(),
+                };
+                write!(f, "{} {} {}", operand(lhs), op.symbol(), operand(rhs))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgrammerQuery {
+    pub expr: IntExpr,
+    /// From "as u8" / "in i16"; otherwise taken from literal suffixes
+    pub width: Option<IntType>,
+    /// From "in hex"; otherwise every base is shown
+    pub output: Option<Radix>,
+}
+
+/// Recognize integer queries that use radix literals, bitwise operators, a
+/// width ("as i8") or an output base ("in hex"); `None` leaves the query to other handlers
+pub fn parse_command(query: &str) -> Option<anyhow::Result<ProgrammerQuery>> {
+    let mut text = query.trim();
+    while let Some(rest) = ["convert", "calculate", "compute", "evaluate", "what is"]
+        .iter()
+        .find_map(|p| super::strip_keyword(text, p))
+    {
+        text = rest;
+    }
+    let (mut width, mut output) = (None, None);
+    while let Some((head, last)) = text.rsplit_once(char::is_whitespace) {
+        let Some((head, keyword)) = head.trim_end().rsplit_once(char::is_whitespace) else { break };
+        if !["as", "in", "to"].contains(&keyword.to_lowercase().as_str()) {
+            break;
+        }
+        match (IntType::parse(last), Radix::parse(last)) {
+            (Some(ty), _) if width.is_none() => width = Some(ty),
+            (_, Some(radix)) if output.is_none() => output = Some(radix),
+            _ => break,
+        }
+        text = head.trim_end();
+    }
+
+    let tokens = tokenize(text).ok()?;
+    if !is_programmer_syntax(&tokens) && width.is_none() && output.is_none() {
+        return None;
+    }
+    // Identifiers other than type suffixes belong to other domains, e.g. "p & q"
+    let suffix_only = tokens.iter().enumerate().all(|(i, token)| match &token.kind {
+        TokenKind::Ident(name) => i > 0 && IntType::parse(name).is_some(),
+        TokenKind::Number(literal) => is_radix_literal(literal) || !literal.contains(['.', 'e', 'E']),
+        _ => true,
+    });
+    if !suffix_only {
+        return None;
+    }
+
+    Some(parse_tokens(tokens).and_then(|expr| {
+        let width = match width {
+            Some(width) => Some(width),
+            None => suffix_width(&expr)?,
+        };
+        Ok(ProgrammerQuery { expr, width, output })
+    }))
+}
+
+fn is_radix_literal(literal: &str) -> bool {
+    literal.len() > 2 && literal.starts_with('0') && literal[1..2].chars().all(|c| "xXoObB".contains(c))
+}
+
+fn is_programmer_syntax(tokens: &[Token]) -> bool {
+    tokens.iter().any(|token| match &token.kind {
+        TokenKind::Number(literal) => is_radix_literal(literal),
+        TokenKind::Symbol(symbol) => ["<<", ">>", "&", "|", "~", "!"].contains(symbol),
+        TokenKind::Ident(name) => IntType::parse(name).is_some(),
+        TokenKind::Text(_) | TokenKind::Pattern(_) => false,
+    })
+}
+
+/// The single width named by literal suffixes, if any
+fn suffix_width(expr: &IntExpr) -> anyhow::Result<Option<IntType>> {
+    match expr {
+        IntExpr::Literal { suffix, .. } => Ok(*suffix),
+        IntExpr::Neg(inner) | IntExpr::Not(inner) => suffix_width(inner),
+        IntExpr::Binary(_, lhs, rhs) => match (suffix_width(lhs)?, suffix_width(rhs)?) {
+            (Some(a), Some(b)) if a != b => Err(anyhow::anyhow!("Mismatched integer types {} and {}", a, b)),
+            (a, b) => Ok(a.or(b)),
+        },
+    }
+}
+
+fn parse_tokens(tokens: Vec<Token>) -> anyhow::Result<IntExpr> {
+    let mut parser = Parser::new(tokens);
+    let expr = parse_binary(&mut parser, 0)?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+
+/// Binary operators from loosest to tightest, following Rust's precedence
+const LEVELS: &[&[(&str, IntOp)]] = &[
+    &[("|", IntOp::Or)],
+    &[("^", IntOp::Xor)],
+    &[("&", IntOp::And)],
+    &[("<<", IntOp::Shl), (">>", IntOp::Shr)],
+    &[("+", IntOp::Add), ("-", IntOp::Sub)],
+    &[("*", IntOp::Mul), ("/", IntOp::Div), ("%", IntOp::Rem)],
+];
+
+/// level := next_level (op next_level)*, left associative
+fn parse_binary(parser: &mut Parser, level: usize) -> anyhow::Result<IntExpr> {
+    let Some(operators) = LEVELS.get(level) else {
+        return parse_unary(parser);
+    };
+    let mut lhs = parse_binary(parser, level + 1)?;
+    while let Some((_, op)) = operators.iter().find(|(symbol, _)| parser.eat_symbol(symbol)) {
+        let rhs = parse_binary(parser, level + 1)?;
+        lhs = IntExpr::Binary(*op, Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+}
+
+/// unary := ('-' | '~' | '!') unary | literal | '(' expr ')'
+fn parse_unary(parser: &mut Parser) -> anyhow::Result<IntExpr> {
+    if parser.eat_symbol("-") {
+        return Ok(IntExpr::Neg(Box::new(parse_unary(parser)?)));
+    }
+    if parser.eat_symbol("~") || parser.eat_symbol("!") {
+        return Ok(IntExpr::Not(Box::new(parse_unary(parser)?)));
+    }
+    if parser.eat_symbol("(") {
+        let inner = parse_binary(parser, 0)?;
+        parser.expect_symbol(")")?;
+        return Ok(inner);
+    }
+
+    let Some(Token { kind: TokenKind::Number(literal), column, .. }) = parser.peek().cloned() else {
+        return Err(parser.error("an integer"));
+    };
+    parser.advance();
+    let (value, radix, mut suffix) = parse_literal(&literal)?;
+    // "255u8" lexes as a number followed by an adjacent type name
+    if let Some(Token { kind: TokenKind::Ident(name), column: next, .. }) = parser.peek().cloned() {
+        if next == column + literal.chars().count() && suffix.is_none() {
+            suffix = IntType::parse(&name);
+            parser.advance();
+        }
+    }
+    Ok(IntExpr::Literal { value, radix, suffix })
+}
+
+/// "0xFF_FF", "0b1010", "0o17", "42", each with an optional suffix like "u8"
+fn parse_literal(literal: &str) -> anyhow::Result<(BigInt, Radix, Option<IntType>)> {
+    let (radix, digits) = match literal.get(..2).map(str::to_lowercase).as_deref() {
+        Some("0x") => (Radix::Hex, &literal[2..]),
+        Some("0o") => (Radix::Octal, &literal[2..]),
+        Some("0b") => (Radix::Binary, &literal[2..]),
+        _ => (Radix::Decimal, literal),
+    };
+    let (digits, suffix) = match digits.find(['u', 'U', 'i', 'I']) {
+        Some(at) => {
+            let suffix = IntType::parse(&digits[at..])
+                .ok_or_else(|| anyhow::anyhow!("Unknown integer type suffix in '{}'", literal))?;
+            (&digits[..at], Some(suffix))
+        }
+        None => (digits, None),
+    };
+    let digits: String = digits.chars().filter(|c| *c != '_').collect();
+    let value = BigInt::parse_bytes(digits.as_bytes(), radix.base())
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} literal '{}'", radix_name(radix), literal))?;
+    Ok((value, radix, suffix))
+}
+
+fn radix_name(radix: Radix) -> &'static str {
+    match radix {
+        Radix::Binary => "binary",
+        Radix::Octal => "octal",
+        Radix::Decimal => "decimal",
+        Radix::Hex => "hexadecimal",
+    }
+}
+
+/// Result with every overflow that was wrapped on the way
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgrammerOutcome {
+    pub value: BigInt,
+    pub overflowed: bool,
+    pub steps: Vec<String>,
+}
+
+impl ProgrammerOutcome {
+    /// "255 = 0xFF = 0o377 = 0b1111_1111", or only the base asked for
+    pub fn summary(&self, query: &ProgrammerQuery) -> String {
+        let mut text = match query.output {
+            Some(radix) => format_radix(&self.value, radix, query.width),
+            None => [Radix::Decimal, Radix::Hex, Radix::Octal, Radix::Binary]
+                .iter()
+                .map(|radix| format_radix(&self.value, *radix, query.width))
+                .collect::<Vec<_>>()
+                .join(" = "),
+        };
+        if self.overflowed {
+            text.push_str(" (overflowed)");
+        }
+        text
+    }
+}
+
+/// Evaluate with arbitrary precision, or in the query's width with wrapping on overflow
+pub fn evaluate(query: &ProgrammerQuery) -> anyhow::Result<ProgrammerOutcome> {
+    let mut evaluator = IntEvaluator { width: query.width, steps: Vec::new(), overflowed: false };
+    if let Some(width) = query.width {
+        evaluator.steps.push(format!("type: {} ({}..={})", width, width.min(), width.max()));
+    }
+    let value = evaluator.evaluate(&query.expr)?;
+    Ok(ProgrammerOutcome { value, overflowed: evaluator.overflowed, steps: evaluator.steps })
+}
+
+struct IntEvaluator {
+    width: Option<IntType>,
+    steps: Vec<String>,
+    overflowed: bool,
+}
+
+impl IntEvaluator {
+    fn evaluate(&mut self, expr: &IntExpr) -> anyhow::Result<BigInt> {
+        match expr {
+            IntExpr::Literal { value, radix, .. } => Ok(self.literal(value, *radix)),
+            IntExpr::Neg(inner) => {
+                // "-128" is one literal, so it fits in i8
+                if let IntExpr::Literal { value, radix: Radix::Decimal, .. } = inner.as_ref() {
+                    return Ok(self.fit(format!("literal -{}", value), -value));
+                }
+                let value = self.evaluate(inner)?;
+                Ok(self.fit(format!("-({})", value), -value))
+            }
+            IntExpr::Not(inner) => {
+                let value = self.evaluate(inner)?;
+                let result = match self.width {
+                    Some(ty) if !ty.signed => ty.max() - &value,
+                    _ => -&value - 1,
+                };
+                self.steps.push(format!("~{} = {}", value, result));
+                Ok(result)
+            }
+            IntExpr::Binary(op, lhs, rhs) => {
+                let a = self.evaluate(lhs)?;
+                let b = self.evaluate(rhs)?;
+                self.binary(*op, a, b)
+            }
+        }
+    }
+
+    /// Radix literals are bit patterns, so 0xFF in i8 reads as -1; decimal literals must fit
+    fn literal(&mut self, value: &BigInt, radix: Radix) -> BigInt {
+        let Some(ty) = self.width else {
+            return value.clone();
+        };
+        if radix != Radix::Decimal && value.bits() <= u64::from(ty.bits) {
+            let read = ty.wrap(value);
+            if read != *value {
+                self.steps.push(format!(
+                    "{} as {} = {} (two's complement)",
+                    format_radix(value, radix, None),
+                    ty,
+                    read
+                ));
+            }
+            return read;
+        }
+        self.fit(format!("literal {}", value), value.clone())
+    }
+
+    fn binary(&mut self, op: IntOp, a: BigInt, b: BigInt) -> anyhow::Result<BigInt> {
+        let description = format!("{} {} {}", a, op.symbol(), b);
+        let exact = match op {
+            IntOp::Add => &a + &b,
+            IntOp::Sub => &a - &b,
+            IntOp::Mul => &a * &b,
+            IntOp::Div | IntOp::Rem if b.is_zero() => {
+                return Err(anyhow::anyhow!("Division by zero in {}", description));
+            }
+            // Truncating division and remainder, as in Rust and C
+            IntOp::Div => &a / &b,
+            IntOp::Rem => &a % &b,
+            IntOp::Shl | IntOp::Shr => return self.shift(op, a, b, description),
+            IntOp::And => &a & &b,
+            IntOp::Xor => &a ^ &b,
+            IntOp::Or => &a | &b,
+        };
+        Ok(self.fit(description, exact))
+    }
+
+    /// Shifts wrap silently like Rust's, but a shift by the width or more overflows
+    fn shift(&mut self, op: IntOp, a: BigInt, b: BigInt, description: String) -> anyhow::Result<BigInt> {
+        if b.is_negative() {
+            return Err(anyhow::anyhow!("Negative shift amount in {}", description));
+        }
+        let amount = match self.width {
+            Some(ty) if b >= BigInt::from(ty.bits) => {
+                let masked = (&b % ty.bits).to_usize().unwrap_or_default();
+                self.overflowed = true;
+                self.steps.push(format!(
+                    "overflow: shift by {} is not less than the {}-bit width of {}; using {}",
+                    b, ty.bits, ty, masked
+                ));
+                masked
+            }
+            Some(_) => b.to_usize().unwrap_or_default(),
+            None => b
+                .to_usize()
+                .filter(|n| *n <= MAX_UNBOUNDED_SHIFT)
+                .ok_or_else(|| anyhow::anyhow!("Shift amount {} exceeds the limit of {}", b, MAX_UNBOUNDED_SHIFT))?,
+        };
+        // An unsigned right shift is logical; signed values shift arithmetically
+        let result = match (op, self.width) {
+            (IntOp::Shl, Some(ty)) => ty.wrap(&(&a << amount)),
+            (IntOp::Shl, None) => &a << amount,
+            (_, _) => a.clone() >> amount,
+        };
+        self.steps.push(format!("{} = {}", description, result));
+        Ok(result)
+    }
+
+    /// Record the step, wrapping and reporting when the value leaves the width's range
+    fn fit(&mut self, description: String, value: BigInt) -> BigInt {
+        match self.width {
+            Some(ty) if !ty.contains(&value) => {
+                let wrapped = ty.wrap(&value);
+                self.overflowed = true;
+                self.steps.push(format!(
+                    "overflow: {} = {} is outside {} ({}..={}); wraps to {}",
+                    description,
+                    value,
+                    ty,
+                    ty.min(),
+                    ty.max(),
+                    wrapped
+                ));
+                wrapped
+            }
+            _ => {
+                if !description.starts_with("literal") {
+                    self.steps.push(format!("{} = {}", description, value));
+                }
+                value
+            }
+        }
+    }
+}
+
+/// Render in `radix`; with a width, negative values show their two's-complement
+/// bits and binary/hex are padded to the full width
+pub fn format_radix(value: &BigInt, radix: Radix, width: Option<IntType>) -> String {
+    if radix == Radix::Decimal {
+        return value.to_string();
+    }
+    let (sign, magnitude) = match width {
+        Some(ty) => ("", ty.bit_pattern(value)),
+        None if value.is_negative() => ("-", -value),
+        None => ("", value.clone()),
+    };
+    let mut digits = magnitude.to_str_radix(radix.base());
+    let digits = match radix {
+        Radix::Hex => {
+            digits = digits.to_uppercase();
+            let pad = width.map_or(0, |ty| ty.bits as usize / 4);
+            format!("{:0>pad$}", digits, pad = pad)
+        }
+        Radix::Binary => {
+            let pad = width.map_or(0, |ty| ty.bits as usize);
+            group_digits(&format!("{:0>pad$}", digits, pad = pad), 4)
+        }
+        _ => digits,
+    };
+    format!("{}{}{}", sign, radix.prefix(), digits)
+}
+
+/// "11111111" → "1111_1111", grouping from the least significant digit
+fn group_digits(digits: &str, size: usize) -> String {
+    let chars: Vec<char> = digits.chars().collect();
+    let mut groups: Vec<String> = chars.rchunks(size).map(|chunk| chunk.iter().collect()).collect();
+    groups.reverse();
+    groups.join("_")
+}
@@ -0,0 +1,1061 @@
+// Horn-clause resolution: Prolog terms, unification and SLD search with proof trees
+use super::error::{Limit, LimitError};
+use super::expr::Parser;
+use super::lexer::{relocate, tokenize, SyntaxError, Token, TokenKind};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Module of the standard clauses and of files without a module directive
+pub const USER_MODULE: &str = "user";
+
+/// Most answers collected for one query
+pub const MAX_SOLUTIONS: usize = 20;
+
+/// Resolution steps allowed per query before giving up
+const MAX_INFERENCES: usize = 100_000;
+
+/// Deepest chain of resolution steps on one branch, which bounds left recursion
+const MAX_DEPTH: usize = 400;
+
+/// Longest list length/2 builds from a partial list
+const MAX_LIST_LENGTH: i64 = 100_000;
+
+/// Binary operators of is/2 expressions, written infix
+const ARITHMETIC_OPERATORS: &[&str] = &["+", "-", "*", "/", "//", "mod"];
+
+/// Facts and rules available without any configuration
+const STANDARD_CLAUSES: &str = "
+    parent(cronus, zeus). parent(rhea, zeus).
+    parent(cronus, hera). parent(rhea, hera).
+    parent(cronus, poseidon). parent(rhea, poseidon).
+    parent(zeus, ares). parent(hera, ares).
+    parent(zeus, hephaestus). parent(hera, hephaestus).
+    parent(zeus, apollo). parent(leto, apollo).
+    parent(zeus, artemis). parent(leto, artemis).
+    parent(zeus, athena).
+    parent(zeus, hermes). parent(maia, hermes).
+    parent(zeus, hercules). parent(alcmene, hercules).
+
+    ancestor(X, Y) :- parent(X, Y).
+    ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y).
+    sibling(X, Y) :- parent(P, X), parent(P, Y), not(X = Y).
+
+    member(X, [X|_]).
+    member(X, [_|T]) :- member(X, T).
+    append([], L, L).
+    append([H|T], L, [H|R]) :- append(T, L, R).
+";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Term {
+    Atom(String),
+    Int(i64),
+    /// Index into the clause's (or query's) variables; renamed apart during resolution
+    Var(usize),
+    Compound(String, Vec<Term>),
+}
+
+impl Term {
+    /// Name and arity, for atoms and compound terms
+    pub fn indicator(&self) -> Option<(&str, usize)> {
+        match self {
+            Term::Atom(name) => Some((name, 0)),
+            Term::Compound(name, args) => Some((name, args.len())),
+            _ => None,
+        }
+    }
+
+    fn rename(&self, base: usize) -> Term {
+        match self {
+            Term::Var(i) => Term::Var(base + i),
+            Term::Compound(name, args) => Term::Compound(name.clone(), args.iter().map(|a| a.rename(base)).collect()),
+            _ => self.clone(),
+        }
+    }
+
+    /// Render with variables named from `names`, falling back to "_G<n>"
+    fn render(&self, names: &[String]) -> String {
+        match self {
+            Term::Atom(name) => name.clone(),
+            Term::Int(value) => value.to_string(),
+            Term::Var(i) => names.get(*i).cloned().unwrap_or_else(|| format!("_G{}", i)),
+            Term::Compound(name, args) if (name == "=" || name == "is") && args.len() == 2 => {
+                format!("{} {} {}", args[0].render(names), name, args[1].render(names))
+            }
+            Term::Compound(name, args) if name == "\\+" && args.len() == 1 => format!("\\+ {}", args[0].render(names)),
+            Term::Compound(name, args) if name == "-" && args.len() == 1 => format!("-{}", args[0].operand(names)),
+            Term::Compound(name, args) if name == "/" && args.len() == 2 => {
+                format!("{}/{}", args[0].operand(names), args[1].operand(names))
+            }
+            Term::Compound(name, args) if name == ":" && args.len() == 2 => {
+                format!("{}:{}", args[0].render(names), args[1].render(names))
+            }
+            Term::Compound(name, args) if ARITHMETIC_OPERATORS.contains(&name.as_str()) && args.len() == 2 => {
+                format!("{} {} {}", args[0].operand(names), name, args[1].operand(names))
+            }
+            Term::Compound(name, args) if name == "." && args.len() == 2 => {
+                let mut items = vec![args[0].render(names)];
+                let mut tail = &args[1];
+                while let Term::Compound(name, rest) = tail {
+                    if name != "." || rest.len() != 2 {
+                        break;
+                    }
+                    items.push(rest[0].render(names));
+                    tail = &rest[1];
+                }
+                match tail {
+                    Term::Atom(nil) if nil == "[]" => format!("[{}]", items.join(", ")),
+                    other => format!("[{}|{}]", items.join(", "), other.render(names)),
+                }
+            }
+            Term::Compound(name, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.render(names)).collect();
+                format!("{}({})", name, args.join(", "))
+            }
+        }
+    }
+}
+
+impl Term {
+    /// Rendered as an operand of an arithmetic operator, parenthesized when it is an operation
+    fn operand(&self, names: &[String]) -> String {
+        match self {
+            Term::Compound(name, args)
+                if (ARITHMETIC_OPERATORS.contains(&name.as_str()) && args.len() == 2) || (name == "-" && args.len() == 1) =>
+            {
+                format!("({})", self.render(names))
+            }
+            _ => self.render(names),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&[]))
+    }
+}
+
+/// A fact when `body` is empty, otherwise a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub module: String,
+    pub head: Term,
+    pub body: Vec<Term>,
+    /// Source names of the clause's variables, indexed by `Term::Var`
+    pub var_names: Vec<String>,
+}
+
+impl fmt::Display for Clause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.module != USER_MODULE {
+            write!(f, "{}:", self.module)?;
+        }
+        write!(f, "{}", self.head.render(&self.var_names))?;
+        if !self.body.is_empty() {
+            let body: Vec<String> = self.body.iter().map(|g| g.render(&self.var_names)).collect();
+            write!(f, " :- {}", body.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Conjunction of goals, with the names of its variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub goals: Vec<Term>,
+    pub var_names: Vec<String>,
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let goals: Vec<String> = self.goals.iter().map(|g| g.render(&self.var_names)).collect();
+        write!(f, "{}", goals.join(", "))
+    }
+}
+
+/// Clauses in consult order, indexed by module, predicate name and arity
+#[derive(Debug, Clone, Default)]
+pub struct KnowledgeBase {
+    clauses: Vec<Clause>,
+    index: BTreeMap<(String, String, usize), Vec<usize>>,
+    /// Files and directories consulted after the standard clauses, in order
+    sources: Vec<PathBuf>,
+}
+
+impl KnowledgeBase {
+    pub fn new() -> Self {
+        KnowledgeBase::default()
+    }
+
+    /// The built-in family tree with ancestor/2, sibling/2, member/2 and append/3
+    pub fn standard() -> Self {
+        let mut kb = KnowledgeBase::new();
+        for clause in parse_program(STANDARD_CLAUSES, USER_MODULE).expect("standard clauses parse") {
+            kb.add(clause);
+        }
+        kb
+    }
+
+    /// Standard clauses followed by every `.pl` file in `sources`; a directory
+    /// contributes its `.pl` files in name order
+    pub fn load(sources: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut kb = KnowledgeBase::standard();
+        for source in sources {
+            for path in source_files(source)? {
+                kb.load_file(&path)?;
+            }
+        }
+        kb.sources = sources.to_vec();
+        Ok(kb)
+    }
+
+    /// Clauses added from one file, with the module they went into
+    fn load_file(&mut self, path: &Path) -> anyhow::Result<(String, usize)> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read knowledge base {}: {}", path.display(), e))?;
+        let clauses = parse_program(&text, USER_MODULE).map_err(|e| match e.downcast::<SyntaxError>() {
+            Ok(syntax) => syntax.in_file(path.display().to_string()).into(),
+            Err(e) => anyhow::anyhow!("{}:{}", path.display(), e),
+        })?;
+        let module = clauses.first().map_or(USER_MODULE, |c| c.module.as_str()).to_string();
+        let count = clauses.len();
+        for clause in clauses {
+            self.add(clause);
+        }
+        Ok((module, count))
+    }
+
+    pub fn apply(&mut self, command: &KbCommand) -> anyhow::Result<String> {
+        match command {
+            KbCommand::Consult(path) => {
+                if !path.is_dir() && path.extension().is_none_or(|ext| ext != "pl") {
+                    return Err(anyhow::anyhow!("Knowledge base files must have a .pl extension: {}", path.display()));
+                }
+                // Consulting a file again replaces its clauses, as in Prolog
+                let mut sources = self.sources.clone();
+                if !sources.contains(path) {
+                    sources.push(path.clone());
+                }
+                let mut kb = KnowledgeBase::load(&sources[..sources.len() - 1])?;
+                let mut loaded = Vec::new();
+                for file in source_files(path)? {
+                    loaded.push(kb.load_file(&file)?);
+                }
+                kb.sources = sources;
+                *self = kb;
+                let count: usize = loaded.iter().map(|(_, count)| count).sum();
+                let mut modules: Vec<&str> = loaded.iter().map(|(module, _)| module.as_str()).collect();
+                modules.dedup();
+                Ok(format!("Consulted {}: {} in {}", path.display(), plural(count, "clause"), modules.join(", ")))
+            }
+            KbCommand::Reload => {
+                // Files are re-read before anything is replaced, so a syntax error keeps the old clauses
+                *self = KnowledgeBase::load(&self.sources)?;
+                Ok(format!("Reloaded {}: {}", plural(self.sources.len(), "source"), plural(self.len(), "clause")))
+            }
+            KbCommand::List => Ok(self.describe()),
+        }
+    }
+
+    /// "user: ancestor/2, append/3, ..." with one line per module
+    pub fn describe(&self) -> String {
+        let mut modules: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (module, name, arity) in self.index.keys() {
+            modules.entry(module).or_default().push(format!("{}/{}", name, arity));
+        }
+        modules
+            .iter()
+            .map(|(module, predicates)| format!("{}: {}", module, predicates.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn add(&mut self, clause: Clause) {
+        if let Some((name, arity)) = clause.head.indicator() {
+            let key = (clause.module.clone(), name.to_string(), arity);
+            self.index.entry(key).or_default().push(self.clauses.len());
+        }
+        self.clauses.push(clause);
+    }
+
+    /// Every clause in consult order
+    pub fn clauses(&self) -> &[Clause] {
+        &self.clauses
+    }
+
+    pub fn len(&self) -> usize {
+        self.clauses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// True when any module defines `name/arity`
+    pub fn defines(&self, name: &str, arity: usize) -> bool {
+        self.index.keys().any(|(_, n, a)| n == name && *a == arity)
+    }
+
+    /// The stored name of `module`, if any clause lives there
+    fn module_name(&self, module: &str) -> Option<&str> {
+        self.index.keys().map(|(m, ..)| m.as_str()).find(|m| *m == module)
+    }
+
+    /// Clauses for a goal called from `module`: that module first, then "user", then
+    /// the one other module defining it; `qualified` goals look only in `module`
+    fn procedure(&self, module: &str, qualified: bool, name: &str, arity: usize) -> anyhow::Result<&[usize]> {
+        let lookup = |m: &str| self.index.get(&(m.to_string(), name.to_string(), arity)).map(Vec::as_slice);
+        if qualified {
+            return lookup(module).ok_or_else(|| anyhow::anyhow!("Unknown procedure {}:{}/{}", module, name, arity));
+        }
+        if let Some(clauses) = lookup(module).or_else(|| lookup(USER_MODULE)) {
+            return Ok(clauses);
+        }
+        let defining: Vec<&str> =
+            self.index.keys().filter(|(_, n, a)| n == name && *a == arity).map(|(m, ..)| m.as_str()).collect();
+        match defining.as_slice() {
+            [] => Err(anyhow::anyhow!("Unknown procedure {}/{}", name, arity)),
+            [only] => Ok(lookup(only).unwrap_or_default()),
+            many => Err(anyhow::anyhow!(
+                "{}/{} is defined in modules {}; qualify the goal, e.g. {}:{}",
+                name,
+                arity,
+                many.join(", "),
+                many[0],
+                name
+            )),
+        }
+    }
+}
+
+/// "1 answer", "3 answers"
+pub fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// The `.pl` files a source names: itself, or a directory's files in name order
+fn source_files(source: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !source.is_dir() {
+        return Ok(vec![source.to_path_buf()]);
+    }
+    let entries = std::fs::read_dir(source)
+        .map_err(|e| anyhow::anyhow!("Failed to read knowledge base directory {}: {}", source.display(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pl"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Changes to the knowledge base made from a query
+#[derive(Debug, Clone, PartialEq)]
+pub enum KbCommand {
+    /// Load a file or directory, replacing clauses from an earlier consult of it
+    Consult(PathBuf),
+    /// Re-read every consulted source
+    Reload,
+    /// Modules and their predicates
+    List,
+}
+
+/// "consult kb/family.pl", "consult('kb/family.pl')", "reload", "list predicates"
+pub fn parse_kb_command(query: &str) -> Option<KbCommand> {
+    let text = query.trim().trim_end_matches('.');
+    if let Some(rest) = super::strip_keyword(text, "consult") {
+        let path = rest.trim_start_matches('(').trim_end_matches(')').trim_matches(|c| c == '\'' || c == '[' || c == ']');
+        return (!path.is_empty()).then(|| KbCommand::Consult(PathBuf::from(path.trim())));
+    }
+    match text.to_lowercase().as_str() {
+        "reload" | "make" | "reload kb" | "reload knowledge base" => Some(KbCommand::Reload),
+        "list predicates" | "list modules" | "list knowledge base" => Some(KbCommand::List),
+        _ => None,
+    }
+}
+
+/// Goals evaluated by the engine itself rather than by clauses
+fn is_builtin(name: &str, arity: usize) -> bool {
+    matches!(
+        (name, arity),
+        ("true", 0) | ("fail", 0) | ("false", 0) | ("=", 2) | ("not", 1) | ("\\+", 1) | ("is", 2) | ("length", 2)
+    )
+}
+
+/// Module and goal of "module:goal"; `None` for a malformed qualification
+fn unqualified(goal: &Term) -> Option<(Option<&str>, &Term)> {
+    match goal {
+        Term::Compound(colon, args) if colon == ":" && args.len() == 2 => match &args[0] {
+            Term::Atom(module) => Some((Some(module), &args[1])),
+            _ => None,
+        },
+        _ => Some((None, goal)),
+    }
+}
+
+/// Parse clauses such as "parent(zeus, ares). ancestor(X, Y) :- parent(X, Y)." into
+/// `module`, or the module named by a ":- module(name, [...])." directive; errors
+/// start with the 1-based line, e.g. "3: Expected ')' at column 12, found '.'"
+pub fn parse_program(input: &str, module: &str) -> anyhow::Result<Vec<Clause>> {
+    let (tokens, lines) = tokenize_source(input)?;
+    let mut reader = TermReader { parser: Parser::new(tokens), names: Vec::new() };
+    let mut module = module.to_string();
+    let mut clauses = Vec::new();
+    while !reader.parser.at_end() {
+        let clause = read_clause(&mut reader, &mut module).map_err(|e| {
+            let line = lines.get(reader.parser.position()).or(lines.last()).copied().unwrap_or(1);
+            on_line(e, line)
+        })?;
+        clauses.extend(clause);
+    }
+    Ok(clauses)
+}
+
+/// One clause, or `None` after a module directive
+fn read_clause(reader: &mut TermReader, module: &mut String) -> anyhow::Result<Option<Clause>> {
+    reader.names.clear();
+    let column = |reader: &TermReader| reader.parser.peek().map_or(1, |t| t.column);
+    if reader.parser.eat_symbol(":-") {
+        let start = column(reader);
+        match reader.term()? {
+            Term::Compound(name, args) if name == "module" && !args.is_empty() => match &args[0] {
+                Term::Atom(name) => *module = name.clone(),
+                other => return Err(SyntaxError::new(start, "an atom as the module name", other.to_string()).into()),
+            },
+            other => return Err(SyntaxError::new(start, "a module(name, [...]) directive", other.to_string()).into()),
+        }
+        reader.parser.expect_symbol(".")?;
+        return Ok(None);
+    }
+    let start = column(reader);
+    let head = reader.term()?;
+    if !matches!(head, Term::Atom(_) | Term::Compound(..)) {
+        return Err(SyntaxError::new(start, "an atom or compound term as the clause head", head.to_string()).into());
+    }
+    let body = if reader.parser.eat_symbol(":-") { reader.goals()? } else { Vec::new() };
+    reader.parser.expect_symbol(".")?;
+    Ok(Some(Clause { module: module.clone(), head, body, var_names: std::mem::take(&mut reader.names) }))
+}
+
+/// Tokens of a source file with the line of each; '%' comments and /* */ blocks
+/// are skipped, and columns count from the start of their line
+fn tokenize_source(text: &str) -> anyhow::Result<(Vec<Token>, Vec<usize>)> {
+    let (mut tokens, mut lines) = (Vec::new(), Vec::new());
+    let mut in_block = false;
+    for (index, line) in text.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut code = String::with_capacity(line.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let opens = chars[i] == '/' && chars.get(i + 1) == Some(&'*');
+            let closes = chars[i] == '*' && chars.get(i + 1) == Some(&'/');
+            if in_block || opens {
+                // Blank out comment text so later columns stay where they were
+                in_block = !closes && (in_block || opens);
+                let width = if opens || closes { 2 } else { 1 };
+                code.extend(std::iter::repeat_n(' ', width));
+                i += width;
+            } else if chars[i] == '%' {
+                break;
+            } else {
+                code.push(chars[i]);
+                i += 1;
+            }
+        }
+        let line_tokens = tokenize(&code).map_err(|e| on_line(e, index + 1))?;
+        lines.extend(std::iter::repeat_n(index + 1, line_tokens.len()));
+        tokens.extend(line_tokens);
+    }
+    Ok((tokens, lines))
+}
+
+/// Put the line on a syntax error, or in front of any other message
+fn on_line(error: anyhow::Error, line: usize) -> anyhow::Error {
+    match error.downcast::<SyntaxError>() {
+        Ok(syntax) => syntax.on_line(line).into(),
+        Err(error) => anyhow::anyhow!("{}: {}", line, error),
+    }
+}
+
+/// Parse a query such as "ancestor(zeus, X), not(X = ares)", with an optional final '.'
+pub fn parse_query(input: &str) -> anyhow::Result<Query> {
+    let mut reader = TermReader { parser: Parser::new(tokenize(input)?), names: Vec::new() };
+    let goals = reader.goals()?;
+    reader.parser.eat_symbol(".");
+    reader.parser.expect_end()?;
+    Ok(Query { goals, var_names: reader.names })
+}
+
+/// "?- goal" always; otherwise goals whose predicates the knowledge base defines
+pub fn parse_command(query: &str, kb: &KnowledgeBase) -> Option<anyhow::Result<Query>> {
+    let text = query.trim();
+    if text.contains(":-") {
+        return Some(Err(anyhow::anyhow!("'{}' is a clause, not a query", text)));
+    }
+    if let Some(rest) = text.strip_prefix("?-") {
+        return Some(parse_query(rest).map_err(|e| relocate(e, query, rest)));
+    }
+    let query = parse_query(text).ok()?;
+    let indicators: Vec<(&str, usize)> =
+        query.goals.iter().filter_map(|goal| unqualified(goal).and_then(|(_, goal)| goal.indicator())).collect();
+    let known = indicators.len() == query.goals.len()
+        && indicators.iter().all(|(name, arity)| is_builtin(name, *arity) || kb.defines(name, *arity))
+        && indicators.iter().any(|(name, arity)| kb.defines(name, *arity));
+    known.then_some(Ok(query))
+}
+
+/// Goals written as predicate calls, e.g. "grandparent(zeus, X)", that are not math functions
+pub fn looks_like_goal(query: &str) -> bool {
+    parse_query(query).is_ok_and(|query| {
+        query.goals.iter().all(|goal| {
+            matches!(goal, Term::Compound(name, _) if name != "=" && !super::expr::BUILTIN_FUNCTIONS.contains(&name.as_str()))
+        })
+    })
+}
+
+/// Terms over the shared lexer; variables are capitalized or start with '_'
+struct TermReader {
+    parser: Parser,
+    names: Vec<String>,
+}
+
+impl TermReader {
+    /// goals := goal (',' goal)*
+    fn goals(&mut self) -> anyhow::Result<Vec<Term>> {
+        let mut goals = vec![self.goal()?];
+        while self.parser.eat_symbol(",") {
+            goals.push(self.goal()?);
+        }
+        Ok(goals)
+    }
+
+    /// goal := '\\+' goal | atom ':' goal | term ('=' term | 'is' expression)?
+    fn goal(&mut self) -> anyhow::Result<Term> {
+        if self.parser.eat_symbol("\\+") {
+            let goal = if self.parser.eat_symbol("(") {
+                let goal = self.goal()?;
+                self.parser.expect_symbol(")")?;
+                goal
+            } else {
+                self.goal()?
+            };
+            return Ok(Term::Compound("\\+".to_string(), vec![goal]));
+        }
+        let lhs = self.term()?;
+        if matches!(lhs, Term::Atom(_)) && self.parser.eat_symbol(":") {
+            let goal = self.goal()?;
+            return Ok(Term::Compound(":".to_string(), vec![lhs, goal]));
+        }
+        if self.parser.eat_symbol("=") {
+            let rhs = self.term()?;
+            return Ok(Term::Compound("=".to_string(), vec![lhs, rhs]));
+        }
+        if self.eat_word("is") {
+            let rhs = self.expression()?;
+            return Ok(Term::Compound("is".to_string(), vec![lhs, rhs]));
+        }
+        Ok(lhs)
+    }
+
+    /// expression := product (('+' | '-') product)*
+    fn expression(&mut self) -> anyhow::Result<Term> {
+        let mut lhs = self.product()?;
+        loop {
+            let op = match () {
+                _ if self.parser.eat_symbol("+") => "+",
+                _ if self.parser.eat_symbol("-") => "-",
+                _ => return Ok(lhs),
+            };
+            lhs = Term::Compound(op.to_string(), vec![lhs, self.product()?]);
+        }
+    }
+
+    /// product := factor (('*' | '/' | '//' | 'mod') factor)*
+    fn product(&mut self) -> anyhow::Result<Term> {
+        let mut lhs = self.factor()?;
+        loop {
+            let slash = self.parser.peek().filter(|t| t.is_symbol("/")).map(|t| t.column);
+            let op = match slash {
+                Some(column) => {
+                    self.parser.advance();
+                    // "//" is two '/' tokens side by side
+                    if self.parser.peek().is_some_and(|t| t.is_symbol("/") && t.column == column + 1) {
+                        self.parser.advance();
+                        "//"
+                    } else {
+                        "/"
+                    }
+                }
+                _ if self.parser.eat_symbol("*") => "*",
+                _ if self.eat_word("mod") => "mod",
+                _ => return Ok(lhs),
+            };
+            lhs = Term::Compound(op.to_string(), vec![lhs, self.factor()?]);
+        }
+    }
+
+    /// factor := '-' factor | '(' expression ')' | term
+    fn factor(&mut self) -> anyhow::Result<Term> {
+        if self.parser.eat_symbol("-") {
+            return Ok(match self.factor()? {
+                Term::Int(value) => Term::Int(-value),
+                other => Term::Compound("-".to_string(), vec![other]),
+            });
+        }
+        if self.parser.eat_symbol("(") {
+            let inner = self.expression()?;
+            self.parser.expect_symbol(")")?;
+            return Ok(inner);
+        }
+        self.term()
+    }
+
+    /// Consume the identifier `word`, used as an operator
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = self.parser.peek().is_some_and(|t| matches!(&t.kind, TokenKind::Ident(name) if name == word));
+        if found {
+            self.parser.advance();
+        }
+        found
+    }
+
+    /// term := integer | variable | atom ('(' term (',' term)* ')')? | list
+    fn term(&mut self) -> anyhow::Result<Term> {
+        if self.parser.eat_symbol("[") {
+            return self.list();
+        }
+        let negative = self.parser.eat_symbol("-");
+        match self.parser.peek().map(|t| t.kind.clone()) {
+            Some(TokenKind::Number(text)) => {
+                let value: i64 = text.parse().map_err(|_| self.parser.error("an integer"))?;
+                self.parser.advance();
+                Ok(Term::Int(if negative { -value } else { value }))
+            }
+            Some(TokenKind::Ident(name)) if !negative => {
+                self.parser.advance();
+                if name.starts_with(|c: char| c.is_uppercase() || c == '_') {
+                    return Ok(self.variable(name));
+                }
+                // Predicate indicators such as "flows_into/2" in export lists
+                if self.parser.peek().is_some_and(|t| t.is_symbol("/"))
+                    && matches!(self.parser.peek_ahead(1).map(|t| &t.kind), Some(TokenKind::Number(_)))
+                {
+                    self.parser.advance();
+                    let arity = self.term()?;
+                    return Ok(Term::Compound("/".to_string(), vec![Term::Atom(name), arity]));
+                }
+                if !self.parser.eat_symbol("(") {
+                    return Ok(Term::Atom(name));
+                }
+                let mut args = vec![self.goal()?];
+                while self.parser.eat_symbol(",") {
+                    args.push(self.goal()?);
+                }
+                self.parser.expect_symbol(")")?;
+                Ok(Term::Compound(name, args))
+            }
+            _ => Err(self.parser.error("a term")),
+        }
+    }
+
+    /// After '[': "]" | term (',' term)* ('|' term)? "]"
+    fn list(&mut self) -> anyhow::Result<Term> {
+        if self.parser.eat_symbol("]") {
+            return Ok(Term::Atom("[]".to_string()));
+        }
+        let mut items = vec![self.term()?];
+        while self.parser.eat_symbol(",") {
+            items.push(self.term()?);
+        }
+        let tail = if self.parser.eat_symbol("|") { self.term()? } else { Term::Atom("[]".to_string()) };
+        self.parser.expect_symbol("]")?;
+        Ok(items.into_iter().rev().fold(tail, |tail, head| Term::Compound(".".to_string(), vec![head, tail])))
+    }
+
+    /// Each "_" is a fresh variable; other names are shared within the clause
+    fn variable(&mut self, name: String) -> Term {
+        if name != "_" {
+            if let Some(i) = self.names.iter().position(|n| *n == name) {
+                return Term::Var(i);
+            }
+        }
+        self.names.push(name);
+        Term::Var(self.names.len() - 1)
+    }
+}
+
+/// Answers to a query, with a proof tree for each
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryOutcome {
+    /// One entry per answer, binding each named query variable
+    pub solutions: Vec<Vec<(String, Term)>>,
+    /// True when the search stopped at the answer limit with more possibly left
+    pub truncated: bool,
+    pub proof: Vec<String>,
+    pub inferences: usize,
+}
+
+impl QueryOutcome {
+    /// Outcome whose proof is "?- query", then `steps`, then the answer and inference counts
+    pub fn new(
+        query: &Query,
+        solutions: Vec<Vec<(String, Term)>>,
+        truncated: bool,
+        steps: Vec<String>,
+        inferences: usize,
+    ) -> Self {
+        let mut proof = vec![format!("?- {}", query)];
+        proof.extend(steps);
+        // Stopping at the answer limit says nothing about how many answers there are
+        let answers = match truncated {
+            true => format!("first {} (limit reached)", plural(solutions.len(), "answer")),
+            false => plural(solutions.len(), "answer"),
+        };
+        proof.push(format!("% {}, {}", answers, plural(inferences, "inference")));
+        QueryOutcome { solutions, truncated, proof, inferences }
+    }
+
+    /// "true", "false", or the answers as "X = ares ; X = apollo"
+    pub fn summary(&self) -> String {
+        if self.solutions.is_empty() {
+            return "false".to_string();
+        }
+        if self.solutions.iter().all(Vec::is_empty) {
+            return "true".to_string();
+        }
+        let answers: Vec<String> = self.solutions.iter().map(|bindings| answer_text(bindings)).collect();
+        let mut text = answers.join(" ; ");
+        if self.truncated {
+            text.push_str(&format!(" ; ... (first {} answers)", MAX_SOLUTIONS));
+        }
+        text
+    }
+}
+
+/// "X = ares, Y = zeus", or "true" without named variables
+fn answer_text(bindings: &[(String, Term)]) -> String {
+    if bindings.is_empty() {
+        return "true".to_string();
+    }
+    bindings.iter().map(|(name, value)| format!("{} = {}", name, value)).collect::<Vec<_>>().join(", ")
+}
+
+/// Solve `query` by SLD resolution: goals left to right, clauses in order, with backtracking
+pub fn solve(query: &Query, kb: &KnowledgeBase) -> anyhow::Result<QueryOutcome> {
+    let mut machine = Machine {
+        kb,
+        bindings: vec![None; query.var_names.len()],
+        trail: Vec::new(),
+        nodes: Vec::new(),
+        inferences: 0,
+        query,
+        solutions: Vec::new(),
+        proof: Vec::new(),
+    };
+    let goals: Vec<Goal> = query
+        .goals
+        .iter()
+        .map(|goal| Goal { term: goal.clone(), node: machine.node(goal), module: USER_MODULE })
+        .collect();
+    let roots: Vec<usize> = goals.iter().map(|goal| goal.node).collect();
+    let truncated = machine.solve(&goals, 0, &mut |m| m.record_solution(&roots))?;
+
+    Ok(QueryOutcome::new(query, machine.solutions, truncated, machine.proof, machine.inferences))
+}
+
+/// How a goal in the proof tree was proved
+#[derive(Debug, Clone, Copy)]
+enum Reason {
+    Pending,
+    Clause(usize),
+    Builtin,
+}
+
+/// A pending goal, its proof tree node and the module it is called from
+#[derive(Debug, Clone)]
+struct Goal<'a> {
+    term: Term,
+    node: usize,
+    module: &'a str,
+}
+
+#[derive(Debug, Clone)]
+struct ProofNode {
+    goal: Term,
+    reason: Reason,
+    children: Vec<usize>,
+}
+
+/// Bindings with a trail for undoing them on backtracking; proof nodes past a
+/// choice point are dropped the same way
+struct Machine<'a> {
+    kb: &'a KnowledgeBase,
+    bindings: Vec<Option<Term>>,
+    trail: Vec<usize>,
+    nodes: Vec<ProofNode>,
+    inferences: usize,
+    query: &'a Query,
+    solutions: Vec<Vec<(String, Term)>>,
+    proof: Vec<String>,
+}
+
+/// Called when every goal is proved; returns true to stop searching
+type OnSolution<'s, 'a> = dyn FnMut(&mut Machine<'a>) -> bool + 's;
+
+impl<'a> Machine<'a> {
+    fn node(&mut self, goal: &Term) -> usize {
+        self.nodes.push(ProofNode { goal: goal.clone(), reason: Reason::Pending, children: Vec::new() });
+        self.nodes.len() - 1
+    }
+
+    /// Follow variable bindings until an unbound variable or a non-variable term
+    fn walk(&self, term: &Term) -> Term {
+        let mut term = term.clone();
+        while let Term::Var(i) = term {
+            match &self.bindings[i] {
+                Some(bound) => term = bound.clone(),
+                None => break,
+            }
+        }
+        term
+    }
+
+    /// Apply every binding, recursively
+    fn resolve(&self, term: &Term) -> Term {
+        match self.walk(term) {
+            Term::Compound(name, args) => Term::Compound(name, args.iter().map(|a| self.resolve(a)).collect()),
+            other => other,
+        }
+    }
+
+    fn occurs(&self, var: usize, term: &Term) -> bool {
+        match self.walk(term) {
+            Term::Var(i) => i == var,
+            Term::Compound(_, args) => args.iter().any(|a| self.occurs(var, a)),
+            _ => false,
+        }
+    }
+
+    fn bind(&mut self, var: usize, value: Term) {
+        self.bindings[var] = Some(value);
+        self.trail.push(var);
+    }
+
+    fn undo(&mut self, mark: usize) {
+        while self.trail.len() > mark {
+            if let Some(var) = self.trail.pop() {
+                self.bindings[var] = None;
+            }
+        }
+    }
+
+    /// Unify with occurs check; the caller undoes partial bindings on failure
+    fn unify(&mut self, a: &Term, b: &Term) -> bool {
+        match (self.walk(a), self.walk(b)) {
+            (Term::Var(x), Term::Var(y)) if x == y => true,
+            (Term::Var(x), other) | (other, Term::Var(x)) => {
+                if self.occurs(x, &other) {
+                    return false;
+                }
+                self.bind(x, other);
+                true
+            }
+            (Term::Atom(x), Term::Atom(y)) => x == y,
+            (Term::Int(x), Term::Int(y)) => x == y,
+            (Term::Compound(f, xs), Term::Compound(g, ys)) => {
+                f == g && xs.len() == ys.len() && xs.iter().zip(&ys).all(|(x, y)| self.unify(x, y))
+            }
+            _ => false,
+        }
+    }
+
+    /// Prove `goals` in order, calling `on_solution` for each complete proof;
+    /// returns true once `on_solution` asks to stop
+    fn solve(&mut self, goals: &[Goal<'a>], depth: usize, on_solution: &mut OnSolution<'_, 'a>) -> anyhow::Result<bool> {
+        let Some((Goal { term, node, module }, rest)) = goals.split_first() else {
+            return Ok(on_solution(self));
+        };
+        if depth >= MAX_DEPTH {
+            let message = format!("Proof depth limit of {} exceeded; the rules may recurse without end", MAX_DEPTH);
+            return Err(LimitError::new(Limit::Depth, message).into());
+        }
+        self.inferences += 1;
+        if self.inferences > MAX_INFERENCES {
+            return Err(LimitError::new(Limit::Inferences, format!("Query exceeded {} inferences", MAX_INFERENCES)).into());
+        }
+
+        let mut goal = self.walk(term);
+        let (mut module, mut qualified) = (*module, false);
+        while let Term::Compound(colon, args) = &goal {
+            if colon != ":" || args.len() != 2 {
+                break;
+            }
+            let Term::Atom(name) = self.walk(&args[0]) else {
+                return Err(anyhow::anyhow!("Module qualifier must be an atom in {}", self.resolve(&goal)));
+            };
+            module = self.kb.module_name(&name).ok_or_else(|| anyhow::anyhow!("Unknown module {}", name))?;
+            qualified = true;
+            goal = self.walk(&args[1]);
+        }
+        let (name, arity) = match goal.indicator() {
+            Some((name, arity)) => (name.to_string(), arity),
+            None if matches!(goal, Term::Var(_)) => return Err(anyhow::anyhow!("Arguments are not sufficiently instantiated")),
+            None => return Err(anyhow::anyhow!("Expected a callable goal, found {}", goal)),
+        };
+
+        if is_builtin(&name, arity) {
+            self.nodes[*node].reason = Reason::Builtin;
+            let mark = self.trail.len();
+            let holds = match (name.as_str(), &goal) {
+                ("true", _) => true,
+                ("=", Term::Compound(_, args)) => self.unify(&args[0], &args[1]),
+                ("is", Term::Compound(_, args)) => {
+                    let value = self.evaluate(&args[1])?;
+                    self.unify(&args[0], &Term::Int(value))
+                }
+                ("length", Term::Compound(_, args)) => self.length(&args[0], &args[1])?,
+                // Negation as failure: true when the inner goal has no proof
+                ("not" | "\\+", Term::Compound(_, args)) => {
+                    let nodes = self.nodes.len();
+                    let inner = Goal { term: args[0].clone(), node: self.node(&args[0]), module };
+                    let provable = self.solve(&[inner], depth + 1, &mut |_| true)?;
+                    self.undo(mark);
+                    self.nodes.truncate(nodes);
+                    !provable
+                }
+                _ => false,
+            };
+            if holds && self.solve(rest, depth + 1, on_solution)? {
+                return Ok(true);
+            }
+            self.undo(mark);
+            return Ok(false);
+        }
+
+        for &index in self.kb.procedure(module, qualified, &name, arity)? {
+            let clause = &self.kb.clauses[index];
+            let (mark, nodes, base) = (self.trail.len(), self.nodes.len(), self.bindings.len());
+            self.bindings.resize(base + clause.var_names.len(), None);
+            if self.unify(&goal, &clause.head.rename(base)) {
+                let mut next = Vec::with_capacity(clause.body.len() + rest.len());
+                let mut children = Vec::with_capacity(clause.body.len());
+                for body_goal in &clause.body {
+                    let body_goal = body_goal.rename(base);
+                    let child = self.node(&body_goal);
+                    children.push(child);
+                    next.push(Goal { term: body_goal, node: child, module: &clause.module });
+                }
+                next.extend_from_slice(rest);
+                self.nodes[*node].reason = Reason::Clause(index);
+                self.nodes[*node].children = children;
+                if self.solve(&next, depth + 1, on_solution)? {
+                    return Ok(true);
+                }
+            }
+            self.undo(mark);
+            self.nodes.truncate(nodes);
+            self.bindings.truncate(base);
+        }
+        Ok(false)
+    }
+
+    /// Integer value of an is/2 expression
+    fn evaluate(&self, term: &Term) -> anyhow::Result<i64> {
+        let overflow = || anyhow::anyhow!("Integer overflow evaluating {}", self.resolve(term));
+        match self.walk(term) {
+            Term::Int(value) => Ok(value),
+            Term::Var(_) => Err(anyhow::anyhow!("Arguments are not sufficiently instantiated")),
+            Term::Compound(op, args) if args.len() == 1 && (op == "-" || op == "abs") => {
+                let value = self.evaluate(&args[0])?;
+                if op == "-" { value.checked_neg() } else { value.checked_abs() }.ok_or_else(overflow)
+            }
+            Term::Compound(op, args)
+                if args.len() == 2 && (ARITHMETIC_OPERATORS.contains(&op.as_str()) || op == "min" || op == "max") =>
+            {
+                let (a, b) = (self.evaluate(&args[0])?, self.evaluate(&args[1])?);
+                if b == 0 && matches!(op.as_str(), "/" | "//" | "mod") {
+                    return Err(anyhow::anyhow!("Division by zero evaluating {}", self.resolve(term)));
+                }
+                match op.as_str() {
+                    "+" => a.checked_add(b),
+                    "-" => a.checked_sub(b),
+                    "*" => a.checked_mul(b),
+                    // Integer division truncates toward zero; mod takes the sign of the divisor
+                    "//" => a.checked_div(b),
+                    "mod" => a.checked_rem(b).map(|r| if r != 0 && (r < 0) != (b < 0) { r + b } else { r }),
+                    "/" if a.checked_rem(b) == Some(0) => a.checked_div(b),
+                    "/" => return Err(anyhow::anyhow!("{} / {} is not an integer; use // for integer division", a, b)),
+                    "min" => Some(a.min(b)),
+                    _ => Some(a.max(b)),
+                }
+                .ok_or_else(overflow)
+            }
+            other => Err(anyhow::anyhow!("{} is not an arithmetic expression", self.resolve(&other))),
+        }
+    }
+
+    /// length/2 of a proper list, or a partial list extended with fresh variables to a given length
+    fn length(&mut self, list: &Term, length: &Term) -> anyhow::Result<bool> {
+        let (mut count, mut tail) = (0i64, self.walk(list));
+        while let Term::Compound(dot, args) = &tail {
+            if dot != "." || args.len() != 2 {
+                break;
+            }
+            count += 1;
+            tail = self.walk(&args[1]);
+        }
+        match (tail, self.walk(length)) {
+            (Term::Atom(nil), length) if nil == "[]" => Ok(self.unify(&length, &Term::Int(count))),
+            (Term::Var(var), Term::Int(n)) if n >= count => {
+                if n - count > MAX_LIST_LENGTH {
+                    let message = format!("length/2 would build a list of more than {} elements", MAX_LIST_LENGTH);
+                    return Err(LimitError::new(Limit::Size, message).into());
+                }
+                let fresh = self.bindings.len();
+                self.bindings.resize(fresh + (n - count) as usize, None);
+                let items = (fresh..self.bindings.len()).rev().map(Term::Var);
+                let nil = Term::Atom("[]".to_string());
+                let rest = items.fold(nil, |tail, head| Term::Compound(".".to_string(), vec![head, tail]));
+                self.bind(var, rest);
+                Ok(true)
+            }
+            (Term::Var(_), Term::Var(_)) => Err(anyhow::anyhow!("Arguments are not sufficiently instantiated")),
+            _ => Ok(false),
+        }
+    }
+
+    fn record_solution(&mut self, roots: &[usize]) -> bool {
+        let bindings: Vec<(String, Term)> = self
+            .query
+            .var_names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !name.starts_with('_'))
+            .map(|(i, name)| (name.clone(), self.resolve(&Term::Var(i))))
+            .collect();
+        // One proof tree per answer, each under the answer it proves
+        self.proof.push(format!("% answer {}: {}", self.solutions.len() + 1, answer_text(&bindings)));
+        self.solutions.push(bindings);
+        for &root in roots {
+            self.render_proof(root, 0);
+        }
+        self.solutions.len() >= MAX_SOLUTIONS
+    }
+
+    /// One line per goal, indented under the goal whose clause introduced it
+    fn render_proof(&mut self, node: usize, depth: usize) {
+        let ProofNode { goal, reason, children } = self.nodes[node].clone();
+        let reason = match reason {
+            Reason::Clause(index) if self.kb.clauses[index].body.is_empty() => "fact".to_string(),
+            Reason::Clause(index) => format!("rule {}", self.kb.clauses[index]),
+            Reason::Builtin => "built-in".to_string(),
+            Reason::Pending => "unproved".to_string(),
+        };
+        let line = format!("{}{} by {}", "  ".repeat(depth), self.resolve(&goal), reason);
+        self.proof.push(line);
+        for child in children {
+            self.render_proof(child, depth + 1);
+        }
+    }
+}
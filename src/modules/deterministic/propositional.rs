@@ -0,0 +1,641 @@
+// Propositional logic: truth tables, and satisfiability by DPLL over a Tseitin CNF
+use super::expr::Parser;
+use super::lexer::{tokenize, Token, TokenKind};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Most variables tabulated in a truth table (256 rows)
+const MAX_TABLE_VARIABLES: usize = 8;
+
+/// Truth value of each variable, by name
+pub type Assignment = BTreeMap<String, bool>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connective {
+    And,
+    Or,
+    Implies,
+    Iff,
+}
+
+impl Connective {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Connective::And => "&",
+            Connective::Or => "|",
+            Connective::Implies => "->",
+            Connective::Iff => "<->",
+        }
+    }
+
+    /// Binding strength; '&' binds tightest and '<->' loosest
+    fn precedence(&self) -> u8 {
+        match self {
+            Connective::Iff => 1,
+            Connective::Implies => 2,
+            Connective::Or => 3,
+            Connective::And => 4,
+        }
+    }
+
+    fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            Connective::And => a && b,
+            Connective::Or => a || b,
+            Connective::Implies => !a || b,
+            Connective::Iff => a == b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    Const(bool),
+    Var(String),
+    Not(Box<Formula>),
+    Binary(Connective, Box<Formula>, Box<Formula>),
+}
+
+impl Formula {
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut vars = BTreeSet::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut BTreeSet<String>) {
+        match self {
+            Formula::Const(_) => {}
+            Formula::Var(name) => {
+                vars.insert(name.clone());
+            }
+            Formula::Not(inner) => inner.collect_variables(vars),
+            Formula::Binary(_, lhs, rhs) => {
+                lhs.collect_variables(vars);
+                rhs.collect_variables(vars);
+            }
+        }
+    }
+
+    /// Truth value under `assignment`, which must give every variable a value
+    pub fn evaluate(&self, assignment: &Assignment) -> anyhow::Result<bool> {
+        self.evaluate_traced(assignment, &mut Vec::new())
+    }
+
+    fn evaluate_traced(&self, assignment: &Assignment, steps: &mut Vec<String>) -> anyhow::Result<bool> {
+        match self {
+            Formula::Const(value) => Ok(*value),
+            Formula::Var(name) => assignment
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("No value given for '{}'", name)),
+            Formula::Not(inner) => Ok(!inner.evaluate_traced(assignment, steps)?),
+            Formula::Binary(op, lhs, rhs) => {
+                let value = op.apply(lhs.evaluate_traced(assignment, steps)?, rhs.evaluate_traced(assignment, steps)?);
+                steps.push(format!("{} = {}", self, value));
+                Ok(value)
+            }
+        }
+    }
+
+    fn binary(op: Connective, lhs: Formula, rhs: Formula) -> Formula {
+        Formula::Binary(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Formula::Binary(op, ..) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Formula::Const(value) => write!(f, "{}", value),
+            Formula::Var(name) => write!(f, "{}", name),
+            Formula::Not(inner) => match inner.as_ref() {
+                Formula::Binary(..) => write!(f, "~({})", inner),
+                _ => write!(f, "~{}", inner),
+            },
+            Formula::Binary(op, lhs, rhs) => {
+                // '->' groups to the right, so only its left operand needs parentheses at equal precedence
+                let left_tight = lhs.precedence() < op.precedence()
+                    || (*op == Connective::Implies && lhs.precedence() == op.precedence());
+                let right_tight = rhs.precedence() < op.precedence();
+                let operand = |e: &Formula, wrap: bool| if wrap { format!("({})", e) } else { e.to_string() };
+                write!(f, "{} {} {}", operand(lhs, left_tight), op.symbol(), operand(rhs, right_tight))
+            }
+        }
+    }
+}
+
+/// Binary connectives from loosest to tightest, each with its symbol and word spellings
+const LEVELS: &[(Connective, &[&str])] = &[
+    (Connective::Iff, &["<->", "iff"]),
+    (Connective::Implies, &["->", "implies"]),
+    (Connective::Or, &["|", "or"]),
+    (Connective::And, &["&", "and"]),
+];
+
+const KEYWORDS: &[&str] = &["not", "and", "or", "implies", "iff", "true", "false"];
+
+/// Parse a formula such as "(p -> q) & p -> q"; "not", "and", "or", "implies"
+/// and "iff" may be written as words, and "&&" and "||" as in C
+pub fn parse_formula(input: &str) -> anyhow::Result<Formula> {
+    let mut parser = Parser::new(tokenize(input)?);
+    let formula = parse_level(&mut parser, 0)?;
+    parser.expect_end()?;
+    Ok(formula)
+}
+
+/// level := next_level (op next_level)*, left associative except '->'
+fn parse_level(parser: &mut Parser, level: usize) -> anyhow::Result<Formula> {
+    let Some((op, spellings)) = LEVELS.get(level) else {
+        return parse_unary(parser);
+    };
+    let mut lhs = parse_level(parser, level + 1)?;
+    while eat_operator(parser, spellings) {
+        if *op == Connective::Implies {
+            let rhs = parse_level(parser, level)?;
+            return Ok(Formula::binary(*op, lhs, rhs));
+        }
+        let rhs = parse_level(parser, level + 1)?;
+        lhs = Formula::binary(*op, lhs, rhs);
+    }
+    Ok(lhs)
+}
+
+fn eat_operator(parser: &mut Parser, spellings: &[&str]) -> bool {
+    spellings.iter().any(|spelling| {
+        if spelling.chars().all(char::is_alphabetic) {
+            let is_word = matches!(&parser.peek().map(|t| &t.kind), Some(TokenKind::Ident(name)) if name.eq_ignore_ascii_case(spelling));
+            if is_word {
+                parser.advance();
+            }
+            is_word
+        } else if parser.eat_symbol(spelling) {
+            // "&&" and "||" read as single connectives
+            if spelling.len() == 1 {
+                parser.eat_symbol(spelling);
+            }
+            true
+        } else {
+            false
+        }
+    })
+}
+
+/// unary := ('~' | '!' | 'not') unary | 'true' | 'false' | variable | '(' formula ')'
+fn parse_unary(parser: &mut Parser) -> anyhow::Result<Formula> {
+    if parser.eat_symbol("~") || parser.eat_symbol("!") || eat_operator(parser, &["not"]) {
+        return Ok(Formula::Not(Box::new(parse_unary(parser)?)));
+    }
+    if parser.eat_symbol("(") {
+        let inner = parse_level(parser, 0)?;
+        parser.expect_symbol(")")?;
+        return Ok(inner);
+    }
+    match parser.peek().map(|t| t.kind.clone()) {
+        Some(TokenKind::Ident(name)) if name.eq_ignore_ascii_case("true") => {
+            parser.advance();
+            Ok(Formula::Const(true))
+        }
+        Some(TokenKind::Ident(name)) if name.eq_ignore_ascii_case("false") => {
+            parser.advance();
+            Ok(Formula::Const(false))
+        }
+        Some(TokenKind::Ident(name)) if !KEYWORDS.contains(&name.to_lowercase().as_str()) => {
+            parser.advance();
+            Ok(Formula::Var(name))
+        }
+        _ => Err(parser.error("a proposition")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogicQuery {
+    TruthTable(Formula),
+    Satisfiable(Formula),
+    Unsatisfiable(Formula),
+    Valid(Formula),
+    Equivalent(Formula, Formula),
+    /// Truth value under the given assignment, e.g. "p -> q where p = true, q = false"
+    Evaluate(Formula, Assignment),
+    /// A bare formula: valid, satisfiable or unsatisfiable
+    Classify(Formula),
+}
+
+/// Builds the query for a parsed formula
+type QueryBuilder = fn(Formula) -> LogicQuery;
+
+/// Questions of the form "is F <verdict>"
+const VERDICTS: &[(&str, QueryBuilder)] = &[
+    ("satisfiable", LogicQuery::Satisfiable),
+    ("consistent", LogicQuery::Satisfiable),
+    ("unsatisfiable", LogicQuery::Unsatisfiable),
+    ("a contradiction", LogicQuery::Unsatisfiable),
+    ("valid", LogicQuery::Valid),
+    ("a tautology", LogicQuery::Valid),
+];
+
+/// Recognize "truth table for F", "is F satisfiable", "is F valid", "are F and G
+/// equivalent", "F where p = true" and bare formulas with logical connectives
+pub fn parse_command(query: &str) -> Option<anyhow::Result<LogicQuery>> {
+    let text = query.trim();
+
+    if let Some(rest) = super::strip_keyword(text, "truth table") {
+        let rest = ["for", "of"].iter().find_map(|k| super::strip_keyword(rest, k)).unwrap_or(rest);
+        return Some(parse_formula(rest).map(LogicQuery::TruthTable));
+    }
+
+    if let Some(rest) = super::strip_keyword(text, "is") {
+        for (verdict, build) in VERDICTS {
+            if let Some(subject) = super::strip_suffix_phrase(rest, verdict) {
+                return formula_command(subject, |f| Ok(build(f)));
+            }
+        }
+        if let Some((lhs, rhs)) = super::split_keyword(rest, "equivalent to") {
+            let rhs = match parse_formula(rhs) {
+                Ok(rhs) => rhs,
+                Err(e) => return has_connective(rhs).then_some(Err(e)),
+            };
+            return formula_command(lhs, |lhs| Ok(LogicQuery::Equivalent(lhs, rhs)));
+        }
+    }
+
+    if let Some(subject) = super::strip_keyword(text, "are").and_then(|rest| super::strip_suffix_phrase(rest, "equivalent")) {
+        return parse_equivalent_pair(subject);
+    }
+
+    if let Some((formula, bindings)) = super::split_keyword(text, "where") {
+        return formula_command(formula, |f| Ok(LogicQuery::Evaluate(f, parse_assignment(bindings)?)));
+    }
+
+    let formula = parse_formula(text).ok()?;
+    let tokens = tokenize(text).ok()?;
+    is_logic_syntax(&tokens, &formula).then_some(Ok(LogicQuery::Classify(formula)))
+}
+
+/// Build a query from `text` when it parses; parse errors are reported only when
+/// the text uses a connective symbol and so was meant as a formula
+fn formula_command(
+    text: &str,
+    build: impl FnOnce(Formula) -> anyhow::Result<LogicQuery>,
+) -> Option<anyhow::Result<LogicQuery>> {
+    match parse_formula(text) {
+        Ok(formula) => Some(build(formula)),
+        Err(e) => has_connective(text).then_some(Err(e)),
+    }
+}
+
+fn has_connective(text: &str) -> bool {
+    tokenize(text).is_ok_and(|tokens| {
+        tokens.iter().any(|t| matches!(t.kind, TokenKind::Symbol(s) if ["->", "<->", "&", "|", "~", "!"].contains(&s)))
+    })
+}
+
+/// Symbolic connectives, or connective words between single-letter variables ("p and not q")
+fn is_logic_syntax(tokens: &[Token], formula: &Formula) -> bool {
+    let variables = formula.variables();
+    if variables.is_empty() {
+        return false;
+    }
+    let symbolic = tokens.iter().any(|t| matches!(t.kind, TokenKind::Symbol(s) if s != "(" && s != ")"));
+    let worded = tokens
+        .iter()
+        .any(|t| matches!(&t.kind, TokenKind::Ident(w) if ["not", "and", "or", "implies", "iff"].contains(&w.to_lowercase().as_str())));
+    symbolic || (worded && variables.iter().all(|v| v.chars().count() == 1))
+}
+
+/// "F and G" where exactly one "and" splits the text into two formulas
+fn parse_equivalent_pair(text: &str) -> Option<anyhow::Result<LogicQuery>> {
+    let splits: Vec<(Formula, Formula)> = text
+        .char_indices()
+        .filter(|(i, _)| {
+            let word = text.get(*i..*i + 3).is_some_and(|w| w.eq_ignore_ascii_case("and"));
+            let before = text[..*i].ends_with(char::is_whitespace);
+            let after = text.get(*i + 3..).is_some_and(|rest| rest.starts_with(char::is_whitespace));
+            word && before && after
+        })
+        .filter_map(|(i, _)| Some((parse_formula(&text[..i]).ok()?, parse_formula(&text[i + 3..]).ok()?)))
+        .collect();
+    match splits.as_slice() {
+        [(lhs, rhs)] => Some(Ok(LogicQuery::Equivalent(lhs.clone(), rhs.clone()))),
+        [] => has_connective(text).then(|| Err(anyhow::anyhow!("Expected two formulas joined by 'and' in '{}'", text))),
+        _ => Some(Err(anyhow::anyhow!(
+            "Ambiguous 'and' in '{}'; write '&' for conjunction inside the formulas",
+            text
+        ))),
+    }
+}
+
+/// "p = true, q = 0"
+fn parse_assignment(text: &str) -> anyhow::Result<Assignment> {
+    let mut assignment = Assignment::new();
+    for binding in text.split(',') {
+        let (name, value) = binding
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected 'name = true' or 'name = false', found '{}'", binding.trim()))?;
+        let value = match value.trim().to_lowercase().as_str() {
+            "true" | "t" | "1" => true,
+            "false" | "f" | "0" => false,
+            other => return Err(anyhow::anyhow!("Expected true or false for '{}', found '{}'", name.trim(), other)),
+        };
+        assignment.insert(name.trim().to_string(), value);
+    }
+    Ok(assignment)
+}
+
+/// "F is valid" with `phrase` = "valid"; the phrase must follow whitespace
+/// Answer with the deciding assignment and the solver's work
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicOutcome {
+    pub result: String,
+    /// Answer to a yes/no question; `None` for truth tables and classification
+    pub verdict: Option<bool>,
+    pub steps: Vec<String>,
+}
+
+pub fn evaluate(query: &LogicQuery) -> anyhow::Result<LogicOutcome> {
+    let mut steps = Vec::new();
+    let (result, verdict) = match query {
+        LogicQuery::TruthTable(formula) => (truth_table(formula, &mut steps)?, None),
+        LogicQuery::Satisfiable(formula) | LogicQuery::Unsatisfiable(formula) => {
+            let model = satisfy(formula, &mut steps);
+            let satisfiable = model.is_some();
+            let text = match model {
+                Some(model) => format!("satisfiable: {}", format_assignment(&model)),
+                None => "unsatisfiable".to_string(),
+            };
+            (text, Some(satisfiable == matches!(query, LogicQuery::Satisfiable(_))))
+        }
+        LogicQuery::Valid(formula) => {
+            steps.push(format!("valid iff {} is unsatisfiable", Formula::Not(Box::new(formula.clone()))));
+            match satisfy(&Formula::Not(Box::new(formula.clone())), &mut steps) {
+                Some(model) => (format!("not valid; counter-model: {}", format_assignment(&model)), Some(false)),
+                None => ("valid".to_string(), Some(true)),
+            }
+        }
+        LogicQuery::Equivalent(lhs, rhs) => {
+            let differ = Formula::Not(Box::new(Formula::binary(Connective::Iff, lhs.clone(), rhs.clone())));
+            steps.push(format!("equivalent iff {} is unsatisfiable", differ));
+            match satisfy(&differ, &mut steps) {
+                Some(model) => {
+                    let left = lhs.evaluate(&model)?;
+                    let text = format!(
+                        "not equivalent; at {} the first is {} and the second {}",
+                        format_assignment(&model),
+                        left,
+                        !left
+                    );
+                    (text, Some(false))
+                }
+                None => ("equivalent".to_string(), Some(true)),
+            }
+        }
+        LogicQuery::Evaluate(formula, assignment) => {
+            let value = formula.evaluate_traced(assignment, &mut steps)?;
+            (value.to_string(), Some(value))
+        }
+        LogicQuery::Classify(formula) => {
+            let witness = satisfy(formula, &mut steps);
+            let negation = Formula::Not(Box::new(formula.clone()));
+            let text = match (witness, satisfy(&negation, &mut steps)) {
+                (None, _) => "unsatisfiable (a contradiction)".to_string(),
+                (Some(_), None) => "valid (a tautology)".to_string(),
+                (Some(model), Some(counter)) => format!(
+                    "satisfiable but not valid: true at {}; false at {}",
+                    format_assignment(&model),
+                    format_assignment(&counter)
+                ),
+            };
+            (text, None)
+        }
+    };
+    Ok(LogicOutcome { result, verdict, steps })
+}
+
+fn format_assignment(assignment: &Assignment) -> String {
+    if assignment.is_empty() {
+        return "the empty assignment".to_string();
+    }
+    assignment.iter().map(|(name, value)| format!("{} = {}", name, value)).collect::<Vec<_>>().join(", ")
+}
+
+/// One row per assignment, true before false, as "p | q | p -> q"
+fn truth_table(formula: &Formula, steps: &mut Vec<String>) -> anyhow::Result<String> {
+    let variables: Vec<String> = formula.variables().into_iter().collect();
+    if variables.len() > MAX_TABLE_VARIABLES {
+        return Err(anyhow::anyhow!(
+            "A truth table over {} variables has {} rows; at most {} variables are tabulated",
+            variables.len(),
+            1u64 << variables.len(),
+            MAX_TABLE_VARIABLES
+        ));
+    }
+
+    let header = variables.iter().cloned().chain(std::iter::once(formula.to_string())).collect::<Vec<_>>();
+    let mut lines = vec![header.join(" | ")];
+    let mut true_rows = 0;
+    let rows = 1usize << variables.len();
+    for row in 0..rows {
+        let assignment: Assignment = variables
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), (row >> (variables.len() - 1 - i)) & 1 == 0))
+            .collect();
+        let value = formula.evaluate(&assignment)?;
+        true_rows += usize::from(value);
+        let cells = variables
+            .iter()
+            .map(|name| assignment[name])
+            .chain(std::iter::once(value))
+            .zip(&header)
+            .map(|(cell, heading)| format!("{:<width$}", if cell { "T" } else { "F" }, width = heading.chars().count()));
+        lines.push(cells.collect::<Vec<_>>().join(" | ").trim_end().to_string());
+    }
+
+    steps.push(format!("{} of {} rows true", true_rows, rows));
+    steps.push(
+        match true_rows {
+            0 => "unsatisfiable",
+            n if n == rows => "valid",
+            _ => "satisfiable, not valid",
+        }
+        .to_string(),
+    );
+    Ok(lines.join("\n"))
+}
+
+/// A satisfying assignment of `formula`'s variables, or `None` when it is unsatisfiable
+pub fn satisfy(formula: &Formula, steps: &mut Vec<String>) -> Option<Assignment> {
+    let cnf = Cnf::tseitin(formula);
+    steps.push(format!(
+        "Tseitin CNF of {}: {} clauses over {} variables ({} auxiliary)",
+        formula,
+        cnf.clauses.len(),
+        cnf.count,
+        cnf.count - cnf.names.len()
+    ));
+
+    let mut dpll = Dpll {
+        clauses: &cnf.clauses,
+        values: vec![None; cnf.count + 1],
+        trail: Vec::new(),
+        decisions: 0,
+        propagations: 0,
+        backtracks: 0,
+    };
+    let satisfiable = dpll.search();
+    steps.push(format!(
+        "DPLL: {} decisions, {} unit propagations, {} backtracks",
+        dpll.decisions, dpll.propagations, dpll.backtracks
+    ));
+    if !satisfiable {
+        steps.push("no assignment satisfies every clause".to_string());
+        return None;
+    }
+
+    // Variables the search never had to fix are free; report them as false
+    let model: Assignment = cnf
+        .names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.clone(), dpll.values[i + 1].unwrap_or(false)))
+        .collect();
+    steps.push(format!("model: {}", format_assignment(&model)));
+    Some(model)
+}
+
+/// Clauses over variables 1..=count; a literal is +v or -v, and the first
+/// `names.len()` variables are the formula's own
+struct Cnf {
+    clauses: Vec<Vec<i32>>,
+    count: usize,
+    names: Vec<String>,
+}
+
+impl Cnf {
+    /// Equisatisfiable CNF with one auxiliary variable per connective
+    fn tseitin(formula: &Formula) -> Cnf {
+        let names: Vec<String> = formula.variables().into_iter().collect();
+        let mut cnf = Cnf { clauses: Vec::new(), count: names.len(), names };
+        let root = cnf.encode(formula);
+        cnf.clauses.push(vec![root]);
+        cnf
+    }
+
+    fn fresh(&mut self) -> i32 {
+        self.count += 1;
+        self.count as i32
+    }
+
+    /// Literal equivalent to `formula`, adding the clauses that define it
+    fn encode(&mut self, formula: &Formula) -> i32 {
+        match formula {
+            Formula::Const(value) => {
+                let x = self.fresh();
+                self.clauses.push(vec![if *value { x } else { -x }]);
+                x
+            }
+            Formula::Var(name) => self.names.iter().position(|n| n == name).map_or(0, |i| i as i32 + 1),
+            Formula::Not(inner) => -self.encode(inner),
+            Formula::Binary(op, lhs, rhs) => {
+                let (a, b) = (self.encode(lhs), self.encode(rhs));
+                let x = self.fresh();
+                let definition: Vec<Vec<i32>> = match op {
+                    Connective::And => vec![vec![-x, a], vec![-x, b], vec![x, -a, -b]],
+                    Connective::Or => vec![vec![-x, a, b], vec![x, -a], vec![x, -b]],
+                    Connective::Implies => vec![vec![-x, -a, b], vec![x, a], vec![x, -b]],
+                    Connective::Iff => vec![vec![-x, -a, b], vec![-x, a, -b], vec![x, a, b], vec![x, -a, -b]],
+                };
+                self.clauses.extend(definition);
+                x
+            }
+        }
+    }
+}
+
+/// Backtracking search with unit propagation over a trail of assigned variables
+struct Dpll<'a> {
+    clauses: &'a [Vec<i32>],
+    /// Indexed by variable; slot 0 is unused
+    values: Vec<Option<bool>>,
+    trail: Vec<usize>,
+    decisions: usize,
+    propagations: usize,
+    backtracks: usize,
+}
+
+impl Dpll<'_> {
+    fn value(&self, literal: i32) -> Option<bool> {
+        self.values[literal.unsigned_abs() as usize].map(|v| v == (literal > 0))
+    }
+
+    fn assign(&mut self, literal: i32) {
+        let var = literal.unsigned_abs() as usize;
+        self.values[var] = Some(literal > 0);
+        self.trail.push(var);
+    }
+
+    fn undo(&mut self, mark: usize) {
+        while self.trail.len() > mark {
+            if let Some(var) = self.trail.pop() {
+                self.values[var] = None;
+            }
+        }
+    }
+
+    /// Assign literals forced by unit clauses; false once a clause is falsified
+    fn propagate(&mut self) -> bool {
+        let clauses = self.clauses;
+        loop {
+            let mut changed = false;
+            for clause in clauses {
+                let mut open = clause.iter().filter(|l| self.value(**l) != Some(false));
+                match (open.next(), open.next()) {
+                    (None, _) => return false,
+                    (Some(&literal), None) if self.value(literal).is_none() => {
+                        self.assign(literal);
+                        self.propagations += 1;
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
+
+    /// An unassigned literal from the first clause not yet satisfied
+    fn branch_literal(&self) -> Option<i32> {
+        self.clauses
+            .iter()
+            .filter(|clause| !clause.iter().any(|l| self.value(*l) == Some(true)))
+            .find_map(|clause| clause.iter().copied().find(|l| self.value(*l).is_none()))
+    }
+
+    fn search(&mut self) -> bool {
+        if !self.propagate() {
+            return false;
+        }
+        let Some(literal) = self.branch_literal() else {
+            return true;
+        };
+        for choice in [literal, -literal] {
+            let mark = self.trail.len();
+            self.decisions += 1;
+            self.assign(choice);
+            if self.search() {
+                return true;
+            }
+            self.undo(mark);
+            self.backtracks += 1;
+        }
+        false
+    }
+}
@@ -0,0 +1,338 @@
+// Sandboxed snippets: Rhai scripts with operation, time and size limits, captured output,
+// a virtual clock and seeded randomness
+use super::error::{Limit, LimitError};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Nested function calls a snippet may make
+const MAX_CALL_LEVELS: usize = 64;
+
+/// Limits for one snippet run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxConfig {
+    /// Rhai operations (roughly, evaluated expressions) before the run is stopped
+    pub max_operations: u64,
+    /// Wall-clock backstop for operations that are individually slow
+    pub time_limit: Duration,
+    /// Seed of `random()` unless a query sets its own
+    pub seed: u64,
+    /// Bytes of printed output kept
+    pub max_output: usize,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        SandboxConfig {
+            max_operations: 1_000_000,
+            time_limit: Duration::from_secs(2),
+            seed: 0,
+            max_output: 64 * 1024,
+        }
+    }
+}
+
+/// A snippet to run, e.g. "run: let x = 6; x * 7" or "run with seed 7: ```rhai ... ```"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub code: String,
+    pub seed: Option<u64>,
+}
+
+/// Leading words that ask for a run; the snippet follows a ':' or a code fence
+const RUN_VERBS: &[&str] = &["run", "execute", "eval"];
+
+/// Recognize "run: CODE", "run rhai: CODE", "run with seed N: CODE" and fenced blocks,
+/// either bare or after "run"; other queries return `None`
+pub fn parse_command(query: &str) -> Option<anyhow::Result<Snippet>> {
+    let text = query.trim();
+    if text.starts_with("```") {
+        return Some(fenced(text).map(|code| Snippet { code, seed: None }));
+    }
+    let mut rest = RUN_VERBS.iter().find_map(|verb| super::strip_keyword(text, verb))?;
+    rest = ["rhai", "code", "script", "snippet"].iter().find_map(|noun| super::strip_keyword(rest, noun)).unwrap_or(rest);
+    let mut seed = None;
+    if let Some(after) = super::strip_keyword(rest, "with seed") {
+        let digits = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+        let value = after[..digits].parse().map_err(|_| anyhow::anyhow!("Expected a seed such as 'with seed 42'"));
+        seed = Some(value);
+        rest = after[digits..].trim_start();
+    }
+    let code = if let Some(body) = rest.strip_prefix(':') {
+        match body.trim_start().starts_with("```") {
+            true => fenced(body.trim_start()),
+            false => Ok(body.trim().to_string()),
+        }
+    } else if rest.starts_with("```") {
+        fenced(rest)
+    } else {
+        return None;
+    };
+    Some((|| Ok(Snippet { code: code?, seed: seed.transpose()? }))())
+}
+
+/// Contents of the first fenced block, without its info string ("```rhai")
+fn fenced(text: &str) -> anyhow::Result<String> {
+    code_blocks(text).into_iter().next().ok_or_else(|| anyhow::anyhow!("Code block is missing its closing ```"))
+}
+
+/// Contents of every closed ``` block in `text`, in order
+pub fn code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut pieces = text.split("```").skip(1);
+    while let (Some(block), Some(_)) = (pieces.next(), pieces.next()) {
+        // The info string runs to the end of the first line, when the block has several
+        let body = match block.split_once('\n') {
+            Some((info, body)) if !info.trim().contains(char::is_whitespace) => body,
+            _ => block,
+        };
+        blocks.push(body.trim().to_string());
+    }
+    blocks
+}
+
+/// Printed output, the final value and how much work the run took
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOutcome {
+    pub output: String,
+    /// Value of the last statement, `None` when it is unit
+    pub value: Option<String>,
+    pub operations: u64,
+    pub truncated: bool,
+}
+
+impl RunOutcome {
+    /// Output followed by the value, or "(no output)"
+    pub fn summary(&self) -> String {
+        let output = self.output.trim_end();
+        match (&self.value, output.is_empty()) {
+            (Some(value), true) => value.clone(),
+            (Some(value), false) => format!("{}\n{}", output, value),
+            (None, true) => "(no output)".to_string(),
+            (None, false) => output.to_string(),
+        }
+    }
+
+    pub fn steps(&self, seed: u64) -> Vec<String> {
+        let mut steps = vec![format!("rhai: {} operations, seed {}", self.operations, seed)];
+        if self.truncated {
+            steps.push("output truncated".to_string());
+        }
+        steps
+    }
+}
+
+/// Run `code` in a fresh engine: no file, module or clock access, `print`/`debug`
+/// captured, `random()` seeded and `clock()` counting operations instead of time
+pub fn run(code: &str, seed: u64, config: &SandboxConfig) -> anyhow::Result<RunOutcome> {
+    let output = Rc::new(RefCell::new(String::new()));
+    let truncated = Rc::new(Cell::new(false));
+    let operations = Rc::new(Cell::new(0u64));
+    let engine = engine(seed, config, &output, &truncated, &operations);
+
+    let result = engine.eval_with_scope::<Dynamic>(&mut Scope::new(), code).map_err(|e| run_error(*e, config))?;
+    let value = (!result.is_unit()).then(|| show(&result));
+    let output = output.borrow().clone();
+    Ok(RunOutcome { output, value, operations: operations.get(), truncated: truncated.get() })
+}
+
+fn engine(
+    seed: u64,
+    config: &SandboxConfig,
+    output: &Rc<RefCell<String>>,
+    truncated: &Rc<Cell<bool>>,
+    operations: &Rc<Cell<u64>>,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(config.max_operations)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(64, 64)
+        .set_max_string_size(1 << 20)
+        .set_max_array_size(100_000)
+        .set_max_map_size(100_000);
+
+    let capture = {
+        let (output, truncated, limit) = (output.clone(), truncated.clone(), config.max_output);
+        move |line: &str| {
+            let mut output = output.borrow_mut();
+            if output.len() + line.len() + 1 > limit {
+                truncated.set(true);
+                return;
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+    };
+    let debug_capture = capture.clone();
+    engine.on_print(capture);
+    engine.on_debug(move |text, _, _| debug_capture(text));
+
+    let start = Instant::now();
+    let (counter, time_limit) = (operations.clone(), config.time_limit);
+    engine.on_progress(move |count| {
+        counter.set(count);
+        (start.elapsed() > time_limit).then_some(Dynamic::UNIT)
+    });
+
+    // SplitMix64, so every run with the same seed draws the same numbers
+    let state = Rc::new(Cell::new(seed));
+    let next = move || {
+        let value = state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state.set(value);
+        let mut z = value;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    let draw = next.clone();
+    engine.register_fn("random", move || (draw() >> 11) as f64 / (1u64 << 53) as f64);
+    engine.register_fn("random", move |lo: i64, hi: i64| -> Result<i64, Box<EvalAltResult>> {
+        if lo > hi {
+            return Err(format!("random({}, {}): empty range", lo, hi).into());
+        }
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        Ok((lo as i128 + (next() as u128 % span) as i128) as i64)
+    });
+    let clock = operations.clone();
+    engine.register_fn("clock", move || clock.get() as i64);
+    engine
+}
+
+/// Values as Rhai would write them back: strings quoted, arrays bracketed
+fn show(value: &Dynamic) -> String {
+    format!("{:?}", value)
+}
+
+fn run_error(error: EvalAltResult, config: &SandboxConfig) -> anyhow::Error {
+    match error {
+        EvalAltResult::ErrorTooManyOperations(_) => {
+            LimitError::new(Limit::Operations, format!("Snippet stopped after {} operations", config.max_operations)).into()
+        }
+        EvalAltResult::ErrorTerminated(..) => {
+            let message = format!("Snippet exceeded the {:.1}s time limit", config.time_limit.as_secs_f64());
+            LimitError::new(Limit::Time, message).into()
+        }
+        EvalAltResult::ErrorStackOverflow(_) => {
+            LimitError::new(Limit::Depth, format!("Snippet exceeded the call depth of {}", MAX_CALL_LEVELS)).into()
+        }
+        EvalAltResult::ErrorDataTooLarge(what, _) => {
+            LimitError::new(Limit::Size, format!("Snippet exceeded a size limit: {} too large", what)).into()
+        }
+        // Limits hit inside a function keep their kind
+        EvalAltResult::ErrorInFunctionCall(_, _, inner, _) if is_limit(&inner) => run_error(*inner, config),
+        EvalAltResult::ErrorParsing(..) => anyhow::anyhow!("{}", error),
+        other => anyhow::anyhow!("Runtime error: {}", other),
+    }
+}
+
+/// Errors raised by an engine limit rather than by the snippet's own code
+fn is_limit(error: &EvalAltResult) -> bool {
+    match error {
+        EvalAltResult::ErrorTooManyOperations(_)
+        | EvalAltResult::ErrorTerminated(..)
+        | EvalAltResult::ErrorStackOverflow(_)
+        | EvalAltResult::ErrorDataTooLarge(..) => true,
+        EvalAltResult::ErrorInFunctionCall(_, _, inner, _) => is_limit(inner),
+        _ => false,
+    }
+}
+
+/// A call with its expected result, e.g. "add(2, 3) == 5"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    pub call: String,
+    pub expected: String,
+}
+
+/// Examples stated in prose outside code blocks: "add(2, 3) == 5", "f(1) returns 2",
+/// "sum([1, 2]) should be 3"
+pub fn examples(text: &str) -> Vec<Example> {
+    use once_cell::sync::Lazy;
+
+    static EXAMPLE_RE: Lazy<regex::Regex> = Lazy::new(|| {
+        regex::Regex::new(
+            r#"\b([A-Za-z_]\w*\((?:[^()\n]|\([^()\n]*\))*\))\s*(?:==|=>|->|returns|should return|should be|gives|is)\s*(-?\d+(?:\.\d+)?|"[^"\n]*"|true|false|\[[^\]\n]*\])"#,
+        )
+        .unwrap()
+    });
+
+    // Only prose counts; calls inside code blocks are the code under test
+    let prose: String = text.split("```").step_by(2).collect::<Vec<_>>().join(" ");
+    EXAMPLE_RE
+        .captures_iter(&prose)
+        .map(|c| Example { call: c[1].to_string(), expected: c[2].to_string() })
+        .collect()
+}
+
+/// Outcome of running one example against a snippet
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExampleCheck {
+    pub example: Example,
+    /// The call's value, or the error it raised
+    pub got: String,
+    pub passed: bool,
+}
+
+/// Run `code`, then the example's call against its functions and variables, and compare
+/// the value with the expected one in Rust, where the snippet cannot redefine equality
+pub fn check_example(code: &str, example: &Example, seed: u64, config: &SandboxConfig) -> ExampleCheck {
+    let (got, passed) = match call_value(code, &example.call, seed, config) {
+        Ok(value) => match expected_value(&example.expected, seed, config) {
+            Ok(expected) => (show(&value), same_value(&value, &expected)),
+            Err(e) => (format!("{} (expected value unreadable: {})", show(&value), e), false),
+        },
+        Err(e) => (format!("error: {}", e), false),
+    };
+    ExampleCheck { example: example.clone(), got, passed }
+}
+
+/// The value of `call` after `code` has run in the same scope
+fn call_value(code: &str, call: &str, seed: u64, config: &SandboxConfig) -> anyhow::Result<Dynamic> {
+    let output = Rc::new(RefCell::new(String::new()));
+    let engine = engine(seed, config, &output, &Rc::new(Cell::new(false)), &Rc::new(Cell::new(0)));
+    let program = engine.compile(code).map_err(|e| run_error(e.into(), config))?;
+    let mut scope = Scope::new();
+    engine.run_ast_with_scope(&mut scope, &program).map_err(|e| run_error(*e, config))?;
+    let call = engine.compile_expression(call).map_err(|e| run_error(e.into(), config))?;
+    let call = program.clone_functions_only().merge(&call);
+    engine.eval_ast_with_scope::<Dynamic>(&mut scope, &call).map_err(|e| run_error(*e, config))
+}
+
+/// The example's expected value, read by a fresh engine that never saw the snippet
+fn expected_value(expected: &str, seed: u64, config: &SandboxConfig) -> anyhow::Result<Dynamic> {
+    let output = Rc::new(RefCell::new(String::new()));
+    let engine = engine(seed, config, &output, &Rc::new(Cell::new(false)), &Rc::new(Cell::new(0)));
+    engine.eval_expression::<Dynamic>(expected).map_err(|e| run_error(*e, config))
+}
+
+/// Structural equality of script values; integers and floats compare by value, as Rhai's
+/// `==` compares them
+fn same_value(a: &Dynamic, b: &Dynamic) -> bool {
+    let number = |value: &Dynamic| value.as_int().map(|i| i as f64).or_else(|_| value.as_float()).ok();
+    if let (Ok(x), Ok(y)) = (a.as_int(), b.as_int()) {
+        return x == y;
+    }
+    if let (Some(x), Some(y)) = (number(a), number(b)) {
+        return x == y;
+    }
+    if let (Ok(x), Ok(y)) = (a.as_bool(), b.as_bool()) {
+        return x == y;
+    }
+    if let (Ok(x), Ok(y)) = (a.as_char(), b.as_char()) {
+        return x == y;
+    }
+    if a.is_string() && b.is_string() {
+        return a.clone().into_immutable_string().ok() == b.clone().into_immutable_string().ok();
+    }
+    if a.is_array() && b.is_array() {
+        let (x, y) = (a.clone().into_array().unwrap_or_default(), b.clone().into_array().unwrap_or_default());
+        return x.len() == y.len() && x.iter().zip(&y).all(|(x, y)| same_value(x, y));
+    }
+    if a.is_map() && b.is_map() {
+        let (x, y) = (a.clone().cast::<rhai::Map>(), b.clone().cast::<rhai::Map>());
+        return x.len() == y.len() && x.iter().all(|(key, x)| y.get(key).is_some_and(|y| same_value(x, y)));
+    }
+    a.is_unit() && b.is_unit()
+}
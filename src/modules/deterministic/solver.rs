@@ -0,0 +1,543 @@
+// Equation solving: linear and quadratic equations and linear systems
+use super::expr::{parse_expression, BinOp, Evaluator, Expr};
+use super::lexer::{column_of, relocate, SyntaxError};
+use super::split_keyword;
+use super::symbolic::{self, Poly};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::collections::BTreeMap;
+
+/// Largest integer whose square factors are extracted when simplifying radicals
+const MAX_RADICAL_FACTORING: u64 = 1_000_000_000_000;
+
+/// Absolute tolerance for numeric substitution checks of irrational roots
+const CHECK_TOLERANCE: f64 = 1e-9;
+
+/// One equation `lhs = rhs` as written by the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equation {
+    pub lhs: Expr,
+    pub rhs: Expr,
+}
+
+impl std::fmt::Display for Equation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {}", self.lhs, self.rhs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveRequest {
+    pub equations: Vec<Equation>,
+    /// Variable named with "for", if any
+    pub target: Option<String>,
+}
+
+/// A solution value: exact expression (possibly with radicals) or a complex pair
+#[derive(Debug, Clone, PartialEq)]
+pub enum Root {
+    Real(Expr),
+    Complex { re: Expr, im: Expr },
+}
+
+impl std::fmt::Display for Root {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Root::Real(e) => write!(f, "{}", e),
+            Root::Complex { re, im } => {
+                let im_text = im.to_string();
+                let (sign, magnitude) = match im_text.strip_prefix('-') {
+                    Some(rest) => ("-", rest.to_string()),
+                    None => ("+", im_text),
+                };
+                let magnitude = match magnitude.as_str() {
+                    "1" => String::new(),
+                    m if m.contains([' ', '/']) => format!("({})", m),
+                    m => m.to_string(),
+                };
+                if matches!(re, Expr::Num(n) if n.is_zero()) {
+                    let sign = if sign == "-" { "-" } else { "" };
+                    write!(f, "{}{}i", sign, magnitude)
+                } else {
+                    write!(f, "{} {} {}i", re, sign, magnitude)
+                }
+            }
+        }
+    }
+}
+
+/// Outcome of solving an equation or system
+#[derive(Debug, Clone, PartialEq)]
+pub enum Solution {
+    /// Each entry is one complete assignment of values to variables
+    Finite(Vec<Vec<(String, Root)>>),
+    /// Contradiction such as 0 = 1
+    NoSolution,
+    /// Underdetermined: pivot variables expressed in terms of the free ones
+    Infinite { free: Vec<String>, assignments: Vec<(String, Expr)> },
+}
+
+#[derive(Debug, Clone)]
+pub struct SolveOutcome {
+    pub solution: Solution,
+    pub steps: Vec<String>,
+}
+
+impl Solution {
+    pub fn summary(&self) -> String {
+        match self {
+            Solution::NoSolution => "no solution".to_string(),
+            Solution::Infinite { free, assignments } if assignments.is_empty() => {
+                format!("infinitely many solutions: any value of {}", free.join(", "))
+            }
+            Solution::Infinite { free, assignments } => format!(
+                "infinitely many solutions: {} ({} free)",
+                assignments.iter().map(|(v, e)| format!("{} = {}", v, e)).collect::<Vec<_>>().join(", "),
+                free.join(", ")
+            ),
+            Solution::Finite(solutions) => solutions
+                .iter()
+                .map(|assignment| {
+                    assignment.iter().map(|(v, r)| format!("{} = {}", v, r)).collect::<Vec<_>>().join(", ")
+                })
+                .collect::<Vec<_>>()
+                .join(" or "),
+        }
+    }
+}
+
+/// Recognize "solve <equations> [for <var>]"; queries without '=' are left to the evaluator
+pub fn parse_command(query: &str) -> Option<anyhow::Result<SolveRequest>> {
+    let body = super::strip_keyword(query.trim(), "solve")?;
+    if !body.contains('=') {
+        return None;
+    }
+    Some(parse_request(body).map_err(|e| relocate(e, query, body)))
+}
+
+/// Syntax errors count columns from the start of `body`
+fn parse_request(body: &str) -> anyhow::Result<SolveRequest> {
+    let (system, target) = match split_keyword(body, "for") {
+        Some((system, var)) => (system, Some(var.trim().to_string())),
+        None => (body, None),
+    };
+
+    let column = |part: &str| column_of(body, part).unwrap_or(1);
+    let parse = |part: &str| parse_expression(part).map_err(|e| relocate(e, body, part));
+    let mut equations = Vec::new();
+    for part in split_top_level(system) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let Some((lhs, rhs)) = part.split_once('=') else {
+            return Err(SyntaxError::new(column(part), "an equation with '='", format!("'{}'", part)).into());
+        };
+        if let Some(at) = rhs.find('=') {
+            return Err(SyntaxError::new(column(&rhs[at..]), "one '=' per equation", "a second '='").into());
+        }
+        equations.push(Equation { lhs: parse(lhs)?, rhs: parse(rhs)? });
+    }
+
+    if equations.is_empty() {
+        return Err(SyntaxError::new(column(system) + system.chars().count(), "an equation", "end of input").into());
+    }
+    Ok(SolveRequest { equations, target })
+}
+
+/// Split a system on commas and " and " outside parentheses, into slices of `text`
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+        .into_iter()
+        .flat_map(|part| {
+            let mut pieces = Vec::new();
+            let mut rest = part;
+            while let Some((head, tail)) = split_keyword(rest, "and") {
+                pieces.push(head);
+                rest = tail;
+            }
+            pieces.push(rest);
+            pieces
+        })
+        .collect()
+}
+
+/// Solve the request, recording rearrangements, formulas and substitution checks
+pub fn solve(request: &SolveRequest) -> anyhow::Result<SolveOutcome> {
+    let mut steps = Vec::new();
+    let mut polys = Vec::new();
+    for equation in &request.equations {
+        let difference = Expr::binary(BinOp::Sub, equation.lhs.clone(), equation.rhs.clone());
+        let poly = symbolic::to_poly(&difference, &mut Vec::new()).ok_or_else(|| {
+            anyhow::anyhow!("Only polynomial equations can be solved symbolically: {}", equation)
+        })?;
+        steps.push(format!("rearrange {} → {} = 0", equation, poly.to_expr()));
+        polys.push(poly);
+    }
+
+    let mut vars: Vec<String> = polys.iter().flat_map(|p| p.variables()).collect();
+    vars.sort();
+    vars.dedup();
+
+    let outcome = match (polys.as_slice(), &request.target) {
+        ([poly], Some(target)) if vars.len() > 1 => solve_for(poly, target, &mut steps)?,
+        ([poly], _) if vars.len() <= 1 => {
+            let var = request.target.clone().or_else(|| vars.first().cloned()).unwrap_or_else(|| "x".to_string());
+            solve_univariate(poly, &var, &mut steps)?
+        }
+        _ => solve_linear_system(&polys, &vars, &mut steps)?,
+    };
+
+    check_solutions(&request.equations, &outcome, &mut steps);
+    steps.push(format!("result: {}", outcome.summary()));
+    Ok(SolveOutcome { solution: outcome, steps })
+}
+
+fn solve_univariate(poly: &Poly, var: &str, steps: &mut Vec<String>) -> anyhow::Result<Solution> {
+    let coeffs = poly.coefficients(var).unwrap_or_default();
+    let degree = coeffs.len() - 1;
+
+    if poly.is_zero() {
+        steps.push("0 = 0 holds for every value".to_string());
+        return Ok(Solution::Infinite { free: vec![var.to_string()], assignments: Vec::new() });
+    }
+
+    match degree {
+        0 => {
+            steps.push(format!("{} = 0 is a contradiction", coeffs[0]));
+            Ok(Solution::NoSolution)
+        }
+        1 => {
+            let root = -&coeffs[0] / &coeffs[1];
+            steps.push(format!("linear: {} = {} / {} = {}", var, -&coeffs[0], coeffs[1], root));
+            Ok(single(var, Root::Real(Expr::Num(root))))
+        }
+        2 => {
+            let roots = solve_quadratic(&coeffs[2], &coeffs[1], &coeffs[0], steps)?;
+            Ok(Solution::Finite(roots.into_iter().map(|r| vec![(var.to_string(), r)]).collect()))
+        }
+        _ => solve_by_factoring(poly, var, steps),
+    }
+}
+
+fn single(var: &str, root: Root) -> Solution {
+    Solution::Finite(vec![vec![(var.to_string(), root)]])
+}
+
+/// Quadratic formula with exact radicals and complex roots for negative discriminants
+fn solve_quadratic(a: &BigRational, b: &BigRational, c: &BigRational, steps: &mut Vec<String>) -> anyhow::Result<Vec<Root>> {
+    let discriminant = b * b - BigRational::from_integer(4.into()) * a * c;
+    steps.push(format!("quadratic: a = {}, b = {}, c = {}", a, b, c));
+    steps.push(format!("discriminant: b^2 - 4ac = {}", discriminant));
+
+    let two_a = BigRational::from_integer(2.into()) * a;
+    let vertex = -b / &two_a;
+
+    if discriminant.is_zero() {
+        steps.push(format!("double root: -b / 2a = {}", vertex));
+        return Ok(vec![Root::Real(Expr::Num(vertex))]);
+    }
+
+    // sqrt(|D|) = coefficient * sqrt(radicand)
+    let (coefficient, radicand) = simplify_sqrt(&discriminant.abs());
+    let spread = coefficient / two_a.abs();
+    let radical = |scale: &BigRational| -> anyhow::Result<Expr> {
+        let term = match &radicand {
+            Some(r) => Expr::binary(BinOp::Mul, Expr::Num(scale.clone()), Expr::call("sqrt", vec![Expr::Num(r.clone())])),
+            None => Expr::Num(scale.clone()),
+        };
+        symbolic::simplify(&term, &mut Vec::new())
+    };
+
+    let offset = radical(&spread)?;
+    if discriminant.is_positive() {
+        steps.push(format!("roots: (-b ± sqrt(D)) / 2a = {} ± {}", vertex, offset));
+        let mut roots = Vec::new();
+        for sign in [-1, 1] {
+            let term = Expr::binary(
+                if sign < 0 { BinOp::Sub } else { BinOp::Add },
+                Expr::Num(vertex.clone()),
+                offset.clone(),
+            );
+            roots.push(Root::Real(symbolic::simplify(&term, &mut Vec::new())?));
+        }
+        if radicand.is_none() {
+            roots.sort_by(|x, y| root_value(x).partial_cmp(&root_value(y)).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        Ok(roots)
+    } else {
+        steps.push(format!("negative discriminant: complex roots {} ± ({})i", vertex, offset));
+        let negated = symbolic::simplify(&Expr::Neg(Box::new(offset.clone())), &mut Vec::new())?;
+        Ok(vec![
+            Root::Complex { re: Expr::Num(vertex.clone()), im: offset },
+            Root::Complex { re: Expr::Num(vertex), im: negated },
+        ])
+    }
+}
+
+fn root_value(root: &Root) -> f64 {
+    match root {
+        Root::Real(e) => Evaluator::new().evaluate(e).map(|n| n.to_f64()).unwrap_or(f64::NAN),
+        Root::Complex { .. } => f64::NAN,
+    }
+}
+
+/// Write sqrt(value) as k * sqrt(m) with m square-free; `None` radicand means a rational root
+fn simplify_sqrt(value: &BigRational) -> (BigRational, Option<BigRational>) {
+    // sqrt(n/d) = sqrt(n*d) / d
+    let product = value.numer() * value.denom();
+    let (outside, inside) = extract_square(&product);
+    let coefficient = BigRational::new(outside, value.denom().clone());
+    if inside.is_one() {
+        (coefficient, None)
+    } else {
+        (coefficient, Some(BigRational::from_integer(inside)))
+    }
+}
+
+/// Split n into k^2 * m, trial-dividing small primes when n is small enough
+fn extract_square(n: &BigInt) -> (BigInt, BigInt) {
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return (root, BigInt::one());
+    }
+    let Some(mut m) = n.to_u64().filter(|v| *v <= MAX_RADICAL_FACTORING) else {
+        return (BigInt::one(), n.clone());
+    };
+
+    let mut outside: u64 = 1;
+    let mut p: u64 = 2;
+    while p * p <= m {
+        while m % (p * p) == 0 {
+            m /= p * p;
+            outside *= p;
+        }
+        p += 1;
+    }
+    (BigInt::from(outside), BigInt::from(m))
+}
+
+/// Higher-degree univariate equations: peel off rational roots, then solve the quadratic rest
+fn solve_by_factoring(poly: &Poly, var: &str, steps: &mut Vec<String>) -> anyhow::Result<Solution> {
+    let mut factor_steps = Vec::new();
+    let factored = symbolic::factor(&poly.to_expr(), &mut factor_steps)?;
+    steps.push(format!("factor: {} = {}", poly.to_expr(), factored));
+
+    let mut roots = Vec::new();
+    let mut unsolved = Vec::new();
+    for factor in product_factors(&factored) {
+        let Some(fpoly) = symbolic::to_poly(&factor, &mut Vec::new()) else { continue };
+        let coeffs = fpoly.coefficients(var).unwrap_or_default();
+        match coeffs.len().saturating_sub(1) {
+            0 => {}
+            1 => roots.push(Root::Real(Expr::Num(-&coeffs[0] / &coeffs[1]))),
+            2 => roots.extend(solve_quadratic(&coeffs[2], &coeffs[1], &coeffs[0], steps)?),
+            _ => unsolved.push(factor),
+        }
+    }
+    roots.dedup();
+
+    if !unsolved.is_empty() {
+        let rest: Vec<String> = unsolved.iter().map(|f| format!("{} = 0", f)).collect();
+        return Err(anyhow::anyhow!(
+            "No closed-form solution for {}{}",
+            rest.join(", "),
+            if roots.is_empty() { String::new() } else { format!(" (other roots: {})", roots.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", ")) }
+        ));
+    }
+    Ok(Solution::Finite(roots.into_iter().map(|r| vec![(var.to_string(), r)]).collect()))
+}
+
+/// Flatten a product of factors, dropping constants and powers
+fn product_factors(expr: &Expr) -> Vec<Expr> {
+    match expr {
+        Expr::Binary(BinOp::Mul, a, b) => {
+            let mut factors = product_factors(a);
+            factors.extend(product_factors(b));
+            factors
+        }
+        Expr::Binary(BinOp::Pow, base, _) => product_factors(base),
+        Expr::Neg(inner) => product_factors(inner),
+        Expr::Num(_) => Vec::new(),
+        other => vec![other.clone()],
+    }
+}
+
+/// Solve one multivariate equation for `target`, which must appear linearly
+fn solve_for(poly: &Poly, target: &str, steps: &mut Vec<String>) -> anyhow::Result<Solution> {
+    let powers = poly.collect_powers(target);
+    if powers.len() != 2 {
+        return Err(anyhow::anyhow!(
+            "Can only solve for {} when it appears linearly (found degree {})",
+            target,
+            powers.len() - 1
+        ));
+    }
+
+    let (rest, coefficient) = (powers[0].to_expr(), powers[1].to_expr());
+    let solved = Expr::binary(BinOp::Div, Expr::Neg(Box::new(rest)), coefficient.clone());
+    let solved = symbolic::simplify(&solved, &mut Vec::new())?;
+    steps.push(format!("isolate {}: {} = {}", target, target, solved));
+    if !matches!(coefficient, Expr::Num(_)) {
+        steps.push(format!("valid when {} ≠ 0", coefficient));
+    }
+    Ok(single(target, Root::Real(solved)))
+}
+
+/// Gauss-Jordan elimination over the rationals
+fn solve_linear_system(polys: &[Poly], vars: &[String], steps: &mut Vec<String>) -> anyhow::Result<Solution> {
+    let mut rows: Vec<Vec<BigRational>> = Vec::new();
+    for poly in polys {
+        let (constant, coeffs) = poly.linear_coefficients().ok_or_else(|| {
+            anyhow::anyhow!("Only linear systems are supported: {} = 0 is nonlinear", poly.to_expr())
+        })?;
+        let mut row: Vec<BigRational> = vars.iter().map(|v| coeffs.get(v).cloned().unwrap_or_default()).collect();
+        row.push(-constant);
+        rows.push(row);
+    }
+    steps.push(format!("linear system of {} equation(s) in {}", rows.len(), vars.join(", ")));
+
+    let n = vars.len();
+    let mut pivots: Vec<usize> = Vec::new();
+    let mut row = 0;
+    for col in 0..n {
+        let Some(pivot) = (row..rows.len()).find(|r| !rows[*r][col].is_zero()) else { continue };
+        if pivot != row {
+            rows.swap(pivot, row);
+            steps.push(format!("swap R{} and R{}", row + 1, pivot + 1));
+        }
+        let lead = rows[row][col].clone();
+        if !lead.is_one() {
+            rows[row].iter_mut().for_each(|v| *v /= &lead);
+            steps.push(format!("R{} = R{} / {}", row + 1, row + 1, lead));
+        }
+        for other in 0..rows.len() {
+            if other == row || rows[other][col].is_zero() {
+                continue;
+            }
+            let factor = rows[other][col].clone();
+            let pivot_row = rows[row].clone();
+            for (v, p) in rows[other].iter_mut().zip(pivot_row.iter()) {
+                *v -= &factor * p;
+            }
+            steps.push(format!("R{} = R{} - ({}) * R{}", other + 1, other + 1, factor, row + 1));
+        }
+        pivots.push(col);
+        row += 1;
+        if row == rows.len() {
+            break;
+        }
+    }
+
+    if rows.iter().any(|r| r[..n].iter().all(|v| v.is_zero()) && !r[n].is_zero()) {
+        steps.push("inconsistent row 0 = c with c ≠ 0".to_string());
+        return Ok(Solution::NoSolution);
+    }
+
+    let free: Vec<String> = (0..n).filter(|c| !pivots.contains(c)).map(|c| vars[c].clone()).collect();
+    let mut assignments = Vec::new();
+    for (r, &col) in pivots.iter().enumerate() {
+        let mut value = Expr::Num(rows[r][n].clone());
+        for (c, var) in vars.iter().enumerate() {
+            if c != col && !rows[r][c].is_zero() {
+                let term = Expr::binary(BinOp::Mul, Expr::Num(rows[r][c].clone()), Expr::Var(var.clone()));
+                value = Expr::binary(BinOp::Sub, value, term);
+            }
+        }
+        assignments.push((vars[col].clone(), symbolic::simplify(&value, &mut Vec::new())?));
+    }
+
+    if free.is_empty() {
+        steps.push(format!("rank {} = number of unknowns: unique solution", pivots.len()));
+        Ok(Solution::Finite(vec![assignments.into_iter().map(|(v, e)| (v, Root::Real(e))).collect()]))
+    } else {
+        steps.push(format!("rank {} < {} unknowns: {} free", pivots.len(), n, free.join(", ")));
+        Ok(Solution::Infinite { free, assignments })
+    }
+}
+
+/// Substitute each solution back into every equation; irrational and complex roots are
+/// checked numerically, parametric ones symbolically
+fn check_solutions(equations: &[Equation], solution: &Solution, steps: &mut Vec<String>) {
+    let Solution::Finite(solutions) = solution else {
+        return;
+    };
+
+    for assignment in solutions {
+        let bindings: BTreeMap<&str, &Root> = assignment.iter().map(|(v, r)| (v.as_str(), r)).collect();
+        let label = assignment.iter().map(|(v, r)| format!("{} = {}", v, r)).collect::<Vec<_>>().join(", ");
+
+        for equation in equations {
+            if bindings.values().any(|r| matches!(r, Root::Complex { .. })) {
+                let residual = complex_residual(equation, &bindings);
+                steps.push(check_line(&label, equation, residual.is_some_and(|r| r < CHECK_TOLERANCE), "numerically"));
+                continue;
+            }
+
+            let substitute = |side: &Expr| {
+                bindings.iter().fold(side.clone(), |acc, (v, r)| match r {
+                    Root::Real(e) => acc.substitute(v, e),
+                    Root::Complex { .. } => acc,
+                })
+            };
+            let (lhs, rhs) = (substitute(&equation.lhs), substitute(&equation.rhs));
+            let evaluator = Evaluator::new();
+            match (evaluator.evaluate(&lhs), evaluator.evaluate(&rhs)) {
+                (Ok(l), Ok(r)) if l.is_exact() && r.is_exact() => {
+                    steps.push(check_line(&label, equation, l == r, &format!("{} = {}", l, r)));
+                }
+                (Ok(l), Ok(r)) => {
+                    let ok = (l.to_f64() - r.to_f64()).abs() < CHECK_TOLERANCE * (1.0 + r.to_f64().abs());
+                    steps.push(check_line(&label, equation, ok, "numerically"));
+                }
+                // Parametric answers keep free symbols; verify by simplifying the difference
+                _ => {
+                    let difference = symbolic::simplify(&Expr::binary(BinOp::Sub, lhs, rhs), &mut Vec::new());
+                    let ok = matches!(difference, Ok(Expr::Num(ref n)) if n.is_zero());
+                    steps.push(check_line(&label, equation, ok, "symbolically"));
+                }
+            }
+        }
+    }
+}
+
+fn check_line(label: &str, equation: &Equation, ok: bool, detail: &str) -> String {
+    format!("check {} in {}: {} ({})", label, equation, if ok { "✓" } else { "✗" }, detail)
+}
+
+/// |lhs - rhs| at a complex point, evaluating the polynomial difference with f64 arithmetic
+fn complex_residual(equation: &Equation, bindings: &BTreeMap<&str, &Root>) -> Option<f64> {
+    let difference = Expr::binary(BinOp::Sub, equation.lhs.clone(), equation.rhs.clone());
+    let poly = symbolic::to_poly(&difference, &mut Vec::new())?;
+    let (var, root) = bindings.iter().next()?;
+    let Root::Complex { re, im } = root else { return None };
+    let evaluator = Evaluator::new();
+    let z = (evaluator.evaluate(re).ok()?.to_f64(), evaluator.evaluate(im).ok()?.to_f64());
+
+    let coeffs = poly.coefficients(var)?;
+    let (mut acc_re, mut acc_im) = (0.0, 0.0);
+    for c in coeffs.iter().rev() {
+        let c = c.to_f64()?;
+        let next_re = acc_re * z.0 - acc_im * z.1 + c;
+        let next_im = acc_re * z.1 + acc_im * z.0;
+        acc_re = next_re;
+        acc_im = next_im;
+    }
+    Some((acc_re * acc_re + acc_im * acc_im).sqrt())
+}
@@ -0,0 +1,605 @@
+// Descriptive statistics, combinatorics and binomial probabilities over inline lists
+use super::context::EvalContext;
+use super::error::{Limit, LimitError};
+use super::expr::{Evaluator, Expr, Parser};
+use super::lexer::{tokenize, Token, TokenKind};
+use super::number::Number;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+
+/// Most multiplications spent on one factorial, nCr or nPr
+const MAX_COMBINATORIC_FACTORS: u64 = 20_000;
+
+/// Most decimal digits of a factorial, nCr or nPr
+const MAX_COMBINATORIC_DIGITS: f64 = 100_000.0;
+
+/// Largest n of the binomial distribution, whose exact probabilities carry n-th powers
+const MAX_BINOMIAL_N: u64 = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatFunction {
+    Mean,
+    Median,
+    Mode,
+    /// Sample (n - 1) or population (n) variance
+    Variance { sample: bool },
+    StdDev { sample: bool },
+    Sum,
+    Min,
+    Max,
+    Range,
+    Count,
+    Percentile,
+    Covariance,
+    Correlation,
+    Combinations,
+    Permutations,
+    Factorial,
+    BinomialPmf,
+    BinomialCdf,
+}
+
+/// Query names, longest first so "population variance" wins over "variance"
+const NAMES: &[(&str, StatFunction)] = &[
+    ("population standard deviation", StatFunction::StdDev { sample: false }),
+    ("sample standard deviation", StatFunction::StdDev { sample: true }),
+    ("standard deviation", StatFunction::StdDev { sample: true }),
+    ("population variance", StatFunction::Variance { sample: false }),
+    ("population stddev", StatFunction::StdDev { sample: false }),
+    ("sample variance", StatFunction::Variance { sample: true }),
+    ("sample stddev", StatFunction::StdDev { sample: true }),
+    ("binomial pmf", StatFunction::BinomialPmf),
+    ("binomial cdf", StatFunction::BinomialCdf),
+    ("binomial_pmf", StatFunction::BinomialPmf),
+    ("binomial_cdf", StatFunction::BinomialCdf),
+    ("correlation", StatFunction::Correlation),
+    ("covariance", StatFunction::Covariance),
+    ("percentile", StatFunction::Percentile),
+    ("pvariance", StatFunction::Variance { sample: false }),
+    ("factorial", StatFunction::Factorial),
+    ("variance", StatFunction::Variance { sample: true }),
+    ("binompdf", StatFunction::BinomialPmf),
+    ("binompmf", StatFunction::BinomialPmf),
+    ("binomcdf", StatFunction::BinomialCdf),
+    ("average", StatFunction::Mean),
+    ("maximum", StatFunction::Max),
+    ("minimum", StatFunction::Min),
+    ("pstddev", StatFunction::StdDev { sample: false }),
+    ("stddev", StatFunction::StdDev { sample: true }),
+    ("median", StatFunction::Median),
+    ("choose", StatFunction::Combinations),
+    ("count", StatFunction::Count),
+    ("range", StatFunction::Range),
+    ("mean", StatFunction::Mean),
+    ("mode", StatFunction::Mode),
+    ("corr", StatFunction::Correlation),
+    ("std", StatFunction::StdDev { sample: true }),
+    ("var", StatFunction::Variance { sample: true }),
+    ("cov", StatFunction::Covariance),
+    ("avg", StatFunction::Mean),
+    ("sum", StatFunction::Sum),
+    ("min", StatFunction::Min),
+    ("max", StatFunction::Max),
+    ("ncr", StatFunction::Combinations),
+    ("npr", StatFunction::Permutations),
+];
+
+/// A list literal or a single scalar, optionally named as in "p = 1/2"
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatArg {
+    List(Vec<Expr>),
+    Scalar(Option<String>, Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsQuery {
+    pub function: StatFunction,
+    pub args: Vec<StatArg>,
+}
+
+/// Recognize "mean of [3, 5, 9]", "percentile 90 of [...]", "nCr(52, 5)" and similar
+pub fn parse_command(query: &str) -> Option<anyhow::Result<StatsQuery>> {
+    let query = query.trim();
+    let query = ordinal_percentile(query).unwrap_or_else(|| query.to_string());
+
+    let (function, rest) = NAMES.iter().find_map(|(name, function)| {
+        let rest = super::strip_keyword(&query, name)?;
+        // A bare name ("mode", "range") followed by nothing is not a statistics query
+        (!rest.is_empty()).then_some((*function, rest))
+    })?;
+
+    // Scalar calls like "max(3, 7) + 1" belong to the math evaluator
+    if rest.starts_with('(') && !closes_at_end(rest) {
+        return None;
+    }
+    // Words like "count" or "range" only start a statistics query when data follows
+    let takes_scalars = matches!(
+        function,
+        StatFunction::Combinations | StatFunction::Permutations | StatFunction::Factorial
+            | StatFunction::BinomialPmf | StatFunction::BinomialCdf
+    );
+    if !(rest.starts_with('(') || rest.contains('[') || takes_scalars) {
+        return None;
+    }
+
+    Some(parse_args(rest).map(|args| StatsQuery { function, args }))
+}
+
+/// Rewrite "90th percentile of ..." to "percentile 90 of ..."
+fn ordinal_percentile(query: &str) -> Option<String> {
+    let (ordinal, rest) = query.split_once(char::is_whitespace)?;
+    let rest = super::strip_keyword(rest.trim_start(), "percentile")?;
+    let digits = ordinal.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &ordinal[digits.len()..].to_lowercase();
+    (["st", "nd", "rd", "th"].contains(&suffix.as_str()) && !digits.is_empty())
+        .then(|| format!("percentile {} {}", digits, rest))
+}
+
+/// True when the opening parenthesis at the start closes at the very end
+fn closes_at_end(text: &str) -> bool {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i == text.len() - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Arguments separated by ',', "and" or "of", optionally wrapped in parentheses
+fn parse_args(text: &str) -> anyhow::Result<Vec<StatArg>> {
+    let text = text.trim();
+    let inner = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) if closes_at_end(text) => inner,
+        _ => text,
+    };
+
+    let tokens: Vec<Token> = tokenize(inner)?
+        .into_iter()
+        .map(|token| match &token.kind {
+            TokenKind::Ident(word) if word.eq_ignore_ascii_case("and") || word.eq_ignore_ascii_case("of") => {
+                Token { kind: TokenKind::Symbol(","), column: token.column, end: token.end }
+            }
+            _ => token,
+        })
+        .collect();
+
+    let mut parser = Parser::new(tokens);
+    let mut args = Vec::new();
+    loop {
+        while parser.eat_symbol(",") {}
+        if parser.at_end() {
+            break;
+        }
+        if parser.eat_symbol("[") {
+            let mut items = vec![parser.parse_expr()?];
+            while parser.eat_symbol(",") {
+                items.push(parser.parse_expr()?);
+            }
+            parser.expect_symbol("]")?;
+            args.push(StatArg::List(items));
+            continue;
+        }
+        let name = match parser.peek().map(|t| &t.kind) {
+            Some(TokenKind::Ident(name)) if is_named_argument(&parser) => Some(name.to_lowercase()),
+            _ => None,
+        };
+        if name.is_some() {
+            parser.advance();
+            parser.expect_symbol("=")?;
+        }
+        args.push(StatArg::Scalar(name, parser.parse_expr()?));
+        if !parser.at_end() && !parser.peek().is_some_and(|t| t.is_symbol(",")) {
+            return Err(parser.error("',' between arguments"));
+        }
+    }
+
+    if args.is_empty() {
+        return Err(anyhow::anyhow!("Expected a list such as [3, 5, 9] or numeric arguments"));
+    }
+    Ok(args)
+}
+
+fn is_named_argument(parser: &Parser) -> bool {
+    parser.peek_ahead(1).is_some_and(|t| t.is_symbol("="))
+}
+
+/// Evaluated statistic with the intermediate quantities that produced it
+#[derive(Debug, Clone)]
+pub struct StatsOutcome {
+    /// Usually one value; several for a multimodal list
+    pub values: Vec<Number>,
+    pub steps: Vec<String>,
+}
+
+pub fn evaluate(query: &StatsQuery, context: &EvalContext) -> anyhow::Result<StatsOutcome> {
+    let evaluator = Evaluator::with_context(context);
+    let mut lists = Vec::new();
+    let mut scalars = Vec::new();
+    for arg in &query.args {
+        match arg {
+            StatArg::List(items) => {
+                lists.push(items.iter().map(|e| evaluator.evaluate(e)).collect::<anyhow::Result<Vec<_>>>()?)
+            }
+            StatArg::Scalar(name, e) => scalars.push((name.clone(), evaluator.evaluate(e)?)),
+        }
+    }
+
+    let mut steps = Vec::new();
+    let values = match query.function {
+        StatFunction::Combinations | StatFunction::Permutations | StatFunction::Factorial => {
+            vec![combinatoric(query.function, &scalars, &mut steps)?]
+        }
+        StatFunction::BinomialPmf | StatFunction::BinomialCdf => vec![binomial(query.function, &scalars, &mut steps)?],
+        StatFunction::Covariance | StatFunction::Correlation => {
+            let [xs, ys] = lists.as_slice() else {
+                return Err(anyhow::anyhow!("Expected two lists, as in 'correlation of [1, 2, 3] and [2, 4, 7]'"));
+            };
+            vec![paired(query.function, xs, ys, &mut steps)?]
+        }
+        StatFunction::Percentile => {
+            let (Some((_, p)), [data]) = (scalars.first(), lists.as_slice()) else {
+                return Err(anyhow::anyhow!("Expected 'percentile <p> of [values]'"));
+            };
+            vec![percentile(data, p, &mut steps)?]
+        }
+        function => {
+            // "mean(1, 2, 3)" is accepted as well as "mean of [1, 2, 3]"
+            let data = match (lists.as_slice(), scalars.is_empty()) {
+                ([data], true) => data.clone(),
+                ([], false) => scalars.into_iter().map(|(_, value)| value).collect(),
+                _ => return Err(anyhow::anyhow!("Expected a single list of values")),
+            };
+            describe(function, &data, &mut steps)?
+        }
+    };
+    Ok(StatsOutcome { values, steps })
+}
+
+fn describe(function: StatFunction, data: &[Number], steps: &mut Vec<String>) -> anyhow::Result<Vec<Number>> {
+    if data.is_empty() {
+        return Err(anyhow::anyhow!("Cannot compute statistics of an empty list"));
+    }
+    let n = Number::from_i64(data.len() as i64);
+    steps.push(format!("n = {}", n));
+
+    let value = match function {
+        StatFunction::Count => n,
+        StatFunction::Sum => sum(data, steps)?,
+        StatFunction::Mean => mean(data, steps)?,
+        StatFunction::Min | StatFunction::Max | StatFunction::Range => {
+            let sorted = sorted(data);
+            let (lo, hi) = (sorted[0].clone(), sorted[sorted.len() - 1].clone());
+            steps.push(format!("min = {}, max = {}", lo, hi));
+            match function {
+                StatFunction::Min => lo,
+                StatFunction::Max => hi,
+                _ => hi.sub(&lo)?,
+            }
+        }
+        StatFunction::Median => {
+            let sorted = sorted(data);
+            steps.push(format!("sorted: {}", join(&sorted)));
+            let mid = sorted.len() / 2;
+            if sorted.len() % 2 == 1 {
+                sorted[mid].clone()
+            } else {
+                let median = sorted[mid - 1].add(&sorted[mid])?.div(&Number::from_i64(2))?;
+                steps.push(format!("median = ({} + {}) / 2 = {}", sorted[mid - 1], sorted[mid], median));
+                median
+            }
+        }
+        StatFunction::Mode => return mode(data, steps),
+        StatFunction::Variance { sample } => variance(data, sample, steps)?,
+        StatFunction::StdDev { sample } => {
+            let var = variance(data, sample, steps)?;
+            let sd = var.pow(&half())?;
+            steps.push(format!("stddev = sqrt({}) = {}", var, sd));
+            sd
+        }
+        _ => unreachable!("list statistics only"),
+    };
+    Ok(vec![value])
+}
+
+fn sum(data: &[Number], steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    let total = data.iter().try_fold(Number::from_i64(0), |acc, x| acc.add(x))?;
+    steps.push(format!("sum = {}", total));
+    Ok(total)
+}
+
+fn mean(data: &[Number], steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    let total = sum(data, steps)?;
+    let mean = total.div(&Number::from_i64(data.len() as i64))?;
+    steps.push(format!("mean = {} / {} = {}", total, data.len(), mean));
+    Ok(mean)
+}
+
+fn variance(data: &[Number], sample: bool, steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    let denominator = if sample { data.len() as i64 - 1 } else { data.len() as i64 };
+    if denominator == 0 {
+        return Err(anyhow::anyhow!("Sample variance needs at least two values"));
+    }
+    let m = mean(data, steps)?;
+    let squares = data.iter().try_fold(Number::from_i64(0), |acc, x| {
+        let d = x.sub(&m)?;
+        acc.add(&d.mul(&d)?)
+    })?;
+    let var = squares.div(&Number::from_i64(denominator))?;
+    steps.push(format!(
+        "{} variance = {} / {} = {}",
+        if sample { "sample" } else { "population" },
+        squares,
+        denominator,
+        var
+    ));
+    Ok(var)
+}
+
+fn mode(data: &[Number], steps: &mut Vec<String>) -> anyhow::Result<Vec<Number>> {
+    let sorted = sorted(data);
+    let mut runs: Vec<(Number, usize)> = Vec::new();
+    for x in sorted {
+        match runs.last_mut() {
+            Some((value, count)) if compare(value, &x) == Ordering::Equal => *count += 1,
+            _ => runs.push((x, 1)),
+        }
+    }
+    let best = runs.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let modes: Vec<Number> = runs.into_iter().filter(|(_, count)| *count == best).map(|(v, _)| v).collect();
+    steps.push(format!("most frequent ({} occurrence(s)): {}", best, join(&modes)));
+    Ok(modes)
+}
+
+/// Linear interpolation between closest ranks (the common "type 7" definition)
+fn percentile(data: &[Number], p: &Number, steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    if data.is_empty() {
+        return Err(anyhow::anyhow!("Cannot compute a percentile of an empty list"));
+    }
+    if p.is_negative() || compare(p, &Number::from_i64(100)) == Ordering::Greater {
+        return Err(anyhow::anyhow!("Percentile must be between 0 and 100, found {}", p));
+    }
+    let sorted = sorted(data);
+    steps.push(format!("sorted: {}", join(&sorted)));
+
+    let rank = p.div(&Number::from_i64(100))?.mul(&Number::from_i64(sorted.len() as i64 - 1))?;
+    let lower = match &rank {
+        Number::Exact(r) => r.floor().to_integer().to_usize().unwrap_or(0),
+        Number::Approx(f) => f.floor() as usize,
+    };
+    let fraction = rank.sub(&Number::from_i64(lower as i64))?;
+    let upper = (lower + 1).min(sorted.len() - 1);
+    let value = sorted[lower].add(&sorted[upper].sub(&sorted[lower])?.mul(&fraction)?)?;
+    steps.push(format!("rank = {}/100 * (n - 1) = {}", p, rank));
+    steps.push(format!("interpolate between {} and {}: {}", sorted[lower], sorted[upper], value));
+    Ok(value)
+}
+
+fn paired(function: StatFunction, xs: &[Number], ys: &[Number], steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    if xs.len() != ys.len() {
+        return Err(anyhow::anyhow!("Lists must have equal length, found {} and {}", xs.len(), ys.len()));
+    }
+    if xs.len() < 2 {
+        return Err(anyhow::anyhow!("Need at least two pairs of values"));
+    }
+    let (mx, my) = (mean(xs, &mut Vec::new())?, mean(ys, &mut Vec::new())?);
+    steps.push(format!("mean x = {}, mean y = {}", mx, my));
+
+    let mut sxy = Number::from_i64(0);
+    let mut sxx = Number::from_i64(0);
+    let mut syy = Number::from_i64(0);
+    for (x, y) in xs.iter().zip(ys) {
+        let (dx, dy) = (x.sub(&mx)?, y.sub(&my)?);
+        sxy = sxy.add(&dx.mul(&dy)?)?;
+        sxx = sxx.add(&dx.mul(&dx)?)?;
+        syy = syy.add(&dy.mul(&dy)?)?;
+    }
+    steps.push(format!("Sxy = {}, Sxx = {}, Syy = {}", sxy, sxx, syy));
+
+    if function == StatFunction::Covariance {
+        let cov = sxy.div(&Number::from_i64(xs.len() as i64 - 1))?;
+        steps.push(format!("sample covariance = Sxy / (n - 1) = {}", cov));
+        return Ok(cov);
+    }
+
+    if sxx.is_zero() || syy.is_zero() {
+        return Err(anyhow::anyhow!("Correlation is undefined when a list has zero variance"));
+    }
+    // r^2 is exact, so take the root last to keep r exact when possible
+    let r_squared = sxy.mul(&sxy)?.div(&sxx.mul(&syy)?)?;
+    let magnitude = r_squared.pow(&half())?;
+    let r = if sxy.is_negative() { magnitude.neg() } else { magnitude };
+    steps.push(format!("r = Sxy / sqrt(Sxx * Syy), r^2 = {}, r = {}", r_squared, r));
+    Ok(r)
+}
+
+fn combinatoric(function: StatFunction, args: &[(Option<String>, Number)], steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    let values: Vec<Number> = args.iter().map(|(_, v)| v.clone()).collect();
+    let name = match function {
+        StatFunction::Factorial => "factorial",
+        StatFunction::Combinations => "nCr",
+        _ => "nPr",
+    };
+    let result = match (function, values.as_slice()) {
+        (StatFunction::Factorial, [n]) => {
+            let n = to_count(n, name)?;
+            check_work(name, &n, &n, false)?;
+            factorial(&n)
+        }
+        (StatFunction::Combinations, [n, k]) => {
+            let (n, k) = (to_count(n, name)?, to_count(k, name)?);
+            // C(n, k) = C(n, n - k): the work and the size follow the smaller of the two
+            if k <= n {
+                check_work(name, &n, &std::cmp::min(k.clone(), &n - &k), true)?;
+            }
+            combinations(&n, &k)
+        }
+        (StatFunction::Permutations, [n, k]) => {
+            let (n, k) = (to_count(n, name)?, to_count(k, name)?);
+            if k <= n {
+                check_work(name, &n, &k, false)?;
+            }
+            permutations(&n, &k)
+        }
+        _ => {
+            let expected = if function == StatFunction::Factorial { 1 } else { 2 };
+            return Err(anyhow::anyhow!("{}() takes {} argument(s), got {}", name, expected, values.len()));
+        }
+    };
+    steps.push(format!("{}({}) = {}", name, join(&values), result));
+    Ok(Number::from_integer(result))
+}
+
+/// Binomial distribution with arguments (n, p, k) or named n =, p =, k =
+fn binomial(function: StatFunction, args: &[(Option<String>, Number)], steps: &mut Vec<String>) -> anyhow::Result<Number> {
+    let mut positional = args.iter().filter(|(name, _)| name.is_none()).map(|(_, v)| v);
+    let mut get = |key: &str| -> anyhow::Result<Number> {
+        args.iter()
+            .find(|(name, _)| name.as_deref() == Some(key))
+            .map(|(_, v)| v.clone())
+            .or_else(|| positional.next().cloned())
+            .ok_or_else(|| anyhow::anyhow!("Binomial distribution needs n, p and k, e.g. 'binomial pmf n=10, p=1/2, k=3'"))
+    };
+    let (n, p, k) = (get("n")?, get("p")?, get("k")?);
+    let n = to_count(&n, "binomial")?;
+    let k = to_count(&k, "binomial")?;
+    if p.is_negative() || compare(&p, &Number::from_i64(1)) == Ordering::Greater {
+        return Err(anyhow::anyhow!("Probability p must be between 0 and 1, found {}", p));
+    }
+    if n.to_u64().is_none_or(|n| n > MAX_BINOMIAL_N) {
+        return Err(anyhow::anyhow!("n must be at most {}", MAX_BINOMIAL_N));
+    }
+
+    let q = Number::from_i64(1).sub(&p)?;
+    let pmf = |i: &BigInt| -> anyhow::Result<Number> {
+        let ways = Number::from_integer(combinations(&n, i));
+        let successes = p.pow(&Number::from_integer(i.clone()))?;
+        let failures = q.pow(&Number::from_integer(&n - i))?;
+        ways.mul(&successes)?.mul(&failures)
+    };
+
+    let result = if function == StatFunction::BinomialPmf {
+        steps.push(format!("P(X = {}) = C({}, {}) * {}^{} * {}^{}", k, n, k, p, k, q, &n - &k));
+        pmf(&k)?
+    } else {
+        steps.push(format!("P(X <= {}) = sum of P(X = i) for i = 0..{}", k, k.clone().min(n.clone())));
+        let mut total = Number::from_i64(0);
+        let mut i = BigInt::zero();
+        while i <= k && i <= n {
+            total = total.add(&pmf(&i)?)?;
+            i += 1;
+        }
+        total
+    };
+    steps.push(format!("= {}", result));
+    Ok(result)
+}
+
+/// Require a non-negative whole number
+fn to_count(value: &Number, name: &str) -> anyhow::Result<BigInt> {
+    match value.as_integer() {
+        Some(n) if !n.is_negative() => Ok(n),
+        _ => Err(anyhow::anyhow!("{}() needs non-negative whole numbers, found {}", name, value)),
+    }
+}
+
+/// Refuse a product of the `factors` terms n, n - 1, ... (divided by 1, 2, ... when
+/// `divided`) that takes too many multiplications or has too many digits
+fn check_work(name: &str, n: &BigInt, factors: &BigInt, divided: bool) -> anyhow::Result<()> {
+    let factors = match factors.to_u64() {
+        Some(factors) if factors <= MAX_COMBINATORIC_FACTORS => factors,
+        _ => {
+            let message = format!("{}() needs more than {} multiplications", name, MAX_COMBINATORIC_FACTORS);
+            return Err(LimitError::new(Limit::Operations, message).into());
+        }
+    };
+    let n = n.to_f64().unwrap_or(f64::INFINITY);
+    let digits: f64 = (0..factors)
+        .map(|i| (n - i as f64).log10() - if divided { (i as f64 + 1.0).log10() } else { 0.0 })
+        .sum();
+    if digits > MAX_COMBINATORIC_DIGITS {
+        let message = format!("{}() has more than {} digits", name, MAX_COMBINATORIC_DIGITS);
+        return Err(LimitError::new(Limit::Size, message).into());
+    }
+    Ok(())
+}
+
+pub fn factorial(n: &BigInt) -> BigInt {
+    let mut result = BigInt::one();
+    let mut i = BigInt::from(2);
+    while &i <= n {
+        result *= &i;
+        i += 1;
+    }
+    result
+}
+
+/// n choose k by the multiplicative formula; zero when k > n
+pub fn combinations(n: &BigInt, k: &BigInt) -> BigInt {
+    if k > n {
+        return BigInt::zero();
+    }
+    let k = std::cmp::min(k.clone(), n - k);
+    let mut result = BigInt::one();
+    let mut i = BigInt::zero();
+    while i < k {
+        result = result * (n - &i) / (&i + 1);
+        i += 1;
+    }
+    result
+}
+
+/// Ordered selections n! / (n - k)!; zero when k > n
+pub fn permutations(n: &BigInt, k: &BigInt) -> BigInt {
+    if k > n {
+        return BigInt::zero();
+    }
+    let mut result = BigInt::one();
+    let mut i = n - k + 1;
+    while &i <= n {
+        result *= &i;
+        i += 1;
+    }
+    result
+}
+
+/// Built-in entry point so combinatorics also work inside math expressions
+pub fn call_combinatoric(name: &str, args: &[Number]) -> anyhow::Result<Number> {
+    let function = match name {
+        "factorial" => StatFunction::Factorial,
+        "ncr" | "choose" => StatFunction::Combinations,
+        _ => StatFunction::Permutations,
+    };
+    let args: Vec<(Option<String>, Number)> = args.iter().map(|a| (None, a.clone())).collect();
+    combinatoric(function, &args, &mut Vec::new())
+}
+
+fn half() -> Number {
+    Number::Exact(BigRational::new(1.into(), 2.into()))
+}
+
+fn compare(a: &Number, b: &Number) -> Ordering {
+    match (a, b) {
+        (Number::Exact(x), Number::Exact(y)) => x.cmp(y),
+        _ => a.to_f64().total_cmp(&b.to_f64()),
+    }
+}
+
+fn sorted(data: &[Number]) -> Vec<Number> {
+    let mut sorted = data.to_vec();
+    sorted.sort_by(compare);
+    sorted
+}
+
+fn join(values: &[Number]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
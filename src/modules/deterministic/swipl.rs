@@ -0,0 +1,217 @@
+// SWI-Prolog backend: each query runs in a fresh `swipl` process loaded with the knowledge base
+use super::error::{Limit, LimitError};
+use super::prolog::{self, Clause, KnowledgeBase, Query, QueryOutcome, Term, USER_MODULE};
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Extra wall-clock time allowed for process start-up and consulting before the process is killed
+const STARTUP_GRACE: Duration = Duration::from_secs(2);
+
+/// How to run SWI-Prolog and how much work one query may do
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwiplConfig {
+    /// The `swipl` binary, looked up on PATH unless absolute
+    pub executable: PathBuf,
+    /// Wall-clock limit per query, enforced by `call_with_time_limit/2`
+    pub time_limit: Duration,
+    /// Inference limit per query, enforced by `call_with_inference_limit/3`
+    pub inference_limit: u64,
+}
+
+impl Default for SwiplConfig {
+    fn default() -> Self {
+        SwiplConfig {
+            executable: PathBuf::from("swipl"),
+            time_limit: Duration::from_secs(5),
+            inference_limit: 10_000_000,
+        }
+    }
+}
+
+/// Driver appended to the knowledge base; `axiom_main` prints one tab-separated line per
+/// answer, then the inference count and a status line
+const DRIVER: &str = r#"
+:- use_module(library(time)).
+
+axiom_main :-
+    axiom_query(Names, Goal),
+    statistics(inferences, Before),
+    catch(call_with_time_limit(@TIME@,
+              call_with_inference_limit(findall(Names, limit(@LIMIT@, Goal), Answers), @INFERENCES@, Result)),
+          Error, true),
+    statistics(inferences, After),
+    Used is After - Before,
+    format("inferences\t~d~n", [Used]),
+    (   nonvar(Error) -> axiom_status(Error)
+    ;   Result == inference_limit_exceeded -> format("status\tinference_limit~n")
+    ;   forall(member(Answer, Answers), axiom_answer(Answer)),
+        format("status\tok~n")
+    ).
+
+axiom_answer(Bindings) :-
+    format("answer"),
+    forall(member(Name = Value, Bindings),
+           ( format("\t~w\t", [Name]),
+             write_term(Value, [quoted(true), spacing(next_argument)])
+           )),
+    nl.
+
+axiom_status(time_limit_exceeded) :- !, format("status\ttime_limit~n").
+axiom_status(error(Formal, _)) :- !, format("status\terror\t~q~n", [Formal]).
+axiom_status(Error) :- format("status\terror\t~q~n", [Error]).
+"#;
+
+/// Kills and reaps the process when dropped, so no early return leaves it running; its
+/// pipes then close and the threads reading them finish
+struct Reaped(Child);
+
+impl Drop for Reaped {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Solve `query` with SWI-Prolog against `kb`; the outcome follows the same contract as
+/// `prolog::solve`, except that SWI-Prolog reports answers without a derivation, so the
+/// proof only says which backend answered
+pub fn solve(query: &Query, kb: &KnowledgeBase, config: &SwiplConfig) -> anyhow::Result<QueryOutcome> {
+    let child = Command::new(&config.executable)
+        .args(["-q", "-f", "none", "-g", "load_files(axiom_kb, [stream(user_input)]), axiom_main", "-t", "halt"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start SWI-Prolog ({}): {}", config.executable.display(), e))?;
+    let mut child = Reaped(child);
+    let stdout = drain(child.0.stdout.take());
+    let stderr = drain(child.0.stderr.take());
+
+    // The whole program is read before any output is written, so this cannot deadlock on the pipes
+    if let Some(mut stdin) = child.0.stdin.take() {
+        stdin.write_all(program(query, kb, config).as_bytes())?;
+    }
+
+    let deadline = Instant::now() + config.time_limit + STARTUP_GRACE;
+    while child.0.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            return Err(time_limit_error(config));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    parse_output(query, &stdout, config).map_err(|e| match stderr.trim() {
+        "" => e,
+        detail => anyhow::anyhow!("{} ({})", e, detail),
+    })
+}
+
+/// Read a child pipe to the end on its own thread
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut text);
+        }
+        text
+    })
+}
+
+/// Knowledge base clauses, forwarding clauses and the driver as one Prolog source
+fn program(query: &Query, kb: &KnowledgeBase, config: &SwiplConfig) -> String {
+    let mut text = String::from(":- style_check(-singleton).\n:- style_check(-discontiguous).\n");
+    let mut defined = BTreeSet::new();
+    for clause in kb.clauses() {
+        if let Some((name, arity)) = clause.head.indicator() {
+            defined.insert((clause.module.as_str(), name, arity));
+        }
+        text.push_str(&clause_source(clause));
+    }
+    // The built-in engine falls back to the single module defining a predicate; user-module
+    // forwarders give SWI-Prolog the same lookup, since every module imports from user
+    for &(module, name, arity) in &defined {
+        let elsewhere = defined.iter().filter(|(m, n, a)| *n == name && *a == arity && *m != module).count();
+        if module != USER_MODULE && elsewhere == 0 {
+            let args: Vec<String> = (0..arity).map(|i| format!("A{}", i)).collect();
+            let head = if arity == 0 { name.to_string() } else { format!("{}({})", name, args.join(", ")) };
+            text.push_str(&format!("{} :- {}:{}.\n", head, module, head));
+        }
+    }
+
+    let names: Vec<String> = query
+        .var_names
+        .iter()
+        .filter(|name| !name.starts_with('_'))
+        .map(|name| format!("'{}' = {}", name, name))
+        .collect();
+    let goal = if query.goals.is_empty() { "true".to_string() } else { query.to_string() };
+    text.push_str(&format!("axiom_query([{}], ({})).\n", names.join(", "), goal));
+    text.push_str(
+        &DRIVER
+            .replace("@TIME@", &format!("{:.3}", config.time_limit.as_secs_f64()))
+            .replace("@LIMIT@", &(prolog::MAX_SOLUTIONS + 1).to_string())
+            .replace("@INFERENCES@", &config.inference_limit.to_string()),
+    );
+    text
+}
+
+/// "h :- b." in user, "geo:(h :- b)." elsewhere so the body also runs in the clause's module
+fn clause_source(clause: &Clause) -> String {
+    if clause.module == USER_MODULE {
+        return format!("{}.\n", clause);
+    }
+    let local = Clause { module: USER_MODULE.to_string(), ..clause.clone() };
+    format!("{}:({}).\n", clause.module, local)
+}
+
+fn parse_output(query: &Query, output: &str, config: &SwiplConfig) -> anyhow::Result<QueryOutcome> {
+    let mut solutions = Vec::new();
+    let mut inferences = 0;
+    let mut status = None;
+    for line in output.lines() {
+        let mut fields = line.split('\t');
+        match fields.next() {
+            Some("answer") => {
+                let fields: Vec<&str> = fields.collect();
+                let bindings =
+                    fields.chunks(2).filter_map(|pair| Some((pair[0].to_string(), answer_term(pair.get(1)?)))).collect();
+                solutions.push(bindings);
+            }
+            Some("inferences") => inferences = fields.next().and_then(|n| n.parse().ok()).unwrap_or(0),
+            Some("status") => status = Some((fields.next().unwrap_or(""), fields.next().unwrap_or(""))),
+            _ => {}
+        }
+    }
+
+    match status {
+        Some(("ok", _)) => {}
+        Some(("time_limit", _)) => return Err(time_limit_error(config)),
+        Some(("inference_limit", _)) => {
+            let message = format!("Query exceeded {} inferences", config.inference_limit);
+            return Err(LimitError::new(Limit::Inferences, message).into());
+        }
+        Some((_, message)) => return Err(anyhow::anyhow!("SWI-Prolog error: {}", message)),
+        None => return Err(anyhow::anyhow!("SWI-Prolog exited without answering")),
+    }
+
+    let truncated = solutions.len() > prolog::MAX_SOLUTIONS;
+    solutions.truncate(prolog::MAX_SOLUTIONS);
+    let steps = vec![format!("% answered by SWI-Prolog ({}), which gives no derivation", config.executable.display())];
+    Ok(QueryOutcome::new(query, solutions, truncated, steps, inferences))
+}
+
+/// An answer printed by SWI-Prolog, read back as a term when our syntax covers it
+fn answer_term(text: &str) -> Term {
+    match prolog::parse_query(text) {
+        Ok(mut parsed) if parsed.goals.len() == 1 => parsed.goals.remove(0),
+        _ => Term::Atom(text.to_string()),
+    }
+}
+
+fn time_limit_error(config: &SwiplConfig) -> anyhow::Error {
+    LimitError::new(Limit::Time, format!("Query exceeded the {:.1}s time limit", config.time_limit.as_secs_f64())).into()
+}
@@ -0,0 +1,1098 @@
+// Symbolic algebra: simplification, expansion, factoring, differentiation and substitution
+use super::error::{Limit, LimitError};
+use super::expr::{is_named_constant, parse_expression, BinOp, Evaluator, Expr};
+use super::number::Number;
+use super::{split_keyword, strip_keyword};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::collections::BTreeMap;
+
+/// Largest exponent expanded symbolically, to bound output size
+const MAX_EXPAND_EXPONENT: u32 = 64;
+
+/// Largest constant term whose divisors are enumerated when searching for rational roots
+const MAX_ROOT_SEARCH: u64 = 1_000_000_000_000;
+
+/// Upper bound on simplifier passes over a single node
+const MAX_SIMPLIFY_PASSES: usize = 32;
+
+/// A parsed symbolic algebra request
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolicCommand {
+    Simplify(Expr),
+    Expand(Expr),
+    Factor(Expr),
+    Differentiate { expr: Expr, var: Option<String> },
+    Substitute { expr: Expr, bindings: Vec<(String, Expr)> },
+}
+
+/// Result expression together with the rewrite steps that produced it
+#[derive(Debug, Clone)]
+pub struct SymbolicResult {
+    pub expr: Expr,
+    /// Numeric value when the result has no free variables left
+    pub value: Option<Number>,
+    pub steps: Vec<String>,
+}
+
+/// Recognize a symbolic command; `None` means the query is not symbolic
+pub fn parse_command(query: &str) -> Option<anyhow::Result<SymbolicCommand>> {
+    let query = query.trim();
+
+    if let Some(rest) = strip_keyword(query, "simplify") {
+        return Some(parse_expression(rest).map(SymbolicCommand::Simplify));
+    }
+    if let Some(rest) = strip_keyword(query, "expand") {
+        return Some(parse_expression(rest).map(SymbolicCommand::Expand));
+    }
+    if let Some(rest) = strip_keyword(query, "factorize").or_else(|| strip_keyword(query, "factor")) {
+        return Some(parse_expression(rest).map(SymbolicCommand::Factor));
+    }
+
+    let derivative_body = strip_keyword(query, "derivative of")
+        .or_else(|| strip_keyword(query, "differentiate"))
+        .or_else(|| strip_keyword(query, "diff"));
+    if let Some(rest) = derivative_body {
+        return Some(parse_derivative(rest, None));
+    }
+    if let Some(rest) = query.strip_prefix("d/d") {
+        let var: String = rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        if !var.is_empty() {
+            return Some(parse_derivative(&rest[var.len()..], Some(var)));
+        }
+    }
+
+    let substitute_body = strip_keyword(query, "substitute").or_else(|| strip_keyword(query, "subs"));
+    if let Some(rest) = substitute_body {
+        return Some(parse_substitution(rest));
+    }
+
+    None
+}
+
+fn parse_derivative(body: &str, var: Option<String>) -> anyhow::Result<SymbolicCommand> {
+    let (expr_text, var) = match split_keyword(body, "with respect to").or_else(|| split_keyword(body, "wrt")) {
+        Some((expr_text, var_text)) => (expr_text, Some(var_text.trim().to_string())),
+        None => (body, var),
+    };
+    let expr = parse_expression(expr_text)?;
+    Ok(SymbolicCommand::Differentiate { expr, var })
+}
+
+/// Parse "x = 2, y = 3 in <expr>"
+fn parse_substitution(body: &str) -> anyhow::Result<SymbolicCommand> {
+    let (assignments, expr_text) = split_keyword(body, "into")
+        .or_else(|| split_keyword(body, "in"))
+        .ok_or_else(|| anyhow::anyhow!("Expected 'substitute <var> = <value> in <expression>'"))?;
+
+    let mut bindings = Vec::new();
+    for assignment in assignments.split(',') {
+        let (name, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected '<var> = <value>', found '{}'", assignment.trim()))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(anyhow::anyhow!("Invalid variable name '{}'", name));
+        }
+        bindings.push((name.to_string(), parse_expression(value)?));
+    }
+
+    let expr = parse_expression(expr_text)?;
+    Ok(SymbolicCommand::Substitute { expr, bindings })
+}
+
+/// Execute a symbolic command, recording each rewrite step
+pub fn execute(command: &SymbolicCommand) -> anyhow::Result<SymbolicResult> {
+    let mut steps = Vec::new();
+    let expr = match command {
+        SymbolicCommand::Simplify(expr) => {
+            steps.push(format!("input: {}", expr));
+            simplify(expr, &mut steps)?
+        }
+        SymbolicCommand::Expand(expr) => {
+            steps.push(format!("input: {}", expr));
+            expand(expr, &mut steps)?
+        }
+        SymbolicCommand::Factor(expr) => {
+            steps.push(format!("input: {}", expr));
+            factor(expr, &mut steps)?
+        }
+        SymbolicCommand::Differentiate { expr, var } => {
+            let var = match var {
+                Some(v) => v.clone(),
+                None => default_variable(expr)?,
+            };
+            steps.push(format!("input: d/d{} [{}]", var, expr));
+            differentiate(expr, &var, &mut steps)?
+        }
+        SymbolicCommand::Substitute { expr, bindings } => {
+            steps.push(format!("input: {}", expr));
+            let mut current = expr.clone();
+            for (name, value) in bindings {
+                let next = current.substitute(name, value);
+                steps.push(format!("substitute {} = {}: {}", name, value, next));
+                current = next;
+            }
+            simplify(&current, &mut steps)?
+        }
+    };
+
+    let value = if expr.free_variables().is_empty() {
+        Evaluator::new().evaluate(&expr).ok()
+    } else {
+        None
+    };
+    if let Some(value) = &value {
+        if !matches!(expr, Expr::Num(_)) {
+            steps.push(format!("evaluate: {} = {}", expr, value));
+        }
+    }
+    steps.push(format!("result: {}", expr));
+
+    Ok(SymbolicResult { expr, value, steps })
+}
+
+/// Pick the differentiation variable when the query does not name one
+fn default_variable(expr: &Expr) -> anyhow::Result<String> {
+    let vars = expr.free_variables();
+    match vars.len() {
+        0 => Ok("x".to_string()),
+        1 => Ok(vars.into_iter().next().unwrap_or_default()),
+        _ if vars.contains("x") => Ok("x".to_string()),
+        _ => Err(anyhow::anyhow!(
+            "Ambiguous variable: specify 'with respect to' one of {}",
+            vars.into_iter().collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// Simplify bottom-up with constant folding, identities and like-term collection
+pub fn simplify(expr: &Expr, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    let node = match expr {
+        Expr::Num(_) | Expr::Var(_) => return Ok(expr.clone()),
+        Expr::Neg(inner) => Expr::Neg(Box::new(simplify(inner, steps)?)),
+        Expr::Binary(op, lhs, rhs) => Expr::binary(*op, simplify(lhs, steps)?, simplify(rhs, steps)?),
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter().map(|a| simplify(a, steps)).collect::<anyhow::Result<_>>()?,
+        ),
+    };
+
+    let mut current = node;
+    for _ in 0..MAX_SIMPLIFY_PASSES {
+        match rewrite(&current)? {
+            Some((rule, next)) => {
+                steps.push(format!("{}: {} → {}", rule, current, next));
+                // Rewrites can expose new opportunities in the children
+                current = match next {
+                    Expr::Num(_) | Expr::Var(_) => next,
+                    _ => simplify_children(&next, steps)?,
+                };
+            }
+            None => break,
+        }
+    }
+    Ok(current)
+}
+
+fn simplify_children(expr: &Expr, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    Ok(match expr {
+        Expr::Neg(inner) => Expr::Neg(Box::new(simplify(inner, steps)?)),
+        Expr::Binary(op, lhs, rhs) => Expr::binary(*op, simplify(lhs, steps)?, simplify(rhs, steps)?),
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter().map(|a| simplify(a, steps)).collect::<anyhow::Result<_>>()?,
+        ),
+        _ => expr.clone(),
+    })
+}
+
+/// Apply the first matching rewrite rule at the root of `expr`
+fn rewrite(expr: &Expr) -> anyhow::Result<Option<(&'static str, Expr)>> {
+    let zero = BigRational::zero();
+    let one = BigRational::one();
+
+    match expr {
+        Expr::Neg(inner) => match inner.as_ref() {
+            Expr::Num(n) => return Ok(Some(("fold constant", Expr::Num(-n)))),
+            Expr::Neg(x) => return Ok(Some(("double negation", (**x).clone()))),
+            _ => {}
+        },
+        Expr::Binary(op, lhs, rhs) => {
+            if let (Expr::Num(a), Expr::Num(b)) = (lhs.as_ref(), rhs.as_ref()) {
+                if *op == BinOp::Div && b.is_zero() || *op == BinOp::Rem && b.is_zero() {
+                    return Err(anyhow::anyhow!("Division by zero in {}", expr));
+                }
+                let value = super::expr::apply_binary(*op, &Number::Exact(a.clone()), &Number::Exact(b.clone()))?;
+                if let Number::Exact(v) = value {
+                    return Ok(Some(("fold constants", Expr::Num(v))));
+                }
+            }
+
+            let is = |e: &Expr, v: &BigRational| matches!(e, Expr::Num(n) if n == v);
+            let identity = match op {
+                BinOp::Add if is(rhs, &zero) => Some(("additive identity", (**lhs).clone())),
+                BinOp::Add if is(lhs, &zero) => Some(("additive identity", (**rhs).clone())),
+                BinOp::Sub if is(rhs, &zero) => Some(("additive identity", (**lhs).clone())),
+                BinOp::Sub if is(lhs, &zero) => Some(("negate", Expr::Neg(rhs.clone()))),
+                BinOp::Mul if is(lhs, &zero) || is(rhs, &zero) => Some(("multiply by zero", Expr::integer(0))),
+                BinOp::Mul if is(rhs, &one) => Some(("multiplicative identity", (**lhs).clone())),
+                BinOp::Mul if is(lhs, &one) => Some(("multiplicative identity", (**rhs).clone())),
+                BinOp::Div if is(rhs, &one) => Some(("divide by one", (**lhs).clone())),
+                BinOp::Div if is(lhs, &zero) => Some(("zero numerator", Expr::integer(0))),
+                BinOp::Pow if is(rhs, &one) => Some(("power of one", (**lhs).clone())),
+                BinOp::Pow if is(rhs, &zero) => Some(("zero exponent", Expr::integer(1))),
+                BinOp::Pow if is(lhs, &one) => Some(("power of one", Expr::integer(1))),
+                BinOp::Pow => match lhs.as_ref() {
+                    // (a^m)^n = a^(m*n) for constant exponents
+                    Expr::Binary(BinOp::Pow, base, inner) => match (inner.as_ref(), rhs.as_ref()) {
+                        (Expr::Num(m), Expr::Num(n)) if n.is_integer() => Some((
+                            "power of a power",
+                            Expr::binary(BinOp::Pow, (**base).clone(), Expr::Num(m * n)),
+                        )),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            };
+            if identity.is_some() {
+                return Ok(identity);
+            }
+
+            match op {
+                BinOp::Add | BinOp::Sub => {
+                    let collected = collect_sum(expr);
+                    if collected != *expr {
+                        return Ok(Some(("combine like terms", collected)));
+                    }
+                }
+                BinOp::Mul | BinOp::Div => {
+                    let collected = collect_product(expr);
+                    if collected != *expr {
+                        return Ok(Some(("combine like factors", collected)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Expr::Call(name, args) => {
+            if let Some(value) = known_function_value(name, args) {
+                return Ok(Some(("known value", value)));
+            }
+        }
+        _ => {}
+    }
+    Ok(None)
+}
+
+/// Exact values of elementary functions at special points
+fn known_function_value(name: &str, args: &[Expr]) -> Option<Expr> {
+    let is_e = |e: &Expr| matches!(e, Expr::Var(v) if v == "e");
+    match (name, args) {
+        ("sin" | "tan" | "asin" | "atan" | "sinh" | "tanh", [Expr::Num(n)]) if n.is_zero() => Some(Expr::integer(0)),
+        ("cos" | "cosh" | "exp", [Expr::Num(n)]) if n.is_zero() => Some(Expr::integer(1)),
+        ("ln" | "log" | "log2" | "log10", [Expr::Num(n)]) if n.is_one() => Some(Expr::integer(0)),
+        ("ln", [arg]) if is_e(arg) => Some(Expr::integer(1)),
+        ("ln", [Expr::Call(f, inner)]) if f == "exp" && inner.len() == 1 => Some(inner[0].clone()),
+        ("exp", [Expr::Call(f, inner)]) if f == "ln" && inner.len() == 1 => Some(inner[0].clone()),
+        ("sqrt", [Expr::Num(n)]) if !n.is_negative() => match Number::Exact(n.clone()).pow(&Number::Exact(BigRational::new(1.into(), 2.into()))) {
+            Ok(Number::Exact(root)) => Some(Expr::Num(root)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Split a product into its rational coefficient and remaining factors with exponents
+fn product_parts(expr: &Expr) -> (BigRational, Vec<(Expr, BigRational)>) {
+    fn walk(e: &Expr, inverse: bool, coef: &mut BigRational, factors: &mut Vec<(Expr, BigRational)>) {
+        match e {
+            Expr::Binary(BinOp::Mul, a, b) => {
+                walk(a, inverse, coef, factors);
+                walk(b, inverse, coef, factors);
+            }
+            Expr::Binary(BinOp::Div, a, b) => {
+                walk(a, inverse, coef, factors);
+                walk(b, !inverse, coef, factors);
+            }
+            Expr::Num(n) if !n.is_zero() || !inverse => {
+                if inverse {
+                    *coef /= n;
+                } else {
+                    *coef *= n;
+                }
+            }
+            Expr::Neg(inner) => {
+                *coef = -coef.clone();
+                walk(inner, inverse, coef, factors);
+            }
+            Expr::Binary(BinOp::Pow, base, exp) => match exp.as_ref() {
+                Expr::Num(k) => {
+                    let k = if inverse { -k } else { k.clone() };
+                    factors.push(((**base).clone(), k));
+                }
+                _ => factors.push((e.clone(), if inverse { -BigRational::one() } else { BigRational::one() })),
+            },
+            _ => factors.push((e.clone(), if inverse { -BigRational::one() } else { BigRational::one() })),
+        }
+    }
+
+    let mut coef = BigRational::one();
+    let mut factors = Vec::new();
+    walk(expr, false, &mut coef, &mut factors);
+    (coef, factors)
+}
+
+/// Merge factors with identical bases, preserving first-occurrence order
+fn merge_factors(factors: Vec<(Expr, BigRational)>) -> Vec<(Expr, BigRational)> {
+    let mut merged: Vec<(String, Expr, BigRational)> = Vec::new();
+    for (base, exp) in factors {
+        let key = base.to_string();
+        match merged.iter_mut().find(|(k, _, _)| *k == key) {
+            Some(entry) => entry.2 += exp,
+            None => merged.push((key, base, exp)),
+        }
+    }
+    merged
+        .into_iter()
+        .filter(|(_, _, exp)| !exp.is_zero())
+        .map(|(_, base, exp)| (base, exp))
+        .collect()
+}
+
+fn power(base: Expr, exp: &BigRational) -> Expr {
+    if exp.is_one() {
+        base
+    } else {
+        Expr::binary(BinOp::Pow, base, Expr::Num(exp.clone()))
+    }
+}
+
+/// Rebuild `coef * factors` with negative powers and the coefficient denominator below the line
+fn build_product(coef: &BigRational, factors: &[(Expr, BigRational)]) -> Expr {
+    if coef.is_zero() {
+        return Expr::integer(0);
+    }
+
+    let multiply = |terms: Vec<Expr>| terms.into_iter().reduce(|acc, t| Expr::binary(BinOp::Mul, acc, t));
+    let numerator = multiply(
+        factors.iter().filter(|(_, e)| e.is_positive()).map(|(b, e)| power(b.clone(), e)).collect(),
+    );
+    let denominator = multiply(
+        factors.iter().filter(|(_, e)| e.is_negative()).map(|(b, e)| power(b.clone(), &-e)).collect(),
+    );
+
+    if denominator.is_none() && (coef.is_integer() || numerator.is_none()) {
+        return scaled(coef, numerator);
+    }
+
+    let top = scaled(&BigRational::from_integer(coef.numer().clone()), numerator);
+    let denom = BigRational::from_integer(coef.denom().clone());
+    let bottom = match denominator {
+        Some(d) if denom.is_one() => d,
+        Some(d) => Expr::binary(BinOp::Mul, Expr::Num(denom), d),
+        None => Expr::Num(denom),
+    };
+    Expr::binary(BinOp::Div, top, bottom)
+}
+
+/// `coef * expr`, omitting a unit coefficient
+fn scaled(coef: &BigRational, expr: Option<Expr>) -> Expr {
+    match expr {
+        None => Expr::Num(coef.clone()),
+        Some(e) if coef.is_one() => e,
+        Some(e) if (-coef).is_one() => Expr::Neg(Box::new(e)),
+        Some(e) => Expr::binary(BinOp::Mul, Expr::Num(coef.clone()), e),
+    }
+}
+
+fn collect_product(expr: &Expr) -> Expr {
+    let (coef, factors) = product_parts(expr);
+    build_product(&coef, &merge_factors(factors))
+}
+
+/// Split a term into its rational coefficient and symbolic remainder
+fn split_term(expr: &Expr) -> (BigRational, Option<Expr>) {
+    let (coef, factors) = product_parts(expr);
+    let factors = merge_factors(factors);
+    if factors.is_empty() {
+        (coef, None)
+    } else {
+        (coef, Some(build_product(&BigRational::one(), &factors)))
+    }
+}
+
+fn collect_sum(expr: &Expr) -> Expr {
+    fn walk(e: &Expr, negate: bool, terms: &mut Vec<(BigRational, Option<Expr>)>) {
+        match e {
+            Expr::Binary(BinOp::Add, a, b) => {
+                walk(a, negate, terms);
+                walk(b, negate, terms);
+            }
+            Expr::Binary(BinOp::Sub, a, b) => {
+                walk(a, negate, terms);
+                walk(b, !negate, terms);
+            }
+            Expr::Neg(inner) if matches!(inner.as_ref(), Expr::Binary(BinOp::Add | BinOp::Sub, _, _)) => {
+                walk(inner, !negate, terms);
+            }
+            _ => {
+                let (coef, rest) = split_term(e);
+                terms.push((if negate { -coef } else { coef }, rest));
+            }
+        }
+    }
+
+    let mut terms = Vec::new();
+    walk(expr, false, &mut terms);
+
+    let mut merged: Vec<(String, BigRational, Option<Expr>)> = Vec::new();
+    let mut constant = BigRational::zero();
+    for (coef, rest) in terms {
+        match rest {
+            None => constant += coef,
+            Some(rest) => {
+                let key = rest.to_string();
+                match merged.iter_mut().find(|(k, _, _)| *k == key) {
+                    Some(entry) => entry.1 += coef,
+                    None => merged.push((key, coef, Some(rest))),
+                }
+            }
+        }
+    }
+    if !constant.is_zero() {
+        merged.push((String::new(), constant, None));
+    }
+
+    build_sum(merged.into_iter().map(|(_, c, r)| (c, r)).filter(|(c, _)| !c.is_zero()).collect())
+}
+
+/// Build `c1*t1 + c2*t2 + ...`, writing negative coefficients as subtraction
+fn build_sum(terms: Vec<(BigRational, Option<Expr>)>) -> Expr {
+    let mut result: Option<Expr> = None;
+    for (coef, rest) in terms {
+        let magnitude = coef.abs();
+        let term = match rest {
+            Some(r) => build_product(&magnitude, &product_parts(&r).1),
+            None => Expr::Num(magnitude),
+        };
+        result = Some(match result {
+            None if coef.is_negative() => Expr::Neg(Box::new(term)),
+            None => term,
+            Some(acc) if coef.is_negative() => Expr::binary(BinOp::Sub, acc, term),
+            Some(acc) => Expr::binary(BinOp::Add, acc, term),
+        });
+    }
+    result.unwrap_or_else(|| Expr::integer(0))
+}
+
+/// Monomial as sorted (variable, exponent) pairs
+type Monomial = Vec<(String, u32)>;
+
+/// Sparse multivariate polynomial with rational coefficients
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Poly {
+    terms: BTreeMap<Monomial, BigRational>,
+}
+
+impl Poly {
+    fn constant(value: BigRational) -> Self {
+        let mut terms = BTreeMap::new();
+        if !value.is_zero() {
+            terms.insert(Vec::new(), value);
+        }
+        Poly { terms }
+    }
+
+    fn variable(name: &str) -> Self {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![(name.to_string(), 1)], BigRational::one());
+        Poly { terms }
+    }
+
+    fn add(&self, other: &Poly) -> Poly {
+        let mut terms = self.terms.clone();
+        for (mono, coef) in &other.terms {
+            let entry = terms.entry(mono.clone()).or_insert_with(BigRational::zero);
+            *entry += coef;
+            if entry.is_zero() {
+                terms.remove(mono);
+            }
+        }
+        Poly { terms }
+    }
+
+    fn scale(&self, factor: &BigRational) -> Poly {
+        if factor.is_zero() {
+            return Poly::default();
+        }
+        Poly { terms: self.terms.iter().map(|(m, c)| (m.clone(), c * factor)).collect() }
+    }
+
+    fn mul(&self, other: &Poly) -> Poly {
+        let mut result = Poly::default();
+        for (m1, c1) in &self.terms {
+            for (m2, c2) in &other.terms {
+                let mut term = Poly::default();
+                term.terms.insert(multiply_monomials(m1, m2), c1 * c2);
+                result = result.add(&term);
+            }
+        }
+        result
+    }
+
+    fn pow(&self, exp: u32) -> Poly {
+        let mut result = Poly::constant(BigRational::one());
+        for _ in 0..exp {
+            result = result.mul(self);
+        }
+        result
+    }
+
+    fn is_monomial(&self) -> bool {
+        self.terms.len() <= 1
+    }
+
+    pub fn variables(&self) -> Vec<String> {
+        let mut vars: Vec<String> = self.terms.keys().flatten().map(|(v, _)| v.clone()).collect();
+        vars.sort();
+        vars.dedup();
+        vars
+    }
+
+    fn total_degree(mono: &Monomial) -> u32 {
+        mono.iter().map(|(_, e)| e).sum()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Constant and per-variable coefficients, or `None` if any term is nonlinear
+    pub fn linear_coefficients(&self) -> Option<(BigRational, BTreeMap<String, BigRational>)> {
+        let mut constant = BigRational::zero();
+        let mut coeffs = BTreeMap::new();
+        for (mono, coef) in &self.terms {
+            match mono.as_slice() {
+                [] => constant = coef.clone(),
+                [(v, 1)] => {
+                    coeffs.insert(v.clone(), coef.clone());
+                }
+                _ => return None,
+            }
+        }
+        Some((constant, coeffs))
+    }
+
+    /// Coefficient polynomials of each power of `var`, lowest power first
+    pub fn collect_powers(&self, var: &str) -> Vec<Poly> {
+        let mut powers = vec![Poly::default(); self.degree_in(var) as usize + 1];
+        for (mono, coef) in &self.terms {
+            let exp = mono.iter().find(|(v, _)| v == var).map(|(_, e)| *e).unwrap_or(0);
+            let rest: Monomial = mono.iter().filter(|(v, _)| v != var).cloned().collect();
+            powers[exp as usize].terms.insert(rest, coef.clone());
+        }
+        powers
+    }
+
+    /// Degree in `var`, or 0 for the zero polynomial
+    pub fn degree_in(&self, var: &str) -> u32 {
+        self.terms
+            .keys()
+            .map(|m| m.iter().find(|(v, _)| v == var).map(|(_, e)| *e).unwrap_or(0))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Dense coefficients of a univariate polynomial, lowest degree first
+    pub fn coefficients(&self, var: &str) -> Option<Vec<BigRational>> {
+        let degree = self.degree_in(var) as usize;
+        let mut coeffs = vec![BigRational::zero(); degree + 1];
+        for (mono, coef) in &self.terms {
+            match mono.as_slice() {
+                [] => coeffs[0] = coef.clone(),
+                [(v, e)] if v == var => coeffs[*e as usize] = coef.clone(),
+                _ => return None,
+            }
+        }
+        Some(coeffs)
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        let mut ordered: Vec<(&Monomial, &BigRational)> = self.terms.iter().collect();
+        // Graded lexicographic order: highest total degree first, then by each variable's power
+        let vars = self.variables();
+        let exponent = |m: &Monomial, v: &str| m.iter().find(|(mv, _)| mv == v).map(|(_, e)| *e).unwrap_or(0);
+        ordered.sort_by(|(a, _), (b, _)| {
+            Poly::total_degree(b).cmp(&Poly::total_degree(a)).then_with(|| {
+                vars.iter()
+                    .map(|v| exponent(b, v).cmp(&exponent(a, v)))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+        build_sum(
+            ordered
+                .into_iter()
+                .map(|(mono, coef)| (coef.clone(), monomial_expr(mono)))
+                .collect(),
+        )
+    }
+}
+
+fn multiply_monomials(a: &Monomial, b: &Monomial) -> Monomial {
+    let mut powers: BTreeMap<String, u32> = BTreeMap::new();
+    for (v, e) in a.iter().chain(b.iter()) {
+        *powers.entry(v.clone()).or_insert(0) += e;
+    }
+    powers.into_iter().collect()
+}
+
+fn monomial_expr(mono: &Monomial) -> Option<Expr> {
+    mono.iter()
+        .map(|(v, e)| power(Expr::Var(v.clone()), &BigRational::from_integer((*e).into())))
+        .reduce(|acc, f| Expr::binary(BinOp::Mul, acc, f))
+}
+
+/// Convert an expression to a polynomial, or `None` if it is not polynomial.
+/// Distribution and power expansions are recorded in `steps`.
+pub fn to_poly(expr: &Expr, steps: &mut Vec<String>) -> Option<Poly> {
+    match expr {
+        Expr::Num(n) => Some(Poly::constant(n.clone())),
+        Expr::Var(v) if !is_named_constant(v) => Some(Poly::variable(v)),
+        Expr::Var(_) => None,
+        Expr::Neg(inner) => Some(to_poly(inner, steps)?.scale(&-BigRational::one())),
+        Expr::Binary(op, lhs, rhs) => match op {
+            BinOp::Add => Some(to_poly(lhs, steps)?.add(&to_poly(rhs, steps)?)),
+            BinOp::Sub => Some(to_poly(lhs, steps)?.add(&to_poly(rhs, steps)?.scale(&-BigRational::one()))),
+            BinOp::Mul => {
+                let (a, b) = (to_poly(lhs, steps)?, to_poly(rhs, steps)?);
+                let product = a.mul(&b);
+                if !a.is_monomial() || !b.is_monomial() {
+                    steps.push(format!("distribute: ({}) * ({}) → {}", a.to_expr(), b.to_expr(), product.to_expr()));
+                }
+                Some(product)
+            }
+            BinOp::Div => match rhs.as_ref() {
+                Expr::Num(d) if !d.is_zero() => Some(to_poly(lhs, steps)?.scale(&(BigRational::one() / d))),
+                _ => None,
+            },
+            BinOp::Pow => {
+                let exp = match rhs.as_ref() {
+                    Expr::Num(k) if k.is_integer() && !k.is_negative() => k.to_integer().to_u32()?,
+                    _ => return None,
+                };
+                if exp > MAX_EXPAND_EXPONENT {
+                    return None;
+                }
+                let base = to_poly(lhs, steps)?;
+                let result = base.pow(exp);
+                if !base.is_monomial() && exp > 1 {
+                    let rule = if base.terms.len() == 2 { "binomial power" } else { "multinomial power" };
+                    steps.push(format!("{}: ({})^{} → {}", rule, base.to_expr(), exp, result.to_expr()));
+                }
+                Some(result)
+            }
+            BinOp::Rem => None,
+        },
+        Expr::Call(..) => None,
+    }
+}
+
+/// Expand products and integer powers of sums
+pub fn expand(expr: &Expr, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    if let Some(poly) = to_poly(expr, steps) {
+        return Ok(poly.to_expr());
+    }
+
+    // Non-polynomial: expand polynomial sub-expressions in place
+    let expanded = match expr {
+        // A sum to a power past the cap would be left as written, which reads as expanded
+        Expr::Binary(BinOp::Pow, base, exp) if exceeds_expand_cap(base, exp) => {
+            return Err(LimitError::new(
+                Limit::Size,
+                format!("({})^{} exceeds the limit of {} for expanded exponents", base, exp, MAX_EXPAND_EXPONENT),
+            )
+            .into());
+        }
+        Expr::Neg(inner) => Expr::Neg(Box::new(expand(inner, steps)?)),
+        Expr::Binary(op, lhs, rhs) => Expr::binary(*op, expand(lhs, steps)?, expand(rhs, steps)?),
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter().map(|a| expand(a, steps)).collect::<anyhow::Result<_>>()?,
+        ),
+        _ => expr.clone(),
+    };
+    simplify(&expanded, steps)
+}
+
+/// True for a polynomial with several terms raised to an integer power above the cap
+fn exceeds_expand_cap(base: &Expr, exp: &Expr) -> bool {
+    let Expr::Num(k) = exp else { return false };
+    k.is_integer()
+        && k.to_integer() > BigInt::from(MAX_EXPAND_EXPONENT)
+        && to_poly(base, &mut Vec::new()).is_some_and(|poly| !poly.is_monomial())
+}
+
+/// Factor a polynomial over the rationals: content, monomial factor and rational roots
+pub fn factor(expr: &Expr, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    let mut scratch = Vec::new();
+    let poly = to_poly(expr, &mut scratch)
+        .ok_or_else(|| anyhow::anyhow!("Only polynomials can be factored: {}", expr))?;
+
+    let vars = poly.variables();
+    let var = match vars.as_slice() {
+        [] => return Ok(poly.to_expr()),
+        [v] => v.clone(),
+        _ => return factor_common_monomial(&poly, steps),
+    };
+    let coeffs = poly.coefficients(&var).unwrap_or_default();
+
+    // Content: rational c with p = c * q, q primitive with integer coefficients
+    let (content, mut prim) = primitive_part(&coeffs);
+    if !content.is_one() {
+        steps.push(format!("extract content {}: {} = {} * ({})", content, poly.to_expr(), content, dense_expr(&prim, &var)));
+    }
+
+    let mut factors: Vec<(Expr, u32)> = Vec::new();
+
+    let shift = prim.iter().take_while(|c| c.is_zero()).count();
+    if shift > 0 {
+        prim.drain(..shift);
+        steps.push(format!("extract {}^{}", var, shift));
+        factors.push((Expr::Var(var.clone()), shift as u32));
+    }
+
+    let mut searched = true;
+    if prim.len() > 2 {
+        match rational_root_candidates(&prim) {
+            Some(candidates) => {
+                for root in candidates {
+                    let mut multiplicity = 0;
+                    while prim.len() > 1 && eval_dense(&prim, &root).is_zero() {
+                        prim = divide_by_root(&prim, &root);
+                        multiplicity += 1;
+                    }
+                    if multiplicity > 0 {
+                        let linear = linear_factor(&root, &var);
+                        steps.push(format!("rational root {} = {} (multiplicity {}) → factor ({})", var, root, multiplicity, linear));
+                        factors.push((linear, multiplicity));
+                    }
+                    if prim.len() <= 2 {
+                        break;
+                    }
+                }
+            }
+            None => searched = false,
+        }
+    }
+
+    // The remaining cofactor is primitive; normalize its sign into the content
+    let (cofactor_content, cofactor) = primitive_part(&prim);
+    let content = content * cofactor_content;
+    if cofactor.len() > 1 {
+        let rest = dense_expr(&cofactor, &var);
+        match cofactor.len() - 1 {
+            1 => {}
+            2 => steps.push(format!("{} is irreducible over the rationals", rest)),
+            _ if searched => steps.push(format!("{} has no rational roots", rest)),
+            _ => steps.push(format!("{}: constant term too large for rational root search", rest)),
+        }
+        factors.push((rest, 1));
+    }
+
+    Ok(build_factored(&content, factors))
+}
+
+fn build_factored(content: &BigRational, factors: Vec<(Expr, u32)>) -> Expr {
+    let mut result = factors
+        .into_iter()
+        .map(|(f, m)| power(f, &BigRational::from_integer(m.into())))
+        .reduce(|acc, f| Expr::binary(BinOp::Mul, acc, f));
+
+    let magnitude = content.abs();
+    if !magnitude.is_one() {
+        result = Some(match result {
+            Some(r) => Expr::binary(BinOp::Mul, Expr::Num(magnitude), r),
+            None => Expr::Num(magnitude),
+        });
+    }
+    let result = result.unwrap_or_else(|| Expr::integer(1));
+    if content.is_negative() {
+        Expr::Neg(Box::new(result))
+    } else {
+        result
+    }
+}
+
+/// Pull out the greatest common monomial and content of a multivariate polynomial
+fn factor_common_monomial(poly: &Poly, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    let mut common: Option<BTreeMap<String, u32>> = None;
+    for mono in poly.terms.keys() {
+        let powers: BTreeMap<String, u32> = mono.iter().cloned().collect();
+        common = Some(match common {
+            None => powers,
+            Some(c) => c
+                .into_iter()
+                .filter_map(|(v, e)| powers.get(&v).map(|e2| (v, e.min(*e2))))
+                .collect(),
+        });
+    }
+    let common: Monomial = common.unwrap_or_default().into_iter().filter(|(_, e)| *e > 0).collect();
+
+    let coeffs: Vec<BigRational> = poly.terms.values().cloned().collect();
+    let (content, _) = primitive_part(&coeffs);
+
+    let mut quotient = Poly::default();
+    for (mono, coef) in &poly.terms {
+        let reduced: Monomial = mono
+            .iter()
+            .filter_map(|(v, e)| {
+                let c = common.iter().find(|(cv, _)| cv == v).map(|(_, ce)| *ce).unwrap_or(0);
+                (e - c > 0).then(|| (v.clone(), e - c))
+            })
+            .collect();
+        quotient.terms.insert(reduced, coef / &content);
+    }
+
+    let mut factors = Vec::new();
+    if let Some(m) = monomial_expr(&common) {
+        factors.push((m, 1));
+    }
+    factors.push((quotient.to_expr(), 1));
+    let result = build_factored(&content, factors);
+    steps.push(format!("extract common factor: {} = {}", poly.to_expr(), result));
+    steps.push("multivariate factoring beyond common factors is not supported".to_string());
+    Ok(result)
+}
+
+/// Split dense coefficients into a rational content and a primitive integer polynomial
+/// whose leading coefficient is positive
+fn primitive_part(coeffs: &[BigRational]) -> (BigRational, Vec<BigRational>) {
+    let nonzero: Vec<&BigRational> = coeffs.iter().filter(|c| !c.is_zero()).collect();
+    if nonzero.is_empty() {
+        return (BigRational::one(), coeffs.to_vec());
+    }
+
+    let lcm = nonzero.iter().fold(BigInt::one(), |acc, c| acc.lcm(c.denom()));
+    let gcd = nonzero
+        .iter()
+        .map(|c| (c.numer() * (&lcm / c.denom())).abs())
+        .fold(BigInt::zero(), |acc, n| acc.gcd(&n));
+
+    let mut content = BigRational::new(gcd, lcm);
+    if coeffs.iter().rev().find(|c| !c.is_zero()).is_some_and(|c| c.is_negative()) {
+        content = -content;
+    }
+    let prim = coeffs.iter().map(|c| c / &content).collect();
+    (content, prim)
+}
+
+fn eval_dense(coeffs: &[BigRational], x: &BigRational) -> BigRational {
+    coeffs.iter().rev().fold(BigRational::zero(), |acc, c| acc * x + c)
+}
+
+/// Synthetic division by (x - root); the caller guarantees root is a root
+fn divide_by_root(coeffs: &[BigRational], root: &BigRational) -> Vec<BigRational> {
+    let degree = coeffs.len() - 1;
+    let mut quotient = vec![BigRational::zero(); degree];
+    let mut carry = BigRational::zero();
+    for i in (1..=degree).rev() {
+        carry = &coeffs[i] + carry * root;
+        quotient[i - 1] = carry.clone();
+    }
+    quotient
+}
+
+/// Rational roots of a polynomial (constant term first), each once, and the primitive
+/// cofactor left after dividing them out; candidates are only searched while the
+/// coefficients are small enough to enumerate
+pub fn split_rational_roots(coeffs: &[BigRational]) -> (Vec<BigRational>, Vec<BigRational>) {
+    let (_, mut rest) = primitive_part(coeffs);
+    let mut roots = Vec::new();
+    let zeros = rest.iter().take_while(|c| c.is_zero()).count();
+    if zeros > 0 && zeros < rest.len() {
+        rest.drain(..zeros);
+        roots.push(BigRational::zero());
+    }
+    if rest.len() > 1 {
+        for root in rational_root_candidates(&rest).unwrap_or_default() {
+            if eval_dense(&rest, &root).is_zero() {
+                while rest.len() > 1 && eval_dense(&rest, &root).is_zero() {
+                    rest = divide_by_root(&rest, &root);
+                }
+                roots.push(root);
+            }
+        }
+    }
+    (roots, rest)
+}
+
+/// Candidates ±p/q with p | a0 and q | an, or `None` if a0 is too large to enumerate
+fn rational_root_candidates(coeffs: &[BigRational]) -> Option<Vec<BigRational>> {
+    let a0 = coeffs.first()?.to_integer().abs().to_u64()?;
+    let an = coeffs.last()?.to_integer().abs().to_u64()?;
+    if a0 == 0 || a0 > MAX_ROOT_SEARCH || an > MAX_ROOT_SEARCH {
+        return None;
+    }
+
+    let mut candidates = Vec::new();
+    for p in divisors(a0) {
+        for q in divisors(an) {
+            let r = BigRational::new(p.into(), q.into());
+            candidates.push(r.clone());
+            candidates.push(-r);
+        }
+    }
+    candidates.sort_by(|a, b| a.abs().cmp(&b.abs()).then_with(|| b.cmp(a)));
+    candidates.dedup();
+    Some(candidates)
+}
+
+fn divisors(n: u64) -> Vec<u64> {
+    let mut small = Vec::new();
+    let mut large = Vec::new();
+    let mut d = 1;
+    while d * d <= n {
+        if n.is_multiple_of(d) {
+            small.push(d);
+            if d != n / d {
+                large.push(n / d);
+            }
+        }
+        d += 1;
+    }
+    small.extend(large.into_iter().rev());
+    small
+}
+
+/// Integer linear factor q*x - p for the root p/q
+fn linear_factor(root: &BigRational, var: &str) -> Expr {
+    let coeffs = vec![BigRational::from_integer(-root.numer()), BigRational::from_integer(root.denom().clone())];
+    dense_expr(&coeffs, var)
+}
+
+fn dense_expr(coeffs: &[BigRational], var: &str) -> Expr {
+    let mut poly = Poly::default();
+    for (degree, coef) in coeffs.iter().enumerate() {
+        if !coef.is_zero() {
+            let mono = if degree == 0 { Vec::new() } else { vec![(var.to_string(), degree as u32)] };
+            poly.terms.insert(mono, coef.clone());
+        }
+    }
+    poly.to_expr()
+}
+
+/// Differentiate with respect to `var`, recording each rule application
+pub fn differentiate(expr: &Expr, var: &str, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    let raw = derive(expr, var, steps)?;
+    let mut scratch = Vec::new();
+    let simplified = simplify(&raw, &mut scratch)?;
+    if simplified != raw {
+        steps.push(format!("simplify: {} → {}", raw, simplified));
+    }
+    Ok(simplified)
+}
+
+fn derive(expr: &Expr, var: &str, steps: &mut Vec<String>) -> anyhow::Result<Expr> {
+    if !expr.contains_var(var) {
+        return Ok(Expr::integer(0));
+    }
+
+    // Omit the trailing "* 1" of chain-rule products with the variable itself
+    let mul = |a: Expr, b: Expr| match b {
+        Expr::Num(ref n) if n.is_one() => a,
+        _ => Expr::binary(BinOp::Mul, a, b),
+    };
+    let div = |a: Expr, b: Expr| Expr::binary(BinOp::Div, a, b);
+    let pow = |a: Expr, b: Expr| Expr::binary(BinOp::Pow, a, b);
+
+    let (rule, result) = match expr {
+        Expr::Var(_) => return Ok(Expr::integer(1)),
+        Expr::Num(_) => return Ok(Expr::integer(0)),
+        Expr::Neg(inner) => ("negation", Expr::Neg(Box::new(derive(inner, var, steps)?))),
+        Expr::Binary(op, u, v) => {
+            let (u, v) = (u.as_ref().clone(), v.as_ref().clone());
+            match op {
+                BinOp::Add => ("sum rule", Expr::binary(BinOp::Add, derive(&u, var, steps)?, derive(&v, var, steps)?)),
+                BinOp::Sub => ("difference rule", Expr::binary(BinOp::Sub, derive(&u, var, steps)?, derive(&v, var, steps)?)),
+                BinOp::Mul if !u.contains_var(var) => ("constant multiple rule", mul(u.clone(), derive(&v, var, steps)?)),
+                BinOp::Mul if !v.contains_var(var) => ("constant multiple rule", mul(v.clone(), derive(&u, var, steps)?)),
+                BinOp::Div if !v.contains_var(var) => ("constant multiple rule", div(derive(&u, var, steps)?, v.clone())),
+                BinOp::Mul => {
+                    let (du, dv) = (derive(&u, var, steps)?, derive(&v, var, steps)?);
+                    ("product rule", Expr::binary(BinOp::Add, mul(du, v.clone()), mul(u.clone(), dv)))
+                }
+                BinOp::Div => {
+                    let (du, dv) = (derive(&u, var, steps)?, derive(&v, var, steps)?);
+                    let numerator = Expr::binary(BinOp::Sub, mul(du, v.clone()), mul(u.clone(), dv));
+                    ("quotient rule", div(numerator, pow(v.clone(), Expr::integer(2))))
+                }
+                BinOp::Pow if !v.contains_var(var) => {
+                    let du = derive(&u, var, steps)?;
+                    let reduced = match &v {
+                        Expr::Num(k) => Expr::Num(k - BigRational::one()),
+                        _ => Expr::binary(BinOp::Sub, v.clone(), Expr::integer(1)),
+                    };
+                    ("power rule", mul(mul(v.clone(), pow(u.clone(), reduced)), du))
+                }
+                BinOp::Pow if !u.contains_var(var) => {
+                    let dv = derive(&v, var, steps)?;
+                    ("exponential rule", mul(mul(expr.clone(), Expr::call("ln", vec![u.clone()])), dv))
+                }
+                BinOp::Pow => {
+                    // d(u^v) = u^v * (v' ln u + v u'/u)
+                    let (du, dv) = (derive(&u, var, steps)?, derive(&v, var, steps)?);
+                    let inner = Expr::binary(
+                        BinOp::Add,
+                        mul(dv, Expr::call("ln", vec![u.clone()])),
+                        div(mul(v.clone(), du), u.clone()),
+                    );
+                    ("generalized power rule", mul(expr.clone(), inner))
+                }
+                BinOp::Rem => return Err(anyhow::anyhow!("Cannot differentiate the remainder operator")),
+            }
+        }
+        Expr::Call(name, args) => {
+            let [u] = args.as_slice() else {
+                return Err(anyhow::anyhow!("Cannot differentiate {}() with {} arguments", name, args.len()));
+            };
+            let du = derive(u, var, steps)?;
+            let outer = match name.as_str() {
+                "sin" => Expr::call("cos", vec![u.clone()]),
+                "cos" => Expr::Neg(Box::new(Expr::call("sin", vec![u.clone()]))),
+                "tan" => div(Expr::integer(1), pow(Expr::call("cos", vec![u.clone()]), Expr::integer(2))),
+                "exp" => expr.clone(),
+                "ln" => div(Expr::integer(1), u.clone()),
+                "log" | "log10" => div(Expr::integer(1), mul(u.clone(), Expr::call("ln", vec![Expr::integer(10)]))),
+                "log2" => div(Expr::integer(1), mul(u.clone(), Expr::call("ln", vec![Expr::integer(2)]))),
+                "sqrt" => div(Expr::integer(1), mul(Expr::integer(2), expr.clone())),
+                "asin" => div(Expr::integer(1), Expr::call("sqrt", vec![Expr::binary(BinOp::Sub, Expr::integer(1), pow(u.clone(), Expr::integer(2)))])),
+                "acos" => Expr::Neg(Box::new(div(Expr::integer(1), Expr::call("sqrt", vec![Expr::binary(BinOp::Sub, Expr::integer(1), pow(u.clone(), Expr::integer(2)))])))),
+                "atan" => div(Expr::integer(1), Expr::binary(BinOp::Add, Expr::integer(1), pow(u.clone(), Expr::integer(2)))),
+                "sinh" => Expr::call("cosh", vec![u.clone()]),
+                "cosh" => Expr::call("sinh", vec![u.clone()]),
+                "tanh" => div(Expr::integer(1), pow(Expr::call("cosh", vec![u.clone()]), Expr::integer(2))),
+                _ => return Err(anyhow::anyhow!("No differentiation rule for {}()", name)),
+            };
+            let rule = if matches!(u, Expr::Var(v) if v == var) { standard_rule_name(name) } else { "chain rule" };
+            (rule, mul(outer, du))
+        }
+    };
+
+    steps.push(format!("{}: d/d{} [{}] = {}", rule, var, expr, result));
+    Ok(result)
+}
+
+fn standard_rule_name(function: &str) -> &'static str {
+    match function {
+        "sin" | "cos" | "tan" | "asin" | "acos" | "atan" => "trigonometric rule",
+        "sinh" | "cosh" | "tanh" => "hyperbolic rule",
+        "exp" => "exponential rule",
+        "ln" | "log" | "log10" | "log2" => "logarithm rule",
+        _ => "root rule",
+    }
+}
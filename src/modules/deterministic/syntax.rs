@@ -0,0 +1,203 @@
+// Front end for every deterministic query: one lexer pass with positions and matched
+// brackets, so input outside the grammar is rejected where it occurs instead of dropped,
+// then one read of the query into its typed form. The domains receive that form, never
+// the query text
+use super::context::{self, EvalContext};
+use super::expr::{self, Expr};
+use super::lexer::{relocate, scan, SyntaxError, Token};
+use super::{
+    calendar, constraints, datalog, linalg, linarith, natural, numerical, programmer, prolog, propositional, sandbox,
+    solver, stats, symbolic, text, units,
+};
+
+/// What the front end made of a query: an expression, a logic goal, or a domain command
+#[derive(Debug, Clone, PartialEq)]
+pub enum Form {
+    /// A snippet for the sandbox; Rhai's own parser checks the code
+    Code(sandbox::Snippet),
+    /// A question about quoted text
+    Text(text::TextQuery),
+    /// "let r = 4", "f(x) = x^2", "clear r"
+    Session(context::ContextCommand),
+    /// "consult kb.pl", "reload", "list predicates"
+    Knowledge(prolog::KbCommand),
+    /// "datalog ancestor(zeus, X)" or "why ancestor(zeus, ares)"
+    Datalog(datalog::DatalogQuery),
+    /// Horn-clause goals for the resolver
+    Goal(prolog::Query),
+    Constraints(constraints::FdProblem),
+    /// A linear arithmetic claim such as "for all reals x: x + 1 > x"
+    Claim(linarith::ArithClaim),
+    Calendar(calendar::CalendarQuery),
+    Propositional(propositional::LogicQuery),
+    Programmer(programmer::ProgrammerQuery),
+    Numerical(numerical::NumericQuery),
+    Stats(stats::StatsQuery),
+    LinearAlgebra(linalg::LinExpr),
+    Symbolic(symbolic::SymbolicCommand),
+    Solve(solver::SolveRequest),
+    /// Arithmetic written in words, with the expression it was read as
+    Words { words: String, expr: Expr },
+    Units(units::UnitQuery),
+    /// An expression for the exact evaluator
+    Math(Expr),
+    /// Arithmetic outside the exact grammar, left to float evaluation
+    Float(String),
+    /// Nothing a domain reads
+    Unrecognized,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// The query as it was read: trimmed, without a closing question mark
+    pub source: String,
+    pub form: Form,
+}
+
+/// What reading a query may depend on: session bindings decide whether names are
+/// variables, and the knowledge base whether a term is a goal
+pub struct Scope<'a> {
+    pub context: &'a EvalContext,
+    pub knowledge: &'a prolog::KnowledgeBase,
+}
+
+/// Lex, check brackets and read a query into its typed form; characters outside the
+/// grammar, unclosed quotes and unbalanced brackets are errors with the column where
+/// they occur, and so are malformed commands
+pub fn parse(input: &str, scope: &Scope) -> anyhow::Result<Query> {
+    // Code keeps its punctuation; the sandbox, not this grammar, contains it
+    if let Some(snippet) = sandbox::parse_command(input) {
+        return Ok(Query { source: input.trim().to_string(), form: Form::Code(snippet?) });
+    }
+
+    let trimmed = input.trim();
+    let source = trimmed.strip_suffix('?').unwrap_or(trimmed).trim_end();
+    let tokens = scan(source)?;
+    if tokens.is_empty() {
+        let found = if trimmed.is_empty() { "end of input".to_string() } else { format!("'{}'", trimmed) };
+        return Err(SyntaxError::new(1, "a query", found).into());
+    }
+
+    let form = match text::parse_command(source) {
+        Some(query) => Form::Text(query?),
+        None => {
+            check_brackets(&tokens)?;
+            read(source, scope)?
+        }
+    };
+    Ok(Query { source: source.to_string(), form })
+}
+
+/// The typed form of checked math, logic or command syntax, trying the commands with
+/// leading keywords before bare expressions and goals
+fn read(source: &str, scope: &Scope) -> anyhow::Result<Form> {
+    let expression = super::extract_math_expression(source);
+    let form = if let Some(command) = context::parse_command(source) {
+        Form::Session(command?)
+    } else if let Some(command) = prolog::parse_kb_command(source) {
+        Form::Knowledge(command)
+    } else if let Some(query) = datalog::parse_command(source) {
+        Form::Datalog(query?)
+    } else if let Some(query) = prolog::parse_command(source, scope.knowledge) {
+        Form::Goal(query?)
+    } else if let Some(problem) = constraints::parse_command(source) {
+        Form::Constraints(problem?)
+    } else if let Some(claim) = linarith::parse_command(source) {
+        Form::Claim(claim?)
+    } else if let Some(query) = calendar::parse_command(source) {
+        Form::Calendar(query?)
+    } else if let Some(query) = propositional::parse_command(source) {
+        Form::Propositional(query?)
+    } else if let Some(query) = programmer::parse_command(source) {
+        Form::Programmer(query?)
+    } else if let Some(query) = numerical::parse_command(source) {
+        Form::Numerical(query?)
+    } else if let Some(query) = stats::parse_command(source) {
+        Form::Stats(query?)
+    } else if let Some(expr) = linalg::parse_command(source) {
+        Form::LinearAlgebra(expr?)
+    } else if let Some(command) = symbolic::parse_command(source) {
+        Form::Symbolic(command?)
+    } else if let Some(request) = solver::parse_command(source) {
+        Form::Solve(request?)
+    } else if let Some(expr) = natural_expression(source, scope.context) {
+        Form::Words { words: source.to_string(), expr }
+    } else if let Some(expr) = super::uses_bindings(source, scope.context) {
+        Form::Math(expr)
+    } else if let Some(query) = units::parse_command(expression) {
+        Form::Units(query.map_err(|e| relocate(e, source, expression))?)
+    } else if looks_like_math(source) {
+        match expr::parse_expression(expression) {
+            Ok(expr) => Form::Math(expr),
+            Err(e) => {
+                // Syntax outside the exact grammar still gets a float answer
+                log::debug!("Exact parser rejected expression, using float evaluation: {}", e);
+                Form::Float(expression.to_string())
+            }
+        }
+    } else if prolog::looks_like_goal(source) {
+        Form::Goal(prolog::parse_query(source)?)
+    } else {
+        Form::Unrecognized
+    };
+    Ok(form)
+}
+
+/// Heuristic to detect mathematical expressions
+fn looks_like_math(s: &str) -> bool {
+    let math_chars = ['+', '-', '*', '/', '^', '%'];
+    s.chars().any(|c| math_chars.contains(&c))
+        || s.trim().chars().all(|c| c.is_ascii_digit() || c.is_whitespace() || "().".contains(c))
+}
+
+/// Expression for a query phrased in words, if it evaluates with the current bindings,
+/// e.g. "twelve times seven plus a half" → 12 * 7 + 1 / 2
+fn natural_expression(query: &str, context: &EvalContext) -> Option<Expr> {
+    let parsed = expr::parse_expression(&natural::normalize(query)?).ok()?;
+    parsed.free_variables().iter().all(|v| context.variable(v).is_some()).then_some(parsed)
+}
+
+/// Match '(' ')' and '[' ']' pairs
+fn check_brackets(tokens: &[Token]) -> Result<(), SyntaxError> {
+    let end = tokens.last().map_or(1, |t| t.end);
+    // Open brackets, innermost last
+    let mut stack: Vec<&Token> = Vec::new();
+
+    for token in tokens {
+        if token.is_symbol("(") || token.is_symbol("[") {
+            stack.push(token);
+            continue;
+        }
+        let Some(wanted) = [(")", "("), ("]", "[")].iter().find(|(close, _)| token.is_symbol(close)).map(|(_, open)| *open) else {
+            continue;
+        };
+        let found = format!("'{}'", if wanted == "(" { ")" } else { "]" });
+        match stack.pop() {
+            Some(open) if open.is_symbol(wanted) => {}
+            Some(open) => {
+                let found = format!("{}, with the {} from column {} still open", found, symbol(open), open.column);
+                return Err(SyntaxError::new(token.column, closer(open), found));
+            }
+            None => {
+                return Err(SyntaxError::new(token.column, "an operand or operator", format!("unmatched {}", found)));
+            }
+        }
+    }
+
+    match stack.pop() {
+        Some(open) => Err(SyntaxError::new(
+            end,
+            closer(open),
+            format!("end of input, with the {} from column {} still open", symbol(open), open.column),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn symbol(open: &Token) -> &'static str {
+    if open.is_symbol("(") { "'('" } else { "'['" }
+}
+
+fn closer(open: &Token) -> &'static str {
+    if open.is_symbol("(") { "')'" } else { "']'" }
+}
@@ -0,0 +1,555 @@
+// Text facts: counts, positions, reversal, case transforms and regex matches over quoted
+// input, measured in grapheme clusters so "é" and emoji count as one character
+use super::lexer::{tokenize, TokenKind};
+use super::{split_keyword, strip_keyword, strip_suffix_phrase};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Pieces of text that can be counted or indexed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Characters,
+    Letters,
+    Words,
+    Vowels,
+    Consonants,
+    Digits,
+    Spaces,
+    Lines,
+    Sentences,
+    Bytes,
+}
+
+const UNITS: &[(&str, &str, Unit)] = &[
+    ("character", "characters", Unit::Characters),
+    ("char", "chars", Unit::Characters),
+    ("grapheme", "graphemes", Unit::Characters),
+    ("letter", "letters", Unit::Letters),
+    ("word", "words", Unit::Words),
+    ("vowel", "vowels", Unit::Vowels),
+    ("consonant", "consonants", Unit::Consonants),
+    ("digit", "digits", Unit::Digits),
+    ("space", "spaces", Unit::Spaces),
+    ("line", "lines", Unit::Lines),
+    ("sentence", "sentences", Unit::Sentences),
+    ("byte", "bytes", Unit::Bytes),
+];
+
+impl Unit {
+    fn name(self, count: usize) -> &'static str {
+        let (one, many, _) = UNITS.iter().find(|(_, _, unit)| *unit == self).unwrap();
+        if count == 1 { one } else { many }
+    }
+
+    /// Whether a bare, unquoted word is a sensible subject, as in "how many letters in banana"
+    fn fits_one_word(self) -> bool {
+        matches!(self, Unit::Characters | Unit::Letters | Unit::Vowels | Unit::Consonants)
+    }
+
+    /// The text's pieces of this kind, in order; bytes have none
+    fn pieces(self, text: &str) -> Vec<&str> {
+        let first = |g: &str| g.chars().next().unwrap_or(' ');
+        let vowel = |g: &str| "aeiouAEIOU".contains(first(g));
+        match self {
+            Unit::Characters => text.graphemes(true).collect(),
+            Unit::Letters => text.graphemes(true).filter(|g| first(g).is_alphabetic()).collect(),
+            Unit::Words => text.unicode_words().collect(),
+            Unit::Vowels => text.graphemes(true).filter(|g| vowel(g)).collect(),
+            Unit::Consonants => text.graphemes(true).filter(|g| first(g).is_alphabetic() && !vowel(g)).collect(),
+            Unit::Digits => text.graphemes(true).filter(|g| first(g).is_numeric()).collect(),
+            Unit::Spaces => text.graphemes(true).filter(|g| first(g).is_whitespace()).collect(),
+            Unit::Lines => text.lines().collect(),
+            Unit::Sentences => text.unicode_sentences().map(str::trim).filter(|s| !s.is_empty()).collect(),
+            Unit::Bytes => Vec::new(),
+        }
+    }
+}
+
+/// What a count looks for: a kind of piece, or a substring
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Unit(Unit),
+    Substring(String),
+}
+
+/// A 1-based position from the start, or from the end ("last" is `FromEnd(1)`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    FromStart(usize),
+    FromEnd(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+    Capitalized,
+}
+
+const CASES: &[(&str, Case)] = &[
+    ("uppercase", Case::Upper),
+    ("upper case", Case::Upper),
+    ("lowercase", Case::Lower),
+    ("lower case", Case::Lower),
+    ("title case", Case::Title),
+    ("titlecase", Case::Title),
+    ("capitalize", Case::Capitalized),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextQuery {
+    /// "how many r's in strawberry"; with `claimed`, "strawberry has 3 r's" is checked
+    Count { target: Target, text: String, claimed: Option<usize> },
+    /// "the 5th word of "...""
+    Nth { unit: Unit, position: Position, text: String },
+    /// "positions of "an" in "banana""
+    Find { needle: String, text: String },
+    /// "reverse "..."", or its word order with `words`
+    Reverse { text: String, words: bool },
+    Case { case: Case, text: String },
+    /// "does "..." match /re/", or every match with `all`
+    Match { pattern: String, text: String, all: bool },
+    Palindrome { text: String },
+}
+
+/// A quoted string or a /regex/ lifted out of the query
+#[derive(Debug, Clone, PartialEq)]
+struct Lifted {
+    text: String,
+    regex: bool,
+}
+
+/// One way of asking: the query with quotes lifted out, and what was lifted
+type Phrasing = fn(&str, &[Lifted]) -> Option<anyhow::Result<TextQuery>>;
+
+/// Leading words dropped before matching a phrasing
+const FILLERS: &[&str] = &["what is", "what's", "whats", "tell me", "please"];
+
+/// Recognize text questions about quoted input (or, for counts and palindromes, a single
+/// bare word); other queries return `None`
+pub fn parse_command(query: &str) -> Option<anyhow::Result<TextQuery>> {
+    let (template, lifted) = lift(query.trim().trim_end_matches(['?', '.', '!']))?;
+    let mut text = template.trim();
+    while let Some(rest) = FILLERS.iter().find_map(|filler| strip_keyword(text, filler)) {
+        text = rest;
+    }
+    let text = strip_keyword(text, "the").filter(|rest| strip_keyword(rest, "number of").is_some()).unwrap_or(text);
+    let parsers: &[Phrasing] = &[count, claim, nth, find, reverse, case, matches, palindrome];
+    parsers.iter().find_map(|parse| parse(text, &lifted))
+}
+
+/// Replace quoted strings and /regexes/ with placeholders, so the phrasing can be matched
+/// without looking inside them; `None` when the query does not lex
+fn lift(query: &str) -> Option<(String, Vec<Lifted>)> {
+    let chars: Vec<char> = query.chars().collect();
+    let (mut template, mut lifted) = (String::new(), Vec::new());
+    let mut next = 1;
+    for token in tokenize(query).ok()? {
+        let (text, regex) = match token.kind {
+            TokenKind::Text(text) => (text, false),
+            TokenKind::Pattern(pattern) => (pattern, true),
+            _ => continue,
+        };
+        template.extend(&chars[next - 1..token.column - 1]);
+        template.push_str(&format!("\u{1}{}\u{2}", lifted.len()));
+        lifted.push(Lifted { text, regex });
+        next = token.end;
+    }
+    template.extend(&chars[next - 1..]);
+    Some((template, lifted))
+}
+
+/// Index of a placeholder filling all of `text`
+fn placeholder(text: &str) -> Option<usize> {
+    text.strip_prefix('\u{1}')?.strip_suffix('\u{2}')?.parse().ok()
+}
+
+/// The text a question is about: a quoted string, or one bare word when `bare` allows
+fn subject(text: &str, lifted: &[Lifted], bare: bool) -> Option<String> {
+    let text = ["the word", "the string", "the text", "the sentence", "the phrase", "word", "string", "text"]
+        .iter()
+        .find_map(|noun| strip_keyword(text, noun))
+        .unwrap_or(text)
+        .trim();
+    if let Some(index) = placeholder(text) {
+        let quoted = &lifted[index];
+        return (!quoted.regex).then(|| quoted.text.clone());
+    }
+    let word = !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '\'');
+    (bare && word).then(|| text.to_string())
+}
+
+/// What to count: "letters", "the letter r", "r's", ""an"s", "the substring "an""
+fn target(text: &str, lifted: &[Lifted]) -> Option<Target> {
+    let text = strip_keyword(text, "the").unwrap_or(text).trim();
+    if let Some(&(_, _, unit)) = UNITS.iter().find(|(_, many, _)| text.eq_ignore_ascii_case(many)) {
+        return Some(Target::Unit(unit));
+    }
+    let text = ["letter", "character", "char", "substring", "string", "sequence", "digit", "word"]
+        .iter()
+        .find_map(|noun| strip_keyword(text, noun))
+        .unwrap_or(text);
+    let text = ["'s", "’s", "s"].iter().find_map(|plural| text.strip_suffix(plural)).filter(|t| !t.is_empty()).unwrap_or(text);
+    if let Some(index) = placeholder(text) {
+        let quoted = &lifted[index];
+        return (!quoted.regex && !quoted.text.is_empty()).then(|| Target::Substring(quoted.text.clone()));
+    }
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_alphanumeric() => Some(Target::Substring(c.to_string())),
+        _ => None,
+    }
+}
+
+fn bare_allowed(target: &Target) -> bool {
+    match target {
+        Target::Unit(unit) => unit.fits_one_word(),
+        Target::Substring(_) => true,
+    }
+}
+
+/// A count written in digits or words up to nineteen
+fn number(text: &str) -> Option<usize> {
+    text.parse().ok().or_else(|| super::natural::UNITS.iter().position(|w| text.eq_ignore_ascii_case(w)))
+}
+
+/// "how many T (are there) in S", "how many T does S have", "how many times does N appear
+/// in S", "count T in S", "number of T in S", "length of S", "how long is S"
+fn count(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    if let Some(rest) = strip_keyword(text, "length of").or_else(|| strip_keyword(text, "how long is")) {
+        let text = subject(rest, lifted, false)?;
+        return Some(Ok(TextQuery::Count { target: Target::Unit(Unit::Characters), text, claimed: None }));
+    }
+    let rest = ["how many", "count the number of", "count", "number of"].iter().find_map(|lead| strip_keyword(text, lead))?;
+    if let Some(rest) = strip_keyword(rest, "times does").or_else(|| strip_keyword(rest, "times is")) {
+        let (needle, text) = ["appear in", "occur in", "appear within", "occur within", "in"]
+            .iter()
+            .find_map(|verb| split_keyword(rest, verb))?;
+        let Target::Substring(needle) = target(needle, lifted)? else { return None };
+        let text = subject(text, lifted, true)?;
+        return Some(Ok(TextQuery::Count { target: Target::Substring(needle), text, claimed: None }));
+    }
+    let (what, of) = match split_keyword(rest, "in").or_else(|| split_keyword(rest, "of")) {
+        Some(split) => split,
+        None => {
+            let (what, of) = split_keyword(rest, "does")?;
+            let of = ["have", "contain"].iter().find_map(|verb| strip_suffix_phrase(of, verb))?;
+            (what, of)
+        }
+    };
+    let what = ["are there", "is there", "are", "is"].iter().find_map(|filler| strip_suffix_phrase(what, filler)).unwrap_or(what);
+    let target = target(what, lifted)?;
+    let text = subject(of, lifted, bare_allowed(&target))?;
+    Some(Ok(TextQuery::Count { target, text, claimed: None }))
+}
+
+/// "S has N T", "S contains N T", "does S have N T", "there are N T in S", checked
+/// against the actual count
+fn claim(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    let (of, rest) = if let Some(rest) = strip_keyword(text, "there are").or_else(|| strip_keyword(text, "there is")) {
+        let (rest, of) = split_keyword(rest, "in")?;
+        (of, rest)
+    } else {
+        let body = strip_keyword(text, "does").unwrap_or(text);
+        ["has", "contains", "have", "contain"].iter().find_map(|verb| split_keyword(body, verb))?
+    };
+    let (count, what) = rest.split_once(char::is_whitespace)?;
+    let claimed = number(count)?;
+    let target = target(what, lifted)?;
+    let text = subject(of, lifted, bare_allowed(&target))?;
+    Some(Ok(TextQuery::Count { target, text, claimed: Some(claimed) }))
+}
+
+/// "the 5th word of S", "the last letter in S", "the second to last character of S"
+fn nth(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    let text = strip_keyword(text, "the").unwrap_or(text);
+    let (head, of) = split_keyword(text, "of").or_else(|| split_keyword(text, "in"))?;
+    let (position, unit) = head.rsplit_once(char::is_whitespace)?;
+    let &(_, _, unit) = UNITS.iter().find(|(one, _, _)| unit.eq_ignore_ascii_case(one))?;
+    let position = ordinal(position)?;
+    let text = subject(of, lifted, unit.fits_one_word())?;
+    if unit == Unit::Bytes {
+        return Some(Err(anyhow::anyhow!("Bytes cannot be picked out by position; ask for a character instead")));
+    }
+    Some(Ok(TextQuery::Nth { unit, position, text }))
+}
+
+/// "first", "5th", "last", "second to last", "3rd-to-last"
+fn ordinal(text: &str) -> Option<Position> {
+    let text = text.trim().to_ascii_lowercase();
+    if text == "last" {
+        return Some(Position::FromEnd(1));
+    }
+    let from_end = ["-to-last", " to last", " from last", " from the end"].iter().find_map(|tail| text.strip_suffix(tail));
+    let word = from_end.unwrap_or(&text);
+    let index = super::natural::ORDINALS.iter().position(|o| *o == word).or_else(|| {
+        let digits = word.strip_suffix("st").or(word.strip_suffix("nd")).or(word.strip_suffix("rd")).or(word.strip_suffix("th"))?;
+        digits.parse().ok()
+    })?;
+    match (index, from_end) {
+        (0, _) => None,
+        (n, Some(_)) => Some(Position::FromEnd(n)),
+        (n, None) => Some(Position::FromStart(n)),
+    }
+}
+
+/// "find N in S", "position(s) of N in S", "where does N appear in S"
+fn find(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    let rest = ["find", "locate", "positions of", "position of", "indices of", "indexes of", "index of", "where does", "where is"]
+        .iter()
+        .find_map(|lead| strip_keyword(text, lead))?;
+    let (needle, of) = ["appear in", "occur in", "in"].iter().find_map(|verb| split_keyword(rest, verb))?;
+    let needle = match placeholder(needle.trim()).map(|index| &lifted[index]) {
+        Some(quoted) if !quoted.regex && !quoted.text.is_empty() => quoted.text.clone(),
+        _ => return None,
+    };
+    let text = subject(of, lifted, false)?;
+    Some(Ok(TextQuery::Find { needle, text }))
+}
+
+/// "reverse S", "spell S backwards", "reverse the words of S"
+fn reverse(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    if let Some(rest) = strip_keyword(text, "spell") {
+        let rest = strip_suffix_phrase(rest, "backwards").or_else(|| strip_suffix_phrase(rest, "backward"))?;
+        return Some(Ok(TextQuery::Reverse { text: subject(rest, lifted, true)?, words: false }));
+    }
+    let rest = strip_keyword(text, "reverse")?;
+    let words = ["the words of", "the words in", "the word order of", "the order of the words in", "words of", "words in"]
+        .iter()
+        .find_map(|phrase| strip_keyword(rest, phrase));
+    let text = subject(words.unwrap_or(rest), lifted, false)?;
+    Some(Ok(TextQuery::Reverse { text, words: words.is_some() }))
+}
+
+/// "uppercase S", "convert S to lowercase", "S in title case"
+fn case(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    for &(name, case) in CASES {
+        if let Some(rest) = strip_keyword(text, name) {
+            return Some(Ok(TextQuery::Case { case, text: subject(rest, lifted, false)? }));
+        }
+    }
+    let rest = ["convert", "make", "turn", "put", "write"].iter().find_map(|verb| strip_keyword(text, verb)).unwrap_or(text);
+    CASES.iter().find_map(|&(name, case)| {
+        let rest = strip_suffix_phrase(rest, name)?;
+        let rest = ["to", "into", "in"].iter().find_map(|word| strip_suffix_phrase(rest, word))?;
+        Some(Ok(TextQuery::Case { case, text: subject(rest, lifted, false)? }))
+    })
+}
+
+/// "does S match /re/", "match /re/ against S", "find all /re/ in S", "all matches of /re/ in S"
+fn matches(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    if let Some(rest) = strip_keyword(text, "does") {
+        let (of, pattern) = split_keyword(rest, "match")?;
+        let pattern = regex_pattern(pattern, lifted)?;
+        return Some(Ok(TextQuery::Match { pattern, text: subject(of, lifted, false)?, all: false }));
+    }
+    if let Some(rest) = strip_keyword(text, "match") {
+        let (pattern, of) = ["against", "in", "on"].iter().find_map(|word| split_keyword(rest, word))?;
+        let pattern = regex_pattern(pattern, lifted)?;
+        return Some(Ok(TextQuery::Match { pattern, text: subject(of, lifted, false)?, all: false }));
+    }
+    let rest = ["find all matches of", "find all", "all matches of", "matches of", "list all matches of"]
+        .iter()
+        .find_map(|lead| strip_keyword(text, lead))?;
+    let (pattern, of) = split_keyword(rest, "in")?;
+    let pattern = regex_pattern(pattern, lifted)?;
+    Some(Ok(TextQuery::Match { pattern, text: subject(of, lifted, false)?, all: true }))
+}
+
+/// A /regex/, or a quoted string after "regex" or "pattern"
+fn regex_pattern(text: &str, lifted: &[Lifted]) -> Option<String> {
+    let text = strip_keyword(text, "the").unwrap_or(text);
+    let named = ["regex", "regexp", "pattern"].iter().find_map(|noun| strip_keyword(text, noun));
+    let quoted = &lifted[placeholder(named.unwrap_or(text).trim())?];
+    (quoted.regex || named.is_some()).then(|| quoted.text.clone())
+}
+
+/// "is S a palindrome"
+fn palindrome(text: &str, lifted: &[Lifted]) -> Option<anyhow::Result<TextQuery>> {
+    let rest = strip_keyword(text, "is")?;
+    let rest = strip_suffix_phrase(rest, "a palindrome")?;
+    Some(Ok(TextQuery::Palindrome { text: subject(rest, lifted, true)? }))
+}
+
+/// Answer, verdict for checked claims and matches, and how the answer was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextOutcome {
+    pub result: String,
+    pub verdict: Option<bool>,
+    pub steps: Vec<String>,
+}
+
+impl TextOutcome {
+    fn answer(result: impl Into<String>, steps: Vec<String>) -> Self {
+        TextOutcome { result: result.into(), verdict: None, steps }
+    }
+}
+
+/// Evaluate a text query; substrings are matched ignoring case, whole graphemes at a time
+pub fn evaluate(query: &TextQuery) -> anyhow::Result<TextOutcome> {
+    match query {
+        TextQuery::Count { target, text, claimed } => {
+            let (count, steps) = match target {
+                Target::Unit(unit) => count_unit(*unit, text),
+                Target::Substring(needle) => count_substring(needle, text),
+            };
+            let mut outcome = TextOutcome::answer(count.to_string(), steps);
+            if let Some(claimed) = *claimed {
+                outcome.verdict = Some(claimed == count);
+                outcome.result = match claimed == count {
+                    true => "true".to_string(),
+                    false => format!("false; actual count {}", count),
+                };
+                outcome.steps.insert(0, format!("claimed {}, counted {}", claimed, count));
+            }
+            Ok(outcome)
+        }
+        TextQuery::Nth { unit, position, text } => {
+            let pieces = unit.pieces(text);
+            let index = match *position {
+                Position::FromStart(n) => n.checked_sub(1).filter(|&i| i < pieces.len()),
+                Position::FromEnd(n) => pieces.len().checked_sub(n),
+            };
+            let Some(index) = index else {
+                return Err(anyhow::anyhow!("{:?} has only {} {}", text, pieces.len(), unit.name(pieces.len())));
+            };
+            let steps = vec![format!("{} {} of {}", unit.name(1), index + 1, pieces.len())];
+            Ok(TextOutcome::answer(pieces[index], steps))
+        }
+        TextQuery::Find { needle, text } => {
+            let starts = occurrences(needle, text);
+            let steps = vec![format!("1-based character positions of {:?} in {:?}, ignoring case", needle, text)];
+            let result = match starts.is_empty() {
+                true => "not found".to_string(),
+                false => join(starts.iter().map(|i| i + 1)),
+            };
+            Ok(TextOutcome::answer(result, steps))
+        }
+        TextQuery::Reverse { text, words } => {
+            let (result, step) = match words {
+                true => {
+                    let words: Vec<&str> = text.split_whitespace().rev().collect();
+                    (words.join(" "), format!("reversed the order of {} words", words.len()))
+                }
+                false => {
+                    let graphemes: Vec<&str> = text.graphemes(true).rev().collect();
+                    (graphemes.concat(), format!("reversed {} characters", graphemes.len()))
+                }
+            };
+            Ok(TextOutcome::answer(result, vec![step]))
+        }
+        TextQuery::Case { case, text } => {
+            let result = match case {
+                Case::Upper => text.to_uppercase(),
+                Case::Lower => text.to_lowercase(),
+                Case::Title => text.split_word_bounds().map(capitalize_word).collect(),
+                Case::Capitalized => capitalize(text),
+            };
+            Ok(TextOutcome::answer(result, vec![format!("{:?} case", case).to_lowercase()]))
+        }
+        TextQuery::Match { pattern, text, all } => match_regex(pattern, text, *all),
+        TextQuery::Palindrome { text } => {
+            let normalized: Vec<String> = text
+                .graphemes(true)
+                .filter(|g| g.chars().next().is_some_and(char::is_alphanumeric))
+                .map(str::to_lowercase)
+                .collect();
+            let palindrome = !normalized.is_empty() && normalized.iter().eq(normalized.iter().rev());
+            let steps = vec![format!("letters and digits, lowercased: {}", normalized.concat())];
+            Ok(TextOutcome { result: palindrome.to_string(), verdict: Some(palindrome), steps })
+        }
+    }
+}
+
+fn count_unit(unit: Unit, text: &str) -> (usize, Vec<String>) {
+    match unit {
+        Unit::Bytes => (text.len(), vec![format!("UTF-8 bytes: {}", text.len())]),
+        Unit::Characters => {
+            let count = text.graphemes(true).count();
+            let detail = format!("graphemes: {}, code points: {}, bytes: {}", count, text.chars().count(), text.len());
+            (count, vec![detail])
+        }
+        unit => {
+            let pieces = unit.pieces(text);
+            let shown: Vec<String> = pieces.iter().take(20).map(|p| format!("{:?}", p)).collect();
+            let more = if pieces.len() > 20 { ", …" } else { "" };
+            (pieces.len(), vec![format!("{}: {}{}", unit.name(pieces.len()), shown.join(", "), more)])
+        }
+    }
+}
+
+fn count_substring(needle: &str, text: &str) -> (usize, Vec<String>) {
+    let starts = occurrences(needle, text);
+    let width = needle.graphemes(true).count();
+    // Non-overlapping, like str::matches: "aa" occurs once in "aaa"
+    let mut count = 0;
+    let mut next = 0;
+    for &start in &starts {
+        if start >= next {
+            count += 1;
+            next = start + width;
+        }
+    }
+    let mut steps = vec![match starts.is_empty() {
+        true => format!("{:?} does not occur in {:?}, ignoring case", needle, text),
+        false => format!("{:?} in {:?}, ignoring case, at characters {}", needle, text, join(starts.iter().map(|i| i + 1))),
+    }];
+    if starts.len() != count {
+        steps.push(format!("{} occurrences when overlaps count", starts.len()));
+    }
+    let exact = text.matches(needle).count();
+    if exact != count {
+        steps.push(format!("{} with matching case", exact));
+    }
+    (count, steps)
+}
+
+/// 0-based grapheme indices where `needle` starts, ignoring case and overlapping allowed
+fn occurrences(needle: &str, text: &str) -> Vec<usize> {
+    let needle: Vec<String> = needle.graphemes(true).map(str::to_lowercase).collect();
+    let text: Vec<String> = text.graphemes(true).map(str::to_lowercase).collect();
+    if needle.is_empty() || needle.len() > text.len() {
+        return Vec::new();
+    }
+    (0..=text.len() - needle.len()).filter(|&i| text[i..i + needle.len()] == needle[..]).collect()
+}
+
+fn match_regex(pattern: &str, text: &str, all: bool) -> anyhow::Result<TextOutcome> {
+    let regex = regex::RegexBuilder::new(pattern)
+        .size_limit(1 << 20)
+        .dfa_size_limit(1 << 20)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid regex /{}/: {}", pattern, e))?;
+    let position = |byte: usize| text[..byte].graphemes(true).count() + 1;
+    let found: Vec<regex::Match> = regex.find_iter(text).collect();
+    if all {
+        let steps = vec![format!("{} matches of /{}/", found.len(), pattern)];
+        let result = match found.is_empty() {
+            true => "no matches".to_string(),
+            false => found.iter().map(|m| format!("{:?}", m.as_str())).collect::<Vec<_>>().join(", "),
+        };
+        return Ok(TextOutcome::answer(result, steps));
+    }
+    let steps = match found.first() {
+        Some(m) => vec![format!("/{}/ first matches {:?} at character {}", pattern, m.as_str(), position(m.start()))],
+        None => vec![format!("/{}/ matches nowhere in {:?}", pattern, text)],
+    };
+    let matched = !found.is_empty();
+    Ok(TextOutcome { result: matched.to_string(), verdict: Some(matched), steps })
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn capitalize_word(word: &str) -> String {
+    capitalize(&word.to_lowercase())
+}
+
+fn join(items: impl Iterator<Item = usize>) -> String {
+    items.map(|i| i.to_string()).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,628 @@
+// Dimensional analysis: unit-aware quantities with SI prefixes and imperial units
+use super::expr::{call_builtin, is_named_constant, BinOp, Expr, Parser};
+use super::lexer::{column_of, relocate, tokenize, SyntaxError, TokenKind};
+use super::number::Number;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+use std::fmt;
+
+/// Names of the seven SI base dimensions, in `Dimension` order
+const BASE_SYMBOLS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// Exponents of the SI base dimensions: length, mass, time, current,
+/// temperature, amount of substance, luminous intensity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dimension(pub [i32; 7]);
+
+impl Dimension {
+    const NONE: Dimension = Dimension([0; 7]);
+
+    fn base(index: usize) -> Self {
+        let mut exps = [0; 7];
+        exps[index] = 1;
+        Dimension(exps)
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Dimension::NONE
+    }
+
+    fn combine(&self, other: &Dimension, sign: i32) -> Dimension {
+        let mut exps = self.0;
+        for (e, o) in exps.iter_mut().zip(other.0.iter()) {
+            *e += sign * o;
+        }
+        Dimension(exps)
+    }
+
+    fn scale(&self, numer: i32, denom: i32) -> Option<Dimension> {
+        let mut exps = self.0;
+        for e in exps.iter_mut() {
+            if (*e * numer) % denom != 0 {
+                return None;
+            }
+            *e = *e * numer / denom;
+        }
+        Some(Dimension(exps))
+    }
+
+    /// Human-readable name such as "velocity" when the dimension is a common one
+    pub fn name(&self) -> Option<&'static str> {
+        let named = [
+            ([0, 0, 0, 0, 0, 0, 0], "dimensionless"),
+            ([1, 0, 0, 0, 0, 0, 0], "length"),
+            ([0, 1, 0, 0, 0, 0, 0], "mass"),
+            ([0, 0, 1, 0, 0, 0, 0], "time"),
+            ([0, 0, 0, 1, 0, 0, 0], "electric current"),
+            ([0, 0, 0, 0, 1, 0, 0], "temperature"),
+            ([0, 0, 0, 0, 0, 1, 0], "amount of substance"),
+            ([0, 0, 0, 0, 0, 0, 1], "luminous intensity"),
+            ([2, 0, 0, 0, 0, 0, 0], "area"),
+            ([3, 0, 0, 0, 0, 0, 0], "volume"),
+            ([1, 0, -1, 0, 0, 0, 0], "velocity"),
+            ([1, 0, -2, 0, 0, 0, 0], "acceleration"),
+            ([1, 1, -2, 0, 0, 0, 0], "force"),
+            ([2, 1, -2, 0, 0, 0, 0], "energy"),
+            ([2, 1, -3, 0, 0, 0, 0], "power"),
+            ([-1, 1, -2, 0, 0, 0, 0], "pressure"),
+            ([0, 0, -1, 0, 0, 0, 0], "frequency"),
+            ([0, 0, 1, 1, 0, 0, 0], "electric charge"),
+            ([2, 1, -3, -1, 0, 0, 0], "voltage"),
+            ([2, 1, -3, -2, 0, 0, 0], "resistance"),
+            ([-3, 1, 0, 0, 0, 0, 0], "density"),
+        ];
+        named.iter().find(|(exps, _)| *exps == self.0).map(|(_, name)| *name)
+    }
+
+    /// "force (kg*m/s^2)" style description used in error messages
+    pub fn describe(&self) -> String {
+        match self.name() {
+            Some(name) => format!("{} ({})", name, self),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_dimensionless() {
+            return write!(f, "1");
+        }
+        let render = |positive: bool| -> Vec<String> {
+            // kg first reads more naturally: kg*m/s^2
+            [1, 0, 2, 3, 4, 5, 6]
+                .iter()
+                .filter_map(|&i| {
+                    let e = self.0[i];
+                    let e = if positive { e } else { -e };
+                    match e {
+                        e if e <= 0 => None,
+                        1 => Some(BASE_SYMBOLS[i].to_string()),
+                        e => Some(format!("{}^{}", BASE_SYMBOLS[i], e)),
+                    }
+                })
+                .collect()
+        };
+        write!(f, "{}", join_units(render(true), render(false)))
+    }
+}
+
+/// "kg*m/s^2" from the numerator and denominator factors
+fn join_units(numer: Vec<String>, denom: Vec<String>) -> String {
+    let numer = if numer.is_empty() { "1".to_string() } else { numer.join("*") };
+    match denom.len() {
+        0 => numer,
+        1 => format!("{}/{}", numer, denom[0]),
+        _ => format!("{}/({})", numer, denom.join("*")),
+    }
+}
+
+/// A value in SI base units together with its dimension
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: Number,
+    pub dim: Dimension,
+}
+
+impl Quantity {
+    fn scalar(value: Number) -> Self {
+        Quantity { value, dim: Dimension::NONE }
+    }
+}
+
+struct UnitDef {
+    names: &'static [&'static str],
+    /// Size of one unit in SI base units, as an exact decimal or ratio of decimals
+    factor: &'static str,
+    dim: [i32; 7],
+    /// Accepts SI prefixes (k, m, µ, ...)
+    prefixable: bool,
+}
+
+const L: [i32; 7] = [1, 0, 0, 0, 0, 0, 0];
+const M: [i32; 7] = [0, 1, 0, 0, 0, 0, 0];
+const T: [i32; 7] = [0, 0, 1, 0, 0, 0, 0];
+
+const UNITS: &[UnitDef] = &[
+    // SI base units (the kilogram is defined through the gram so prefixes compose)
+    UnitDef { names: &["m", "meter", "metre"], factor: "1", dim: L, prefixable: true },
+    UnitDef { names: &["g", "gram"], factor: "0.001", dim: M, prefixable: true },
+    UnitDef { names: &["s", "sec", "second"], factor: "1", dim: T, prefixable: true },
+    UnitDef { names: &["A", "amp", "ampere"], factor: "1", dim: [0, 0, 0, 1, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["K", "kelvin"], factor: "1", dim: [0, 0, 0, 0, 1, 0, 0], prefixable: true },
+    UnitDef { names: &["mol", "mole"], factor: "1", dim: [0, 0, 0, 0, 0, 1, 0], prefixable: true },
+    UnitDef { names: &["cd", "candela"], factor: "1", dim: [0, 0, 0, 0, 0, 0, 1], prefixable: true },
+    // SI derived units
+    UnitDef { names: &["N", "newton"], factor: "1", dim: [1, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["J", "joule"], factor: "1", dim: [2, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["W", "watt"], factor: "1", dim: [2, 1, -3, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["Pa", "pascal"], factor: "1", dim: [-1, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["Hz", "hertz"], factor: "1", dim: [0, 0, -1, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["C", "coulomb"], factor: "1", dim: [0, 0, 1, 1, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["V", "volt"], factor: "1", dim: [2, 1, -3, -1, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["ohm"], factor: "1", dim: [2, 1, -3, -2, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["L", "l", "liter", "litre"], factor: "0.001", dim: [3, 0, 0, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["t", "tonne"], factor: "1000", dim: M, prefixable: false },
+    UnitDef { names: &["eV"], factor: "1.602176634e-19", dim: [2, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["Wh"], factor: "3600", dim: [2, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["cal", "calorie"], factor: "4.184", dim: [2, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["bar"], factor: "100000", dim: [-1, 1, -2, 0, 0, 0, 0], prefixable: true },
+    UnitDef { names: &["atm"], factor: "101325", dim: [-1, 1, -2, 0, 0, 0, 0], prefixable: false },
+    // Time
+    UnitDef { names: &["min", "minute"], factor: "60", dim: T, prefixable: false },
+    UnitDef { names: &["h", "hr", "hour"], factor: "3600", dim: T, prefixable: false },
+    UnitDef { names: &["day", "d"], factor: "86400", dim: T, prefixable: false },
+    UnitDef { names: &["week", "wk"], factor: "604800", dim: T, prefixable: false },
+    UnitDef { names: &["year", "yr"], factor: "31557600", dim: T, prefixable: false },
+    // Imperial and US customary (exact international definitions)
+    UnitDef { names: &["in", "inch", "inches"], factor: "0.0254", dim: L, prefixable: false },
+    UnitDef { names: &["ft", "foot", "feet"], factor: "0.3048", dim: L, prefixable: false },
+    UnitDef { names: &["yd", "yard"], factor: "0.9144", dim: L, prefixable: false },
+    UnitDef { names: &["mi", "mile"], factor: "1609.344", dim: L, prefixable: false },
+    UnitDef { names: &["nmi"], factor: "1852", dim: L, prefixable: false },
+    UnitDef { names: &["acre"], factor: "4046.8564224", dim: [2, 0, 0, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["lb", "lbs", "pound"], factor: "0.45359237", dim: M, prefixable: false },
+    UnitDef { names: &["oz", "ounce"], factor: "0.028349523125", dim: M, prefixable: false },
+    UnitDef { names: &["st", "stone"], factor: "6.35029318", dim: M, prefixable: false },
+    UnitDef { names: &["ton"], factor: "907.18474", dim: M, prefixable: false },
+    UnitDef { names: &["gal", "gallon"], factor: "0.003785411784", dim: [3, 0, 0, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["qt", "quart"], factor: "0.000946352946", dim: [3, 0, 0, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["floz"], factor: "0.0000295735295625", dim: [3, 0, 0, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["mph"], factor: "0.44704", dim: [1, 0, -1, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["kph", "kmh"], factor: "1000/3600", dim: [1, 0, -1, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["knot", "kn"], factor: "1852/3600", dim: [1, 0, -1, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["lbf"], factor: "4.4482216152605", dim: [1, 1, -2, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["psi"], factor: "4.4482216152605/0.00064516", dim: [-1, 1, -2, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["hp", "horsepower"], factor: "745.69987158227022", dim: [2, 1, -3, 0, 0, 0, 0], prefixable: false },
+    UnitDef { names: &["BTU", "btu"], factor: "1055.05585262", dim: [2, 1, -2, 0, 0, 0, 0], prefixable: false },
+];
+
+/// SI prefixes as (symbol, long name, power of ten)
+const PREFIXES: &[(&str, &str, i32)] = &[
+    ("Y", "yotta", 24), ("Z", "zetta", 21), ("E", "exa", 18), ("P", "peta", 15),
+    ("T", "tera", 12), ("G", "giga", 9), ("M", "mega", 6), ("k", "kilo", 3),
+    ("h", "hecto", 2), ("da", "deca", 1), ("d", "deci", -1), ("c", "centi", -2),
+    ("m", "milli", -3), ("u", "micro", -6), ("µ", "micro", -6), ("n", "nano", -9),
+    ("p", "pico", -12), ("f", "femto", -15), ("a", "atto", -18),
+];
+
+/// Temperature scales with an offset, only valid in direct conversions
+const AFFINE_TEMPERATURES: &[(&[&str], &str, &str)] = &[
+    // (names, scale to kelvin, offset added after scaling)
+    (&["degC", "celsius"], "1", "273.15"),
+    (&["degF", "fahrenheit"], "5/9", "459.67"),
+];
+
+/// Resolve a unit name such as "km", "kilometers" or "mph" to a quantity of one unit
+pub fn lookup_unit(name: &str) -> Option<Quantity> {
+    let singular = name.strip_suffix("es").filter(|s| s.ends_with("inch")).or_else(|| name.strip_suffix('s'));
+    let candidates = [Some(name), singular.filter(|s| s.len() > 1)];
+
+    for candidate in candidates.into_iter().flatten() {
+        if let Some(def) = UNITS.iter().find(|u| u.names.contains(&candidate)) {
+            return Some(unit_quantity(def, 0));
+        }
+    }
+
+    for candidate in candidates.into_iter().flatten() {
+        for (symbol, long, power) in PREFIXES {
+            let rest = candidate
+                .strip_prefix(long)
+                .filter(|r| r.len() > 2)
+                .or_else(|| candidate.strip_prefix(symbol).filter(|r| r.len() <= 3));
+            if let Some(def) = rest.and_then(|r| UNITS.iter().find(|u| u.prefixable && u.names.contains(&r))) {
+                return Some(unit_quantity(def, *power));
+            }
+        }
+    }
+    None
+}
+
+fn unit_quantity(def: &UnitDef, power: i32) -> Quantity {
+    let factor = parse_factor(def.factor);
+    let scale = Number::Exact(num_traits::pow(BigRational::from_integer(10.into()), power.unsigned_abs() as usize));
+    let value = if power >= 0 { factor.mul(&scale) } else { factor.div(&scale) };
+    Quantity { value: value.unwrap_or(factor), dim: Dimension(def.dim) }
+}
+
+fn affine_temperature(name: &str) -> Option<(Number, Number)> {
+    AFFINE_TEMPERATURES.iter().find(|(names, _, _)| names.contains(&name)).map(|(_, scale, offset)| {
+        (parse_factor(scale), parse_factor(offset))
+    })
+}
+
+/// Parse a table factor such as "0.3048" or "1852/3600" exactly
+fn parse_factor(text: &str) -> Number {
+    let parsed = match text.split_once('/') {
+        Some((numer, denom)) => Number::parse_decimal(numer).and_then(|n| n.div(&Number::parse_decimal(denom)?)),
+        None => Number::parse_decimal(text),
+    };
+    parsed.expect("unit table factors are valid decimals")
+}
+
+/// A unit-aware query: an expression and an optional conversion target
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitQuery {
+    pub expr: Expr,
+    /// Target unit expression and its source text, from "... in <unit>" or "... to <unit>"
+    pub target: Option<(Expr, String)>,
+}
+
+/// Recognize expressions that mention units; `None` leaves the query to other handlers
+pub fn parse_command(query: &str) -> Option<anyhow::Result<UnitQuery>> {
+    let text = query.trim();
+    let parse = |part: &str| parse_quantities(part).map_err(|e| relocate(e, query, part));
+
+    // The last " in " / " to " is the conversion; earlier ones may be inches
+    for keyword in ["to", "in"] {
+        if let Some((source, target)) = rsplit_keyword(text, keyword) {
+            if mentions_only_units(target) && is_unit_expression(source) {
+                return Some(parse(source).and_then(|expr| {
+                    let target_expr = parse(target)?;
+                    Ok(UnitQuery { expr, target: Some((target_expr, target.to_string())) })
+                }));
+            }
+        }
+    }
+
+    // "5 km in" asks for a conversion but names no target; "5 in" is five inches
+    for keyword in ["to", "in"] {
+        let Some(source) = text.get(..text.len().saturating_sub(keyword.len())) else { continue };
+        let ends_with_keyword = text[source.len()..].eq_ignore_ascii_case(keyword);
+        if ends_with_keyword && source.ends_with(char::is_whitespace) && is_unit_expression(source) {
+            let column = column_of(query, &text[text.len()..]).unwrap_or(1);
+            let expected = format!("a target unit after '{}'", &text[source.len()..]);
+            return Some(Err(SyntaxError::new(column, expected, "end of input").into()));
+        }
+    }
+
+    if is_unit_expression(text) {
+        return Some(parse(text).map(|expr| UnitQuery { expr, target: None }));
+    }
+    None
+}
+
+/// An expression in which each number binds the units written after it
+fn parse_quantities(input: &str) -> anyhow::Result<Expr> {
+    let mut parser = Parser::new(tokenize(input)?).with_quantities();
+    let expr = parser.parse_expr()?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+
+/// Split around the last whitespace-delimited occurrence of `keyword`
+fn rsplit_keyword<'a>(text: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    text.char_indices().rev().find_map(|(i, _)| {
+        let candidate = text.get(i..i + keyword.len())?;
+        let before = text[..i].chars().next_back()?;
+        let after = text[i + keyword.len()..].chars().next()?;
+        (candidate.eq_ignore_ascii_case(keyword) && before.is_whitespace() && after.is_whitespace())
+            .then(|| (text[..i].trim(), text[i + keyword.len()..].trim()))
+    })
+}
+
+/// True when every identifier is a unit and at least one appears
+fn mentions_only_units(text: &str) -> bool {
+    let Ok(tokens) = tokenize(text) else { return false };
+    let mut any = false;
+    for token in tokens {
+        if let TokenKind::Ident(name) = token.kind {
+            if lookup_unit(&name).is_none() && affine_temperature(&name).is_none() {
+                return false;
+            }
+            any = true;
+        }
+    }
+    any
+}
+
+/// True when the text uses at least one unit and no unknown identifiers
+fn is_unit_expression(text: &str) -> bool {
+    let Ok(tokens) = tokenize(text) else { return false };
+    let mut units = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if let TokenKind::Ident(name) = &token.kind {
+            let is_call = tokens.get(i + 1).is_some_and(|t| t.is_symbol("("));
+            if is_call || is_named_constant(name) {
+                continue;
+            }
+            if lookup_unit(name).is_none() && affine_temperature(name).is_none() {
+                return false;
+            }
+            units += 1;
+        }
+    }
+    units > 0
+}
+
+/// Evaluated unit query, ready for display
+#[derive(Debug, Clone)]
+pub struct UnitResult {
+    pub value: Number,
+    /// Unit label for `value`, e.g. "mi" or "kg*m/s^2"
+    pub unit: String,
+    pub dim: Dimension,
+    pub steps: Vec<String>,
+}
+
+pub fn evaluate_query(query: &UnitQuery) -> anyhow::Result<UnitResult> {
+    let mut steps = Vec::new();
+
+    if let Some(result) = convert_affine(query, &mut steps)? {
+        return Ok(result);
+    }
+
+    let quantity = evaluate(&query.expr)?;
+    steps.push(format!("{} = {} {} ({})", query.expr, quantity.value.to_decimal(12), quantity.dim, quantity.dim.name().unwrap_or("derived")));
+
+    match &query.target {
+        Some((target_expr, target_text)) => {
+            let target = evaluate(target_expr)?;
+            if target.dim != quantity.dim {
+                return Err(anyhow::anyhow!(
+                    "Cannot convert {} to {}",
+                    quantity.dim.describe(),
+                    target.dim.describe()
+                ));
+            }
+            let value = quantity.value.div(&target.value)?;
+            steps.push(format!("1 {} = {} {}", target_text, target.value.to_decimal(12), target.dim));
+            steps.push(format!("convert: {} / {} = {} {}", quantity.value.to_decimal(12), target.value.to_decimal(12), value.to_decimal(12), target_text));
+            Ok(UnitResult { value, unit: target_text.clone(), dim: quantity.dim, steps })
+        }
+        None => {
+            // "3 ft * 2 ft" stays in feet; mixed units with a named SI unit, like "10 N * 2 m", use it
+            let derived = derived_unit_name(&quantity.dim);
+            match written_units(&query.expr).filter(|units| !units.is_empty()) {
+                Some(units) if units.len() == 1 || derived.is_none() => {
+                    let scale = units.iter().try_fold(Number::from_i64(1), |scale, (name, power)| {
+                        let unit = lookup_unit(name).expect("written_units keeps only units");
+                        scale.mul(&unit.value.pow(&Number::from_i64(*power as i64))?)
+                    })?;
+                    let value = quantity.value.div(&scale)?;
+                    let unit = written_label(&units);
+                    steps.push(format!("in the units written: {} {}", value.to_decimal(12), unit));
+                    Ok(UnitResult { value, unit, dim: quantity.dim, steps })
+                }
+                _ => {
+                    let unit = derived.map(str::to_string).unwrap_or_else(|| quantity.dim.to_string());
+                    Ok(UnitResult { value: quantity.value, unit, dim: quantity.dim, steps })
+                }
+            }
+        }
+    }
+}
+
+/// Units with their powers as the query writes them, in order of appearance, e.g.
+/// [("km", 1), ("h", -1)] for "100 km / 2 h"; None when added terms use different units
+fn written_units(expr: &Expr) -> Option<Vec<(String, i32)>> {
+    let merge = |mut units: Vec<(String, i32)>, other: Vec<(String, i32)>, sign: i32| {
+        for (name, power) in other {
+            match units.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, p)) => *p += sign * power,
+                None => units.push((name, sign * power)),
+            }
+        }
+        units.retain(|(_, p)| *p != 0);
+        units
+    };
+    let sorted = |units: &[(String, i32)]| {
+        let mut units = units.to_vec();
+        units.sort();
+        units
+    };
+    let scaled = |units: Vec<(String, i32)>, numer: i32, denom: i32| -> Option<Vec<(String, i32)>> {
+        units.into_iter().map(|(name, p)| (p * numer % denom == 0).then(|| (name, p * numer / denom))).collect()
+    };
+
+    match expr {
+        Expr::Num(_) => Some(Vec::new()),
+        Expr::Var(name) if lookup_unit(name).is_some() => Some(vec![(name.clone(), 1)]),
+        Expr::Var(_) => Some(Vec::new()),
+        Expr::Neg(inner) => written_units(inner),
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (written_units(lhs)?, written_units(rhs)?);
+            match op {
+                BinOp::Add | BinOp::Sub | BinOp::Rem => (sorted(&a) == sorted(&b)).then_some(a),
+                BinOp::Mul => Some(merge(a, b, 1)),
+                BinOp::Div => Some(merge(a, b, -1)),
+                BinOp::Pow if a.is_empty() => Some(a),
+                BinOp::Pow => match rhs.as_ref() {
+                    Expr::Num(r) if r.is_integer() => scaled(a, r.to_integer().to_i32()?, 1),
+                    _ => None,
+                },
+            }
+        }
+        Expr::Call(name, args) => match (name.as_str(), args.as_slice()) {
+            ("abs", [arg]) => written_units(arg),
+            ("sqrt", [arg]) => scaled(written_units(arg)?, 1, 2),
+            _ => args.iter().all(|arg| written_units(arg).is_some_and(|units| units.is_empty())).then(Vec::new),
+        },
+    }
+}
+
+/// "km/h" or "ft^2" for the units of `written_units`
+fn written_label(units: &[(String, i32)]) -> String {
+    let render = |sign: i32| {
+        units
+            .iter()
+            .filter(|(_, p)| p * sign > 0)
+            .map(|(name, p)| match p * sign {
+                1 => name.clone(),
+                p => format!("{}^{}", name, p),
+            })
+            .collect()
+    };
+    join_units(render(1), render(-1))
+}
+
+/// Named SI unit for common derived dimensions
+fn derived_unit_name(dim: &Dimension) -> Option<&'static str> {
+    let named = [
+        ([1, 1, -2, 0, 0, 0, 0], "N"),
+        ([2, 1, -2, 0, 0, 0, 0], "J"),
+        ([2, 1, -3, 0, 0, 0, 0], "W"),
+        ([-1, 1, -2, 0, 0, 0, 0], "Pa"),
+        ([0, 0, -1, 0, 0, 0, 0], "Hz"),
+        ([0, 0, 1, 1, 0, 0, 0], "C"),
+        ([2, 1, -3, -1, 0, 0, 0], "V"),
+        ([2, 1, -3, -2, 0, 0, 0], "ohm"),
+    ];
+    named.iter().find(|(exps, _)| *exps == dim.0).map(|(_, name)| *name)
+}
+
+/// Direct conversions between offset temperature scales, e.g. "100 degC in degF"
+fn convert_affine(query: &UnitQuery, steps: &mut Vec<String>) -> anyhow::Result<Option<UnitResult>> {
+    // "-40 degC" reads as -(40 degC)
+    let (negative, expr) = match &query.expr {
+        Expr::Neg(inner) => (true, inner.as_ref()),
+        expr => (false, expr),
+    };
+    let source = match expr {
+        Expr::Binary(BinOp::Mul, value, unit) => match unit.as_ref() {
+            Expr::Var(name) => affine_temperature(name).map(|scale| (value.as_ref(), scale)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let target = match &query.target {
+        Some((Expr::Var(name), text)) => {
+            affine_temperature(name).or_else(|| lookup_unit(name).map(|q| (q.value, Number::from_i64(0)))).map(|t| (t, text))
+        }
+        _ => None,
+    };
+
+    let uses_affine = |e: &Expr| format!("{}", e).split(|c: char| !c.is_alphanumeric()).any(|w| affine_temperature(w).is_some());
+    let (Some((value_expr, (scale, offset))), Some(((t_scale, t_offset), t_text))) = (source, target) else {
+        if uses_affine(&query.expr) || query.target.as_ref().is_some_and(|(e, _)| uses_affine(e)) {
+            return Err(anyhow::anyhow!("Offset temperature scales (degC, degF) only support direct conversion like '100 degC in degF'"));
+        }
+        return Ok(None);
+    };
+
+    let mut value = evaluate(value_expr)?;
+    if negative {
+        value.value = value.value.neg();
+    }
+    if !value.dim.is_dimensionless() {
+        return Err(anyhow::anyhow!("Temperature value must be a plain number"));
+    }
+    // kelvin = (value + offset) * scale, where Fahrenheit offsets are in Rankine degrees
+    let kelvin = value.value.add(&offset)?.mul(&scale)?;
+    let result = kelvin.div(&t_scale)?.sub(&t_offset)?;
+    steps.push(format!("{} → {} K", query.expr, kelvin.to_decimal(12)));
+    steps.push(format!("{} K → {} {}", kelvin.to_decimal(12), result.to_decimal(12), t_text));
+    Ok(Some(UnitResult { value: result, unit: t_text.clone(), dim: Dimension::base(4), steps: std::mem::take(steps) }))
+}
+
+/// Evaluate an expression whose identifiers are units
+pub fn evaluate(expr: &Expr) -> anyhow::Result<Quantity> {
+    match expr {
+        Expr::Num(n) => Ok(Quantity::scalar(Number::Exact(n.clone()))),
+        Expr::Var(name) => {
+            if let Some(unit) = lookup_unit(name) {
+                return Ok(unit);
+            }
+            if is_named_constant(name) {
+                return super::expr::Evaluator::new().evaluate(expr).map(Quantity::scalar);
+            }
+            Err(anyhow::anyhow!("Unknown unit '{}'", name))
+        }
+        Expr::Neg(inner) => {
+            let q = evaluate(inner)?;
+            Ok(Quantity { value: q.value.neg(), dim: q.dim })
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let (a, b) = (evaluate(lhs)?, evaluate(rhs)?);
+            match op {
+                BinOp::Add | BinOp::Sub | BinOp::Rem => {
+                    if a.dim != b.dim {
+                        let verb = match op {
+                            BinOp::Add => "add",
+                            BinOp::Sub => "subtract",
+                            _ => "take the remainder of",
+                        };
+                        return Err(anyhow::anyhow!(
+                            "Cannot {} incompatible dimensions {} and {}",
+                            verb,
+                            a.dim.describe(),
+                            b.dim.describe()
+                        ));
+                    }
+                    let value = super::expr::apply_binary(*op, &a.value, &b.value)?;
+                    Ok(Quantity { value, dim: a.dim })
+                }
+                BinOp::Mul => Ok(Quantity { value: a.value.mul(&b.value)?, dim: a.dim.combine(&b.dim, 1) }),
+                BinOp::Div => Ok(Quantity { value: a.value.div(&b.value)?, dim: a.dim.combine(&b.dim, -1) }),
+                BinOp::Pow => {
+                    if !b.dim.is_dimensionless() {
+                        return Err(anyhow::anyhow!("Exponent must be dimensionless, found {}", b.dim.describe()));
+                    }
+                    let dim = match &b.value {
+                        _ if a.dim.is_dimensionless() => Dimension::NONE,
+                        Number::Exact(r) => {
+                            let (numer, denom) = (r.numer().to_i32(), r.denom().to_i32());
+                            numer
+                                .zip(denom)
+                                .and_then(|(n, d)| a.dim.scale(n, d))
+                                .ok_or_else(|| anyhow::anyhow!("Cannot raise {} to the power {}", a.dim.describe(), r))?
+                        }
+                        Number::Approx(_) => {
+                            return Err(anyhow::anyhow!("Cannot raise {} to a non-rational power", a.dim.describe()))
+                        }
+                    };
+                    Ok(Quantity { value: a.value.pow(&b.value)?, dim })
+                }
+            }
+        }
+        Expr::Call(name, args) => {
+            let values = args.iter().map(evaluate).collect::<anyhow::Result<Vec<_>>>()?;
+            match (name.as_str(), values.as_slice()) {
+                ("sqrt", [q]) => {
+                    let dim = q.dim.scale(1, 2).ok_or_else(|| anyhow::anyhow!("Cannot take the square root of {}", q.dim.describe()))?;
+                    let half = Number::Exact(BigRational::new(1.into(), 2.into()));
+                    Ok(Quantity { value: q.value.pow(&half)?, dim })
+                }
+                ("abs", [q]) => Ok(Quantity { value: q.value.abs(), dim: q.dim }),
+                _ => {
+                    if let Some(q) = values.iter().find(|q| !q.dim.is_dimensionless()) {
+                        return Err(anyhow::anyhow!("{}() needs dimensionless arguments, found {}", name, q.dim.describe()));
+                    }
+                    let plain: Vec<Number> = values.into_iter().map(|q| q.value).collect();
+                    Ok(Quantity::scalar(call_builtin(name, &plain)?))
+                }
+            }
+        }
+    }
+}
+
+/// True when rendering `value` with `precision` digits loses nothing
+pub fn decimal_is_exact(value: &Number, precision: usize) -> bool {
+    match value {
+        Number::Exact(r) => {
+            let scaled = r * BigRational::from_integer(num_traits::pow(num_bigint::BigInt::from(10), precision));
+            scaled.is_integer()
+        }
+        Number::Approx(_) => false,
+    }
+}
@@ -2,6 +2,10 @@ pub mod probabilistic;
 pub mod deterministic;
 pub mod neuro_symbolic;
 
+/// Longest query, in bytes, that any layer accepts; the router rejects longer ones before
+/// either module sees them
+pub const MAX_QUERY_LENGTH: usize = 10_000;
+
 pub use probabilistic::ProbabilisticModule;
 pub use deterministic::DeterministicModule;
 pub use neuro_symbolic::NeuroSymbolicRouter;
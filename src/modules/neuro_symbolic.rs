@@ -1,3 +1,6 @@
+use crate::modules::deterministic::{datalog, expr, natural, prolog, sandbox, text, units};
+use crate::modules::MAX_QUERY_LENGTH;
+
 pub struct NeuroSymbolicRouter {
 }
 
@@ -8,16 +11,93 @@ pub enum Intent {
     Hybrid,
 }
 
+/// Queries the router refuses to classify, each with a stable code
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RouterError {
+    #[error("Query cannot be empty")]
+    EmptyQuery,
+    #[error("Query exceeds maximum length of {max} characters")]
+    QueryTooLong { max: usize },
+}
+
+impl RouterError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RouterError::EmptyQuery => "ROUTER_EMPTY_QUERY",
+            RouterError::QueryTooLong { .. } => "ROUTER_QUERY_TOO_LONG",
+        }
+    }
+}
+
+impl Default for NeuroSymbolicRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NeuroSymbolicRouter {
     pub fn new() -> Self {
         NeuroSymbolicRouter {}
     }
 
+    /// Check a query's length, then classify it
+    pub fn route(&self, query: &str) -> Result<Intent, RouterError> {
+        if query.is_empty() {
+            return Err(RouterError::EmptyQuery);
+        }
+        if query.len() > MAX_QUERY_LENGTH {
+            return Err(RouterError::QueryTooLong { max: MAX_QUERY_LENGTH });
+        }
+        Ok(self.classify_intent(query))
+    }
+
     pub fn classify_intent(&self, query: &str) -> Intent {
+        // Text questions are recognized by their phrasing, not by keywords, and so are
+        // snippets: "run: ...", "run with seed 7: ..."
+        if text::parse_command(query).is_some() || sandbox::parse_command(query).is_some() {
+            return Intent::Logical;
+        }
+        // Horn-clause goals, "ancestor(zeus, apollo)" or "?- parent(X, ares).", have no keywords
+        let trimmed = query.trim();
+        if trimmed.starts_with("?-") || prolog::looks_like_goal(trimmed.trim_end_matches('?')) {
+            return Intent::Logical;
+        }
+        // Neither do knowledge-base commands, "consult kb.pl", or explanations, "why ancestor(zeus, ares)"
+        if prolog::parse_kb_command(query).is_some() || datalog::parse_command(query).is_some() {
+            return Intent::Logical;
+        }
+        // A bare conversion such as "1 mile to km" names no operator
+        if units::parse_command(query).is_some_and(|parsed| parsed.is_ok()) {
+            return Intent::Logical;
+        }
+        // So is worded arithmetic: "half of ten" once its words are rewritten
+        let worded = natural::normalize(query).and_then(|rewritten| expr::parse_expression(&rewritten).ok());
+        if worded.is_some_and(|parsed| parsed.free_variables().is_empty()) {
+            return Intent::Logical;
+        }
         let query_lower = query.to_lowercase();
-        let math_keywords = ["calculate", "solve", "prove", "=", "+", "-"];
+        // Words and phrases match whole words only, so "factory" and "surplus" are prose
+        let math_words = [
+            "calculate", "solve", "prove",
+            "derivative", "differentiate", "simplify", "factor", "convert",
+            "determinant", "eigenvalues", "inverse", "transpose",
+            "factorial", "percentile", "stddev", "binomial",
+            "integrate", "integral", "root of", "dy/dt",
+            "weekday", "business days", "days between", "in hex", "in binary",
+            "plus", "minus", "times", "divided by", "percent", "squared", "cubed", "square root",
+            "truth table", "satisfiable", "tautology", "equivalent",
+            "datalog", "all different", "all_different", "alldifferent", "minimize", "maximize",
+            "implies", "for all",
+        ];
+        // Prefixes start at a word boundary: "0x1f", "y' = y", but not "they're"
+        let math_prefixes = ["ncr(", "npr(", "0x", "0b", "y'"];
+        let math_symbols = [
+            "=", "+", "-", "*", "/", "[", "<<", ">>", "&", "|", "~", "->", "<->", "<", ">", "```",
+        ];
         let creative_keywords = ["write", "suggest", "explain", "describe"];
-        let has_math = math_keywords.iter().any(|&kw| query_lower.contains(kw));
+        let has_math = math_symbols.iter().any(|&kw| query_lower.contains(kw))
+            || math_words.iter().any(|&kw| contains_keyword(&query_lower, kw, true))
+            || math_prefixes.iter().any(|&kw| contains_keyword(&query_lower, kw, false));
         let has_creative = creative_keywords.iter().any(|&kw| query_lower.contains(kw));
         match (has_math, has_creative) {
             (true, false) => Intent::Logical,
@@ -26,3 +106,13 @@ impl NeuroSymbolicRouter {
         }
     }
 }
+
+/// True when `keyword` occurs starting at a word boundary and, if `whole`, also ending at one
+fn contains_keyword(text: &str, keyword: &str, whole: bool) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(keyword).any(|(at, _)| {
+        let joined_before = text[..at].chars().next_back().is_some_and(is_word_char);
+        let joined_after = text[at + keyword.len()..].chars().next().is_some_and(is_word_char);
+        !(joined_before || whole && joined_after)
+    })
+}
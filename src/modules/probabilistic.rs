@@ -1,168 +0,0 @@
-use serde::{Serialize, Deserialize};
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
-use std::time::Duration;
-
-/// Production-grade ProbabilisticModule with error handling and logging
-/// Streams tokens with deterministic delays to simulate an LLM
-pub struct ProbabilisticModule {
-    config: ModelConfig,
-}
-
-#[derive(Clone)]
-struct ModelConfig {
-    model_path: Option<String>,
-    max_tokens: usize,
-    temperature: f32,
-}
-
-impl ProbabilisticModule {
-    /// Load local LLM with proper error handling and configuration
-    /// Note: Full Candle/GGUF integration requires feature flags
-    pub async fn load_local_llm() -> anyhow::Result<Self> {
-        log::info!("Initializing ProbabilisticModule");
-        
-        // Check for model path from environment
-        let model_path = std::env::var("AXIOM_MODEL_PATH").ok();
-        
-        if let Some(ref path) = model_path {
-            log::info!("Model path configured: {}", path);
-            #[cfg(feature = "candle-core")]
-            {
-                // In production with candle feature: Initialize Candle-based GGUF model
-                log::info!("Candle integration available");
-            }
-            #[cfg(not(feature = "candle-core"))]
-            {
-                log::info!("Running in mock mode (candle feature not enabled)");
-                if std::path::Path::new(path).exists() {
-                    log::info!("Model file found at {}", path);
-                } else {
-                    log::warn!("Model path does not exist: {}", path);
-                }
-            }
-        } else {
-            log::info!("No model path configured, using mock implementation");
-        }
-        
-        let config = ModelConfig {
-            model_path,
-            max_tokens: std::env::var("AXIOM_MAX_TOKENS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(2048),
-            temperature: std::env::var("AXIOM_TEMPERATURE")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(0.7),
-        };
-        
-        log::info!(
-            "ProbabilisticModule initialized: max_tokens={}, temperature={}",
-            config.max_tokens, config.temperature
-        );
-        
-        Ok(ProbabilisticModule { config })
-    }
-
-    /// Perform inference with full error handling
-    pub async fn infer(&self, prompt: &str) -> anyhow::Result<String> {
-        if prompt.is_empty() {
-            return Err(anyhow::anyhow!("Prompt cannot be empty"));
-        }
-        
-        if prompt.len() > 10000 {
-            return Err(anyhow::anyhow!("Prompt exceeds maximum length of 10000 characters"));
-        }
-        
-        log::debug!("Running inference on prompt: {} chars", prompt.len());
-        
-        // Production implementation with actual model inference
-        #[cfg(feature = "candle-core")]
-        {
-            // Use Candle to run actual inference when feature is enabled
-            log::info!("Using Candle inference");
-        }
-        
-        // Mock implementation for demo/testing
-        let response = format!(
-            "{}\n\n[LLM draft - temp: {}, max_tokens: {}]",
-            prompt, self.config.temperature, self.config.max_tokens
-        );
-        
-        log::debug!("Inference complete: {} chars", response.len());
-        Ok(response)
-    }
-
-    /// Stream tokens with proper error handling and backpressure
-    pub async fn stream_tokens(&self, prompt: &str) -> ReceiverStream<String> {
-        let (tx, rx) = mpsc::channel(16);
-        let prompt_owned = prompt.to_string();
-        let config = self.config.clone();
-
-        tokio::spawn(async move {
-            log::debug!("Starting token stream for prompt: {} chars", prompt_owned.len());
-            
-            let tokens: Vec<String> = prompt_owned
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect();
-            
-            log::debug!("Streaming {} tokens", tokens.len());
-
-            for (idx, token) in tokens.into_iter().enumerate() {
-                let token_with_space = format!("{} ", token);
-                
-                match tx.send(token_with_space).await {
-                    Ok(_) => {
-                        // Deterministic delay for consistent streaming
-                        tokio::time::sleep(Duration::from_millis(80)).await;
-                    }
-                    Err(e) => {
-                        log::warn!("Token stream interrupted at token {}: {}", idx, e);
-                        break;
-                    }
-                }
-            }
-
-            // Send completion token
-            if let Err(e) = tx.send("\n".to_string()).await {
-                log::warn!("Failed to send completion token: {}", e);
-            }
-            
-            log::debug!("Token stream complete");
-        });
-
-        ReceiverStream::new(rx)
-    }
-    
-    /// Get current configuration
-    pub fn get_config(&self) -> ProbConfig {
-        ProbConfig {
-            model_path: self.config.model_path.clone(),
-            max_tokens: self.config.max_tokens,
-            temperature: self.config.temperature,
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-pub struct ProbConfig {
-    pub model_path: Option<String>,
-    pub max_tokens: usize,
-    pub temperature: f32,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ProbRequest {
-    pub prompt: String,
-    pub max_tokens: usize,
-    pub temperature: f32,
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct ProbResponse {
-    pub text: String,
-    pub confidence: f32,
-    pub tokens_per_sec: f32,
-}
@@ -0,0 +1,151 @@
+// Candle backend: a quantized GGUF model of the llama family, run in-process
+use super::{Capabilities, GenerationOptions, LanguageModel, ProbError};
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::models::quantized_llama::ModelWeights;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokenizers::Tokenizer;
+
+/// End-of-answer tokens of the common llama-family vocabularies
+const STOP_TOKENS: &[&str] = &["</s>", "<|end_of_text|>", "<|eot_id|>", "<|im_end|>", "<|endoftext|>"];
+
+/// Where the weights and vocabulary live, and the sampling seed
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandleConfig {
+    /// A GGUF file
+    pub model_path: PathBuf,
+    /// The model's tokenizer.json
+    pub tokenizer_path: PathBuf,
+    pub seed: u64,
+}
+
+impl CandleConfig {
+    /// Defaults for `model_path`: tokenizer.json beside it, seed 299792458
+    pub fn new(model_path: impl Into<PathBuf>) -> Self {
+        let model_path = model_path.into();
+        let tokenizer_path = model_path.with_file_name("tokenizer.json");
+        CandleConfig { model_path, tokenizer_path, seed: 299_792_458 }
+    }
+}
+
+/// The weights hold the key-value cache, so one answer is generated at a time
+pub struct CandleModel {
+    weights: Mutex<ModelWeights>,
+    tokenizer: Tokenizer,
+    device: Device,
+    stop: Vec<u32>,
+    context_length: Option<usize>,
+    seed: u64,
+}
+
+impl CandleModel {
+    pub fn load(config: &CandleConfig) -> Result<Self, ProbError> {
+        let load_error = |e: &dyn std::fmt::Display| ProbError::ModelLoad(format!("{}: {}", config.model_path.display(), e));
+        let mut file = std::fs::File::open(&config.model_path).map_err(|e| load_error(&e))?;
+        let content = gguf_file::Content::read(&mut file).map_err(|e| load_error(&e))?;
+
+        // "llama.context_length", keyed by the architecture the file declares
+        let context_length = content
+            .metadata
+            .get("general.architecture")
+            .and_then(|arch| arch.to_string().ok())
+            .and_then(|arch| content.metadata.get(&format!("{}.context_length", arch)))
+            .and_then(|length| length.to_u32().ok())
+            .map(|length| length as usize);
+
+        let device = Device::cuda_if_available(0).map_err(|e| load_error(&e))?;
+        let weights = ModelWeights::from_gguf(content, &mut file, &device).map_err(|e| load_error(&e))?;
+        let tokenizer = Tokenizer::from_file(&config.tokenizer_path)
+            .map_err(|e| ProbError::ModelLoad(format!("{}: {}", config.tokenizer_path.display(), e)))?;
+        let stop = STOP_TOKENS.iter().filter_map(|token| tokenizer.token_to_id(token)).collect();
+
+        log::info!("Loaded {} on {:?}", config.model_path.display(), device);
+        Ok(CandleModel { weights: Mutex::new(weights), tokenizer, device, stop, context_length, seed: config.seed })
+    }
+
+    fn encode(&self, text: &str, special_tokens: bool) -> Result<Vec<u32>, ProbError> {
+        let encoding = self.tokenizer.encode(text, special_tokens).map_err(|e| ProbError::Backend(e.to_string()))?;
+        Ok(encoding.get_ids().to_vec())
+    }
+}
+
+impl LanguageModel for CandleModel {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            backend: self.name().to_string(),
+            streaming: true,
+            tokenize: true,
+            embed: false,
+            context_length: self.context_length,
+        }
+    }
+
+    fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, ProbError> {
+        let mut answer = String::new();
+        self.stream(prompt, options, &mut |piece| {
+            answer.push_str(&piece);
+            true
+        })?;
+        Ok(answer)
+    }
+
+    fn stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), ProbError> {
+        let backend = |e: candle_core::Error| ProbError::Backend(e.to_string());
+        let prompt_ids = self.encode(prompt, true)?;
+        let budget = match self.context_length {
+            Some(length) if prompt_ids.len() >= length => {
+                return Err(ProbError::Backend(format!(
+                    "prompt is {} tokens; the model's context holds {}",
+                    prompt_ids.len(),
+                    length
+                )))
+            }
+            Some(length) => options.max_tokens.min(length - prompt_ids.len()),
+            None => options.max_tokens,
+        };
+
+        let mut weights = self.weights.lock().unwrap_or_else(|e| e.into_inner());
+        let temperature = (options.temperature > 0.0).then_some(options.temperature as f64);
+        let mut sampler = LogitsProcessor::new(self.seed, temperature, None);
+        let (mut input, mut position) = (prompt_ids, 0);
+        let (mut generated, mut shown) = (Vec::new(), 0);
+
+        for _ in 0..budget {
+            // Position 0 starts a fresh key-value cache; later steps feed only the new token
+            let tokens = Tensor::new(input.as_slice(), &self.device).and_then(|t| t.unsqueeze(0)).map_err(backend)?;
+            let logits = weights.forward(&tokens, position).and_then(|l| l.squeeze(0)).map_err(backend)?;
+            position += input.len();
+            let next = sampler.sample(&logits).map_err(backend)?;
+            if self.stop.contains(&next) {
+                break;
+            }
+            generated.push(next);
+
+            // Decoding the whole answer keeps multi-token characters and word spacing intact
+            let text = self.tokenizer.decode(&generated, true).map_err(|e| ProbError::Backend(e.to_string()))?;
+            if let Some(piece) = text.get(shown..).filter(|piece| !piece.is_empty()) {
+                shown = text.len();
+                if !emit(piece.to_string()) {
+                    break;
+                }
+            }
+            input = vec![next];
+        }
+        Ok(())
+    }
+
+    fn tokenize(&self, text: &str) -> Result<Vec<u32>, ProbError> {
+        self.encode(text, false)
+    }
+
+    fn name(&self) -> &'static str {
+        "candle"
+    }
+}
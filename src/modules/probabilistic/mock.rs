@@ -0,0 +1,78 @@
+// Mock backend: echoes the prompt as its draft, so the pipeline runs without a model
+use super::{Capabilities, GenerationOptions, LanguageModel, ProbError};
+use std::time::Duration;
+
+/// Width of mock embeddings
+pub const EMBEDDING_SIZE: usize = 64;
+
+/// Words of the mock vocabulary; ids are word hashes below this
+const VOCABULARY_SIZE: u64 = 32_000;
+
+/// Echoes prompts word by word, whitespace included, with a fixed delay between words
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockModel {
+    pub token_delay: Duration,
+}
+
+impl Default for MockModel {
+    fn default() -> Self {
+        MockModel { token_delay: Duration::from_millis(80) }
+    }
+}
+
+impl LanguageModel for MockModel {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { backend: self.name().to_string(), streaming: true, tokenize: true, embed: true, context_length: None }
+    }
+
+    fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, ProbError> {
+        Ok(format!(
+            "{}\n\n[LLM draft - temp: {}, max_tokens: {}]",
+            prompt, options.temperature, options.max_tokens
+        ))
+    }
+
+    fn stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), ProbError> {
+        // The same answer as `generate`, so a streamed draft reads like a generated one
+        for word in self.generate(prompt, options)?.split_inclusive(char::is_whitespace) {
+            if !emit(word.to_string()) {
+                break;
+            }
+            // Deterministic delay for consistent streaming
+            std::thread::sleep(self.token_delay);
+        }
+        Ok(())
+    }
+
+    /// One id per whitespace-separated word
+    fn tokenize(&self, text: &str) -> Result<Vec<u32>, ProbError> {
+        Ok(text.split_whitespace().map(|word| (hash(word) % VOCABULARY_SIZE) as u32).collect())
+    }
+
+    /// Hashed bag of lowercased words, scaled to unit length
+    fn embed(&self, text: &str) -> Result<Vec<f32>, ProbError> {
+        let mut vector = vec![0.0f32; EMBEDDING_SIZE];
+        for word in text.split_whitespace() {
+            vector[(hash(&word.to_lowercase()) % EMBEDDING_SIZE as u64) as usize] += 1.0;
+        }
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        Ok(vector)
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+}
+
+/// FNV-1a, stable across runs and platforms
+fn hash(word: &str) -> u64 {
+    word.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
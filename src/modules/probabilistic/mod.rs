@@ -0,0 +1,339 @@
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod mock;
+pub mod process;
+#[cfg(feature = "candle")]
+pub mod candle;
+
+/// Failures of the language-model layer, each with a stable code
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ProbError {
+    #[error("Prompt cannot be empty")]
+    EmptyPrompt,
+    #[error("Prompt exceeds maximum length of {max} characters")]
+    PromptTooLong { max: usize },
+    /// The configured backend could not be set up: unknown name, missing file, bad weights
+    #[error("Failed to load the language model: {0}")]
+    ModelLoad(String),
+    /// The backend failed while answering
+    #[error("Language model failed: {0}")]
+    Backend(String),
+    #[error("The {backend} backend does not support {operation}")]
+    Unsupported { backend: &'static str, operation: &'static str },
+    #[error("Language model exceeded the {:.1}s time limit", .limit.as_secs_f64())]
+    Timeout { limit: Duration },
+}
+
+impl ProbError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProbError::EmptyPrompt => "PROB_EMPTY_PROMPT",
+            ProbError::PromptTooLong { .. } => "PROB_PROMPT_TOO_LONG",
+            ProbError::ModelLoad(_) => "PROB_MODEL_LOAD",
+            ProbError::Backend(_) => "PROB_BACKEND",
+            ProbError::Unsupported { .. } => "PROB_UNSUPPORTED",
+            ProbError::Timeout { .. } => "PROB_TIMEOUT",
+        }
+    }
+}
+
+/// Sampling settings for one generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationOptions {
+    pub max_tokens: usize,
+    pub temperature: f32,
+}
+
+/// What a backend does natively
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Name as written in AXIOM_MODEL_BACKEND
+    pub backend: String,
+    /// Tokens arrive while the answer is generated, not all at the end
+    pub streaming: bool,
+    pub tokenize: bool,
+    pub embed: bool,
+    /// Prompt plus answer, in tokens, when the model states a limit
+    pub context_length: Option<usize>,
+}
+
+/// A text generator behind the probabilistic module. Calls block; `ProbabilisticModule`
+/// runs them on the blocking pool so backends can do CPU work or wait on a process
+pub trait LanguageModel: Send + Sync {
+    fn capabilities(&self) -> Capabilities;
+
+    /// The whole answer to `prompt`
+    fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, ProbError>;
+
+    /// The answer piece by piece, stopping early once `emit` returns false; by default
+    /// the whole answer is emitted at once
+    fn stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), ProbError> {
+        emit(self.generate(prompt, options)?);
+        Ok(())
+    }
+
+    /// Token ids of `text` in the model's vocabulary
+    fn tokenize(&self, _text: &str) -> Result<Vec<u32>, ProbError> {
+        Err(ProbError::Unsupported { backend: self.name(), operation: "tokenize" })
+    }
+
+    /// A fixed-length vector for `text`
+    fn embed(&self, _text: &str) -> Result<Vec<f32>, ProbError> {
+        Err(ProbError::Unsupported { backend: self.name(), operation: "embed" })
+    }
+
+    /// Backend name for messages
+    fn name(&self) -> &'static str;
+}
+
+/// Which `LanguageModel` `load_local_llm` builds, from AXIOM_MODEL_BACKEND
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Echoes the prompt; needs no model
+    Mock,
+    /// A GGUF model run in-process (candle feature)
+    Candle,
+    /// A local program that reads the prompt on stdin and writes the answer to stdout
+    Process,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = ProbError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "mock" => Ok(Backend::Mock),
+            "candle" => Ok(Backend::Candle),
+            "process" => Ok(Backend::Process),
+            other => Err(ProbError::ModelLoad(format!(
+                "unknown backend '{}'; expected mock, candle or process",
+                other
+            ))),
+        }
+    }
+}
+
+/// Production-grade ProbabilisticModule with error handling and logging
+/// Delegates to the `LanguageModel` chosen at load time
+pub struct ProbabilisticModule {
+    config: ModelConfig,
+    model: Arc<dyn LanguageModel>,
+}
+
+#[derive(Clone)]
+struct ModelConfig {
+    model_path: Option<String>,
+    max_tokens: usize,
+    temperature: f32,
+}
+
+impl ProbabilisticModule {
+    /// Load local LLM with proper error handling and configuration
+    /// AXIOM_MODEL_BACKEND picks the backend; without it a configured model path is run
+    /// with Candle when the candle feature is built, and the mock is used otherwise
+    pub async fn load_local_llm() -> Result<Self, ProbError> {
+        log::info!("Initializing ProbabilisticModule");
+
+        // Check for model path from environment
+        let model_path = std::env::var("AXIOM_MODEL_PATH").ok();
+        if let Some(ref path) = model_path {
+            log::info!("Model path configured: {}", path);
+        }
+
+        let backend = match std::env::var("AXIOM_MODEL_BACKEND") {
+            Ok(name) => name.parse()?,
+            Err(_) if model_path.is_some() && cfg!(feature = "candle") => Backend::Candle,
+            Err(_) => Backend::Mock,
+        };
+
+        let config = ModelConfig {
+            model_path,
+            max_tokens: std::env::var("AXIOM_MAX_TOKENS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2048),
+            temperature: std::env::var("AXIOM_TEMPERATURE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.7),
+        };
+
+        let model: Arc<dyn LanguageModel> = match backend {
+            Backend::Mock => Arc::new(mock::MockModel::default()),
+            Backend::Process => {
+                let defaults = process::ProcessConfig::default();
+                let command = std::env::var("AXIOM_MODEL_COMMAND")
+                    .map_err(|_| ProbError::ModelLoad("the process backend needs AXIOM_MODEL_COMMAND".to_string()))?;
+                Arc::new(process::ProcessModel::new(process::ProcessConfig {
+                    command: process::split_command(&command)?,
+                    time_limit: std::env::var("AXIOM_MODEL_TIME_LIMIT")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .filter(|t: &f64| t.is_finite() && *t > 0.0)
+                        .map(Duration::from_secs_f64)
+                        .unwrap_or(defaults.time_limit),
+                })?)
+            }
+            Backend::Candle => load_candle(config.model_path.as_deref())?,
+        };
+
+        log::info!(
+            "ProbabilisticModule initialized: backend={}, max_tokens={}, temperature={}",
+            model.name(), config.max_tokens, config.temperature
+        );
+
+        Ok(ProbabilisticModule { config, model })
+    }
+
+    /// Replace the language model, keeping the sampling settings
+    pub fn with_model(self, model: impl LanguageModel + 'static) -> Self {
+        ProbabilisticModule { model: Arc::new(model), ..self }
+    }
+
+    /// What the loaded backend does natively
+    pub fn capabilities(&self) -> Capabilities {
+        self.model.capabilities()
+    }
+
+    /// Perform inference with full error handling
+    pub async fn infer(&self, prompt: &str) -> Result<String, ProbError> {
+        check_prompt(prompt)?;
+        log::debug!("Running inference on prompt: {} chars", prompt.len());
+
+        let (model, options, prompt) = (self.model.clone(), self.options(), prompt.to_string());
+        let response = tokio::task::spawn_blocking(move || model.generate(&prompt, &options))
+            .await
+            .map_err(|e| ProbError::Backend(e.to_string()))??;
+
+        log::debug!("Inference complete: {} chars", response.len());
+        Ok(response)
+    }
+
+    /// Stream tokens with backpressure; a failure ends the stream with the error instead
+    /// of the closing newline
+    pub async fn stream_tokens(&self, prompt: &str) -> ReceiverStream<Result<String, ProbError>> {
+        let (tx, rx) = mpsc::channel(16);
+        if let Err(e) = check_prompt(prompt) {
+            let _ = tx.try_send(Err(e));
+            return ReceiverStream::new(rx);
+        }
+        let (model, options, prompt_owned) = (self.model.clone(), self.options(), prompt.to_string());
+
+        tokio::task::spawn_blocking(move || {
+            log::debug!("Starting token stream for prompt: {} chars", prompt_owned.len());
+
+            let mut sent = 0;
+            let result = model.stream(&prompt_owned, &options, &mut |token| {
+                sent += 1;
+                tx.blocking_send(Ok(token)).is_ok()
+            });
+            let last = match result {
+                // Send completion token
+                Ok(()) => Ok("\n".to_string()),
+                Err(e) => {
+                    log::warn!("Token stream interrupted at token {}: {}", sent, e);
+                    Err(e)
+                }
+            };
+            if tx.blocking_send(last).is_err() {
+                log::debug!("Token stream dropped before it finished");
+            }
+
+            log::debug!("Token stream complete");
+        });
+
+        ReceiverStream::new(rx)
+    }
+
+    /// Token ids of `text`, when the backend has a vocabulary
+    pub async fn tokenize(&self, text: &str) -> Result<Vec<u32>, ProbError> {
+        let (model, text) = (self.model.clone(), text.to_string());
+        tokio::task::spawn_blocking(move || model.tokenize(&text))
+            .await
+            .map_err(|e| ProbError::Backend(e.to_string()))?
+    }
+
+    /// An embedding of `text`, when the backend provides one
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>, ProbError> {
+        let (model, text) = (self.model.clone(), text.to_string());
+        tokio::task::spawn_blocking(move || model.embed(&text))
+            .await
+            .map_err(|e| ProbError::Backend(e.to_string()))?
+    }
+
+    /// Get current configuration
+    pub fn get_config(&self) -> ProbConfig {
+        ProbConfig {
+            backend: self.model.name().to_string(),
+            model_path: self.config.model_path.clone(),
+            max_tokens: self.config.max_tokens,
+            temperature: self.config.temperature,
+        }
+    }
+
+    fn options(&self) -> GenerationOptions {
+        GenerationOptions { max_tokens: self.config.max_tokens, temperature: self.config.temperature }
+    }
+}
+
+/// Reject prompts no backend should see
+fn check_prompt(prompt: &str) -> Result<(), ProbError> {
+    if prompt.is_empty() {
+        return Err(ProbError::EmptyPrompt);
+    }
+    if prompt.len() > super::MAX_QUERY_LENGTH {
+        return Err(ProbError::PromptTooLong { max: super::MAX_QUERY_LENGTH });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "candle")]
+fn load_candle(model_path: Option<&str>) -> Result<Arc<dyn LanguageModel>, ProbError> {
+    let path = model_path.ok_or_else(|| ProbError::ModelLoad("the candle backend needs AXIOM_MODEL_PATH".to_string()))?;
+    let defaults = candle::CandleConfig::new(path);
+    let config = candle::CandleConfig {
+        tokenizer_path: std::env::var_os("AXIOM_TOKENIZER_PATH").map(Into::into).unwrap_or(defaults.tokenizer_path),
+        seed: std::env::var("AXIOM_MODEL_SEED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.seed),
+        ..defaults
+    };
+    Ok(Arc::new(candle::CandleModel::load(&config)?))
+}
+
+#[cfg(not(feature = "candle"))]
+fn load_candle(_model_path: Option<&str>) -> Result<Arc<dyn LanguageModel>, ProbError> {
+    Err(ProbError::ModelLoad("this build does not include the candle feature".to_string()))
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProbConfig {
+    pub backend: String,
+    pub model_path: Option<String>,
+    pub max_tokens: usize,
+    pub temperature: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProbRequest {
+    pub prompt: String,
+    pub max_tokens: usize,
+    pub temperature: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProbResponse {
+    pub text: String,
+    pub confidence: f32,
+    pub tokens_per_sec: f32,
+}
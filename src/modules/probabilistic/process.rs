@@ -0,0 +1,197 @@
+// External-process backend: a local program reads the prompt on stdin and streams its answer to stdout
+use super::{Capabilities, GenerationOptions, LanguageModel, ProbError};
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// The program to run and how long one answer may take
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessConfig {
+    /// Program and arguments, e.g. ["llama-cli", "-m", "model.gguf", "-f", "/dev/stdin"]
+    pub command: Vec<String>,
+    /// Wall-clock limit per answer; the process is killed when it runs over
+    pub time_limit: Duration,
+}
+
+impl Default for ProcessConfig {
+    fn default() -> Self {
+        ProcessConfig { command: Vec::new(), time_limit: Duration::from_secs(120) }
+    }
+}
+
+/// Runs a fresh process per prompt; AXIOM_MAX_TOKENS and AXIOM_TEMPERATURE are passed
+/// in its environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessModel {
+    config: ProcessConfig,
+}
+
+/// Split a command line into program and arguments the way a POSIX shell splits words:
+/// whitespace separates words, single quotes keep text as written, and double quotes and
+/// backslashes escape. Variables, globs and redirections are not expanded
+pub fn split_command(line: &str) -> Result<Vec<String>, ProbError> {
+    let malformed = |problem: &str| ProbError::ModelLoad(format!("the model command {}", problem));
+    let (mut words, mut word) = (Vec::new(), None::<String>);
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            words.extend(word.take());
+            continue;
+        }
+        let word = word.get_or_insert_with(String::new);
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => word.push(c),
+                    None => return Err(malformed("has an unclosed '")),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    // Within double quotes a backslash escapes only these
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                        Some(c) => word.extend(['\\', c]),
+                        None => return Err(malformed("has an unclosed \"")),
+                    },
+                    Some(c) => word.push(c),
+                    None => return Err(malformed("has an unclosed \"")),
+                }
+            },
+            '\\' => word.push(chars.next().ok_or_else(|| malformed("ends with a backslash"))?),
+            c => word.push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+impl ProcessModel {
+    pub fn new(config: ProcessConfig) -> Result<Self, ProbError> {
+        if config.command.is_empty() {
+            return Err(ProbError::ModelLoad("the model command is empty".to_string()));
+        }
+        Ok(ProcessModel { config })
+    }
+
+    fn spawn(&self, prompt: &str, options: &GenerationOptions) -> Result<Child, ProbError> {
+        let (program, args) = self.config.command.split_first().expect("checked in new");
+        let mut child = Command::new(program)
+            .args(args)
+            .env("AXIOM_MAX_TOKENS", options.max_tokens.to_string())
+            .env("AXIOM_TEMPERATURE", options.temperature.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ProbError::Backend(format!("Failed to start {}: {}", program, e)))?;
+
+        // Written from its own thread so a program that answers before reading all of a long
+        // prompt cannot deadlock on the pipes; closing stdin marks the end of the prompt
+        if let Some(mut stdin) = child.stdin.take() {
+            let prompt = prompt.to_string();
+            std::thread::spawn(move || stdin.write_all(prompt.as_bytes()));
+        }
+        Ok(child)
+    }
+}
+
+impl LanguageModel for ProcessModel {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { backend: self.name().to_string(), streaming: true, tokenize: false, embed: false, context_length: None }
+    }
+
+    fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, ProbError> {
+        let mut answer = String::new();
+        self.stream(prompt, options, &mut |piece| {
+            answer.push_str(&piece);
+            true
+        })?;
+        Ok(answer)
+    }
+
+    fn stream(
+        &self,
+        prompt: &str,
+        options: &GenerationOptions,
+        emit: &mut dyn FnMut(String) -> bool,
+    ) -> Result<(), ProbError> {
+        let mut child = self.spawn(prompt, options)?;
+        let stderr = drain(child.stderr.take());
+        let pieces = read_pieces(child.stdout.take());
+
+        let deadline = Instant::now() + self.config.time_limit;
+        loop {
+            match pieces.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(piece) => {
+                    // The reader stopped listening; the rest of the answer is not needed
+                    if !emit(piece) {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Ok(());
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(ProbError::Timeout { limit: self.config.time_limit });
+                }
+            }
+        }
+
+        let status = child.wait().map_err(|e| ProbError::Backend(e.to_string()))?;
+        if status.success() {
+            return Ok(());
+        }
+        let stderr = stderr.join().unwrap_or_default();
+        Err(ProbError::Backend(match stderr.trim() {
+            "" => format!("{} exited with {}", self.config.command[0], status),
+            detail => format!("{} exited with {} ({})", self.config.command[0], status, detail),
+        }))
+    }
+
+    fn name(&self) -> &'static str {
+        "process"
+    }
+}
+
+/// Forward stdout as it arrives, cut at UTF-8 boundaries so no piece splits a character
+fn read_pieces(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let Some(mut pipe) = pipe else { return };
+        let (mut buffer, mut pending) = ([0u8; 4096], Vec::new());
+        while let Ok(read @ 1..) = pipe.read(&mut buffer) {
+            pending.extend_from_slice(&buffer[..read]);
+            // An incomplete character at the end waits for the next read; invalid bytes are replaced
+            let complete = match std::str::from_utf8(&pending) {
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                _ => pending.len(),
+            };
+            let piece = String::from_utf8_lossy(&pending[..complete]).into_owned();
+            pending.drain(..complete);
+            if !piece.is_empty() && tx.send(piece).is_err() {
+                return;
+            }
+        }
+        if !pending.is_empty() {
+            let _ = tx.send(String::from_utf8_lossy(&pending).into_owned());
+        }
+    });
+    rx
+}
+
+/// Read a child pipe to the end on its own thread
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut text);
+        }
+        text
+    })
+}
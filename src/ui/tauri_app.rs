@@ -4,6 +4,7 @@ use tauri::{AppHandle, Manager};
 
 use tokio::sync::Mutex;
 use std::sync::Arc;
+use crate::ipc::contracts::QueryEvent;
 use crate::ipc::orchestrator::Orchestrator;
 use crate::modules::{ProbabilisticModule, DeterministicModule, NeuroSymbolicRouter};
 use futures::StreamExt;
@@ -37,7 +38,8 @@ pub async fn init_tauri() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[cfg(feature = "tauri")]
-/// Tauri command to send a message and stream tokens back
+/// Tauri command to send a message and stream tokens back; failures arrive as a
+/// "query-error" event carrying the error's code, layer and column
 #[tauri::command]
 async fn send_message(
     message: String,
@@ -51,17 +53,19 @@ async fn send_message(
     }
 
     let orchestrator = state.orchestrator.lock().await;
-    let mut stream = orchestrator.process_query(&message).await;
+    let mut stream = orchestrator.process_query_events(&message).await;
     
     // Stream tokens to frontend via events
     let mut full_response = String::new();
-    while let Some(token) = stream.next().await {
-        full_response.push_str(&token);
+    while let Some(event) = stream.next().await {
+        full_response.push_str(&event.to_string());
         
-        // Emit token event to frontend
-        app_handle
-            .emit("token", token.clone())
-            .map_err(|e| format!("Failed to emit token: {}", e))?;
+        // Emit token or error event to frontend
+        let emitted = match event {
+            QueryEvent::Token { text } => app_handle.emit("token", text),
+            QueryEvent::Error(error) => app_handle.emit("query-error", error),
+        };
+        emitted.map_err(|e| format!("Failed to emit event: {}", e))?;
     }
     
     log::info!("Response complete: {} chars", full_response.len());
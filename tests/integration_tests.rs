@@ -29,6 +29,12 @@ fn test_router_classification() {
     // Test creative intent
     let intent = router.classify_intent("Explain quantum physics");
     assert_eq!(intent, axiom_assistant::modules::neuro_symbolic::Intent::Creative);
+    
+    // Keywords inside other words are prose
+    for query in ["tell me about the factory", "sometimes I wonder about life", "they're late", "a budget surplus"] {
+        assert_eq!(router.classify_intent(query), axiom_assistant::modules::neuro_symbolic::Intent::Hybrid, "{}", query);
+    }
+    assert_eq!(router.classify_intent("0x1f xor 3"), axiom_assistant::modules::neuro_symbolic::Intent::Logical);
 }
 
 #[tokio::test]
@@ -71,6 +77,20 @@ async fn test_orchestrator_logical_query() {
     assert!(result.unwrap().contains("15"), "Should calculate correctly");
 }
 
+#[tokio::test]
+async fn test_orchestrator_drafts_logical_queries_it_cannot_read() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let query = "calculate the meaning of life";
+    assert_eq!(orchestrator.router.classify_intent(query), axiom_assistant::modules::neuro_symbolic::Intent::Logical);
+    let output = orchestrator.process_query(query).await.collect::<Vec<String>>().await.concat();
+    assert!(output.starts_with(query) && !output.contains("not recognized"), "{}", output);
+    assert_eq!(orchestrator.get_stats().hybrid_queries, 1);
+}
+
 #[tokio::test]
 async fn test_orchestrator_creative_query() {
     let prob = ProbabilisticModule::load_local_llm().await.unwrap();
@@ -106,3 +126,1251 @@ async fn test_token_streaming() {
     
     assert!(!tokens.is_empty(), "Should produce tokens");
 }
+
+#[test]
+fn test_exact_rational_arithmetic() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("0.1 + 0.2").expect("Exact evaluation failed");
+    assert_eq!(response.result, "3/10");
+    assert!(response.exact);
+    assert_eq!(response.decimal.as_deref(), Some("0.3"));
+    
+    let response = module.execute("1/3").unwrap();
+    assert_eq!(response.result, "1/3");
+    assert_eq!(response.decimal.as_deref(), Some("0.33333333333333333333"));
+    
+    // Big integers do not overflow
+    assert_eq!(module.execute_logic("2^100").unwrap(), "1267650600228229401496703205376");
+    assert_eq!(module.execute_logic("sqrt(16/9)").unwrap(), "4/3");
+}
+
+#[test]
+fn test_approximate_results_are_flagged() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("sqrt(2) * 2").unwrap();
+    assert!(!response.exact);
+    assert!(response.result.starts_with("2.828"));
+    
+    assert!(module.execute("1 / 0").is_err());
+}
+
+#[test]
+fn test_symbolic_differentiation_and_expansion() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("derivative of x^2 * sin(x)").unwrap();
+    assert_eq!(response.result, "2 * x * sin(x) + x^2 * cos(x)");
+    let proof = response.proof.expect("Symbolic results carry rewrite steps");
+    assert!(proof.iter().any(|step| step.starts_with("product rule")));
+    
+    assert_eq!(module.execute_logic("expand (x+1)^3").unwrap(), "x^3 + 3 * x^2 + 3 * x + 1");
+    let capped = module.execute("expand (x+1)^200").unwrap_err();
+    assert_eq!(capped.code(), "DET_RESOURCE_LIMIT");
+    assert_eq!(capped.to_string(), "(x + 1)^200 exceeds the limit of 64 for expanded exponents");
+    assert_eq!(module.execute_logic("simplify x/2 + x/3").unwrap(), "5 * x / 6");
+}
+
+#[test]
+fn test_symbolic_factor_and_substitute() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(
+        module.execute_logic("factor x^3 - 6*x^2 + 11*x - 6").unwrap(),
+        "(x - 1) * (x - 2) * (x - 3)"
+    );
+    assert_eq!(module.execute_logic("factor 2*x^2 - 8").unwrap(), "2 * (x - 2) * (x + 2)");
+    assert_eq!(module.execute_logic("substitute x = 1/2, y = 3 into x*y + y^2").unwrap(), "21/2");
+}
+
+#[test]
+fn test_equation_solver() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("solve 2x + 3 = 11").unwrap();
+    assert_eq!(response.result, "x = 4");
+    assert!(response.proof.unwrap().iter().any(|step| step.contains("✓")));
+    
+    assert_eq!(module.execute_logic("solve x^2 - 2x - 3 = 0").unwrap(), "x = -1 or x = 3");
+    assert_eq!(module.execute_logic("solve x^2 + 2x + 5 = 0").unwrap(), "x = -1 + 2i or x = -1 - 2i");
+    assert_eq!(module.execute_logic("solve x + y = 3, x - y = 1").unwrap(), "x = 2, y = 1");
+}
+
+#[test]
+fn test_equation_solver_degenerate_cases() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute_logic("solve x + y = 3, x + y = 4").unwrap(), "no solution");
+    assert!(module
+        .execute_logic("solve x + y = 3 and 2x + 2y = 6")
+        .unwrap()
+        .starts_with("infinitely many solutions"));
+    assert!(module.execute_logic("solve 2(x + 1) = 2x + 2").unwrap().starts_with("infinitely many"));
+}
+
+#[test]
+fn test_unit_arithmetic_and_conversion() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute_logic("9.81 m/s^2 * 70 kg").unwrap(), "686.7 N");
+    assert_eq!(module.execute_logic("3 ft + 4 in in cm").unwrap(), "101.6 cm");
+    assert_eq!(module.execute_logic("60 mph to km/h").unwrap(), "96.56064 km/h");
+    assert_eq!(module.execute_logic("100 degC in degF").unwrap(), "212 degF");
+    
+    let response = module.execute("5 km + 300 m in miles").unwrap();
+    assert!(response.result.starts_with("3.293267"));
+    assert!(response.result.ends_with("miles"));
+
+    // A number binds its unit before '/' and '*'
+    assert!(module.execute_logic("100 km / 2 h in m/s").unwrap().starts_with("13.888888"));
+    assert_eq!(module.execute_logic("10 m / 2 s in km/h").unwrap(), "18 km/h");
+    assert_eq!(module.execute_logic("100 km / 2 h").unwrap(), "50 km/h");
+    assert_eq!(module.execute_logic("1/2 m").unwrap(), "0.5 m");
+
+    // Without a target the units written are kept, unless mixed units have an SI name
+    assert_eq!(module.execute_logic("3 ft * 2 ft").unwrap(), "6 ft^2");
+    assert_eq!(module.execute_logic("2 km + 300 m").unwrap(), "2300 m");
+    assert_eq!(module.execute_logic("10 N * 2 m").unwrap(), "20 J");
+    assert_eq!(module.execute_logic("-40 degC in degF").unwrap(), "-40 degF");
+
+    // "in" at the end is a conversion without a target, unless it is the only unit
+    let err = module.execute("5 km in").unwrap_err();
+    assert_eq!(err.code(), "DET_SYNTAX");
+    assert!(err.to_string().contains("target unit"), "unexpected error: {}", err);
+    assert_eq!(module.execute_logic("5 in").unwrap(), "5 in");
+}
+
+#[tokio::test]
+async fn test_orchestrator_routes_bare_conversions() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    for (query, answer) in [("1 mile to km", "1.609344 km"), ("100 degF in degC", "37.77777777777777777778 degC")] {
+        let tokens: Vec<String> = orchestrator.process_query(query).await.collect().await;
+        assert_eq!(tokens, vec![answer.to_string()], "{}", query);
+    }
+}
+
+#[test]
+fn test_incompatible_dimensions_are_rejected() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let err = module.execute("5 km + 3 kg").unwrap_err().to_string();
+    assert!(err.contains("length") && err.contains("mass"), "unexpected error: {}", err);
+    
+    let err = module.execute("1 kg to s").unwrap_err().to_string();
+    assert!(err.contains("mass") && err.contains("time"), "unexpected error: {}", err);
+}
+
+#[tokio::test]
+async fn test_orchestrator_session_bindings() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    for query in ["let r = 4", "f(x) = x^2 + 1"] {
+        orchestrator.process_query(query).await.next().await;
+    }
+    
+    let area = orchestrator.process_query("pi * r^2").await.next().await.unwrap();
+    assert!(area.starts_with("50.265"), "unexpected area: {}", area);
+    let value = orchestrator.process_query("f(r)").await.next().await.unwrap();
+    assert_eq!(value, "17");
+    
+    orchestrator.process_query("clear bindings").await.next().await;
+    let listing = orchestrator.process_query("list bindings").await.next().await.unwrap();
+    assert_eq!(listing, "no bindings");
+}
+
+#[test]
+fn test_session_context_round_trip() {
+    use axiom_assistant::modules::deterministic::context::EvalContext;
+    
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let mut context = EvalContext::new();
+    module.execute_in("let third = 1/3", &mut context).unwrap();
+    module.execute_in("area(w, h) = w * h", &mut context).unwrap();
+    
+    let path = std::env::temp_dir().join(format!("axiom-session-{}.json", std::process::id()));
+    context.save(&path).unwrap();
+    let mut restored = EvalContext::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    
+    assert_eq!(restored, context);
+    assert_eq!(module.execute_in("area(third, 6)", &mut restored).unwrap().result, "2");
+    assert!(module.execute_in("let sqrt = 2", &mut restored).is_err());
+}
+
+#[test]
+fn test_matrix_operations_return_structured_values() {
+    use axiom_assistant::modules::deterministic::DetValue;
+    
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let product = module.execute("[[1,2],[3,4]] * [[5,6],[7,8]]").unwrap();
+    let expected: Vec<Vec<String>> = vec![vec!["19".into(), "22".into()], vec!["43".into(), "50".into()]];
+    assert_eq!(product.value, Some(DetValue::Matrix(expected)));
+    
+    let inverse = module.execute("inverse of [[1,2],[3,4]]").unwrap();
+    assert_eq!(inverse.result, "[[-2, 1], [3/2, -1/2]]");
+    assert_eq!(module.execute_logic("det [[1,2],[3,4]]").unwrap(), "-2");
+    assert_eq!(module.execute_logic("rank([[1,2],[2,4]])").unwrap(), "1");
+    assert_eq!(module.execute_logic("transpose([[1,2,3],[4,5,6]])").unwrap(), "[[1, 4], [2, 5], [3, 6]]");
+    assert!(module.execute("inverse([[1,2],[2,4]])").is_err());
+}
+
+#[test]
+fn test_linear_solve_and_eigenvalues() {
+    use axiom_assistant::modules::deterministic::DetValue;
+    
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let solution = module.execute("solve [[2,1],[1,3]] x = [3,5]").unwrap();
+    assert_eq!(solution.value, Some(DetValue::Vector(vec!["4/5".into(), "7/5".into()])));
+    
+    assert_eq!(module.execute_logic("eigenvalues of [[2,1],[1,2]]").unwrap(), "[1, 3]");
+    assert_eq!(module.execute_logic("eigenvalues([[2,0,0],[0,3,4],[0,4,9]])").unwrap(), "[1, 2, 11]");
+    assert_eq!(module.execute_logic("eig([[0,-1],[1,0]])").unwrap(), "[i, -i]");
+
+    // Without a closed form the roots are approximated, and the response says so
+    let response = module.execute("eigenvalues([[1,2,3],[4,5,6],[7,8,10]])").unwrap();
+    assert!(!response.exact);
+    assert_eq!(response.result, "[-0.905740179522, 0.198246863397, 16.7074933161]");
+    assert!(response.proof.unwrap().iter().any(|step| step.contains("approximating numerically")));
+    let cyclic = "[[0,1,0,0,0],[0,0,1,0,0],[0,0,0,1,0],[0,0,0,0,1],[1,0,0,0,0]]";
+    assert!(module.execute_logic(&format!("eigenvalues({})", cyclic)).unwrap().ends_with("0.309016994375 - 0.951056516295i, 1]"));
+    assert_eq!(module.execute_logic("eigenvalues([[1,1,0,0,0],[0,1,0,0,0],[0,0,2,0,0],[0,0,0,2,0],[0,0,0,0,7]])").unwrap(), "[1, 2, 7]");
+    // A zero eigenvalue is split off exactly instead of surviving as rounding noise
+    let singular = "[[1,2,3,4,5,6],[1,1,1,1,1,1],[2,0,1,0,1,0],[0,0,0,1,3,0],[1,2,1,2,1,1],[9,8,7,6,5,4]]";
+    let response = module.execute(&format!("eigenvalues({})", singular)).unwrap();
+    assert_eq!(response.result, "[-4.63362153411, -1.33172990992, -0.136280343887, 0, 1.99618569634, 13.1054460916]");
+    assert!(response.proof.unwrap().contains(&"rational roots: 0".to_string()));
+    
+    let err = module.execute("[[1,2],[3,4]] * [[1,2,3]]").unwrap_err().to_string();
+    assert!(err.contains("2x2") && err.contains("1x3"), "unexpected error: {}", err);
+}
+
+#[test]
+fn test_descriptive_statistics() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let mean = module.execute("mean of [3, 5, 9, 12]").unwrap();
+    assert_eq!(mean.result, "29/4");
+    assert_eq!(mean.decimal.as_deref(), Some("7.25"));
+    assert_eq!(module.execute_logic("median of [3, 5, 9, 12]").unwrap(), "7");
+    assert_eq!(module.execute_logic("population stddev of [2, 4, 4, 4, 5, 5, 7, 9]").unwrap(), "2");
+    assert_eq!(module.execute_logic("percentile 90 of [1,2,3,4,5,6,7,8,9,10]").unwrap(), "91/10");
+    assert_eq!(module.execute_logic("correlation of [1,2,3,4] and [2,4,6,8]").unwrap(), "1");
+    
+    let stddev = module.execute("stddev of [2, 4, 4, 4, 5, 5, 7, 9]").unwrap();
+    assert!(!stddev.exact);
+    assert!(stddev.result.starts_with("2.138"));
+}
+
+#[test]
+fn test_combinatorics_and_binomial_probabilities() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute_logic("nCr(52,5)").unwrap(), "2598960");
+    assert_eq!(module.execute_logic("factorial(25)").unwrap(), "15511210043330985984000000");
+    let big = module.execute_logic("nCr(1000, 500)").unwrap();
+    assert_eq!(big.len(), 300);
+    assert!(big.starts_with("2702882409"));
+    
+    assert_eq!(module.execute_logic("binomial pmf n=10, p=0.5, k=3").unwrap(), "15/128");
+    assert_eq!(module.execute_logic("binomial cdf(10, 1/2, 3)").unwrap(), "11/64");
+    assert!(module.execute("nCr(5, 1/2)").is_err());
+
+    // The work follows the smaller side of nCr and the size of the answer, not n
+    assert_eq!(module.execute_logic("nCr(1000000000, 3)").unwrap(), "166666666166666667000000000");
+    assert_eq!(module.execute_logic("nCr(1000000000, 999999998)").unwrap(), "499999999500000000");
+    assert_eq!(module.execute_logic("nPr(1000000000, 2)").unwrap(), "999999999000000000");
+    assert_eq!(module.execute_logic("nCr(3, 1000000000)").unwrap(), "0");
+    let err = module.execute("factorial(1000000)").unwrap_err();
+    assert_eq!(err.code(), "DET_RESOURCE_LIMIT");
+    assert_eq!(err.to_string(), "factorial() needs more than 20000 multiplications");
+    let err = module.execute("nPr(10^100, 2000)").unwrap_err();
+    assert_eq!(err.to_string(), "nPr() has more than 100000 digits");
+}
+
+#[test]
+fn test_math_evaluation_trace() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("2 + 3 * 4").unwrap();
+    assert_eq!(
+        response.proof.unwrap(),
+        vec!["parse: 2 + 3 * 4 → 2 + (3 * 4)", "3 * 4 = 12", "2 + 12 = 14", "result: 14"]
+    );
+    
+    let proof = module.execute("(1/3 + 1/6) * 2^3").unwrap().proof.unwrap();
+    assert!(proof.contains(&"1/3 + 1/6 = 1/2".to_string()));
+    assert!(proof.contains(&"(1/2) * 8 = 4".to_string()));
+
+    // Fractions and negative numbers keep their parentheses as operands
+    let proof = module.execute("2^(1/2)").unwrap().proof.unwrap();
+    assert_eq!(proof[0], "parse: 2^(1 / 2) → 2^(1 / 2)");
+    assert_eq!(proof[2], "2 ^ (1/2) = 1.4142135623730951");
+    let proof = module.execute("(-2)^2 - (-3) / (3/4)").unwrap().proof.unwrap();
+    assert!(proof.contains(&"(-2) ^ 2 = 4".to_string()), "{:?}", proof);
+    assert!(proof.contains(&"-3 / (3/4) = -4".to_string()), "{:?}", proof);
+    assert!(proof.contains(&"4 - (-4) = 8".to_string()), "{:?}", proof);
+}
+
+#[tokio::test]
+async fn test_orchestrator_streams_steps_on_request() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let tokens: Vec<String> = orchestrator.process_query("show steps for 2 + 3 * 4").await.collect().await;
+    let output = tokens.concat();
+    assert!(output.starts_with("14"), "unexpected output: {}", output);
+    assert!(output.contains("[Steps]"));
+    assert!(output.contains("3 * 4 = 12"));
+    
+    let plain: Vec<String> = orchestrator.process_query("2 + 3 * 4").await.collect().await;
+    assert_eq!(plain, vec!["14".to_string()]);
+}
+
+/// Split "label ≈ value ± error (method)" into its numeric parts
+fn numeric_estimate(result: &str) -> (f64, f64) {
+    let (_, rest) = result.split_once('≈').unwrap();
+    let (value, rest) = rest.split_once('±').unwrap();
+    let error = rest.split_whitespace().next().unwrap();
+    (value.trim().parse().unwrap(), error.parse().unwrap())
+}
+
+#[test]
+fn test_numerical_integration_and_root_finding() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let integral = module.execute("integrate sin(x) from 0 to pi").unwrap();
+    assert!(!integral.exact);
+    assert!(integral.result.ends_with("(adaptive Simpson)"), "unexpected result: {}", integral.result);
+    let (value, error) = numeric_estimate(&integral.result);
+    assert!((value - 2.0).abs() < 1e-9 && error < 1e-9);
+    
+    let root = module.execute("root of x^3 - 2x - 5 near 2").unwrap();
+    assert!(root.result.starts_with("x ≈") && root.result.ends_with("(Brent)"));
+    let (value, error) = numeric_estimate(&root.result);
+    assert!((value - 2.0945514815423265).abs() <= error.max(1e-12));
+    
+    assert!(module.execute("integrate 1/x from 0 to 1").is_err());
+}
+
+#[test]
+fn test_ode_initial_value_problem() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("solve y' = -y, y(0)=1 at t=1").unwrap();
+    assert!(response.result.starts_with("y(1) ≈"), "unexpected result: {}", response.result);
+    assert!(response.result.contains("RK45"));
+    let (value, error) = numeric_estimate(&response.result);
+    assert!((value - (-1.0f64).exp()).abs() <= error);
+    assert!(response.proof.unwrap().iter().any(|line| line.starts_with("error estimate")));
+    
+    let (value, _) = numeric_estimate(&module.execute("solve dy/dx = x*y, y(0) = 1 at x = 2").unwrap().result);
+    assert!((value - 2.0f64.exp()).abs() < 1e-6);
+}
+
+#[test]
+fn test_calendar_arithmetic() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute("what weekday is 2031-07-04").unwrap().result, "Friday");
+    assert_eq!(module.execute("days between 2026-03-01 and 2026-04-15").unwrap().result, "45 days");
+    assert_eq!(module.execute("2026-01-31 + 1 month").unwrap().result, "2026-02-28");
+    assert_eq!(module.execute("10 business days after 2026-03-01").unwrap().result, "2026-03-13");
+    assert_eq!(
+        module.execute("2026-03-01T10:00+05:30 in UTC-8").unwrap().result,
+        "2026-02-28T20:30-08:00"
+    );
+    assert!(module.execute("2026-02-30").is_err());
+    
+    // Offsets are read by character, name only the zone, and stop at ±14:00
+    let err = |query: &str| module.execute(query).unwrap_err().to_string();
+    let offset = |found: &str| format!("Expected a UTC offset such as utc+5 or +05:30 at column 27, found '{}'", found);
+    assert_eq!(err("2026-03-01T10:00+05:30 in ü"), offset("ü"));
+    assert_eq!(err("2026-03-01T10:00+05:30 in utc+ü"), offset("utc+ü"));
+    assert_eq!(err("2026-03-01T10:00+05:30 in UTC+14:30"), offset("utc+14:30"));
+    assert_eq!(module.execute("2026-03-01T10:00Z in UTC-14:00").unwrap().result, "2026-02-28T20:00-14:00");
+}
+
+#[test]
+fn test_business_days_use_holiday_list() {
+    use axiom_assistant::modules::deterministic::calendar::HolidayList;
+    
+    let query = "how many business days between 2026-03-01 and 2026-04-15";
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    assert_eq!(module.execute(query).unwrap().result, "32 business days");
+    
+    let good_friday = chrono::NaiveDate::from_ymd_opt(2026, 4, 3).unwrap();
+    let module = module.with_holidays(HolidayList::new([good_friday]));
+    let response = module.execute(query).unwrap();
+    assert_eq!(response.result, "31 business days");
+    assert!(response.proof.unwrap().contains(&"minus 1 holiday: 2026-04-03".to_string()));
+}
+
+#[test]
+fn test_programmer_bitwise_and_bases() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute("0xFF & 0b1010").unwrap().result, "10 = 0xA = 0o12 = 0b1010");
+    assert_eq!(module.execute("1 << 12").unwrap().result, "4096 = 0x1000 = 0o10000 = 0b1_0000_0000_0000");
+    assert_eq!(module.execute("255 in hex").unwrap().result, "0xFF");
+    assert_eq!(module.execute("0xFF ^ 0x0F in binary").unwrap().result, "0b1111_0000");
+    // Without programmer syntax '^' is still a power
+    assert_eq!(module.execute("2^10").unwrap().result, "1024");
+}
+
+#[test]
+fn test_fixed_width_overflow_is_reported() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("0xFF as i8").unwrap();
+    assert!(response.result.starts_with("-1 = 0xFF"), "unexpected result: {}", response.result);
+    
+    let response = module.execute("200u8 + 100u8").unwrap();
+    assert_eq!(response.result, "44 = 0x2C = 0o54 = 0b0010_1100 (overflowed)");
+    assert!(response.proof.unwrap().iter().any(|line| line.contains("300 is outside u8")));
+    
+    assert!(module.execute("255u8 + 1i16").is_err());
+}
+
+#[test]
+fn test_worded_math_is_normalized() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("What is twelve times seven plus a half").unwrap();
+    assert_eq!(response.result, "169/2");
+    assert_eq!(response.interpreted.as_deref(), Some("12 * 7 + 1 / 2"));
+    
+    assert_eq!(module.execute("15 percent of 80").unwrap().result, "12");
+    assert_eq!(module.execute("the square root of sixteen plus nine").unwrap().result, "13");
+    assert_eq!(module.execute("twenty-one divided by seven squared").unwrap().result, "3/7");
+    // Symbolic input is not marked as reinterpreted
+    assert_eq!(module.execute("2 + 2").unwrap().interpreted, None);
+}
+
+#[tokio::test]
+async fn test_orchestrator_echoes_canonical_expression() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let tokens: Vec<String> = orchestrator.process_query("what is five squared minus four").await.collect().await;
+    assert_eq!(tokens, vec!["5^2 - 4 = 21".to_string()]);
+    
+    // Worded arithmetic without operator keywords is routed to the deterministic module too
+    for (query, answer) in [
+        ("what is two to the power of ten", "2^10 = 1024"),
+        ("three quarters of twelve", "3 / 4 * 12 = 9"),
+        ("half of ten", "1 / 2 * 10 = 5"),
+    ] {
+        assert_eq!(orchestrator.router.classify_intent(query), axiom_assistant::modules::neuro_symbolic::Intent::Logical);
+        let tokens: Vec<String> = orchestrator.process_query(query).await.collect().await;
+        assert_eq!(tokens, vec![answer.to_string()], "{}", query);
+    }
+    assert_ne!(orchestrator.router.classify_intent("tell me about three little pigs"), axiom_assistant::modules::neuro_symbolic::Intent::Logical);
+}
+
+#[test]
+fn test_propositional_logic_decisions() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute("(p -> q) & p -> q").unwrap().result, "valid (a tautology)");
+    assert_eq!(
+        module.execute("truth table for p -> q").unwrap().result,
+        "p | q | p -> q\nT | T | T\nT | F | F\nF | T | T\nF | F | T"
+    );
+    
+    let response = module.execute("is (p -> q) & q -> p valid").unwrap();
+    assert_eq!(response.result, "not valid; counter-model: p = false, q = true");
+    assert_eq!(response.verdict, Some(false));
+    
+    assert_eq!(module.execute("is p & ~p satisfiable").unwrap().result, "unsatisfiable");
+    assert_eq!(module.execute("are p -> q and ~q -> ~p equivalent").unwrap().verdict, Some(true));
+    assert_eq!(module.execute("p -> q where p = true, q = false").unwrap().result, "false");
+    assert!(module.execute("are p and q and q and p equivalent").is_err());
+}
+
+#[tokio::test]
+async fn test_hybrid_checks_logical_claims() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let tokens: Vec<String> = orchestrator.process_query("Explain why (p -> q) & q -> p is valid").await.collect().await;
+    let output = tokens.concat();
+    assert!(
+        output.contains("✗ Claim: is (p -> q) & q -> p valid → not valid; counter-model: p = false, q = true"),
+        "unexpected output: {}",
+        output
+    );
+}
+
+#[test]
+fn test_horn_clause_resolution() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let response = module.execute("ancestor(zeus, hercules)").unwrap();
+    assert_eq!(response.result, "true");
+    assert_eq!(response.verdict, Some(true));
+    let proof = response.proof.unwrap();
+    assert!(proof.contains(&"ancestor(zeus, hercules) by rule ancestor(X, Y) :- parent(X, Y)".to_string()));
+    assert!(proof.contains(&"  parent(zeus, hercules) by fact".to_string()));
+    
+    // No fabricated proof for a claim the facts don't support
+    let response = module.execute("ancestor(zeus, poseidon)").unwrap();
+    assert_eq!(response.result, "false");
+    assert_eq!(response.verdict, Some(false));
+    
+    assert_eq!(module.execute("ancestor(X, hercules)").unwrap().result, "X = zeus ; X = alcmene ; X = cronus ; X = rhea");
+    assert_eq!(module.execute("?- parent(zeus, X), parent(hera, X).").unwrap().result, "X = ares ; X = hephaestus");
+}
+
+#[tokio::test]
+async fn test_orchestrator_answers_goals() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    for (query, answer) in [
+        ("ancestor(zeus, apollo)", "true"),
+        ("ancestor(X, hercules)", "X = zeus ; X = alcmene ; X = cronus ; X = rhea"),
+        ("?- parent(zeus, X), parent(hera, X).", "X = ares ; X = hephaestus"),
+    ] {
+        let tokens: Vec<String> = orchestrator.process_query(query).await.collect().await;
+        assert_eq!(tokens, vec![answer.to_string()], "{}", query);
+    }
+}
+
+#[test]
+fn test_horn_clause_lists_and_errors() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(
+        module.execute("append(X, Y, [1, 2])").unwrap().result,
+        "X = [], Y = [1, 2] ; X = [1], Y = [2] ; X = [1, 2], Y = []"
+    );
+    assert_eq!(module.execute("member(b, [a, b, c])").unwrap().result, "true");
+    assert_eq!(module.execute("sibling(ares, hephaestus)").unwrap().result, "true");
+    
+    let error = module.execute("grandparent(zeus, X)").unwrap_err();
+    assert_eq!(error.to_string(), "Unknown procedure grandparent/2");
+
+    // Negation as failure, integer arithmetic and list length
+    assert_eq!(
+        module.execute("?- parent(zeus, X), \\+ parent(hera, X), \\+ (X = athena).").unwrap().result,
+        "X = apollo ; X = artemis ; X = hermes ; X = hercules"
+    );
+    assert_eq!(module.execute("?- X is (2 + 3) * 4 - 7 // 2, Y is -7 mod 3.").unwrap().result, "X = 17, Y = 2");
+    assert_eq!(module.execute("?- length([a, b, c], N).").unwrap().result, "N = 3");
+    assert_eq!(module.execute("?- append(X, Y, [1, 2]), length(X, 1).").unwrap().result, "X = [1], Y = [2]");
+    
+    // Endless answers stop at the limit, and the count says so
+    let proof = module.execute("?- append(X, Y, Z).").unwrap().proof.unwrap();
+    assert_eq!(proof.last().unwrap(), "% first 20 answers (limit reached), 20 inferences");
+    assert_eq!(module.execute("?- length([a|T], 3).").unwrap().result, "T = [_G1, _G2]");
+    assert_eq!(module.execute("?- X is Y + 1.").unwrap_err().to_string(), "Arguments are not sufficiently instantiated");
+    assert_eq!(module.execute("?- X is 7 / 2.").unwrap_err().to_string(), "7 / 2 is not an integer; use // for integer division");
+
+    // Each answer has its own proof tree
+    let proof = module.execute("ancestor(X, hercules)").unwrap().proof.unwrap();
+    let labels: Vec<&String> = proof.iter().filter(|line| line.starts_with("% answer")).collect();
+    assert_eq!(labels, ["% answer 1: X = zeus", "% answer 2: X = alcmene", "% answer 3: X = cronus", "% answer 4: X = rhea"]);
+    let rhea = proof.iter().position(|line| line == "% answer 4: X = rhea").unwrap();
+    assert_eq!(proof[rhea + 1], "ancestor(rhea, hercules) by rule ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y)");
+}
+
+#[test]
+fn test_knowledge_base_consult_and_reload() {
+    let dir = std::env::temp_dir().join(format!("axiom-kb-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("rivers.pl");
+    std::fs::write(&file, ":- module(geo, [flows_into/2]).\nflows_into(thames, north_sea).\n").unwrap();
+    
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let consulted = module.execute(&format!("consult {}", file.display())).unwrap();
+    assert_eq!(consulted.result, format!("Consulted {}: 1 clause in geo", file.display()));
+    assert_eq!(module.execute("geo:flows_into(thames, X)").unwrap().result, "X = north_sea");
+    
+    // Edits are picked up without rebuilding the module
+    std::fs::write(&file, ":- module(geo, [flows_into/2]).\nflows_into(thames, north_sea).\nflows_into(rhine, north_sea).\n").unwrap();
+    module.execute("reload").unwrap();
+    assert_eq!(module.execute("flows_into(X, north_sea)").unwrap().result, "X = thames ; X = rhine");
+    
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn test_orchestrator_runs_knowledge_base_commands() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    // A relative path is consulted, not drafted by the language model
+    for (query, answer) in [
+        ("list predicates", "user: ancestor/2, append/3, member/2, parent/2, sibling/2"),
+        ("reload", "Reloaded 0 sources: 26 clauses"),
+        ("consult axiom-missing.pl", "[deterministic error] Failed to read knowledge base axiom-missing.pl"),
+    ] {
+        let output = orchestrator.process_query(query).await.collect::<Vec<String>>().await.concat();
+        assert!(output.starts_with(answer), "{}: {}", query, output);
+    }
+}
+
+#[test]
+fn test_knowledge_base_syntax_errors_name_file_and_line() {
+    use axiom_assistant::modules::deterministic::prolog::KnowledgeBase;
+    
+    let dir = std::env::temp_dir().join(format!("axiom-kb-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("likes.pl"), "% who likes whom\nlikes(a, b).\nlikes(b c).\n").unwrap();
+    
+    let error = KnowledgeBase::load(std::slice::from_ref(&dir)).unwrap_err().to_string();
+    assert_eq!(error, format!("{}:3: Expected ')' at column 9, found name 'c'", dir.join("likes.pl").display()));
+    
+    // A failed consult is a syntax error and leaves the existing clauses in place
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let error = module.execute(&format!("consult {}", dir.display())).unwrap_err();
+    assert_eq!((error.code(), error.column()), ("DET_SYNTAX", Some(9)));
+    assert_eq!(module.execute("ancestor(zeus, hercules)").unwrap().result, "true");
+    
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[cfg(all(feature = "swipl", unix))]
+#[test]
+fn test_swipl_backend_reads_driver_output() {
+    use axiom_assistant::modules::deterministic::prolog::{parse_query, KnowledgeBase};
+    use axiom_assistant::modules::deterministic::swipl::{solve, SwiplConfig};
+    use std::os::unix::fs::PermissionsExt;
+    
+    // A stand-in for swipl that checks the generated driver and replies in its line protocol
+    let dir = std::env::temp_dir().join(format!("axiom-swipl-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let stub = dir.join("swipl");
+    std::fs::write(&stub, "#!/bin/sh\nprogram=$(cat)\ncase \"$program\" in\n  *\"axiom_query(['X' = X], (parent(zeus, X)))\"*)\n    printf 'answer\\tX\\tares\\nanswer\\tX\\t[a, b]\\ninferences\\t7\\nstatus\\tok\\n' ;;\n  *) printf 'inferences\\t0\\nstatus\\tinference_limit\\n' ;;\nesac\n").unwrap();
+    std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let config = SwiplConfig { executable: stub, ..SwiplConfig::default() };
+    let kb = KnowledgeBase::standard();
+    
+    let outcome = solve(&parse_query("parent(zeus, X)").unwrap(), &kb, &config).unwrap();
+    assert_eq!(outcome.summary(), "X = ares ; X = [a, b]");
+    assert_eq!(outcome.proof.first().unwrap(), "?- parent(zeus, X)");
+    assert!(outcome.proof.iter().any(|step| step.ends_with("which gives no derivation")), "{:?}", outcome.proof);
+    assert_eq!(outcome.proof.last().unwrap(), "% 2 answers, 7 inferences");
+    
+    let error = solve(&parse_query("ancestor(X, Y)").unwrap(), &kb, &config).unwrap_err();
+    assert_eq!(error.to_string(), "Query exceeded 10000000 inferences");
+    
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_datalog_stratified_evaluation() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    // Recursive rules over the shared knowledge base, evaluated bottom-up
+    let ancestors = module.execute("datalog ancestor(X, hercules)").unwrap();
+    assert_eq!(ancestors.result, "X = alcmene ; X = cronus ; X = rhea ; X = zeus");
+    assert_eq!(ancestors.proof.unwrap(), vec!["stratum 0: ancestor/2: 33 new facts in 2 rounds", "% 4 answers"]);
+    
+    // Left recursion terminates; negation and aggregates read lower strata only
+    let program = "datalog e(a, b). e(b, c). e(c, a). e(c, d). \
+                   p(X, Y) :- p(X, Z), e(Z, Y). p(X, Y) :- e(X, Y). \
+                   stuck(X) :- e(_, X), not(p(X, a)). \
+                   fanout(X, N) :- e(X, _), aggregate_all(count, e(X, _), N).";
+    assert_eq!(module.execute(&format!("{} ?- p(a, X)", program)).unwrap().result, "X = a ; X = b ; X = c ; X = d");
+    assert_eq!(module.execute(&format!("{} ?- stuck(X)", program)).unwrap().result, "X = d");
+    assert_eq!(module.execute(&format!("{} ?- fanout(c, N)", program)).unwrap().result, "N = 2");
+    assert_eq!(module.execute("datalog ancestor(hera, zeus)").unwrap().verdict, Some(false));
+    
+    let unstratified = module.execute("datalog win(X) :- move(X, Y), not(win(Y)). move(a, b). ?- win(X)");
+    assert_eq!(unstratified.unwrap_err().to_string(), "Not stratifiable: win/1 depends on its own negation or aggregate");
+    let unsafe_rule = module.execute("datalog p(X, Y) :- n(X). n(a). ?- p(a, Y)");
+    assert_eq!(unsafe_rule.unwrap_err().to_string(), "Unsafe rule 'p(X, Y) :- n(X)': Y must appear in a positive goal");
+}
+
+#[test]
+fn test_datalog_explains_derived_facts() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let why = module.execute("why ancestor(cronus, hercules)").unwrap();
+    assert_eq!(why.verdict, Some(true));
+    assert_eq!(why.proof.unwrap(), vec![
+        "ancestor(cronus, hercules) by rule ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y)",
+        "  parent(cronus, zeus) by fact",
+        "  ancestor(zeus, hercules) by rule ancestor(X, Y) :- parent(X, Y)",
+        "    parent(zeus, hercules) by fact",
+    ]);
+    
+    // Every answer is explained, each under its own label
+    let sibling = module.execute("why sibling(ares, X)").unwrap();
+    assert_eq!(sibling.result, "X = apollo ; X = artemis ; X = athena ; X = hephaestus ; X = hercules ; X = hermes");
+    let proof = sibling.proof.unwrap();
+    assert_eq!(proof[..5], [
+        "% answer 1: X = apollo",
+        "sibling(ares, apollo) by rule sibling(X, Y) :- parent(P, X), parent(P, Y), not(X = Y)",
+        "  parent(zeus, ares) by fact",
+        "  parent(zeus, apollo) by fact",
+        "  not(ares = apollo) by built-in",
+    ]);
+    assert_eq!(proof.iter().filter(|line| line.starts_with("% answer")).count(), 6);
+    assert!(proof.contains(&"  not(ares = hermes) by built-in".to_string()));
+    assert_eq!(proof.last().unwrap(), "% 6 answers");
+    
+    let missing = module.execute("why ancestor(hera, zeus)").unwrap();
+    assert_eq!(missing.verdict, Some(false));
+    assert_eq!(missing.proof.unwrap(), vec!["ancestor(hera, zeus) is not derivable"]);
+    
+    // List predicates are Horn clauses but not Datalog
+    let error = module.execute("datalog append(X, Y, Z)").unwrap_err().to_string();
+    assert!(error.starts_with("append/3 is not a Datalog predicate"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_orchestrator_explains_goals() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    let tokens: Vec<String> = orchestrator.process_query("why ancestor(cronus, ares) with steps").await.collect().await;
+    assert_eq!(tokens[0], "true");
+    assert!(tokens.contains(&"\n  3.   ancestor(zeus, ares) by rule ancestor(X, Y) :- parent(X, Y)".to_string()), "{:?}", tokens);
+    // A why-question about anything but a goal is still the language model's
+    assert_ne!(orchestrator.router.classify_intent("why is the sky blue"), axiom_assistant::modules::neuro_symbolic::Intent::Logical);
+}
+
+#[test]
+fn test_finite_domain_puzzles() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let first = module.execute("assign A, B, C in 1..9 all different with A + B = C * 2").unwrap();
+    assert_eq!(first.result, "A = 1, B = 3, C = 2");
+    assert_eq!(first.proof.unwrap()[..3], [
+        "domains: A, B, C in 1..9",
+        "constraint: all_different(all variables)",
+        "constraint: A + B = C * 2",
+    ]);
+    assert_eq!(module.execute("A B C in 1..9, all different, A + B = C * 2, count solutions").unwrap().result, "32 solutions");
+    
+    // Solutions come in declaration order, capped by "first N"
+    let divisors = module.execute("A, B in 1..1000000 with A * B = 999999, A < B, first 3 solutions").unwrap();
+    assert_eq!(divisors.result, "A = 1, B = 999999 ; A = 3, B = 333333 ; A = 7, B = 142857");
+    
+    let money = "S E N D M O R Y in 0..9, all different, S != 0, M != 0, \
+                 1000*S + 100*E + 10*N + D + 1000*M + 100*O + 10*R + E = 10000*M + 1000*O + 100*N + 10*E + Y";
+    assert_eq!(module.execute(money).unwrap().result, "S = 9, E = 5, N = 6, D = 7, M = 1, O = 0, R = 8, Y = 2");
+    assert_eq!(module.execute("X in [2, 4, 8] with X > 5, all solutions").unwrap().result, "X = 8");
+    assert_eq!(module.execute("A in 1..5 with A > 7").unwrap().result, "no solution");
+}
+
+#[test]
+fn test_finite_domain_optimization_and_limits() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let schedule = module.execute("T1 T2 T3 in 0..20 with T1 + 3 <= T2, T2 + 2 <= T3, minimize T3").unwrap();
+    assert_eq!(schedule.result, "T1 = 0, T2 = 3, T3 = 5 (minimum T3 = 5)");
+    let plan = module.execute("X, Y in 0..10 with X + 2Y <= 14, 3X - Y >= 0, maximize 3X + 4Y").unwrap();
+    assert_eq!(plan.result, "X = 10, Y = 2 (maximum 3 * X + 4 * Y = 38)");
+    
+    // A query's own timeout can only shorten the configured limit
+    let slow = module.execute("A B C D E F G H I J in 1..12, all different, count solutions, timeout 20ms").unwrap();
+    assert!(slow.result.starts_with("at least ") && slow.result.ends_with("(time limit of 20ms reached)"), "{}", slow.result);
+    
+    let undeclared = module.execute("A in 1..5 with A + B = 3").unwrap_err().to_string();
+    assert_eq!(undeclared, "B has no domain; declare it, e.g. 'B in 1..9'");
+    let missing = module.execute("A, B with A < B, C in 1..3").unwrap_err().to_string();
+    assert_eq!(missing, "A needs a domain, e.g. 'A in 1..9'");
+}
+
+#[test]
+fn test_linear_arithmetic_claims() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    let valid = module.execute("if x > 3 and y < 2 then x - y > 1").unwrap();
+    assert_eq!(valid.result, "valid");
+    assert_eq!(valid.verdict, Some(true));
+    assert_eq!(valid.proof.unwrap()[..2], [
+        "valid iff no reals satisfy not (x > 3 and y < 2 implies x - y > 1)",
+        "constraints: x > 3, y < 2, x - y <= 1",
+    ]);
+    
+    let invalid = module.execute("if x >= 0 and y >= 0 and x + y <= 10 then 2x + y <= 19").unwrap();
+    assert_eq!(invalid.result, "not valid; counterexample: x = 10, y = 0");
+    assert_eq!(invalid.verdict, Some(false));
+    assert_eq!(module.execute("if 0 < x < 1 then x = 1/2").unwrap().result, "not valid; counterexample: x = 1/4");
+    
+    // Integrality matters: 2n = 7 has a real solution but no integer one
+    assert_eq!(module.execute("for all integers n: 2n != 7").unwrap().result, "valid");
+    assert_eq!(module.execute("for all reals n: 2n != 7").unwrap().result, "not valid; counterexample: n = 7/2");
+    let diophantine = "if 3x + 5y = 17 and x >= 0 and y >= 0 then x = 9 over the integers";
+    assert_eq!(module.execute(diophantine).unwrap().result, "not valid; counterexample: x = 4, y = 1");
+    
+    let nonlinear = module.execute("if x * y > 3 then x > 1").unwrap_err().to_string();
+    assert!(nonlinear.starts_with("'x * y > 3' is not linear"), "{}", nonlinear);
+}
+
+#[tokio::test]
+async fn test_hybrid_checks_arithmetic_implications() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let tokens: Vec<String> = orchestrator
+        .process_query("Explain why if x > 3 then x > 5 for any such value")
+        .await
+        .collect()
+        .await;
+    let output = tokens.concat();
+    assert!(
+        output.contains("✗ Claim: if x > 3 then x > 5 → not valid; counterexample: x = 4"),
+        "unexpected output: {}",
+        output
+    );
+}
+
+#[test]
+fn test_sandboxed_code_snippets() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    
+    assert_eq!(module.execute("run: let x = 6; x * 7").unwrap().result, "42");
+    let printed = module.execute("run: for i in 0..3 { print(`line ${i}`); } \"done\"").unwrap();
+    assert_eq!(printed.result, "line 0\nline 1\nline 2\n\"done\"");
+    let fenced = module.execute("run ```rhai\nfn fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\nfib(15)\n```").unwrap();
+    assert_eq!(fenced.result, "610");
+    
+    // Randomness is seeded and time is an operation count, so reruns agree
+    let dice = "run with seed 7: [random(1, 6), random(1, 6), random(1, 6), clock()]";
+    let first = module.execute(dice).unwrap();
+    assert_eq!(first.result, module.execute(dice).unwrap().result);
+    assert_eq!(first.proof.unwrap()[0], "rhai: 16 operations, seed 7");
+    
+    assert_eq!(module.execute("run: loop { }").unwrap_err().to_string(), "Snippet stopped after 1000000 operations");
+    assert_eq!(module.execute("run: let x = ;").unwrap_err().to_string(), "Syntax error: Unexpected ';' (line 1, position 9)");
+    let no_clock = module.execute("run: timestamp()").unwrap_err().to_string();
+    assert!(no_clock.starts_with("Runtime error: Function not found: timestamp"), "{}", no_clock);
+}
+
+#[tokio::test]
+async fn test_orchestrator_runs_seeded_snippets() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    // No "run:" in the query, but the sandbox still runs it
+    let query = "run with seed 7: random(1, 6)";
+    let expected = orchestrator.det_module.execute(query).unwrap().result;
+    let tokens: Vec<String> = orchestrator.process_query(query).await.collect().await;
+    assert_eq!(tokens, vec![expected]);
+}
+
+#[tokio::test]
+async fn test_hybrid_runs_code_against_examples() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let query = "Write add so that add(2, 3) == 5 and add(1, 1) returns 3:\n```rhai\nfn add(a, b) { a + b }\n```";
+    let tokens: Vec<String> = orchestrator.process_query(query).await.collect().await;
+    let output = tokens.concat();
+    assert!(output.contains("✓ Example: add(2, 3) == 5"), "unexpected output: {}", output);
+    assert!(output.contains("✗ Example: add(1, 1) == 3 → got 2"), "unexpected output: {}", output);
+}
+
+#[test]
+fn test_example_checks_compare_outside_the_snippet() {
+    use axiom_assistant::modules::deterministic::sandbox::{check_example, Example, SandboxConfig};
+    let example = |call: &str, expected: &str| Example { call: call.to_string(), expected: expected.to_string() };
+    let config = SandboxConfig::default();
+    
+    // A snippet that returns early cannot hand back its own verdict
+    let forged = check_example("fn add(a, b) { a - b }\nreturn [5, true];", &example("add(2, 3)", "5"), 0, &config);
+    assert_eq!((forged.got.as_str(), forged.passed), ("-1", false));
+    
+    let top_level = check_example("let base = 40;\nfn add(a, b) { a + b }", &example("add(base, 2)", "42.0"), 0, &config);
+    assert!(top_level.passed, "{:?}", top_level);
+    let listed = check_example("fn pair(x) { [x, `${x}`] }", &example("pair(1)", "[1, \"1\"]"), 0, &config);
+    assert!(listed.passed, "{:?}", listed);
+}
+
+#[test]
+fn test_text_facts() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let result = |query: &str| module.execute(query).unwrap().result;
+    
+    assert_eq!(result("how many r's in strawberry?"), "3");
+    assert_eq!(result("how many times does \"aa\" appear in \"aaaa\""), "2");
+    assert_eq!(result("what's the 5th word of \"The quick brown fox jumps over the lazy dog\""), "jumps");
+    assert_eq!(result("the second to last character in \"hello\""), "l");
+    assert_eq!(result("positions of \"an\" in \"banana\""), "2, 4");
+    assert_eq!(result("convert \"hello world\" to title case"), "Hello World");
+    assert_eq!(result("find all /\\d+/ in \"a1 b22 c333\""), "\"1\", \"22\", \"333\"");
+    
+    // Characters are grapheme clusters: the thumbs-up with its skin tone is one
+    let counted = module.execute("how many characters in \"naïve 👍🏽\"").unwrap();
+    assert_eq!(counted.result, "7");
+    assert_eq!(counted.proof.unwrap()[0], "graphemes: 7, code points: 8, bytes: 15");
+    assert_eq!(result("reverse \"ok 👍🏽\""), "👍🏽 ko");
+    
+    let claim = module.execute("there are 2 r's in strawberry").unwrap();
+    assert_eq!((claim.result.as_str(), claim.verdict), ("false; actual count 3", Some(false)));
+    assert_eq!(module.execute("does \"order 1234\" match /\\d{3,}/").unwrap().verdict, Some(true));
+    assert_eq!(module.execute("the 12th word of \"one two\"").unwrap_err().to_string(), "\"one two\" has only 2 words");
+    
+    let router = NeuroSymbolicRouter::new();
+    assert_eq!(router.classify_intent("uppercase \"explain this\""), axiom_assistant::modules::neuro_symbolic::Intent::Logical);
+}
+
+#[tokio::test]
+async fn test_hybrid_checks_letter_counts() {
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let router = NeuroSymbolicRouter::new();
+    let orchestrator = Orchestrator::new(prob, det, router);
+    
+    let query = "Is it right that strawberry has 2 r's while the word \"banana\" contains three a's?";
+    let tokens: Vec<String> = orchestrator.process_query(query).await.collect().await;
+    let output = tokens.concat();
+    assert!(output.contains("✗ Claim: strawberry has 2 r's → false; actual count 3"), "unexpected output: {}", output);
+    assert!(output.contains("✓ Claim: \"banana\" has three a's → true"), "unexpected output: {}", output);
+}
+
+#[test]
+fn test_front_end_rejects_input_outside_the_grammar() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let error = |query: &str| module.execute(query).unwrap_err().to_string();
+    
+    // Comparisons and quotes reach the domains intact instead of losing characters
+    assert_eq!(module.execute("2 > 1").unwrap().result, "true");
+    assert_eq!(module.execute("x != y").unwrap().result, "not valid; counterexample: x = 0, y = 0");
+    assert_eq!(module.execute("What is 2 + 2?").unwrap().result, "4");
+    assert_eq!(module.execute("how many words in \"a; b {c}\"").unwrap().result, "3");
+    
+    assert_eq!(error("5 $ 3"), "Expected a number, name, operator or quoted text at column 3, found '$'");
+    assert_eq!(error("2 × 3"), "Expected '*' at column 3, found '×'");
+    assert_eq!(error("x ? y"), "Expected '?-' or the end of the query at column 3, found '?'");
+    assert_eq!(error("(1 + 2"), "Expected ')' at column 7, found end of input, with the '(' from column 1 still open");
+    assert_eq!(error("[1, 2) + 3"), "Expected ']' at column 6, found ')', with the '[' from column 1 still open");
+    assert_eq!(error("1 + 2)"), "Expected an operand or operator at column 6, found unmatched ')'");
+    assert_eq!(error("reverse \"abc"), "Expected '\"' at column 13, found end of input, with the quote from column 9 still open");
+}
+
+#[test]
+fn test_front_end_typed_forms_and_errors() {
+    use axiom_assistant::modules::deterministic::context::EvalContext;
+    use axiom_assistant::modules::deterministic::error::DetError;
+    use axiom_assistant::modules::deterministic::prolog::KnowledgeBase;
+    use axiom_assistant::modules::deterministic::syntax::{self, Form, Scope};
+    
+    let (context, knowledge) = (EvalContext::new(), KnowledgeBase::standard());
+    let parse = |query: &str| syntax::parse(query, &Scope { context: &context, knowledge: &knowledge }).unwrap();
+    
+    let query = parse("gcd(12, 18) + 1?");
+    assert_eq!(query.source, "gcd(12, 18) + 1");
+    let Form::Math(expr) = query.form else { panic!("expected an expression, got {:?}", query.form) };
+    assert_eq!(expr.to_string(), "gcd(12, 18) + 1");
+    
+    let Form::Goal(goal) = parse("ancestor(zeus, X)").form else { panic!("expected a goal") };
+    assert_eq!((goal.goals.len(), goal.var_names.as_slice()), (1, ["X".to_string()].as_slice()));
+    let Form::Words { expr, .. } = parse("half of ten").form else { panic!("expected words") };
+    assert_eq!(expr.to_string(), "1 / 2 * 10");
+    
+    assert!(matches!(parse("run: 1 + 1").form, Form::Code(_)));
+    assert!(matches!(parse("reverse 'ab'").form, Form::Text(_)));
+    assert!(matches!(parse("let r = 4").form, Form::Session(_)));
+    assert!(matches!(parse("reload").form, Form::Knowledge(_)));
+    assert!(matches!(parse("why ancestor(cronus, ares)").form, Form::Datalog(_)));
+    assert!(matches!(parse("solve x + 1 = 3").form, Form::Solve(_)));
+    assert!(matches!(parse("1 mile to km").form, Form::Units(_)));
+    assert!(matches!(parse("det([[1, 2], [3, 4]])").form, Form::LinearAlgebra(_)));
+    assert_eq!(parse("tell me about the factory").form, Form::Unrecognized);
+    
+    // Domain parsers report the same typed error, counting columns within the expression
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let failure = module.execute("derivative of sin(x +)").unwrap_err();
+    let DetError::Syntax(typed) = failure else { panic!("expected a syntax error, got {:?}", failure) };
+    assert_eq!((typed.column, typed.expected.as_str(), typed.found.as_str()), (8, "a number, name or '('", "')'"));
+}
+
+#[test]
+fn test_errors_carry_stable_codes() {
+    use axiom_assistant::modules::deterministic::error::DetError;
+    use axiom_assistant::modules::deterministic::sandbox::{self, SandboxConfig};
+    use axiom_assistant::modules::MAX_QUERY_LENGTH;
+    use std::time::Duration;
+    
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let code = |query: &str| module.execute(query).unwrap_err().code();
+    assert_eq!(code(""), "DET_EMPTY_QUERY");
+    assert_eq!(code(&"1+".repeat(6000)), "DET_QUERY_TOO_LONG");
+    assert_eq!(code("2 + 3 ×"), "DET_SYNTAX");
+    assert_eq!(code("run: loop { }"), "DET_RESOURCE_LIMIT");
+    assert_eq!(code("1 / 0"), "DET_EVALUATION");
+    for power in ["10^10000", "2^2^2^2^2"] {
+        let err = module.execute(power).unwrap_err();
+        assert_eq!((err.code(), err.to_string().contains("limit of 4096")), ("DET_RESOURCE_LIMIT", true), "{}", err);
+    }
+    
+    // Limits keep their kind through the domains' anyhow errors
+    let config = SandboxConfig { time_limit: Duration::from_millis(100), max_operations: u64::MAX, ..SandboxConfig::default() };
+    let timeout = DetError::from(sandbox::run("loop { }", 1, &config).unwrap_err());
+    assert_eq!(timeout.code(), "DET_TIMEOUT");
+    assert_eq!(timeout.to_string(), "Snippet exceeded the 0.1s time limit");
+    
+    // Rhai's own limits are resource limits, also inside functions
+    assert_eq!(code("run: fn f(x) { f(x + 1) } f(1)"), "DET_RESOURCE_LIMIT");
+    assert_eq!(code("run: let s = \"ab\"; loop { s += s; }"), "DET_RESOURCE_LIMIT");
+    assert_eq!(code("run: fn g() { let s = \"ab\"; loop { s += s; } } g()"), "DET_RESOURCE_LIMIT");
+    
+    let router = NeuroSymbolicRouter::new();
+    assert_eq!(router.route("").unwrap_err().code(), "ROUTER_EMPTY_QUERY");
+    assert_eq!(router.route(&"a".repeat(MAX_QUERY_LENGTH + 1)).unwrap_err().code(), "ROUTER_QUERY_TOO_LONG");
+    assert!(router.route(&"a".repeat(MAX_QUERY_LENGTH)).is_ok());
+    // Whatever the router lets through, neither module rejects for length
+    let longest = "1".repeat(MAX_QUERY_LENGTH);
+    assert_eq!(module.execute(&longest).unwrap().result, longest);
+}
+
+#[test]
+fn test_domain_parse_errors_are_syntax_errors_with_query_columns() {
+    let module = DeterministicModule::init_deterministic_module().unwrap();
+    let error = |query: &str| {
+        let error = module.execute(query).unwrap_err();
+        (error.code(), error.column(), error.to_string())
+    };
+    
+    // Calendar dates, times and offsets
+    let (code, column, message) = error("days between 2026-03-01 and 2026-02-30");
+    assert_eq!((code, column), ("DET_SYNTAX", Some(29)));
+    assert_eq!(message, "Expected a valid date (YYYY-MM-DD) at column 29, found '2026-02-30'");
+    assert_eq!(error("2026-03-01T25:00Z in UTC").1, Some(12));
+    
+    // Units: the target is read after " in ", the columns still count from the query start
+    assert_eq!(error("5 km in m/").0, "DET_SYNTAX");
+    assert_eq!(error("5 km in m/").1, Some(11));
+    
+    // Solver equations
+    assert_eq!(error("solve x = 1, y").2, "Expected an equation with '=' at column 14, found 'y'");
+    assert_eq!(error("solve x + 2 = 3 = 4").1, Some(17));
+    assert_eq!(error("solve x + = 3").0, "DET_SYNTAX");
+    
+    // Inline Datalog programs and Prolog queries
+    assert_eq!(error("datalog parent(zeus, ares) parent(a, b). ?- parent(X, Y)").2, "Expected '.' at column 28, found name 'parent'");
+    assert_eq!(error("?- parent(zeus X).").1, Some(16));
+}
+
+#[tokio::test]
+async fn test_orchestrator_streams_typed_error_events() {
+    use axiom_assistant::ipc::contracts::{ErrorLayer, QueryEvent};
+    
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap();
+    assert_eq!(prob.infer("").await.unwrap_err().code(), "PROB_EMPTY_PROMPT");
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    let events: Vec<QueryEvent> = orchestrator.process_query_events("calculate (2 + 3").await.collect().await;
+    let [QueryEvent::Error(error)] = events.as_slice() else { panic!("expected one error event, got {:?}", events) };
+    assert_eq!((error.code.as_str(), error.layer, error.column), ("DET_SYNTAX", ErrorLayer::Deterministic, Some(17)));
+    
+    let json = serde_json::to_value(&events[0]).unwrap();
+    assert_eq!(json["type"], "error");
+    assert_eq!(json["code"], "DET_SYNTAX");
+    assert_eq!(json["layer"], "deterministic");
+    assert_eq!(json["column"], 17);
+    
+    // The text stream keeps the prefixes the CLI has always printed
+    let text: Vec<String> = orchestrator.process_query("").await.collect().await;
+    assert_eq!(text, vec!["[error] Query cannot be empty"]);
+    let events: Vec<QueryEvent> = orchestrator.process_query_events("").await.collect().await;
+    assert!(matches!(&events[..], [QueryEvent::Error(e)] if e.code == "ROUTER_EMPTY_QUERY" && e.layer == ErrorLayer::Router));
+}
+
+mod unicode_input {
+    use super::*;
+    use axiom_assistant::modules::deterministic::{strip_leading_phrase, strip_trailing_phrase};
+    use axiom_assistant::modules::probabilistic::mock::MockModel;
+    use proptest::prelude::*;
+    
+    /// Prefix words, case-changing letters, combining marks and math mixed together
+    fn mixed_query() -> impl Strategy<Value = String> {
+        let piece = prop_oneof![
+            Just("calculate ".to_string()), Just("What is ".to_string()), Just("solve ".to_string()),
+            Just("show steps".to_string()), Just("İ".to_string()), Just("ẞ".to_string()), Just("ﬁ".to_string()),
+            Just("\u{301}".to_string()), Just("Σ".to_string()), Just("\"".to_string()), Just("'s ".to_string()),
+            "[0-9]{1,3}", "[ +*/()=<>^-]", "\\PC{1,3}",
+        ];
+        proptest::collection::vec(piece, 0..12).prop_map(|pieces| pieces.concat())
+    }
+    
+    /// Something a domain recognizes, then one of the keywords domains split on and
+    /// characters of two to four bytes, where byte-indexed slicing would land mid-character
+    fn keyword_then_multibyte() -> impl Strategy<Value = String> {
+        let seeds = proptest::sample::select(vec![
+            "2026-03-01T10:00+05:30", "2026-03-01", "today", "5 km", "3 ft * 2", "0xFF", "255", "[[1,2],[3,4]]",
+            "mean of [1, 2]", "nCr(5, 2)", "x + 1", "p & q", "solve x", "run:", "count the", "\"abc\"",
+        ]);
+        let keywords = proptest::sample::select(vec![
+            "in", "to", "as", "of", "from", "between", "and", "after", "before", "with seed", "utc+", "+", "-", "d",
+        ]);
+        let multibyte = "[\\PC&&[^\\x00-\\x7F]]{1,3}";
+        (seeds, keywords, multibyte, "\\PC{0,4}")
+            .prop_map(|(seed, keyword, multibyte, tail)| format!("{} {} {}{}", seed, keyword, multibyte, tail))
+    }
+    
+    #[test]
+    fn phrases_are_cut_at_grapheme_boundaries() {
+        assert_eq!(strip_leading_phrase("WHAT IS 2 + 2", "what is"), Some(" 2 + 2"));
+        assert_eq!(strip_leading_phrase("calculate\u{301} 2", "calculate"), None);
+        assert_eq!(strip_leading_phrase("calculated 2+2", "calculate"), None);
+        assert_eq!(strip_trailing_phrase("reshow steps", "show steps"), None);
+        assert_eq!(strip_trailing_phrase("ΣΊΣΥΦΟΣ With Steps", "with steps"), Some("ΣΊΣΥΦΟΣ "));
+        
+        // 'İ' lowercases to two chars; the expression after the prefix must stay intact
+        let module = DeterministicModule::init_deterministic_module().unwrap();
+        assert_eq!(module.execute_logic("Calculate 2 + 2").unwrap(), "4");
+        assert!(module.execute("İ").is_ok());
+        assert!(module.execute("calculate İİ + 1").is_err());
+    }
+    
+    #[test]
+    fn offsets_after_a_keyword_are_read_by_character() {
+        // Once a char-boundary panic in the UTC offset parser
+        let module = DeterministicModule::init_deterministic_module().unwrap();
+        for query in ["2026-03-01T10:00+05:30 in ü", "2026-03-01T10:00 to utc+aü1", "2026-03-01T10:00 in -é"] {
+            assert!(module.execute(query).is_err(), "{}", query);
+        }
+    }
+    
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1024))]
+        
+        #[test]
+        fn deterministic_module_never_panics(query in prop_oneof![1 => mixed_query(), 3 => keyword_then_multibyte(), 1 => "\\PC{0,40}"]) {
+            let module = DeterministicModule::init_deterministic_module().unwrap();
+            let _ = module.execute(&query);
+        }
+        
+        #[test]
+        fn orchestrator_never_panics(query in prop_oneof![1 => mixed_query(), 3 => keyword_then_multibyte(), 1 => "\\PC{0,40}"]) {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async {
+                // Without the mock's pacing, abandoned streams end at once
+                let instant = MockModel { token_delay: std::time::Duration::ZERO };
+                let prob = ProbabilisticModule::load_local_llm().await.unwrap().with_model(instant);
+                let det = DeterministicModule::init_deterministic_module().unwrap();
+                let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+                drop(orchestrator.process_query_events(&query).await);
+            });
+        }
+        
+        #[test]
+        fn stripped_phrases_are_suffixes(text in "\\PC{0,20}", prefix in "\\PC{0,6}") {
+            if let Some(rest) = strip_leading_phrase(&text, &prefix) {
+                prop_assert!(text.ends_with(rest));
+            }
+            if let Some(rest) = strip_trailing_phrase(&text, &prefix) {
+                prop_assert!(text.starts_with(rest));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_orchestrator_drafts_with_any_language_model() {
+    use axiom_assistant::modules::probabilistic::{Capabilities, GenerationOptions, LanguageModel, ProbError};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    
+    /// Counts its generations
+    struct Fixed(Arc<AtomicUsize>);
+    impl LanguageModel for Fixed {
+        fn capabilities(&self) -> Capabilities {
+            Capabilities { backend: "fixed".to_string(), streaming: false, tokenize: false, embed: false, context_length: None }
+        }
+        fn generate(&self, _prompt: &str, _options: &GenerationOptions) -> Result<String, ProbError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok("I think 6 * 7 is 42".to_string())
+        }
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+    }
+    
+    let generations = Arc::new(AtomicUsize::new(0));
+    let prob = ProbabilisticModule::load_local_llm().await.unwrap().with_model(Fixed(generations.clone()));
+    assert_eq!(prob.capabilities().backend, "fixed");
+    assert_eq!(prob.embed("x").await.unwrap_err().code(), "PROB_UNSUPPORTED");
+    let det = DeterministicModule::init_deterministic_module().unwrap();
+    let orchestrator = Orchestrator::new(prob, det, NeuroSymbolicRouter::new());
+    
+    // Hybrid: the draft comes from the plugged-in model and its claim is still checked
+    let output: Vec<String> = orchestrator.process_query("Tell me about the product").await.collect().await;
+    let output = output.concat();
+    assert!(output.starts_with("I think 6 * 7 is 42"), "{}", output);
+    assert!(output.contains("✓ Claim: 6 * 7 → 42"), "{}", output);
+    // The streamed draft is the one verified; the model is not run a second time
+    assert_eq!(generations.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_mock_and_process_backends() {
+    use axiom_assistant::ipc::contracts::{ErrorLayer, QueryEvent};
+    use axiom_assistant::modules::probabilistic::mock::EMBEDDING_SIZE;
+    use axiom_assistant::modules::probabilistic::process::{split_command, ProcessConfig, ProcessModel};
+    use axiom_assistant::modules::probabilistic::Backend;
+    use std::time::Duration;
+    
+    let mock = ProbabilisticModule::load_local_llm().await.unwrap();
+    let capabilities = mock.capabilities();
+    assert_eq!((capabilities.backend.as_str(), capabilities.tokenize, capabilities.embed), ("mock", true, true));
+    let ids = mock.tokenize("two words").await.unwrap();
+    assert_eq!((ids.len(), ids), (2, mock.tokenize("two words").await.unwrap()));
+    let embedding = mock.embed("Hello hello world").await.unwrap();
+    assert_eq!(embedding.len(), EMBEDDING_SIZE);
+    assert!((embedding.iter().map(|x| x * x).sum::<f32>() - 1.0).abs() < 1e-6);
+    assert_eq!("gpt".parse::<Backend>().unwrap_err().code(), "PROB_MODEL_LOAD");
+    
+    // `cat` answers with the prompt itself, streamed from its stdout
+    let config = ProcessConfig { command: vec!["cat".to_string()], ..ProcessConfig::default() };
+    let echo = mock.with_model(ProcessModel::new(config).unwrap());
+    assert_eq!(echo.infer("héllo wörld").await.unwrap(), "héllo wörld");
+    let streamed: Vec<String> = echo.stream_tokens("héllo wörld").await.map(Result::unwrap).collect().await;
+    assert_eq!(streamed.concat(), "héllo wörld\n");
+    assert_eq!(echo.tokenize("x").await.unwrap_err().code(), "PROB_UNSUPPORTED");
+    
+    let config = ProcessConfig { command: vec!["sleep".to_string(), "5".to_string()], time_limit: Duration::from_millis(200) };
+    let slow = echo.with_model(ProcessModel::new(config).unwrap());
+    let error = slow.infer("anything").await.unwrap_err();
+    assert_eq!((error.code(), error.to_string().as_str()), ("PROB_TIMEOUT", "Language model exceeded the 0.2s time limit"));
+    assert!(ProcessModel::new(ProcessConfig::default()).is_err());
+    
+    // AXIOM_MODEL_COMMAND is split into words like a shell would
+    let words = split_command(r#"llama-cli -m '/models/My Model.gguf' -p "say \"hi\"" two\ words"#).unwrap();
+    assert_eq!(words, ["llama-cli", "-m", "/models/My Model.gguf", "-p", "say \"hi\"", "two words"]);
+    assert_eq!(split_command("llama-cli -m 'model.gguf").unwrap_err().code(), "PROB_MODEL_LOAD");
+    
+    // A backend that fails mid-answer ends the stream with its error, not a newline
+    let command = ["sh", "-c", "cat; echo out of memory >&2; exit 3"].map(str::to_string).to_vec();
+    let failing = slow.with_model(ProcessModel::new(ProcessConfig { command, ..ProcessConfig::default() }).unwrap());
+    let streamed: Vec<_> = failing.stream_tokens("partial").await.collect().await;
+    assert_eq!(streamed[0], Ok("partial".to_string()));
+    let error = streamed.last().unwrap().clone().unwrap_err();
+    assert_eq!((error.code(), error.to_string().as_str()), ("PROB_BACKEND", "Language model failed: sh exited with exit status: 3 (out of memory)"));
+    
+    let orchestrator = Orchestrator::new(failing, DeterministicModule::init_deterministic_module().unwrap(), NeuroSymbolicRouter::new());
+    let events: Vec<QueryEvent> = orchestrator.process_query_events("Write a poem about the sea").await.collect().await;
+    let Some(QueryEvent::Error(error)) = events.last() else { panic!("expected an error event, got {:?}", events) };
+    assert_eq!((error.code.as_str(), error.layer), ("PROB_BACKEND", ErrorLayer::Probabilistic));
+}
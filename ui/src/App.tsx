@@ -4,6 +4,7 @@ import { listen } from '@tauri-apps/api/event';
 import './styles/axiom_dark.css';
 
 type Message = { role: 'user' | 'assistant'; content: string };
+type QueryError = { code: string; layer: string; message: string; column?: number };
 
 function App() {
   const [messages, setMessages] = useState<Message[]>([]);
@@ -14,7 +15,15 @@ function App() {
     const unlisten = listen('token', (event: any) => {
       setStreaming(prev => prev + event.payload);
     });
-    return () => { unlisten.then((fn:any) => fn()); };
+    const unlistenError = listen('query-error', (event: any) => {
+      const error: QueryError = event.payload;
+      const where = error.column ? ` (column ${error.column})` : '';
+      setStreaming(prev => prev + `[${error.code}] ${error.message}${where}`);
+    });
+    return () => {
+      unlisten.then((fn:any) => fn());
+      unlistenError.then((fn:any) => fn());
+    };
   }, []);
 
   const sendMessage = async () => {